@@ -0,0 +1,385 @@
+//! The attribute macros of the [`thread-priority`](https://docs.rs/thread-priority/)
+//! crate, re-exported by it with its `macros` feature.
+//!
+//! The arguments are parsed without `syn`, so the crate has no dependencies:
+//! they are the `name = value` pairs, separated by commas, the values being
+//! booleans, strings or integers, the latter two in the textual forms of the
+//! `thread_priority::parse` module.
+
+use std::iter::once;
+
+use proc_macro::{Delimiter, Group, Literal, TokenStream, TokenTree};
+
+/// Applies the scheduling to the main thread before the body of `fn main` runs, and
+/// panics if it can't be applied.
+///
+/// The arguments are:
+///
+/// * `priority`, like `50` or `"max"`.
+/// * `policy`, the unix policy, like `"fifo"`, with the minimum priority if there's no
+///   `priority`. The policies aren't supported on the other platforms.
+/// * `profile`, one of `"audio"`, `"game_simulation"` and `"background_batch"`, which
+///   can't be combined with the others. The profile stays applied until `main` returns.
+///
+/// ```rust
+/// #[thread_priority::main(priority = "min")]
+/// fn main() {
+///     // The main thread runs with the minimum priority.
+/// }
+/// # main();
+/// ```
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    let prelude = match parse_args(args).and_then(|args| main_prelude(&args)) {
+        Ok(prelude) => prelude,
+        Err(message) => return compile_error(&message),
+    };
+    let mut tokens: Vec<TokenTree> = item.into_iter().collect();
+    let is_main = tokens.windows(2).any(|pair| match pair {
+        [TokenTree::Ident(keyword), TokenTree::Ident(name)] => {
+            keyword.to_string() == "fn" && name.to_string() == "main"
+        }
+        _ => false,
+    });
+    match tokens.last() {
+        Some(TokenTree::Group(body)) if is_main && body.delimiter() == Delimiter::Brace => {
+            let mut stream: TokenStream = prelude.parse().unwrap();
+            stream.extend(body.stream());
+            let mut body = Group::new(Delimiter::Brace, stream);
+            body.set_span(tokens[tokens.len() - 1].span());
+            let last = tokens.len() - 1;
+            tokens[last] = TokenTree::Group(body);
+            tokens.into_iter().collect()
+        }
+        _ => compile_error("The attribute must be put on `fn main`"),
+    }
+}
+
+/// Turns the function into a helper spawning its body on a new thread with the
+/// scheduling, which returns the `std::io::Result<std::thread::JoinHandle<T>>` of
+/// `ThreadBuilder::spawn`, `T` being the return type of the function.
+///
+/// The arguments are:
+///
+/// * `name`, the name of the thread, the name of the function by default.
+/// * `priority`, like `50` or `"max"`.
+/// * `policy`, the unix policy, like `"fifo"`. The policies aren't supported on the
+///   other platforms, where the helper fails.
+/// * `required`, `true` to panic in the thread rather than run the body when the
+///   scheduling can't be applied, `false` by default.
+///
+/// The priority and the policy which don't parse fail the helper with
+/// `std::io::ErrorKind::InvalidInput`. The arguments of the function are moved into the
+/// thread, so they must be `Send + 'static`.
+///
+/// ```rust
+/// #[thread_priority::prioritized_thread(priority = "min", name = "background")]
+/// fn sum(values: Vec<u32>) -> u32 {
+///     values.iter().sum()
+/// }
+///
+/// assert_eq!(sum(vec![1, 2, 3]).unwrap().join().unwrap(), 6);
+/// ```
+#[proc_macro_attribute]
+pub fn prioritized_thread(args: TokenStream, item: TokenStream) -> TokenStream {
+    match parse_args(args).and_then(|args| spawn_helper(&args, item)) {
+        Ok(helper) => helper,
+        Err(message) => compile_error(&message),
+    }
+}
+
+fn spawn_helper(args: &[Arg], item: TokenStream) -> Result<TokenStream, String> {
+    if let Some(arg) = args
+        .iter()
+        .find(|arg| !["name", "priority", "policy", "required"].contains(&arg.name.as_str()))
+    {
+        return Err(format!("Unknown argument `{}`", arg.name));
+    }
+    let required = match find(args, "required") {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(_) => return Err("Expected `true` or `false` for `required`".to_owned()),
+    };
+    let mut tokens: Vec<TokenTree> = item.into_iter().collect();
+    let body = match tokens.pop() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        _ => return Err("The attribute must be put on a function".to_owned()),
+    };
+    if tokens
+        .iter()
+        .any(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "async"))
+    {
+        return Err("The attribute can't be put on an async function".to_owned());
+    }
+    let (name_at, name) = tokens
+        .windows(2)
+        .enumerate()
+        .find_map(|(i, pair)| match pair {
+            [TokenTree::Ident(keyword), TokenTree::Ident(name)] if keyword.to_string() == "fn" => {
+                Some((i + 1, name.to_string()))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| "The attribute must be put on a function".to_owned())?;
+
+    // The return type is after the `->` following the parameters, up to the `where`
+    // clause; the parameters are the first parenthesized group after the name out of the
+    // generics.
+    let mut depth = 0;
+    let mut parameters = None;
+    for (i, token) in tokens.iter().enumerate().skip(name_at) {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '<' => depth += 1,
+            TokenTree::Punct(punct) if punct.as_char() == '>' => depth -= 1,
+            TokenTree::Group(group)
+                if depth == 0 && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                parameters = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let parameters = parameters.ok_or_else(|| "The function has no parameters list".to_owned())?;
+    let (signature, output) = match &tokens[parameters + 1..] {
+        [TokenTree::Punct(minus), TokenTree::Punct(greater), rest @ ..]
+            if minus.as_char() == '-' && greater.as_char() == '>' =>
+        {
+            let end = rest
+                .iter()
+                .position(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "where"))
+                .unwrap_or(rest.len());
+            let output: TokenStream = rest[..end].iter().cloned().collect();
+            let mut signature: Vec<TokenTree> = tokens[..=parameters].to_vec();
+            signature.extend(rest[end..].iter().cloned());
+            (signature, output.to_string())
+        }
+        _ => (tokens.clone(), "()".to_owned()),
+    };
+
+    let (before_where, where_clause) = match signature
+        .iter()
+        .position(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "where"))
+    {
+        Some(i) => (&signature[..i], &signature[i..]),
+        None => (&signature[..], &[][..]),
+    };
+    let mut helper: TokenStream = before_where.iter().cloned().collect();
+    helper.extend(
+        format!(
+            "-> ::std::io::Result<::std::thread::JoinHandle<{}>>",
+            output
+        )
+        .parse::<TokenStream>()
+        .unwrap(),
+    );
+    helper.extend(where_clause.iter().cloned());
+
+    let mut builder = format!(
+        "let builder = ::thread_priority::ThreadBuilder::default().name({:?});",
+        find(args, "name").unwrap_or(&name)
+    );
+    if let Some(priority) = find(args, "priority") {
+        builder.push_str(&format!(
+            "let builder = builder.priority(
+                <::thread_priority::ThreadPriority as ::std::str::FromStr>::from_str({:?})
+                    .map_err(|error| ::std::io::Error::new(::std::io::ErrorKind::InvalidInput, error))?,
+            );",
+            priority
+        ));
+    }
+    if let Some(policy) = find(args, "policy") {
+        builder.push_str(&format!(
+            "let builder = builder.policy(
+                <::thread_priority::ThreadSchedulePolicy as ::std::str::FromStr>::from_str({:?})
+                    .map_err(|error| ::std::io::Error::new(::std::io::ErrorKind::InvalidInput, error))?,
+            );",
+            policy
+        ));
+    }
+    let check = if required {
+        "if let ::std::result::Result::Err(error) = result {
+            panic!(\"Couldn't set the scheduling of the thread: {}\", error);
+        }"
+    } else {
+        "let _ = result;"
+    };
+    let mut closure: TokenStream = check.parse().unwrap();
+    closure.extend(body.stream());
+    let mut arguments: TokenStream = "move |result|".parse().unwrap();
+    arguments.extend(once(TokenTree::Group(Group::new(
+        Delimiter::Brace,
+        closure,
+    ))));
+    let mut spawn: TokenStream = builder.parse().unwrap();
+    spawn.extend("return builder.spawn".parse::<TokenStream>().unwrap());
+    spawn.extend(once(TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        arguments,
+    ))));
+    spawn.extend(";".parse::<TokenStream>().unwrap());
+
+    let helper_body = if find(args, "policy").is_some() {
+        // The policies exist on unix only, the helper fails on the other platforms.
+        let mut helper_body: TokenStream = "#[cfg(unix)]".parse().unwrap();
+        helper_body.extend(once(TokenTree::Group(Group::new(Delimiter::Brace, spawn))));
+        helper_body.extend(
+            "#[cfg(not(unix))]
+            {
+                return ::std::result::Result::Err(::std::io::Error::new(
+                    ::std::io::ErrorKind::Other,
+                    \"The schedule policies are not supported on this platform\",
+                ));
+            }"
+            .parse::<TokenStream>()
+            .unwrap(),
+        );
+        helper_body
+    } else {
+        spawn
+    };
+    helper.extend(once(TokenTree::Group(Group::new(
+        Delimiter::Brace,
+        helper_body,
+    ))));
+    Ok(helper)
+}
+
+/// An argument of the macros, the strings being unquoted.
+struct Arg {
+    name: String,
+    value: String,
+}
+
+fn parse_args(args: TokenStream) -> Result<Vec<Arg>, String> {
+    let mut parsed = Vec::new();
+    let mut tokens = args.into_iter();
+    loop {
+        let name = match tokens.next() {
+            Some(TokenTree::Ident(name)) => name.to_string(),
+            None => return Ok(parsed),
+            Some(token) => return Err(format!("Expected an argument name, found `{}`", token)),
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+            _ => return Err(format!("Expected `=` after `{}`", name)),
+        }
+        let value = match tokens.next() {
+            Some(TokenTree::Literal(literal)) => unquote(&literal)?,
+            Some(TokenTree::Ident(value)) if ["true", "false"].contains(&&*value.to_string()) => {
+                value.to_string()
+            }
+            _ => {
+                return Err(format!(
+                    "Expected a string, an integer or a boolean for `{}`",
+                    name
+                ))
+            }
+        };
+        if parsed.iter().any(|arg: &Arg| arg.name == name) {
+            return Err(format!("The argument `{}` is repeated", name));
+        }
+        parsed.push(Arg { name, value });
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            None => return Ok(parsed),
+            Some(token) => return Err(format!("Expected `,`, found `{}`", token)),
+        }
+    }
+}
+
+fn unquote(literal: &Literal) -> Result<String, String> {
+    let text = literal.to_string();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        let inner = &text[1..text.len() - 1];
+        if inner.contains('\\') {
+            return Err("The strings can't have escapes".to_owned());
+        }
+        Ok(inner.to_owned())
+    } else if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+        Ok(text)
+    } else {
+        Err(format!("Expected a string or an integer, found `{}`", text))
+    }
+}
+
+fn find<'a>(args: &'a [Arg], name: &str) -> Option<&'a str> {
+    args.iter()
+        .find(|arg| arg.name == name)
+        .map(|arg| arg.value.as_str())
+}
+
+/// The statement applying the scheduling, binding the applied profile to keep it alive.
+fn main_prelude(args: &[Arg]) -> Result<String, String> {
+    if let Some(arg) = args
+        .iter()
+        .find(|arg| !["priority", "policy", "profile"].contains(&arg.name.as_str()))
+    {
+        return Err(format!("Unknown argument `{}`", arg.name));
+    }
+    let apply = match (find(args, "priority"), find(args, "policy"), find(args, "profile")) {
+        (None, None, None) => {
+            return Err("Expected a `priority`, a `policy` or a `profile`".to_owned())
+        }
+        (None, None, Some(profile)) => format!(
+            "return ::std::result::Result::Ok(::thread_priority::profiles::Profile::{}.apply_to_current()?);",
+            profile_variant(profile)?
+        ),
+        (_, _, Some(_)) => {
+            return Err("The `profile` can't be combined with a `priority` or a `policy`".to_owned())
+        }
+        (priority, policy, None) => scheduling(priority, policy),
+    };
+    Ok(format!(
+        "let _thread_priority_applied = match (|| -> ::std::result::Result<_, ::thread_priority::Error> {{ {} }})() {{
+            ::std::result::Result::Ok(applied) => applied,
+            ::std::result::Result::Err(error) => panic!(\"Couldn't set the scheduling of the main thread: {{}}\", error),
+        }};",
+        apply
+    ))
+}
+
+/// The statements setting the priority, and the policy on unix, of the current thread.
+fn scheduling(priority: Option<&str>, policy: Option<&str>) -> String {
+    match policy {
+        None => format!(
+            "return <::thread_priority::ThreadPriority as ::std::str::FromStr>::from_str({:?})
+                .map_err(::thread_priority::Error::Priority)?
+                .set_for_current();",
+            priority.unwrap_or_default()
+        ),
+        Some(policy) => {
+            let scheduling = match priority {
+                Some(priority) => format!("{}:{}", policy, priority),
+                None => policy.to_owned(),
+            };
+            format!(
+                "#[cfg(unix)]
+                return <::thread_priority::parse::Scheduling as ::std::str::FromStr>::from_str({:?})
+                    .map_err(::thread_priority::Error::Priority)?
+                    .apply_to_current();
+                #[cfg(not(unix))]
+                return ::std::result::Result::Err(::thread_priority::Error::Unsupported(
+                    \"The schedule policies are not supported on this platform\",
+                ));",
+                scheduling
+            )
+        }
+    }
+}
+
+fn profile_variant(profile: &str) -> Result<&'static str, String> {
+    match profile.to_ascii_lowercase().replace('-', "_").as_str() {
+        "audio" => Ok("Audio"),
+        "game_simulation" => Ok("GameSimulation"),
+        "background_batch" => Ok("BackgroundBatch"),
+        _ => Err(format!(
+            "Unknown profile `{}`, expected one of audio, game_simulation or background_batch",
+            profile
+        )),
+    }
+}
+
+fn compile_error(message: &str) -> TokenStream {
+    format!("compile_error!({:?});", message).parse().unwrap()
+}
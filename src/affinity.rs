@@ -0,0 +1,1203 @@
+//! This module defines the thread CPU affinity control.
+//!
+//! Pinning a thread to a set of CPUs is configured together with its
+//! priority in virtually every low-latency deployment, so the crate
+//! provides it as well, in a cross-platform way.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::affinity::*;
+//!
+//! let cpus: CpuSet = vec![0].into_iter().collect();
+//! assert!(set_thread_affinity(thread_native_id(), &cpus).is_ok());
+//! ```
+
+#[cfg(windows)]
+use std::convert::TryFrom;
+
+use crate::{Error, ThreadId};
+
+const BITS: usize = 64;
+
+/// The bound of the CPU indices, the largest `NR_CPUS` of Linux, so that an index or a
+/// range can't grow a set without bound.
+const MAX_CPUS: usize = 8192;
+
+/// A set of logical CPUs, identified by their zero-based indices, which must be below 8192.
+///
+/// ```rust
+/// use thread_priority::affinity::CpuSet;
+///
+/// let mut cpus = CpuSet::new();
+/// cpus.insert(0);
+/// cpus.insert(3);
+/// assert!(cpus.contains(3));
+/// assert_eq!(cpus.iter().collect::<Vec<_>>(), vec![0, 3]);
+/// ```
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct CpuSet {
+    // Always trimmed of the trailing empty words, so that the derived traits
+    // don't depend on how the set has been built.
+    words: Vec<u64>,
+}
+
+impl CpuSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a set containing the single CPU, or an empty one if the CPU index is
+    /// too large. For more info read [`CpuSet::insert`].
+    pub fn single(cpu: usize) -> Self {
+        let mut set = Self::new();
+        set.insert(cpu);
+        set
+    }
+
+    /// Adds the CPU to the set. Returns whether it wasn't present yet.
+    ///
+    /// The CPU indices from 8192 on are dropped with a warning, as are those collected
+    /// into a set. Use [`CpuSet::try_insert`] to get an error for them instead.
+    pub fn insert(&mut self, cpu: usize) -> bool {
+        self.try_insert(cpu).unwrap_or_else(|e| {
+            log::warn!("Dropping the CPU {} from the set: {}", cpu, e);
+            false
+        })
+    }
+
+    /// Adds the CPU to the set. Returns whether it wasn't present yet, or an error if
+    /// the CPU index is 8192 or above.
+    ///
+    /// ```rust
+    /// use thread_priority::affinity::CpuSet;
+    ///
+    /// let mut cpus = CpuSet::new();
+    /// assert!(cpus.try_insert(0).unwrap());
+    /// assert!(cpus.try_insert(8192).is_err());
+    /// ```
+    pub fn try_insert(&mut self, cpu: usize) -> Result<bool, Error> {
+        if cpu >= MAX_CPUS {
+            return Err(Error::Priority("The CPU index is too large"));
+        }
+        let (word, bit) = (cpu / BITS, cpu % BITS);
+        if self.words.len() <= word {
+            self.words.resize(word + 1, 0);
+        }
+        let inserted = self.words[word] & (1 << bit) == 0;
+        self.words[word] |= 1 << bit;
+        Ok(inserted)
+    }
+
+    /// Removes the CPU from the set. Returns whether it was present.
+    pub fn remove(&mut self, cpu: usize) -> bool {
+        let removed = self.contains(cpu);
+        if removed {
+            self.words[cpu / BITS] &= !(1 << (cpu % BITS));
+            self.trim();
+        }
+        removed
+    }
+
+    /// Returns whether the set contains the CPU.
+    pub fn contains(&self, cpu: usize) -> bool {
+        self.words
+            .get(cpu / BITS)
+            .is_some_and(|word| word & (1 << (cpu % BITS)) != 0)
+    }
+
+    /// Returns the number of CPUs in the set.
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns whether the set contains no CPUs.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Returns an iterator over the CPUs of the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, word)| {
+            (0..BITS)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| index * BITS + bit)
+        })
+    }
+
+    /// Returns the set of the CPUs contained in both sets.
+    pub fn intersection(&self, other: &CpuSet) -> CpuSet {
+        let mut set = CpuSet {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a & b)
+                .collect(),
+        };
+        set.trim();
+        set
+    }
+
+    /// Returns whether all the CPUs of the set are contained in the other one.
+    pub fn is_subset(&self, other: &CpuSet) -> bool {
+        self.intersection(other) == *self
+    }
+
+    fn trim(&mut self) {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+}
+
+impl CpuSet {
+    /// Parses a list of CPUs in the format used by `taskset --cpu-list` and
+    /// the cgroup `cpuset.cpus` files: comma-separated CPU indices and
+    /// inclusive ranges of those. The indices must be below 8192.
+    ///
+    /// ```rust
+    /// use thread_priority::affinity::CpuSet;
+    ///
+    /// let cpus = CpuSet::from_list_str("0-3,8,10-11").unwrap();
+    /// assert_eq!(cpus.len(), 7);
+    /// assert_eq!(cpus.to_string(), "0-3,8,10-11");
+    /// assert!(CpuSet::from_list_str("3-1").is_err());
+    /// ```
+    pub fn from_list_str(list: &str) -> Result<Self, &'static str> {
+        let mut set = CpuSet::new();
+        for item in list
+            .trim()
+            .split(',')
+            .filter(|item| !item.trim().is_empty())
+        {
+            let mut bounds = item
+                .splitn(2, '-')
+                .map(|bound| bound.trim().parse::<usize>());
+            let first = bounds
+                .next()
+                .and_then(Result::ok)
+                .ok_or("The CPU index is not a number")?;
+            let last = match bounds.next() {
+                Some(last) => last.map_err(|_| "The CPU index is not a number")?,
+                None => first,
+            };
+            if first > last {
+                return Err("The CPU range is reversed");
+            }
+            if last >= MAX_CPUS {
+                return Err("The CPU index is too large");
+            }
+            set.extend(first..=last);
+        }
+        Ok(set)
+    }
+}
+
+impl CpuSet {
+    /// Returns the set of CPUs belonging to the NUMA node.
+    ///
+    /// * On Linux and Android, read from `/sys/devices/system/node/node<n>/cpulist`.
+    /// * On Windows, based on `GetNumaNodeProcessorMaskEx`. The CPUs are numbered
+    ///   as `group * 64 + processor`, so for the first processor group the indices
+    ///   are those of the affinity mask.
+    ///
+    /// ```rust
+    /// use thread_priority::affinity::CpuSet;
+    ///
+    /// assert!(!CpuSet::for_numa_node(0).unwrap().is_empty());
+    /// ```
+    pub fn for_numa_node(node: usize) -> Result<Self, Error> {
+        numa_node_cpus(node)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn numa_node_cpus(node: usize) -> Result<CpuSet, Error> {
+    std::fs::read_to_string(format!("/sys/devices/system/node/node{}/cpulist", node))?
+        .parse()
+        .map_err(|_| Error::Ffi("Can't parse the NUMA node CPU list"))
+}
+
+#[cfg(windows)]
+fn numa_node_cpus(node: usize) -> Result<CpuSet, Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::systemtopologyapi::GetNumaNodeProcessorMaskEx;
+
+    let node = u16::try_from(node).map_err(|_| Error::Ffi("The NUMA node is out of range"))?;
+    unsafe {
+        let mut affinity = std::mem::zeroed();
+        if GetNumaNodeProcessorMaskEx(node, &mut affinity) == 0 {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+        Ok(GroupAffinity::from_native(&affinity).into())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn numa_node_cpus(_node: usize) -> Result<CpuSet, Error> {
+    Err(Error::Unsupported(
+        "NUMA topology is not supported on this platform",
+    ))
+}
+
+/// Returns the set of CPUs the process is allowed to run on, which tells the CPUs
+/// available to a container from the ones of the host.
+///
+/// * On Linux and Android, read from the `cpuset` cgroup controller, for both the
+///   unified and the legacy hierarchies, falling back to the affinity of the
+///   process' main thread (`sched_getaffinity`) when there is no such controller.
+///   The set is read once, the first time it's needed, so the main thread pinning
+///   itself later doesn't change it, nor do the later changes of the cgroup.
+/// * On FreeBSD, the CPUs of the process' cpuset, based on `cpuset_getaffinity`.
+/// * On Windows, based on `GetProcessAffinityMask`. When the process has threads
+///   in several processor groups, these are all the active processors.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::affinity::*;
+///
+/// let allowed = allowed_cpus().unwrap();
+/// assert!(get_current_thread_affinity().unwrap().is_subset(&allowed));
+/// ```
+pub fn allowed_cpus() -> Result<CpuSet, Error> {
+    allowed_native_cpus()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn cgroup_cpus() -> Option<CpuSet> {
+    let cgroups = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+    cgroups.lines().find_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let (id, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+        let file = if id == "0" && controllers.is_empty() {
+            format!("/sys/fs/cgroup{}/cpuset.cpus.effective", path)
+        } else if controllers
+            .split(',')
+            .any(|controller| controller == "cpuset")
+        {
+            format!("/sys/fs/cgroup/cpuset{}/cpuset.effective_cpus", path)
+        } else {
+            return None;
+        };
+        std::fs::read_to_string(file)
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+            .filter(|cpus: &CpuSet| !cpus.is_empty())
+    })
+}
+
+// The allowed CPUs, read the first time they're needed.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+static ALLOWED_CPUS: std::sync::Mutex<Option<CpuSet>> = std::sync::Mutex::new(None);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn allowed_native_cpus() -> Result<CpuSet, Error> {
+    let mut allowed = ALLOWED_CPUS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(cpus) = &*allowed {
+        return Ok(cpus.clone());
+    }
+    let cpus = match cgroup_cpus() {
+        Some(cpus) => cpus,
+        None => unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            match libc::sched_getaffinity(
+                libc::getpid(),
+                std::mem::size_of::<libc::cpu_set_t>(),
+                &mut set,
+            ) {
+                0 => CpuSet::from_native(&set),
+                _ => return Err(Error::OS(crate::unix::errno())),
+            }
+        },
+    };
+    *allowed = Some(cpus.clone());
+    Ok(cpus)
+}
+
+#[cfg(windows)]
+fn allowed_native_cpus() -> Result<CpuSet, Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::processthreadsapi::{GetCurrentProcess, GetCurrentThread};
+    use winapi::um::winbase::{
+        GetActiveProcessorCount, GetActiveProcessorGroupCount, GetProcessAffinityMask,
+    };
+
+    unsafe {
+        let (mut process_mask, mut system_mask) = (0, 0);
+        if GetProcessAffinityMask(GetCurrentProcess(), &mut process_mask, &mut system_mask) == 0 {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+        if process_mask != 0 {
+            let group = get_thread_group_affinity(GetCurrentThread())?.group;
+            return Ok(GroupAffinity {
+                group,
+                mask: process_mask,
+            }
+            .into());
+        }
+        Ok((0..GetActiveProcessorGroupCount())
+            .flat_map(|group| {
+                (0..GetActiveProcessorCount(group) as usize)
+                    .map(move |processor| group as usize * BITS + processor)
+            })
+            .collect())
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+fn allowed_native_cpus() -> Result<CpuSet, Error> {
+    crate::freebsd::cpuset_cpus(libc::CPU_LEVEL_CPUSET, libc::CPU_WHICH_PID, -1)
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    windows
+)))]
+fn allowed_native_cpus() -> Result<CpuSet, Error> {
+    Err(Error::Unsupported(
+        "The CPU affinity is not supported on this platform",
+    ))
+}
+
+impl std::str::FromStr for CpuSet {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CpuSet::from_list_str(s)
+    }
+}
+
+impl std::fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut cpus = self.iter().peekable();
+        let mut separator = "";
+        while let Some(first) = cpus.next() {
+            let mut last = first;
+            while cpus.peek() == Some(&(last + 1)) {
+                last = cpus.next().unwrap_or(last);
+            }
+            if first == last {
+                write!(f, "{}{}", separator, first)?;
+            } else {
+                write!(f, "{}{}-{}", separator, first, last)?;
+            }
+            separator = ",";
+        }
+        Ok(())
+    }
+}
+
+impl std::iter::FromIterator<usize> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = CpuSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for CpuSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for cpu in iter {
+            self.insert(cpu);
+        }
+    }
+}
+
+// The set is (de)serialized as the list of its CPUs, not as the bitmask words.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CpuSet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CpuSet {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut set = CpuSet::new();
+        for cpu in <Vec<usize> as serde::Deserialize>::deserialize(deserializer)? {
+            set.try_insert(cpu)
+                .map_err(<D::Error as serde::de::Error>::custom)?;
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) type NativeCpuSet = libc::cpu_set_t;
+#[cfg(target_os = "freebsd")]
+pub(crate) type NativeCpuSet = libc::cpuset_t;
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+impl CpuSet {
+    pub(crate) fn to_native(&self) -> Result<NativeCpuSet, Error> {
+        unsafe {
+            let mut native: NativeCpuSet = std::mem::zeroed();
+            let capacity = std::mem::size_of::<NativeCpuSet>() * 8;
+            for cpu in self.iter() {
+                if cpu >= capacity {
+                    return Err(Error::Ffi(
+                        "The CPU index is out of the native CPU set range",
+                    ));
+                }
+                libc::CPU_SET(cpu, &mut native);
+            }
+            Ok(native)
+        }
+    }
+
+    pub(crate) fn from_native(native: &NativeCpuSet) -> Self {
+        let capacity = std::mem::size_of::<NativeCpuSet>() * 8;
+        (0..capacity)
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, native) })
+            .collect()
+    }
+}
+
+/// The processors of a Windows processor group. Threads run on the processors of a
+/// single group, the systems with more than 64 logical CPUs have several groups.
+///
+/// Converts from and into a [`CpuSet`], in which the CPUs are numbered as
+/// `group * 64 + processor`.
+///
+/// ```rust
+/// use std::convert::TryFrom;
+/// use thread_priority::affinity::*;
+///
+/// let affinity = GroupAffinity::try_from(&CpuSet::single(65)).unwrap();
+/// assert_eq!(affinity, GroupAffinity { group: 1, mask: 0b10 });
+/// assert_eq!(CpuSet::from(affinity), CpuSet::single(65));
+/// ```
+#[cfg(windows)]
+#[derive(Debug, Default, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct GroupAffinity {
+    /// The processor group.
+    pub group: u16,
+    /// The mask of the processors within the group.
+    pub mask: usize,
+}
+
+#[cfg(windows)]
+impl GroupAffinity {
+    fn to_native(self) -> winapi::um::winnt::GROUP_AFFINITY {
+        winapi::um::winnt::GROUP_AFFINITY {
+            Mask: self.mask,
+            Group: self.group,
+            Reserved: [0; 3],
+        }
+    }
+
+    pub(crate) fn from_native(native: &winapi::um::winnt::GROUP_AFFINITY) -> Self {
+        GroupAffinity {
+            group: native.Group,
+            mask: native.Mask,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl TryFrom<&CpuSet> for GroupAffinity {
+    type Error = Error;
+
+    fn try_from(cpus: &CpuSet) -> Result<Self, Self::Error> {
+        let bits = std::mem::size_of::<usize>() * 8;
+        let group = cpus.iter().next().map_or(0, |cpu| cpu / BITS);
+        let mut affinity = GroupAffinity {
+            group: u16::try_from(group)
+                .map_err(|_| Error::Ffi("The processor group is out of range"))?,
+            mask: 0,
+        };
+        for cpu in cpus.iter() {
+            if cpu / BITS != group {
+                return Err(Error::Ffi("The CPU set spans several processor groups"));
+            }
+            if cpu % BITS >= bits {
+                return Err(Error::Ffi(
+                    "The CPU index is out of the native affinity mask range",
+                ));
+            }
+            affinity.mask |= 1 << (cpu % BITS);
+        }
+        Ok(affinity)
+    }
+}
+
+#[cfg(windows)]
+impl From<GroupAffinity> for CpuSet {
+    fn from(affinity: GroupAffinity) -> Self {
+        let offset = affinity.group as usize * BITS;
+        (0..std::mem::size_of::<usize>() * 8)
+            .filter(|bit| affinity.mask & (1 << bit) != 0)
+            .map(|bit| offset + bit)
+            .collect()
+    }
+}
+
+/// Sets the processor group and the processors within it the thread is allowed to
+/// run on, returning the previous ones.
+///
+/// Based on `SetThreadGroupAffinity`, so it is possible to move the thread to another
+/// processor group, unlike `SetThreadAffinityMask`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::affinity::*;
+///
+/// let affinity = GroupAffinity { group: 0, mask: 1 };
+/// unsafe {
+///     let previous = set_thread_group_affinity(thread_native_id(), affinity).unwrap();
+///     assert_eq!(get_thread_group_affinity(thread_native_id()).unwrap(), affinity);
+///     assert!(set_thread_group_affinity(thread_native_id(), previous).is_ok());
+/// }
+/// ```
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+#[cfg(windows)]
+pub unsafe fn set_thread_group_affinity(
+    native: ThreadId,
+    affinity: GroupAffinity,
+) -> Result<GroupAffinity, Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::processtopologyapi::SetThreadGroupAffinity;
+
+    unsafe {
+        let mut previous = std::mem::zeroed();
+        if SetThreadGroupAffinity(native, &affinity.to_native(), &mut previous) != 0 {
+            Ok(GroupAffinity::from_native(&previous))
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Returns the processor group and the processors within it the thread is allowed
+/// to run on.
+///
+/// Based on `GetThreadGroupAffinity`.
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+#[cfg(windows)]
+pub unsafe fn get_thread_group_affinity(native: ThreadId) -> Result<GroupAffinity, Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::processtopologyapi::GetThreadGroupAffinity;
+
+    unsafe {
+        let mut affinity = std::mem::zeroed();
+        if GetThreadGroupAffinity(native, &mut affinity) != 0 {
+            Ok(GroupAffinity::from_native(&affinity))
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Sets the set of CPUs the thread is allowed to run on.
+///
+/// * On Linux, based on `pthread_setaffinity_np`.
+/// * On Android, based on `sched_setaffinity` for the thread's tid.
+/// * On FreeBSD, based on `pthread_setaffinity_np`.
+/// * On Windows, based on `SetThreadGroupAffinity`, so the CPUs must belong to a
+///   single processor group. For more info read `GroupAffinity`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::affinity::*;
+///
+/// assert!(set_thread_affinity(thread_native_id(), &CpuSet::single(0)).is_ok());
+/// assert!(set_thread_affinity(thread_native_id(), &CpuSet::new()).is_err());
+/// ```
+pub fn set_thread_affinity(native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+    traced!(
+        DEBUG,
+        validate_affinity(cpus).and_then(|()| crate::hooks::notified(
+            crate::hooks::Change::Affinity {
+                thread: native,
+                cpus
+            },
+            || set_native_thread_affinity(native, cpus)
+        )),
+        "the affinity",
+        thread = ?native,
+        cpus = %cpus
+    )
+}
+
+/// Checks whether the set of CPUs could be set as the affinity of a thread, without
+/// touching any thread: the set must not be empty, the process must be allowed to run
+/// on all the CPUs, and on Windows, they must belong to a single processor group.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::Error;
+/// use thread_priority::affinity::*;
+///
+/// assert!(validate_affinity(&allowed_cpus().unwrap()).is_ok());
+/// assert!(matches!(validate_affinity(&CpuSet::new()), Err(Error::Priority(_))));
+/// ```
+pub fn validate_affinity(cpus: &CpuSet) -> Result<(), Error> {
+    if cpus.is_empty() {
+        return Err(Error::Priority("The CPU set is empty"));
+    }
+    if !cpus.is_subset(&allowed_cpus()?) {
+        return Err(Error::Priority(
+            "The CPU set contains CPUs the process is not allowed to run on",
+        ));
+    }
+    #[cfg(windows)]
+    GroupAffinity::try_from(cpus)?;
+    Ok(())
+}
+
+/// Sets the set of CPUs the thread is allowed to run on, leaving out the CPUs the
+/// process is not allowed to run on (see [`allowed_cpus`]), and returns the set which
+/// has been applied. Fails only if none of the CPUs is allowed.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::affinity::*;
+///
+/// let previous = get_current_thread_affinity().unwrap();
+/// let cpus: CpuSet = (0..4096).collect();
+/// assert_eq!(set_thread_affinity_clamped(thread_native_id(), &cpus).unwrap(), allowed_cpus().unwrap());
+/// assert!(set_current_thread_affinity(&previous).is_ok());
+/// ```
+pub fn set_thread_affinity_clamped(native: ThreadId, cpus: &CpuSet) -> Result<CpuSet, Error> {
+    let cpus = cpus.intersection(&allowed_cpus()?);
+    if cpus.is_empty() {
+        return Err(Error::Priority(
+            "None of the CPUs of the set is allowed for the process",
+        ));
+    }
+    traced!(
+        DEBUG,
+        crate::hooks::notified(
+            crate::hooks::Change::Affinity {
+                thread: native,
+                cpus: &cpus
+            },
+            || set_native_thread_affinity(native, &cpus)
+        ),
+        "the affinity",
+        thread = ?native,
+        cpus = %cpus
+    )?;
+    Ok(cpus)
+}
+
+/// Sets the set of CPUs the current thread is allowed to run on.
+/// For more info read [`set_thread_affinity`].
+pub fn set_current_thread_affinity(cpus: &CpuSet) -> Result<(), Error> {
+    set_thread_affinity(crate::thread_native_id(), cpus)
+}
+
+#[cfg(target_os = "linux")]
+fn set_native_thread_affinity(native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+    let set = cpus.to_native()?;
+    unsafe {
+        match libc::pthread_setaffinity_np(native, std::mem::size_of::<libc::cpu_set_t>(), &set) {
+            0 => Ok(()),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+fn set_native_thread_affinity(native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+    set_tid_affinity(unsafe { libc::pthread_gettid_np(native) }, cpus)
+}
+
+/// Sets the affinity of the thread of the current process addressed by its tid.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn set_tid_affinity(tid: crate::ThreadTid, cpus: &CpuSet) -> Result<(), Error> {
+    let set = cpus.to_native()?;
+    unsafe {
+        match libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) {
+            0 => Ok(()),
+            _ => Err(Error::OS(crate::unix::errno())),
+        }
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+fn set_native_thread_affinity(native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+    let set = cpus.to_native()?;
+    unsafe {
+        match libc::pthread_setaffinity_np(native, std::mem::size_of::<libc::cpuset_t>(), &set) {
+            0 => Ok(()),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn set_native_thread_affinity(native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+    // The handle is taken on trust, as by the other cross-platform functions.
+    unsafe { set_thread_group_affinity(native, GroupAffinity::try_from(cpus)?) }.map(|_| ())
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    windows
+)))]
+fn set_native_thread_affinity(_native: ThreadId, _cpus: &CpuSet) -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "Setting the thread affinity is not supported on this platform",
+    ))
+}
+
+/// Pins the current thread to the single CPU, returning the set of CPUs it was
+/// allowed to run on before, so that it can be restored later.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::affinity::*;
+///
+/// let previous = pin_current_thread_to(0).unwrap();
+/// assert_eq!(get_current_thread_affinity().unwrap(), CpuSet::single(0));
+/// assert!(set_current_thread_affinity(&previous).is_ok());
+/// ```
+pub fn pin_current_thread_to(cpu: usize) -> Result<CpuSet, Error> {
+    let previous = get_current_thread_affinity()?;
+    set_current_thread_affinity(&CpuSet::single(cpu))?;
+    Ok(previous)
+}
+
+/// Constrains the current thread to the CPUs of the NUMA node, returning the set of
+/// CPUs it was allowed to run on before, so that it can be restored later. The CPUs
+/// of the node the process is not allowed to run on are left out.
+/// For more info read [`CpuSet::for_numa_node`].
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::affinity::*;
+///
+/// let previous = pin_current_thread_to_node(0).unwrap();
+/// assert!(set_current_thread_affinity(&previous).is_ok());
+/// ```
+pub fn pin_current_thread_to_node(node: usize) -> Result<CpuSet, Error> {
+    let cpus = CpuSet::for_numa_node(node)?;
+    let previous = get_current_thread_affinity()?;
+    set_thread_affinity_clamped(crate::thread_native_id(), &cpus)?;
+    Ok(previous)
+}
+
+/// Returns the set of CPUs the thread is allowed to run on.
+///
+/// * On Linux, based on `pthread_getaffinity_np`.
+/// * On Android, based on `sched_getaffinity` for the thread's tid.
+/// * On FreeBSD, based on `pthread_getaffinity_np`.
+/// * On Windows, based on `GetThreadGroupAffinity`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::affinity::*;
+///
+/// let cpus = get_thread_affinity(thread_native_id()).unwrap();
+/// assert!(!cpus.is_empty());
+/// ```
+pub fn get_thread_affinity(native: ThreadId) -> Result<CpuSet, Error> {
+    get_native_thread_affinity(native)
+}
+
+/// Returns the set of CPUs the current thread is allowed to run on.
+/// For more info read [`get_thread_affinity`].
+pub fn get_current_thread_affinity() -> Result<CpuSet, Error> {
+    get_thread_affinity(crate::thread_native_id())
+}
+
+#[cfg(target_os = "linux")]
+fn get_native_thread_affinity(native: ThreadId) -> Result<CpuSet, Error> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        match libc::pthread_getaffinity_np(native, std::mem::size_of::<libc::cpu_set_t>(), &mut set)
+        {
+            0 => Ok(CpuSet::from_native(&set)),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+fn get_native_thread_affinity(native: ThreadId) -> Result<CpuSet, Error> {
+    tid_affinity(unsafe { libc::pthread_gettid_np(native) })
+}
+
+/// Returns the affinity of the thread of the current process addressed by its tid.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn tid_affinity(tid: crate::ThreadTid) -> Result<CpuSet, Error> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        match libc::sched_getaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &mut set) {
+            0 => Ok(CpuSet::from_native(&set)),
+            _ => Err(Error::OS(crate::unix::errno())),
+        }
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+fn get_native_thread_affinity(native: ThreadId) -> Result<CpuSet, Error> {
+    unsafe {
+        let mut set: libc::cpuset_t = std::mem::zeroed();
+        match libc::pthread_getaffinity_np(native, std::mem::size_of::<libc::cpuset_t>(), &mut set)
+        {
+            0 => Ok(CpuSet::from_native(&set)),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn get_native_thread_affinity(native: ThreadId) -> Result<CpuSet, Error> {
+    // The handle is taken on trust, as by the other cross-platform functions.
+    unsafe { get_thread_group_affinity(native) }.map(CpuSet::from)
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    windows
+)))]
+fn get_native_thread_affinity(_native: ThreadId) -> Result<CpuSet, Error> {
+    Err(Error::Unsupported(
+        "Getting the thread affinity is not supported on this platform",
+    ))
+}
+
+/// An alias type for the id of a Windows CPU Set, as assigned by the system.
+#[cfg(windows)]
+pub type CpuSetId = u32;
+
+/// A Windows CPU Set, the system's description of a logical CPU threads can express
+/// a placement preference for.
+#[cfg(windows)]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct SystemCpuSet {
+    /// The id of the CPU Set.
+    pub id: CpuSetId,
+    /// The logical CPU, numbered as in a [`CpuSet`], so `group * 64 + processor`.
+    pub cpu: usize,
+    /// The index of the physical core the CPU belongs to.
+    pub core_index: u8,
+    /// The index of the last level cache the CPU shares.
+    pub last_level_cache_index: u8,
+    /// The NUMA node the CPU belongs to.
+    pub numa_node_index: u8,
+    /// The efficiency class of the core, the higher the class the more performant the core.
+    pub efficiency_class: u8,
+    /// Whether the CPU is parked by the power management.
+    pub parked: bool,
+    /// Whether the CPU is allocated to a process, so other threads barely run on it.
+    pub allocated: bool,
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetSystemCpuSetInformation(
+        information: *mut winapi::um::winnt::SYSTEM_CPU_SET_INFORMATION,
+        buffer_length: u32,
+        returned_length: *mut u32,
+        process: winapi::um::winnt::HANDLE,
+        flags: u32,
+    ) -> i32;
+    fn SetThreadSelectedCpuSets(
+        thread: winapi::um::winnt::HANDLE,
+        cpu_set_ids: *const u32,
+        cpu_set_id_count: u32,
+    ) -> i32;
+    fn GetThreadSelectedCpuSets(
+        thread: winapi::um::winnt::HANDLE,
+        cpu_set_ids: *mut u32,
+        cpu_set_id_array_count: u32,
+        required_id_count: *mut u32,
+    ) -> i32;
+}
+
+/// Returns the CPU Sets of the system.
+///
+/// Based on `GetSystemCpuSetInformation`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::affinity::*;
+///
+/// let cpu_sets = system_cpu_sets().unwrap();
+/// assert!(cpu_sets.iter().any(|cpu_set| cpu_set.cpu == 0));
+/// ```
+#[cfg(windows)]
+pub fn system_cpu_sets() -> Result<Vec<SystemCpuSet>, Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::winnt::{CpuSetInformation, SYSTEM_CPU_SET_INFORMATION};
+
+    let buffer = unsafe {
+        let mut length = 0;
+        GetSystemCpuSetInformation(
+            std::ptr::null_mut(),
+            0,
+            &mut length,
+            std::ptr::null_mut(),
+            0,
+        );
+        let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+        if GetSystemCpuSetInformation(
+            buffer.as_mut_ptr() as *mut SYSTEM_CPU_SET_INFORMATION,
+            length,
+            &mut length,
+            std::ptr::null_mut(),
+            0,
+        ) == 0
+        {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+        buffer
+    };
+
+    let mut cpu_sets = Vec::new();
+    let mut offset = 0;
+    let length = buffer.len() * 8;
+    while offset < length {
+        let info = unsafe {
+            &*((buffer.as_ptr() as *const u8).add(offset) as *const SYSTEM_CPU_SET_INFORMATION)
+        };
+        if info.Size == 0 {
+            break;
+        }
+        if info.Type == CpuSetInformation {
+            let cpu_set = &info.CpuSet;
+            cpu_sets.push(SystemCpuSet {
+                id: cpu_set.Id,
+                cpu: cpu_set.Group as usize * BITS + cpu_set.LogicalProcessorIndex as usize,
+                core_index: cpu_set.CoreIndex,
+                last_level_cache_index: cpu_set.LastLevelCacheIndex,
+                numa_node_index: cpu_set.NumaNodeIndex,
+                efficiency_class: cpu_set.EfficiencyClass,
+                parked: cpu_set.Parked() != 0,
+                allocated: cpu_set.Allocated() != 0,
+            });
+        }
+        offset += info.Size as usize;
+    }
+    Ok(cpu_sets)
+}
+
+/// Selects the CPU Sets the thread prefers to run on. Unlike the affinity, this is a
+/// soft preference the system may override under pressure. An empty list clears the
+/// selection.
+///
+/// Based on `SetThreadSelectedCpuSets`.
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+#[cfg(windows)]
+pub unsafe fn set_thread_selected_cpu_sets(
+    native: ThreadId,
+    ids: &[CpuSetId],
+) -> Result<(), Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+
+    unsafe {
+        if SetThreadSelectedCpuSets(native, ids.as_ptr(), ids.len() as u32) != 0 {
+            Ok(())
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Returns the CPU Sets selected for the thread, empty if there is no selection.
+///
+/// Based on `GetThreadSelectedCpuSets`.
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+#[cfg(windows)]
+pub unsafe fn get_thread_selected_cpu_sets(native: ThreadId) -> Result<Vec<CpuSetId>, Error> {
+    use winapi::shared::winerror::ERROR_INSUFFICIENT_BUFFER;
+    use winapi::um::errhandlingapi::GetLastError;
+
+    let mut ids = Vec::new();
+    loop {
+        let mut count = 0;
+        unsafe {
+            if GetThreadSelectedCpuSets(native, ids.as_mut_ptr(), ids.len() as u32, &mut count) != 0
+            {
+                ids.truncate(count as usize);
+                return Ok(ids);
+            }
+            match GetLastError() {
+                ERROR_INSUFFICIENT_BUFFER => ids.resize(count as usize, 0),
+                e => return Err(Error::OS(e as i32)),
+            }
+        }
+    }
+}
+
+/// Makes the thread prefer to run on the CPUs, by selecting their CPU Sets. This is the
+/// soft alternative to [`set_thread_affinity`]. An empty set clears the preference.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::affinity::*;
+///
+/// unsafe {
+///     assert!(set_thread_preferred_cpus(thread_native_id(), &CpuSet::single(0)).is_ok());
+///     assert_eq!(get_thread_preferred_cpus(thread_native_id()).unwrap(), CpuSet::single(0));
+///     assert!(set_thread_preferred_cpus(thread_native_id(), &CpuSet::new()).is_ok());
+/// }
+/// ```
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+#[cfg(windows)]
+pub unsafe fn set_thread_preferred_cpus(native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+    let cpu_sets = system_cpu_sets()?;
+    let ids = cpus
+        .iter()
+        .map(|cpu| {
+            cpu_sets
+                .iter()
+                .find(|cpu_set| cpu_set.cpu == cpu)
+                .map(|cpu_set| cpu_set.id)
+                .ok_or(Error::Ffi("The CPU has no CPU Set"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    set_thread_selected_cpu_sets(native, &ids)
+}
+
+/// Returns the CPUs the thread prefers to run on, empty if there is no preference.
+/// For more info read [`set_thread_preferred_cpus`].
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+#[cfg(windows)]
+pub unsafe fn get_thread_preferred_cpus(native: ThreadId) -> Result<CpuSet, Error> {
+    let cpu_sets = system_cpu_sets()?;
+    Ok(get_thread_selected_cpu_sets(native)?
+        .into_iter()
+        .filter_map(|id| cpu_sets.iter().find(|cpu_set| cpu_set.id == id))
+        .map(|cpu_set| cpu_set.cpu)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_set_test() {
+        let mut cpus: CpuSet = vec![1, 65, 200].into_iter().collect();
+
+        assert_eq!(cpus.len(), 3);
+        assert!(cpus.contains(65));
+        assert!(!cpus.contains(64));
+        assert!(!cpus.insert(65));
+        assert!(!cpus.insert(8192));
+        assert!(cpus.try_insert(8192).is_err());
+        assert_eq!(CpuSet::single(8192), CpuSet::new());
+        assert!(cpus.remove(200));
+        assert!(!cpus.remove(200));
+        assert_eq!(cpus, vec![65, 1].into_iter().collect());
+        assert_eq!(cpus.iter().collect::<Vec<_>>(), vec![1, 65]);
+
+        let other: CpuSet = vec![0, 1, 130].into_iter().collect();
+        assert_eq!(cpus.intersection(&other), CpuSet::single(1));
+        assert!(CpuSet::single(1).is_subset(&cpus));
+        assert!(!other.is_subset(&cpus));
+        assert!(CpuSet::new().is_subset(&cpus));
+    }
+
+    #[test]
+    fn cpu_list_test() {
+        assert_eq!(CpuSet::from_list_str("").unwrap(), CpuSet::new());
+        assert_eq!(
+            CpuSet::from_list_str(" 5, 1-2 ,0").unwrap().to_string(),
+            "0-2,5"
+        );
+        assert_eq!("7".parse::<CpuSet>().unwrap(), CpuSet::single(7));
+        assert!(CpuSet::from_list_str("1-").is_err());
+        assert!(CpuSet::from_list_str("a").is_err());
+        assert!(CpuSet::from_list_str("0-99999999999").is_err());
+        assert!(CpuSet::from_list_str("8191").is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn cpu_set_deserialize_test() {
+        use serde::de::value::{Error, SeqDeserializer};
+        use serde::Deserialize;
+
+        let cpus = SeqDeserializer::<_, Error>::new(vec![0usize, 8191].into_iter());
+        assert_eq!(
+            CpuSet::deserialize(cpus).unwrap(),
+            vec![0, 8191].into_iter().collect()
+        );
+        let cpus = SeqDeserializer::<_, Error>::new(vec![0usize, 8192].into_iter());
+        assert!(CpuSet::deserialize(cpus).is_err());
+    }
+
+    #[test]
+    fn affinity_roundtrip_test() {
+        let native = crate::thread_native_id();
+        let previous = get_thread_affinity(native).unwrap();
+        let first = previous.iter().next().unwrap();
+
+        assert!(set_thread_affinity(native, &CpuSet::single(first)).is_ok());
+        assert_eq!(get_thread_affinity(native).unwrap(), CpuSet::single(first));
+        assert!(set_thread_affinity(native, &previous).is_ok());
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        windows
+    ))]
+    #[test]
+    fn validate_affinity_test() {
+        let disallowed = CpuSet::single(MAX_CPUS - 1);
+        assert!(matches!(
+            validate_affinity(&disallowed),
+            Err(Error::Priority(_))
+        ));
+        assert!(matches!(
+            set_thread_affinity_clamped(crate::thread_native_id(), &disallowed),
+            Err(Error::Priority(_))
+        ));
+    }
+}
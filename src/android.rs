@@ -0,0 +1,121 @@
+//! This module defines the android thread priority levels.
+//!
+//! Android doesn't make use of the realtime schedule policies for the
+//! application threads, instead it assigns them well-known nice values
+//! (see `android.os.Process.setThreadPriority`). This module maps the
+//! crate's priorities onto those levels, so that Rust code running in
+//! an Android application behaves consistently with the Java one.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use thread_priority::*;
+//! use thread_priority::android::*;
+//!
+//! assert!(set_current_thread_android_priority(AndroidThreadPriority::Audio).is_ok());
+//! assert_eq!(current_thread_android_priority().unwrap(), AndroidThreadPriority::Audio as i32);
+//! ```
+
+use std::convert::TryFrom;
+
+use crate::unix::{thread_native_tid, ThreadTid};
+use crate::{Error, ThreadPriority, ThreadPriorityValue};
+
+/// The Android thread priority levels, as defined in `android.os.Process`.
+/// The values are the nice values the levels are applied with.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum AndroidThreadPriority {
+    /// Most important audio threads. Applications can not normally change to this priority.
+    UrgentAudio = -19,
+    /// Audio threads. Applications can not normally change to this priority.
+    Audio = -16,
+    /// Video threads. Applications can not normally change to this priority.
+    Video = -10,
+    /// Most important display threads, for compositing the screen and retrieving
+    /// input events. Applications can not normally change to this priority.
+    UrgentDisplay = -8,
+    /// The main UI threads of the system. Applications can not normally change
+    /// to this priority.
+    Display = -4,
+    /// Threads currently running a user interface that the user is interacting with.
+    /// Applications can not normally change to this priority.
+    Foreground = -2,
+    /// The standard priority of application threads.
+    Default = 0,
+    /// Background threads. Threads at this level get a slightly lower chance to run.
+    Background = 10,
+    /// The lowest available priority, for threads that only run when nothing else is.
+    Lowest = 19,
+}
+
+impl TryFrom<ThreadPriority> for AndroidThreadPriority {
+    type Error = Error;
+
+    fn try_from(priority: ThreadPriority) -> Result<Self, Self::Error> {
+        Ok(match priority {
+            ThreadPriority::Min => AndroidThreadPriority::Lowest,
+            ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => match p {
+                0..=9 => AndroidThreadPriority::Lowest,
+                10..=29 => AndroidThreadPriority::Background,
+                30..=49 => AndroidThreadPriority::Default,
+                50..=59 => AndroidThreadPriority::Foreground,
+                60..=69 => AndroidThreadPriority::Display,
+                70..=79 => AndroidThreadPriority::UrgentDisplay,
+                80..=89 => AndroidThreadPriority::Video,
+                90..=97 => AndroidThreadPriority::Audio,
+                98..=99 => AndroidThreadPriority::UrgentAudio,
+                _ => return Err(Error::Priority("The value is out of range [0; 99].")),
+            },
+            ThreadPriority::Max => AndroidThreadPriority::UrgentAudio,
+            _ => {
+                return Err(Error::Priority(
+                    "The priority can't be mapped onto an android priority level.",
+                ))
+            }
+        })
+    }
+}
+
+/// Sets the android priority level of the thread with the given kernel thread id (tid).
+///
+/// * May require privileges
+pub fn set_thread_android_priority(
+    tid: ThreadTid,
+    priority: AndroidThreadPriority,
+) -> Result<(), Error> {
+    crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::Setpriority)?;
+    unsafe {
+        match libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, priority as i32) {
+            0 => Ok(()),
+            _ => Err(Error::OS(*libc::__errno())),
+        }
+    }
+}
+
+/// Sets the android priority level of the current thread.
+///
+/// * May require privileges
+pub fn set_current_thread_android_priority(priority: AndroidThreadPriority) -> Result<(), Error> {
+    set_thread_android_priority(thread_native_tid(), priority)
+}
+
+/// Returns the nice value of the thread with the given kernel thread id (tid). It is
+/// one of the [`AndroidThreadPriority`] values, unless the nice value has been set
+/// by other means.
+pub fn thread_android_priority(tid: ThreadTid) -> Result<i32, Error> {
+    unsafe {
+        *libc::__errno() = 0;
+        let nice = libc::getpriority(libc::PRIO_PROCESS, tid as libc::id_t);
+        match *libc::__errno() {
+            0 => Ok(nice),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+/// Returns the nice value of the current thread.
+/// For more info read [`thread_android_priority`].
+pub fn current_thread_android_priority() -> Result<i32, Error> {
+    thread_android_priority(thread_native_tid())
+}
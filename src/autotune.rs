@@ -0,0 +1,332 @@
+//! This module defines the automatic tuning of the priorities.
+//!
+//! Boosting the latency-critical threads all the time takes the CPU from the
+//! others even when there's enough for everybody. An [`AutoTuner`] follows
+//! how long each registered thread waits in the run queue, and moves it up a
+//! tier of properties when the wait grows over a threshold, and back down
+//! once it has dropped under another one, so that the threads are only
+//! boosted under contention.
+//!
+//! The run queue delay is read from `/proc/self/task/<tid>/schedstat`, so the
+//! tuning is only supported on Linux and Android.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::autotune::*;
+//! use thread_priority::properties::ThreadProperties;
+//!
+//! # #[cfg(any(target_os = "linux", target_os = "android"))] {
+//! let tuner = AutoTuner::start(AutoTuneConfig::new(vec![
+//!     ThreadProperties::default(),
+//!     ThreadProperties::default().nice(-5),
+//! ]))
+//! .unwrap();
+//! let guard = tuner.register_current_thread();
+//! // The latency-critical work.
+//! drop(guard);
+//! # }
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::properties::ThreadProperties;
+use crate::Error;
+
+/// The configuration of an [`AutoTuner`].
+#[derive(Clone, Debug)]
+pub struct AutoTuneConfig {
+    tiers: Vec<ThreadProperties>,
+    interval: Duration,
+    raise_above: f64,
+    lower_below: f64,
+}
+
+impl AutoTuneConfig {
+    /// Creates the configuration with the tiers of properties, from the lowest to the
+    /// highest. The threads start in the first one, every `100` milliseconds they're
+    /// raised a tier when they've waited more than `10%` of the time, and lowered one
+    /// when they've waited less than `2%` of it.
+    pub fn new(tiers: Vec<ThreadProperties>) -> Self {
+        AutoTuneConfig {
+            tiers,
+            interval: Duration::from_millis(100),
+            raise_above: 0.1,
+            lower_below: 0.02,
+        }
+    }
+
+    /// The interval between the samples.
+    pub fn interval(mut self, value: Duration) -> Self {
+        self.interval = value;
+        self
+    }
+
+    /// The share of the time spent in the run queue, from `0.0` to `1.0`, above which a
+    /// thread is raised to the next tier.
+    pub fn raise_above(mut self, value: f64) -> Self {
+        self.raise_above = value;
+        self
+    }
+
+    /// The share of the time spent in the run queue, from `0.0` to `1.0`, below which a
+    /// thread is lowered to the previous tier.
+    pub fn lower_below(mut self, value: f64) -> Self {
+        self.lower_below = value;
+        self
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.tiers.is_empty() {
+            return Err(Error::Priority("There must be at least a tier."));
+        }
+        if self.interval.is_zero() {
+            return Err(Error::Priority("The interval must be positive."));
+        }
+        if !(0.0..=1.0).contains(&self.lower_below)
+            || !(0.0..=1.0).contains(&self.raise_above)
+            || self.lower_below > self.raise_above
+        {
+            return Err(Error::Priority(
+                "The thresholds must be ordered shares of the time.",
+            ));
+        }
+        Ok(())
+    }
+}
+
+struct Entry {
+    id: u64,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    tid: crate::ThreadTid,
+    tier: usize,
+    run_delay: Option<Duration>,
+}
+
+impl std::fmt::Debug for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entry")
+            .field("id", &self.id)
+            .field("tier", &self.tier)
+            .finish()
+    }
+}
+
+/// The controller thread, stopped when it is dropped.
+#[derive(Debug)]
+pub struct AutoTuner {
+    tiers: Arc<Vec<ThreadProperties>>,
+    entries: Arc<Mutex<Vec<Entry>>>,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+static NEXT_ENTRY_ID: AtomicU64 = AtomicU64::new(0);
+
+fn lock(entries: &Mutex<Vec<Entry>>) -> std::sync::MutexGuard<'_, Vec<Entry>> {
+    // The entries are always left consistent, a panic can't poison them.
+    entries.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl AutoTuner {
+    /// Starts the controller thread.
+    ///
+    /// * May require privileges to raise the threads.
+    pub fn start(mut config: AutoTuneConfig) -> Result<Self, Error> {
+        config.validate()?;
+        check_supported()?;
+        let tiers = Arc::new(std::mem::take(&mut config.tiers));
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let (stop, stopped) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("priority-autotune".to_owned())
+            .spawn({
+                let tiers = tiers.clone();
+                let entries = entries.clone();
+                move || run(&config, &tiers, &entries, &stopped)
+            })?;
+        Ok(AutoTuner {
+            tiers,
+            entries,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Registers the current thread, until the returned guard is dropped. The properties
+    /// of the first tier are applied to it; if they can't be, the error is logged and the
+    /// thread is tuned from its current state.
+    pub fn register_current_thread(&self) -> TuneGuard {
+        let id = NEXT_ENTRY_ID.fetch_add(1, Ordering::Relaxed);
+        if let Err(e) = self.tiers[0].apply_to_current() {
+            log::warn!("Couldn't apply the first tier to the thread: {:?}", e);
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let tid = crate::thread_native_tid();
+            lock(&self.entries).push(Entry {
+                id,
+                tid,
+                tier: 0,
+                run_delay: run_delay(tid).ok(),
+            });
+        }
+        TuneGuard {
+            id,
+            entries: self.entries.clone(),
+            _not_send: std::marker::PhantomData,
+        }
+    }
+}
+
+impl Drop for AutoTuner {
+    fn drop(&mut self) {
+        // The controller thread stops once the sender is dropped.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A registered thread, unregistered when the guard is dropped, staying in its current
+/// tier. The guard must be dropped on the thread it registers, so it can't be sent to
+/// another thread.
+#[derive(Debug)]
+pub struct TuneGuard {
+    id: u64,
+    entries: Arc<Mutex<Vec<Entry>>>,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl TuneGuard {
+    /// Returns the index of the current tier of the thread.
+    pub fn tier(&self) -> Option<usize> {
+        lock(&self.entries)
+            .iter()
+            .find(|entry| entry.id == self.id)
+            .map(|entry| entry.tier)
+    }
+}
+
+impl Drop for TuneGuard {
+    fn drop(&mut self) {
+        lock(&self.entries).retain(|entry| entry.id != self.id);
+    }
+}
+
+/// Returns the time the thread has spent waiting in the run queue.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn run_delay(tid: crate::ThreadTid) -> Result<Duration, Error> {
+    let schedstat = std::fs::read_to_string(format!("/proc/self/task/{}/schedstat", tid))?;
+    // The CPU time, the run queue delay and the number of time slices.
+    schedstat
+        .split_whitespace()
+        .nth(1)
+        .and_then(|delay| delay.parse().ok())
+        .map(Duration::from_nanos)
+        .ok_or(Error::Priority(
+            "The schedstat of the thread can't be parsed.",
+        ))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn check_supported() -> Result<(), Error> {
+    run_delay(crate::thread_native_tid()).map(|_| ())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn check_supported() -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "The priority auto-tuning is not supported on this platform",
+    ))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn apply_tier(properties: &ThreadProperties, entry: &Entry) -> Result<(), Error> {
+    properties.apply_to_tid(entry.tid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn apply_tier(_properties: &ThreadProperties, _entry: &Entry) -> Result<(), Error> {
+    check_supported()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn sample(entry: &Entry) -> Result<Duration, Error> {
+    run_delay(entry.tid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn sample(_entry: &Entry) -> Result<Duration, Error> {
+    check_supported().map(|()| Duration::ZERO)
+}
+
+fn run(
+    config: &AutoTuneConfig,
+    tiers: &[ThreadProperties],
+    entries: &Mutex<Vec<Entry>>,
+    stopped: &mpsc::Receiver<()>,
+) {
+    let mut sampled = Instant::now();
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(config.interval) {
+        let now = Instant::now();
+        let elapsed = (now - sampled).as_secs_f64();
+        sampled = now;
+        for entry in lock(entries).iter_mut() {
+            let run_delay = match sample(entry) {
+                Ok(run_delay) => run_delay,
+                Err(_) => continue,
+            };
+            let waited = entry.run_delay.map_or(Duration::ZERO, |previous| {
+                run_delay.saturating_sub(previous)
+            });
+            entry.run_delay = Some(run_delay);
+            let pressure = waited.as_secs_f64() / elapsed;
+            let tier = if pressure > config.raise_above && entry.tier + 1 < tiers.len() {
+                entry.tier + 1
+            } else if pressure < config.lower_below && entry.tier > 0 {
+                entry.tier - 1
+            } else {
+                continue;
+            };
+            match apply_tier(&tiers[tier], entry) {
+                Ok(()) => {
+                    log::debug!(
+                        "Moved a thread from the tier {} to {} at the pressure {:.3}",
+                        entry.tier,
+                        tier,
+                        pressure
+                    );
+                    entry.tier = tier;
+                }
+                Err(e) => log::warn!("Couldn't move a thread to the tier {}: {:?}", tier, e),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_current_thread_test() {
+        assert!(AutoTuner::start(AutoTuneConfig::new(Vec::new())).is_err());
+        let tuner = AutoTuner::start(
+            AutoTuneConfig::new(vec![ThreadProperties::default()])
+                .interval(Duration::from_millis(10)),
+        )
+        .unwrap();
+        let thread = std::thread::spawn(move || {
+            let guard = tuner.register_current_thread();
+            std::thread::sleep(Duration::from_millis(30));
+            assert!(run_delay(crate::thread_native_tid()).is_ok());
+            assert_eq!(guard.tier(), Some(0));
+        });
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,354 @@
+//! This module defines the scheduler backends: the trait of the scheduling
+//! operations, implemented by the operating system and by a mock.
+//!
+//! The code written against [`SchedulerBackend`] runs with [`OsScheduler`] in
+//! production and with [`MockScheduler`] in its unit tests, which need neither
+//! the privileges nor the side effects of the real calls. The mock keeps the
+//! state of every thread in memory, checks the values like the system would,
+//! and can be told to deny the realtime policies or to fail the next call.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::backend::*;
+//!
+//! fn lower_priority<B: SchedulerBackend>(backend: &B, native: ThreadId) -> Result<(), Error> {
+//!     backend.set_thread_priority(native, ThreadPriority::Min)
+//! }
+//!
+//! let mock = MockScheduler::new();
+//! lower_priority(&mock, thread_native_id()).unwrap();
+//! assert_eq!(mock.thread(thread_native_id()).priority, ThreadPriority::Min);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::affinity::CpuSet;
+use crate::io_priority::IoPriority;
+use crate::{Error, ThreadId, ThreadPriority, ThreadPriorityValue};
+
+/// The scheduling operations on the threads.
+pub trait SchedulerBackend {
+    /// Sets the priority of the thread, with the normal policy on unix.
+    /// For more info read [`crate::set_current_thread_priority`].
+    fn set_thread_priority(&self, native: ThreadId, priority: ThreadPriority) -> Result<(), Error>;
+
+    /// Returns the priority of the thread.
+    /// For more info read [`crate::get_thread_priority`].
+    fn thread_priority(&self, native: ThreadId) -> Result<ThreadPriority, Error>;
+
+    /// Sets the priority and the policy of the thread.
+    /// For more info read [`crate::set_thread_priority_and_policy`].
+    #[cfg(unix)]
+    fn set_thread_priority_and_policy(
+        &self,
+        native: ThreadId,
+        priority: ThreadPriority,
+        policy: crate::ThreadSchedulePolicy,
+    ) -> Result<(), Error>;
+
+    /// Returns the policy of the thread.
+    /// For more info read [`crate::thread_schedule_policy_for`].
+    #[cfg(unix)]
+    fn thread_schedule_policy(
+        &self,
+        native: ThreadId,
+    ) -> Result<crate::ThreadSchedulePolicy, Error>;
+
+    /// Sets the affinity of the thread.
+    /// For more info read [`crate::affinity::set_thread_affinity`].
+    fn set_thread_affinity(&self, native: ThreadId, cpus: &CpuSet) -> Result<(), Error>;
+
+    /// Returns the affinity of the thread.
+    /// For more info read [`crate::affinity::get_thread_affinity`].
+    fn thread_affinity(&self, native: ThreadId) -> Result<CpuSet, Error>;
+
+    /// Sets the I/O priority of the thread.
+    /// For more info read [`crate::io_priority::set_thread_io_priority`].
+    fn set_thread_io_priority(&self, native: ThreadId, priority: IoPriority) -> Result<(), Error>;
+
+    /// Returns the I/O priority of the thread.
+    /// For more info read [`crate::io_priority::thread_io_priority`].
+    fn thread_io_priority(&self, native: ThreadId) -> Result<IoPriority, Error>;
+}
+
+/// The backend of the operating system, forwarding to the crate's functions. The crate
+/// itself doesn't go through the backends, they are to be injected into the callers'
+/// code, so that its tests can swap this one for the [`MockScheduler`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct OsScheduler;
+
+impl SchedulerBackend for OsScheduler {
+    fn set_thread_priority(&self, native: ThreadId, priority: ThreadPriority) -> Result<(), Error> {
+        #[cfg(unix)]
+        return crate::set_thread_priority_and_policy(
+            native,
+            priority,
+            crate::ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Normal),
+        );
+        #[cfg(not(unix))]
+        crate::set_thread_priority(native, priority)
+    }
+
+    fn thread_priority(&self, native: ThreadId) -> Result<ThreadPriority, Error> {
+        crate::get_thread_priority(native)
+    }
+
+    #[cfg(unix)]
+    fn set_thread_priority_and_policy(
+        &self,
+        native: ThreadId,
+        priority: ThreadPriority,
+        policy: crate::ThreadSchedulePolicy,
+    ) -> Result<(), Error> {
+        crate::set_thread_priority_and_policy(native, priority, policy)
+    }
+
+    #[cfg(unix)]
+    fn thread_schedule_policy(
+        &self,
+        native: ThreadId,
+    ) -> Result<crate::ThreadSchedulePolicy, Error> {
+        crate::thread_schedule_policy_for(native)
+    }
+
+    fn set_thread_affinity(&self, native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+        crate::affinity::set_thread_affinity(native, cpus)
+    }
+
+    fn thread_affinity(&self, native: ThreadId) -> Result<CpuSet, Error> {
+        crate::affinity::get_thread_affinity(native)
+    }
+
+    fn set_thread_io_priority(&self, native: ThreadId, priority: IoPriority) -> Result<(), Error> {
+        crate::io_priority::set_thread_io_priority(native, priority)
+    }
+
+    fn thread_io_priority(&self, native: ThreadId) -> Result<IoPriority, Error> {
+        crate::io_priority::thread_io_priority(native)
+    }
+}
+
+/// The state of a thread in the [`MockScheduler`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MockThread {
+    /// The priority, `0` at first.
+    pub priority: ThreadPriority,
+    /// The policy, the normal one at first.
+    #[cfg(unix)]
+    pub policy: crate::ThreadSchedulePolicy,
+    /// The affinity, all the CPUs of the mock at first.
+    pub affinity: CpuSet,
+    /// The I/O priority, the normal one at first.
+    pub io_priority: IoPriority,
+}
+
+/// An in-memory backend for the tests.
+///
+/// The threads start with the default state, see [`MockThread`]. The values are checked
+/// like the system would, and the invalid ones fail with `EINVAL`, or
+/// `ERROR_INVALID_PARAMETER` on Windows; the realtime policies fail with `EPERM` when
+/// they're denied.
+#[derive(Debug)]
+pub struct MockScheduler {
+    cpus: usize,
+    realtime_allowed: bool,
+    threads: Mutex<HashMap<usize, MockThread>>,
+    failure: Mutex<Option<Error>>,
+}
+
+impl Default for MockScheduler {
+    fn default() -> Self {
+        MockScheduler {
+            cpus: 4,
+            realtime_allowed: true,
+            threads: Mutex::new(HashMap::new()),
+            failure: Mutex::new(None),
+        }
+    }
+}
+
+#[cfg(unix)]
+const INVALID: Error = Error::OS(libc::EINVAL);
+#[cfg(windows)]
+const INVALID: Error = Error::OS(winapi::shared::winerror::ERROR_INVALID_PARAMETER as i32);
+#[cfg(not(any(unix, windows)))]
+const INVALID: Error = Error::Priority("The value is not valid.");
+
+impl MockScheduler {
+    /// Creates the mock, with 4 CPUs and the realtime policies allowed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of CPUs of the mock, the affinities must be subsets of them.
+    pub fn cpus(mut self, cpus: usize) -> Self {
+        self.cpus = cpus;
+        self
+    }
+
+    /// Whether the realtime policies are allowed, as they would be with the privileges.
+    pub fn realtime_allowed(mut self, allowed: bool) -> Self {
+        self.realtime_allowed = allowed;
+        self
+    }
+
+    /// Makes the next call fail with the error, without any change.
+    pub fn fail_next(&self, error: Error) {
+        *self.failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(error);
+    }
+
+    /// Returns the state of the thread.
+    pub fn thread(&self, native: ThreadId) -> MockThread {
+        self.with_thread(native, |thread| thread.clone())
+    }
+
+    fn default_thread(&self) -> MockThread {
+        MockThread {
+            priority: ThreadPriority::Crossplatform(ThreadPriorityValue(0)),
+            #[cfg(unix)]
+            policy: crate::ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Normal),
+            affinity: (0..self.cpus).collect(),
+            io_priority: IoPriority::Normal,
+        }
+    }
+
+    fn with_thread<T, F: FnOnce(&mut MockThread) -> T>(&self, native: ThreadId, f: F) -> T {
+        let mut threads = self.threads.lock().unwrap_or_else(|e| e.into_inner());
+        // The handles are only used as the keys.
+        #[allow(clippy::unnecessary_cast)]
+        let key = native as usize;
+        f(threads.entry(key).or_insert_with(|| self.default_thread()))
+    }
+
+    fn call<T, F: FnOnce(&mut MockThread) -> Result<T, Error>>(
+        &self,
+        native: ThreadId,
+        f: F,
+    ) -> Result<T, Error> {
+        if let Some(error) = self
+            .failure
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        {
+            return Err(error);
+        }
+        self.with_thread(native, f)
+    }
+}
+
+impl SchedulerBackend for MockScheduler {
+    fn set_thread_priority(&self, native: ThreadId, priority: ThreadPriority) -> Result<(), Error> {
+        #[cfg(unix)]
+        return self.set_thread_priority_and_policy(
+            native,
+            priority,
+            crate::ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Normal),
+        );
+        #[cfg(windows)]
+        <crate::WinAPIThreadPriority as std::convert::TryFrom<ThreadPriority>>::try_from(priority)?;
+        #[cfg(not(unix))]
+        self.call(native, |thread| {
+            thread.priority = priority;
+            Ok(())
+        })
+    }
+
+    fn thread_priority(&self, native: ThreadId) -> Result<ThreadPriority, Error> {
+        self.call(native, |thread| Ok(thread.priority))
+    }
+
+    #[cfg(unix)]
+    fn set_thread_priority_and_policy(
+        &self,
+        native: ThreadId,
+        priority: ThreadPriority,
+        policy: crate::ThreadSchedulePolicy,
+    ) -> Result<(), Error> {
+        match (priority, policy) {
+            #[cfg(target_os = "linux")]
+            (
+                ThreadPriority::Deadline(..),
+                crate::ThreadSchedulePolicy::Realtime(
+                    crate::RealtimeThreadSchedulePolicy::Deadline,
+                ),
+            ) => {}
+            _ => {
+                priority.to_posix(policy).map_err(|_| INVALID)?;
+            }
+        }
+        if let crate::ThreadSchedulePolicy::Realtime(_) = policy {
+            if !self.realtime_allowed {
+                return Err(Error::OS(libc::EPERM));
+            }
+        }
+        self.call(native, |thread| {
+            thread.priority = priority;
+            thread.policy = policy;
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    fn thread_schedule_policy(
+        &self,
+        native: ThreadId,
+    ) -> Result<crate::ThreadSchedulePolicy, Error> {
+        self.call(native, |thread| Ok(thread.policy))
+    }
+
+    fn set_thread_affinity(&self, native: ThreadId, cpus: &CpuSet) -> Result<(), Error> {
+        if cpus.is_empty() || cpus.iter().any(|cpu| cpu >= self.cpus) {
+            return Err(INVALID);
+        }
+        self.call(native, |thread| {
+            thread.affinity = cpus.clone();
+            Ok(())
+        })
+    }
+
+    fn thread_affinity(&self, native: ThreadId) -> Result<CpuSet, Error> {
+        self.call(native, |thread| Ok(thread.affinity.clone()))
+    }
+
+    fn set_thread_io_priority(&self, native: ThreadId, priority: IoPriority) -> Result<(), Error> {
+        self.call(native, |thread| {
+            thread.io_priority = priority;
+            Ok(())
+        })
+    }
+
+    fn thread_io_priority(&self, native: ThreadId) -> Result<IoPriority, Error> {
+        self.call(native, |thread| Ok(thread.io_priority))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_scheduler_test() {
+        let mock = MockScheduler::new().cpus(2).realtime_allowed(false);
+        let native = crate::thread_native_id();
+        assert!(mock
+            .set_thread_affinity(native, &CpuSet::single(2))
+            .is_err());
+        assert!(mock.set_thread_affinity(native, &CpuSet::single(1)).is_ok());
+        assert_eq!(mock.thread_affinity(native).unwrap(), CpuSet::single(1));
+        #[cfg(unix)]
+        assert!(mock
+            .set_thread_priority_and_policy(
+                native,
+                ThreadPriority::Max,
+                crate::ThreadSchedulePolicy::Realtime(crate::RealtimeThreadSchedulePolicy::Fifo),
+            )
+            .is_err());
+        mock.fail_next(Error::Priority("Injected."));
+        assert!(mock.thread_io_priority(native).is_err());
+        assert_eq!(mock.thread_io_priority(native).unwrap(), IoPriority::Normal);
+    }
+}
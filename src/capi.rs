@@ -0,0 +1,407 @@
+//! This module defines the C API of the crate.
+//!
+//! The audio engines and the other C and C++ programs embedding Rust code can
+//! set the scheduling of their threads through the same cross-platform logic.
+//! The functions are `extern "C"`, prefixed with `thread_priority_`, and take
+//! and return `#[repr(C)]` types only, so that a header can be generated with
+//! `cbindgen`. They all act on the calling thread and return `0` on success, a
+//! positive OS error code, the `errno` value on unix and the `GetLastError`
+//! value on Windows, or one of the negative `THREAD_PRIORITY_ERROR_*` codes.
+//! The functions which don't apply to the platform are still exported, and
+//! return [`THREAD_PRIORITY_ERROR_UNSUPPORTED`], so the header is the same for
+//! all of them.
+//!
+//! Enabled by the `capi` feature.
+//!
+//! # Usage
+//!
+//! ```c
+//! CThreadPriority priority = { CThreadPriorityKind_Max, 0 };
+//! if (thread_priority_set_current(priority) != THREAD_PRIORITY_OK) {
+//!     // The thread keeps its priority.
+//! }
+//! ```
+
+use std::convert::TryFrom;
+use std::os::raw::c_int;
+
+use crate::affinity::CpuSet;
+use crate::{Error, ThreadPriority, ThreadPriorityOsValue, ThreadPriorityValue};
+
+/// The success.
+pub const THREAD_PRIORITY_OK: c_int = 0;
+/// The priority is invalid, see [`Error::Priority`].
+pub const THREAD_PRIORITY_ERROR_PRIORITY: c_int = -1;
+/// An FFI failure, or a null pointer, see [`Error::Ffi`].
+pub const THREAD_PRIORITY_ERROR_FFI: c_int = -2;
+/// The operation isn't supported on the platform, see [`Error::Unsupported`].
+pub const THREAD_PRIORITY_ERROR_UNSUPPORTED: c_int = -3;
+
+/// The kind of a [`CThreadPriority`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CThreadPriorityKind {
+    /// The minimum priority, [`ThreadPriority::Min`].
+    Min = 0,
+    /// The maximum priority, [`ThreadPriority::Max`].
+    Max = 1,
+    /// A cross-platform value from `0` to `99`, [`ThreadPriority::Crossplatform`].
+    Crossplatform = 2,
+    /// A native value, [`ThreadPriority::Os`].
+    Os = 3,
+}
+
+impl TryFrom<c_int> for CThreadPriorityKind {
+    type Error = Error;
+
+    fn try_from(kind: c_int) -> Result<Self, Self::Error> {
+        match kind {
+            0 => Ok(CThreadPriorityKind::Min),
+            1 => Ok(CThreadPriorityKind::Max),
+            2 => Ok(CThreadPriorityKind::Crossplatform),
+            3 => Ok(CThreadPriorityKind::Os),
+            _ => Err(Error::Priority("The priority kind is unknown")),
+        }
+    }
+}
+
+/// A priority, the value being ignored for the minimum and the maximum ones.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct CThreadPriority {
+    /// The kind, one of the [`CThreadPriorityKind`] values. It's an integer, as
+    /// the C code may pass any value, which is rejected unless it's a known kind.
+    pub kind: c_int,
+    /// The value.
+    pub value: i32,
+}
+
+/// A unix schedule policy.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CThreadSchedulePolicy {
+    /// `SCHED_OTHER`.
+    Other = 0,
+    /// `SCHED_IDLE`.
+    Idle = 1,
+    /// `SCHED_BATCH`.
+    Batch = 2,
+    /// `SCHED_FIFO`.
+    Fifo = 3,
+    /// `SCHED_RR`.
+    RoundRobin = 4,
+}
+
+impl TryFrom<c_int> for CThreadSchedulePolicy {
+    type Error = Error;
+
+    fn try_from(policy: c_int) -> Result<Self, Self::Error> {
+        match policy {
+            0 => Ok(CThreadSchedulePolicy::Other),
+            1 => Ok(CThreadSchedulePolicy::Idle),
+            2 => Ok(CThreadSchedulePolicy::Batch),
+            3 => Ok(CThreadSchedulePolicy::Fifo),
+            4 => Ok(CThreadSchedulePolicy::RoundRobin),
+            _ => Err(Error::Priority("The schedule policy is unknown")),
+        }
+    }
+}
+
+impl TryFrom<CThreadPriority> for ThreadPriority {
+    type Error = Error;
+
+    fn try_from(priority: CThreadPriority) -> Result<Self, Self::Error> {
+        Ok(match CThreadPriorityKind::try_from(priority.kind)? {
+            CThreadPriorityKind::Min => ThreadPriority::Min,
+            CThreadPriorityKind::Max => ThreadPriority::Max,
+            CThreadPriorityKind::Crossplatform => ThreadPriority::Crossplatform(
+                u8::try_from(priority.value)
+                    .map_err(|_| Error::Priority("The value is not from 0 to 99"))
+                    .and_then(|value| {
+                        ThreadPriorityValue::try_from(value).map_err(Error::Priority)
+                    })?,
+            ),
+            CThreadPriorityKind::Os => ThreadPriority::Os(ThreadPriorityOsValue(
+                u32::try_from(priority.value)
+                    .map_err(|_| Error::Priority("The OS value is negative"))?,
+            )),
+        })
+    }
+}
+
+impl TryFrom<ThreadPriority> for CThreadPriority {
+    type Error = Error;
+
+    fn try_from(priority: ThreadPriority) -> Result<Self, Self::Error> {
+        let (kind, value) = match priority {
+            ThreadPriority::Min => (CThreadPriorityKind::Min, 0),
+            ThreadPriority::Max => (CThreadPriorityKind::Max, 0),
+            ThreadPriority::Crossplatform(ThreadPriorityValue(value)) => {
+                (CThreadPriorityKind::Crossplatform, value as i32)
+            }
+            ThreadPriority::Os(ThreadPriorityOsValue(value)) => (
+                CThreadPriorityKind::Os,
+                i32::try_from(value).map_err(|_| Error::Priority("The OS value is too large"))?,
+            ),
+            #[cfg(target_os = "linux")]
+            ThreadPriority::Deadline(..) => {
+                return Err(Error::Unsupported(
+                    "The deadline priorities are not supported by the C API",
+                ))
+            }
+        };
+        Ok(CThreadPriority {
+            kind: kind as c_int,
+            value,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl From<CThreadSchedulePolicy> for crate::ThreadSchedulePolicy {
+    fn from(policy: CThreadSchedulePolicy) -> Self {
+        use crate::{
+            NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ThreadSchedulePolicy,
+        };
+
+        match policy {
+            CThreadSchedulePolicy::Other => {
+                ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Other)
+            }
+            CThreadSchedulePolicy::Idle => {
+                ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle)
+            }
+            CThreadSchedulePolicy::Batch => {
+                ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Batch)
+            }
+            CThreadSchedulePolicy::Fifo => {
+                ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo)
+            }
+            CThreadSchedulePolicy::RoundRobin => {
+                ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::RoundRobin)
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl TryFrom<crate::ThreadSchedulePolicy> for CThreadSchedulePolicy {
+    type Error = Error;
+
+    fn try_from(policy: crate::ThreadSchedulePolicy) -> Result<Self, Self::Error> {
+        use crate::{
+            NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ThreadSchedulePolicy,
+        };
+
+        match policy {
+            ThreadSchedulePolicy::Normal(
+                NormalThreadSchedulePolicy::Other | NormalThreadSchedulePolicy::Normal,
+            ) => Ok(CThreadSchedulePolicy::Other),
+            ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle) => {
+                Ok(CThreadSchedulePolicy::Idle)
+            }
+            ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Batch) => {
+                Ok(CThreadSchedulePolicy::Batch)
+            }
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo) => {
+                Ok(CThreadSchedulePolicy::Fifo)
+            }
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::RoundRobin) => {
+                Ok(CThreadSchedulePolicy::RoundRobin)
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::Unsupported(
+                "The policy is not supported by the C API",
+            )),
+        }
+    }
+}
+
+/// Converts the result into its C code.
+fn code(result: Result<(), Error>) -> c_int {
+    match result {
+        Ok(()) => THREAD_PRIORITY_OK,
+        Err(Error::OS(code)) => code,
+        Err(Error::Priority(_)) => THREAD_PRIORITY_ERROR_PRIORITY,
+        Err(Error::Ffi(_)) => THREAD_PRIORITY_ERROR_FFI,
+        Err(Error::Unsupported(_)) => THREAD_PRIORITY_ERROR_UNSUPPORTED,
+    }
+}
+
+/// Writes the value through the pointer, unless it's null.
+///
+/// # Safety
+///
+/// The pointer must be null or valid for writes.
+unsafe fn write<T>(pointer: *mut T, value: T) -> Result<(), Error> {
+    if pointer.is_null() {
+        return Err(Error::Ffi("The pointer is null."));
+    }
+    pointer.write(value);
+    Ok(())
+}
+
+/// Sets the priority of the current thread, with the normal policy on unix.
+/// For more info read [`crate::set_current_thread_priority`].
+#[no_mangle]
+pub extern "C" fn thread_priority_set_current(priority: CThreadPriority) -> c_int {
+    code(ThreadPriority::try_from(priority).and_then(crate::set_current_thread_priority))
+}
+
+/// Writes the priority of the current thread to `priority`.
+/// For more info read [`crate::get_thread_priority`].
+///
+/// # Safety
+///
+/// `priority` must be null, which fails, or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn thread_priority_get_current(priority: *mut CThreadPriority) -> c_int {
+    code(
+        crate::get_thread_priority(crate::thread_native_id())
+            .and_then(CThreadPriority::try_from)
+            .and_then(|value| write(priority, value)),
+    )
+}
+
+/// Sets the priority and the policy, one of the [`CThreadSchedulePolicy`] values, of the
+/// current thread, unsupported off unix.
+/// For more info read `set_thread_priority_and_policy`.
+#[no_mangle]
+pub extern "C" fn thread_priority_set_current_policy(
+    policy: c_int,
+    priority: CThreadPriority,
+) -> c_int {
+    #[cfg(unix)]
+    return code(CThreadSchedulePolicy::try_from(policy).and_then(|policy| {
+        ThreadPriority::try_from(priority).and_then(|priority| {
+            crate::set_thread_priority_and_policy(
+                crate::thread_native_id(),
+                priority,
+                policy.into(),
+            )
+        })
+    }));
+    #[cfg(not(unix))]
+    {
+        let _ = (policy, priority);
+        code(Err(Error::Unsupported(
+            "The schedule policies are not supported on this platform",
+        )))
+    }
+}
+
+/// Writes the policy of the current thread to `policy`, unsupported off unix.
+/// For more info read `thread_schedule_policy_for`.
+///
+/// # Safety
+///
+/// `policy` must be null, which fails, or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn thread_priority_get_current_policy(
+    policy: *mut CThreadSchedulePolicy,
+) -> c_int {
+    #[cfg(unix)]
+    return code(
+        crate::thread_schedule_policy_for(crate::thread_native_id())
+            .and_then(CThreadSchedulePolicy::try_from)
+            .and_then(|value| write(policy, value)),
+    );
+    #[cfg(not(unix))]
+    {
+        let _ = policy;
+        code(Err(Error::Unsupported(
+            "The schedule policies are not supported on this platform",
+        )))
+    }
+}
+
+/// Sets the affinity of the current thread to the `len` CPUs of `cpus`.
+/// For more info read [`crate::affinity::set_current_thread_affinity`].
+///
+/// # Safety
+///
+/// `cpus` must be null, which fails, or valid for reads of `len` elements.
+#[no_mangle]
+pub unsafe extern "C" fn thread_priority_set_current_affinity(
+    cpus: *const usize,
+    len: usize,
+) -> c_int {
+    if cpus.is_null() {
+        return code(Err(Error::Ffi("The pointer is null.")));
+    }
+    let mut set = CpuSet::new();
+    for &cpu in std::slice::from_raw_parts(cpus, len) {
+        if let Err(e) = set.try_insert(cpu) {
+            return code(Err(e));
+        }
+    }
+    code(crate::affinity::set_current_thread_affinity(&set))
+}
+
+/// Writes the CPUs of the affinity of the current thread to `cpus`, up to `capacity` of
+/// them in ascending order, and their count to `len`, which may be larger than `capacity`.
+/// For more info read [`crate::affinity::get_thread_affinity`].
+///
+/// # Safety
+///
+/// `cpus` must be valid for writes of `capacity` elements, and `len` must be null, which
+/// fails, or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn thread_priority_get_current_affinity(
+    cpus: *mut usize,
+    capacity: usize,
+    len: *mut usize,
+) -> c_int {
+    code(
+        crate::affinity::get_thread_affinity(crate::thread_native_id()).and_then(|affinity| {
+            if cpus.is_null() && capacity != 0 {
+                return Err(Error::Ffi("The pointer is null."));
+            }
+            for (i, cpu) in affinity.iter().take(capacity).enumerate() {
+                cpus.add(i).write(cpu);
+            }
+            write(len, affinity.len())
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_test() {
+        let priority = CThreadPriority {
+            kind: CThreadPriorityKind::Crossplatform as c_int,
+            value: 100,
+        };
+        assert_eq!(
+            thread_priority_set_current(priority),
+            THREAD_PRIORITY_ERROR_PRIORITY
+        );
+        let priority = CThreadPriority { kind: 42, value: 0 };
+        assert_eq!(
+            thread_priority_set_current(priority),
+            THREAD_PRIORITY_ERROR_PRIORITY
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            thread_priority_set_current_policy(
+                42,
+                CThreadPriority {
+                    kind: CThreadPriorityKind::Min as c_int,
+                    value: 0,
+                },
+            ),
+            THREAD_PRIORITY_ERROR_PRIORITY
+        );
+        assert_eq!(
+            unsafe { thread_priority_get_current(std::ptr::null_mut()) },
+            THREAD_PRIORITY_ERROR_FFI
+        );
+        assert_eq!(
+            unsafe { thread_priority_set_current_affinity([8192].as_ptr(), 1) },
+            THREAD_PRIORITY_ERROR_PRIORITY
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! This module defines the CPU time consumed by the threads.
+//!
+//! Whether a priority change has had any effect shows in the CPU time the
+//! thread actually got. [`thread_cpu_time`] returns the CPU time of a thread,
+//! and [`thread_usage`] splits it into the user and the system times, with
+//! the context switches or the cycles where the platform counts them.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::cpu_time::*;
+//!
+//! # #[cfg(any(target_os = "linux", target_os = "android", windows, target_vendor = "apple"))] {
+//! let before = current_thread_cpu_time().unwrap();
+//! let _ = (0..1_000_000u64).sum::<u64>();
+//! assert!(current_thread_cpu_time().unwrap() >= before);
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use crate::{Error, ThreadId};
+
+/// The usage of the CPU by a thread.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreadUsage {
+    /// The CPU time spent in the user mode.
+    pub user: Duration,
+    /// The CPU time spent in the kernel.
+    pub system: Duration,
+    /// The number of times the thread has given the CPU up, usually to wait.
+    pub voluntary_switches: Option<u64>,
+    /// The number of times the thread has been preempted.
+    pub involuntary_switches: Option<u64>,
+    /// The number of CPU cycles, see `thread_cycle_time`.
+    pub cycles: Option<u64>,
+}
+
+/// Returns the CPU time consumed by the thread.
+///
+/// * On Linux, Android, FreeBSD and DragonFly BSD, based on `pthread_getcpuclockid` and
+///   `clock_gettime`.
+/// * On Apple platforms, the user and the system times of `thread_info`.
+/// * On Windows, the user and the kernel times of `GetThreadTimes`.
+pub fn thread_cpu_time(native: ThreadId) -> Result<Duration, Error> {
+    native_thread_cpu_time(native)
+}
+
+/// Returns the CPU time consumed by the current thread.
+/// For more info read [`thread_cpu_time`].
+pub fn current_thread_cpu_time() -> Result<Duration, Error> {
+    thread_cpu_time(crate::thread_native_id())
+}
+
+/// Returns the usage of the CPU by the thread.
+///
+/// * On Linux, Android, FreeBSD and OpenBSD, based on `getrusage(RUSAGE_THREAD)`, which
+///   only knows the current thread, with the context switches.
+/// * On Apple platforms, based on `thread_info`.
+/// * On Windows, based on `GetThreadTimes`, with the cycles.
+pub fn thread_usage(native: ThreadId) -> Result<ThreadUsage, Error> {
+    native_thread_usage(native)
+}
+
+/// Returns the usage of the CPU by the current thread.
+/// For more info read [`thread_usage`].
+pub fn current_thread_usage() -> Result<ThreadUsage, Error> {
+    thread_usage(crate::thread_native_id())
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+))]
+fn native_thread_cpu_time(native: ThreadId) -> Result<Duration, Error> {
+    let mut clock: libc::clockid_t = 0;
+    let mut time: libc::timespec = unsafe { std::mem::zeroed() };
+    unsafe {
+        match libc::pthread_getcpuclockid(native, &mut clock) {
+            0 => {}
+            e => return Err(Error::OS(e)),
+        }
+        if libc::clock_gettime(clock, &mut time) != 0 {
+            return Err(Error::OS(crate::unix::errno()));
+        }
+    }
+    Ok(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(target_vendor = "apple")]
+fn native_thread_cpu_time(native: ThreadId) -> Result<Duration, Error> {
+    native_thread_usage(native).map(|usage| usage.user + usage.system)
+}
+
+#[cfg(windows)]
+fn native_thread_cpu_time(native: ThreadId) -> Result<Duration, Error> {
+    native_thread_usage(native).map(|usage| usage.user + usage.system)
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_vendor = "apple",
+    windows
+)))]
+fn native_thread_cpu_time(_native: ThreadId) -> Result<Duration, Error> {
+    Err(Error::Unsupported(
+        "The CPU time of the threads is not supported on this platform",
+    ))
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "openbsd"
+))]
+fn native_thread_usage(native: ThreadId) -> Result<ThreadUsage, Error> {
+    // The constant is missing from the Android bindings, the kernel has it.
+    #[cfg(target_os = "android")]
+    const RUSAGE_THREAD: libc::c_int = 1;
+    #[cfg(not(target_os = "android"))]
+    use libc::RUSAGE_THREAD;
+
+    if unsafe { libc::pthread_equal(native, libc::pthread_self()) } == 0 {
+        return Err(Error::Unsupported(
+            "The usage of the other threads is not supported on this platform",
+        ));
+    }
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(RUSAGE_THREAD, &mut usage) } != 0 {
+        return Err(Error::OS(crate::unix::errno()));
+    }
+    let duration =
+        |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+    Ok(ThreadUsage {
+        user: duration(usage.ru_utime),
+        system: duration(usage.ru_stime),
+        voluntary_switches: Some(usage.ru_nvcsw as u64),
+        involuntary_switches: Some(usage.ru_nivcsw as u64),
+        cycles: None,
+    })
+}
+
+#[cfg(target_vendor = "apple")]
+fn native_thread_usage(native: ThreadId) -> Result<ThreadUsage, Error> {
+    let mut info: libc::thread_basic_info = unsafe { std::mem::zeroed() };
+    let mut count = libc::THREAD_BASIC_INFO_COUNT;
+    let result = unsafe {
+        libc::thread_info(
+            libc::pthread_mach_thread_np(native),
+            libc::THREAD_BASIC_INFO as libc::thread_flavor_t,
+            &mut info as *mut _ as libc::thread_info_t,
+            &mut count,
+        )
+    };
+    if result != libc::KERN_SUCCESS {
+        return Err(Error::OS(result));
+    }
+    let duration = |time: libc::time_value_t| {
+        Duration::new(time.seconds as u64, time.microseconds as u32 * 1000)
+    };
+    Ok(ThreadUsage {
+        user: duration(info.user_time),
+        system: duration(info.system_time),
+        ..Default::default()
+    })
+}
+
+#[cfg(windows)]
+fn native_thread_usage(native: ThreadId) -> Result<ThreadUsage, Error> {
+    use winapi::shared::minwindef::FILETIME;
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::processthreadsapi::GetThreadTimes;
+
+    let mut times: [FILETIME; 4] = unsafe { std::mem::zeroed() };
+    let [creation, exit, kernel, user] = &mut times;
+    if unsafe { GetThreadTimes(native, creation, exit, kernel, user) } == 0 {
+        return Err(Error::OS(unsafe { GetLastError() } as i32));
+    }
+    // The times are counted in the units of 100 nanoseconds.
+    let duration = |time: &FILETIME| {
+        Duration::from_nanos(
+            ((u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime)) * 100,
+        )
+    };
+    Ok(ThreadUsage {
+        user: duration(user),
+        system: duration(kernel),
+        // The handle is taken on trust, as by the other cross-platform functions.
+        cycles: unsafe { crate::windows::thread_cycle_time(native) }.ok(),
+        ..Default::default()
+    })
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_vendor = "apple",
+    windows
+)))]
+fn native_thread_usage(_native: ThreadId) -> Result<ThreadUsage, Error> {
+    Err(Error::Unsupported(
+        "The usage of the threads is not supported on this platform",
+    ))
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_thread_usage_test() {
+        let usage = current_thread_usage().unwrap();
+        assert!(usage.voluntary_switches.is_some());
+        assert!(current_thread_cpu_time().unwrap() >= usage.user);
+    }
+}
@@ -0,0 +1,194 @@
+//! This module defines the extensions of the `crossbeam` scoped threads.
+//!
+//! The codebases spawning their threads with [`::crossbeam::thread::scope`]
+//! can set the priority, and on unix the policy, of the spawned threads with
+//! [`ScopeExt`] and [`ScopedThreadBuilderExt`], like the ones spawned with
+//! [`crate::ThreadBuilderExt`]. The default properties are applied first.
+//!
+//! Enabled by the `crossbeam` feature.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::crossbeam::*;
+//!
+//! let data = vec![1, 2, 3];
+//! ::crossbeam::thread::scope(|scope| {
+//!     scope.spawn_with_priority(ThreadPriority::Min, |_, result| {
+//!         assert!(result.is_ok());
+//!         println!("{:?}", data);
+//!     });
+//!     scope
+//!         .builder()
+//!         .name("MyScopedThread".to_owned())
+//!         .spawn_with_priority(ThreadPriority::Min, |_, result| assert!(result.is_ok()))
+//!         .unwrap();
+//! })
+//! .unwrap();
+//! ```
+
+use ::crossbeam::thread::{Scope, ScopedJoinHandle, ScopedThreadBuilder};
+
+#[cfg(unix)]
+use crate::ThreadSchedulePolicy;
+use crate::{defaults, registry, Error, ThreadPriority};
+
+/// Returns the closure registering the thread it runs on and setting its priority, and
+/// its policy if there's one, the default properties first.
+fn scheduling(
+    priority: ThreadPriority,
+    #[cfg(unix)] policy: Option<ThreadSchedulePolicy>,
+) -> impl FnOnce() -> Result<(), Error> + Send + 'static {
+    let defaults = defaults::default_properties();
+
+    move || {
+        registry::register_spawned_thread();
+        defaults::apply_inherited(defaults).and_then(|()| {
+            #[cfg(unix)]
+            if let Some(policy) = policy {
+                return crate::set_thread_priority_and_policy(
+                    crate::thread_native_id(),
+                    priority,
+                    policy,
+                );
+            }
+            priority.set_for_current()
+        })
+    }
+}
+
+/// Adds the spawning functions using the priority to the `crossbeam` scopes.
+pub trait ScopeExt<'env> {
+    /// Spawns a scoped thread with set priority. The passed functor `f` is executed in
+    /// the spawned thread and receives the scope and the result of setting the priority.
+    /// See [`::crossbeam::thread::Scope::spawn`] and [`ThreadPriority::set_for_current`]
+    /// for more info.
+    fn spawn_with_priority<'scope, F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env;
+
+    /// Spawns a scoped thread with set priority and policy.
+    /// For more info read [`ScopeExt::spawn_with_priority`] and
+    /// [`crate::set_thread_priority_and_policy`].
+    #[cfg(unix)]
+    fn spawn_with_priority_and_policy<'scope, F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        policy: ThreadSchedulePolicy,
+        f: F,
+    ) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env;
+}
+
+impl<'env> ScopeExt<'env> for Scope<'env> {
+    fn spawn_with_priority<'scope, F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env,
+    {
+        let scheduling = scheduling(
+            priority,
+            #[cfg(unix)]
+            None,
+        );
+        self.spawn(move |scope| f(scope, scheduling()))
+    }
+
+    #[cfg(unix)]
+    fn spawn_with_priority_and_policy<'scope, F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        policy: ThreadSchedulePolicy,
+        f: F,
+    ) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env,
+    {
+        let scheduling = scheduling(priority, Some(policy));
+        self.spawn(move |scope| f(scope, scheduling()))
+    }
+}
+
+/// Adds the spawning functions using the priority to the `crossbeam` scoped thread
+/// builders, keeping their name and stack size.
+pub trait ScopedThreadBuilderExt<'scope, 'env> {
+    /// Spawns a scoped thread with set priority.
+    /// For more info read [`ScopeExt::spawn_with_priority`] and
+    /// [`::crossbeam::thread::ScopedThreadBuilder::spawn`].
+    fn spawn_with_priority<F, T>(
+        self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> std::io::Result<ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env;
+
+    /// Spawns a scoped thread with set priority and policy.
+    /// For more info read [`ScopeExt::spawn_with_priority_and_policy`].
+    #[cfg(unix)]
+    fn spawn_with_priority_and_policy<F, T>(
+        self,
+        priority: ThreadPriority,
+        policy: ThreadSchedulePolicy,
+        f: F,
+    ) -> std::io::Result<ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env;
+}
+
+impl<'scope, 'env> ScopedThreadBuilderExt<'scope, 'env> for ScopedThreadBuilder<'scope, 'env> {
+    fn spawn_with_priority<F, T>(
+        self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> std::io::Result<ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env,
+    {
+        let scheduling = scheduling(
+            priority,
+            #[cfg(unix)]
+            None,
+        );
+        self.spawn(move |scope| f(scope, scheduling()))
+    }
+
+    #[cfg(unix)]
+    fn spawn_with_priority_and_policy<F, T>(
+        self,
+        priority: ThreadPriority,
+        policy: ThreadSchedulePolicy,
+        f: F,
+    ) -> std::io::Result<ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce(&Scope<'env>, Result<(), Error>) -> T,
+        F: Send + 'env,
+        T: Send + 'env,
+    {
+        let scheduling = scheduling(priority, Some(policy));
+        self.spawn(move |scope| f(scope, scheduling()))
+    }
+}
@@ -0,0 +1,794 @@
+//! This module defines the darwin (macOS, iOS, tvOS and watchOS) thread control.
+//!
+//! The Apple schedulers largely ignore the POSIX thread priorities, instead
+//! it schedules threads by their quality of service (QoS) class, which tells
+//! how important the work a thread performs is to the user. This module
+//! provides the QoS control and maps the crate's priorities onto it.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::darwin::*;
+//!
+//! assert!(set_current_thread_qos_class(QosClass::Utility).is_ok());
+//! assert_eq!(current_thread_qos_class().unwrap(), QosClass::Utility);
+//! ```
+//!
+//! The threads needing real realtime service, like the audio ones, use the
+//! [`TimeConstraintPolicy`] instead.
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::unix::errno;
+use crate::{Error, ThreadId, ThreadPriority, ThreadPriorityValue};
+
+#[repr(C)]
+struct MachTimebaseInfo {
+    numer: u32,
+    denom: u32,
+}
+
+/// The `os_workgroup_join_token_s` structure of `os/workgroup_object.h`.
+#[repr(C)]
+struct WorkgroupJoinToken {
+    sig: u32,
+    opaque: [libc::c_char; 36],
+}
+
+impl std::fmt::Debug for WorkgroupJoinToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkgroupJoinToken").finish()
+    }
+}
+
+extern "C" {
+    fn os_workgroup_join(workgroup: OsWorkgroup, token: *mut WorkgroupJoinToken) -> libc::c_int;
+    fn os_workgroup_leave(workgroup: OsWorkgroup, token: *mut WorkgroupJoinToken);
+    fn pthread_set_qos_class_self_np(
+        qos_class: libc::c_uint,
+        relative_priority: libc::c_int,
+    ) -> libc::c_int;
+    fn pthread_get_qos_class_np(
+        thread: libc::pthread_t,
+        qos_class: *mut libc::c_uint,
+        relative_priority: *mut libc::c_int,
+    ) -> libc::c_int;
+    fn setiopolicy_np(iotype: libc::c_int, scope: libc::c_int, policy: libc::c_int) -> libc::c_int;
+    fn getiopolicy_np(iotype: libc::c_int, scope: libc::c_int) -> libc::c_int;
+    fn mach_timebase_info(info: *mut MachTimebaseInfo) -> libc::c_int;
+}
+
+/// The quality of service (QoS) classes, as defined in `sys/qos.h`. The higher the
+/// class, the more CPU time and the more performant cores the thread gets.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QosClass {
+    /// No QoS class has been set, the thread is scheduled by its POSIX priority.
+    Unspecified = 0x00,
+    /// Work the user is not aware of, like indexing or backups.
+    Background = 0x09,
+    /// Long-running work the user is aware of, like downloads.
+    Utility = 0x11,
+    /// The default class, between `Utility` and `UserInitiated`.
+    Default = 0x15,
+    /// Work the user has started and is waiting for, like opening a document.
+    UserInitiated = 0x19,
+    /// Work interacting with the user, like the main thread or the animations.
+    UserInteractive = 0x21,
+}
+
+impl TryFrom<u32> for QosClass {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0x00 => QosClass::Unspecified,
+            0x09 => QosClass::Background,
+            0x11 => QosClass::Utility,
+            0x15 => QosClass::Default,
+            0x19 => QosClass::UserInitiated,
+            0x21 => QosClass::UserInteractive,
+            _ => return Err(Error::Priority("The value is not a QoS class.")),
+        })
+    }
+}
+
+impl TryFrom<ThreadPriority> for QosClass {
+    type Error = Error;
+
+    fn try_from(priority: ThreadPriority) -> Result<Self, Self::Error> {
+        Qos::try_from(priority).map(|qos| qos.class)
+    }
+}
+
+/// The lowest relative priority of a thread within its QoS class.
+pub const MIN_QOS_RELATIVE_PRIORITY: i32 = -15;
+
+/// A QoS class with the relative priority of the thread within it, from
+/// [`MIN_QOS_RELATIVE_PRIORITY`] to `0`, which orders the threads of the same class.
+///
+/// As a [`ThreadPriorityOsValue`](crate::ThreadPriorityOsValue), the class is stored in
+/// the lowest byte and the negated relative priority in the next one, so the plain
+/// class values are the QoS of a zero relative priority.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Qos {
+    /// The QoS class.
+    pub class: QosClass,
+    /// The relative priority within the class.
+    pub relative_priority: i32,
+}
+
+impl Qos {
+    /// Creates the QoS, checking the relative priority is in the allowed range.
+    pub fn new(class: QosClass, relative_priority: i32) -> Result<Self, Error> {
+        if !(MIN_QOS_RELATIVE_PRIORITY..=0).contains(&relative_priority) {
+            return Err(Error::Priority(
+                "The relative priority is out of range [-15; 0].",
+            ));
+        }
+        Ok(Qos {
+            class,
+            relative_priority,
+        })
+    }
+}
+
+impl From<Qos> for crate::ThreadPriorityOsValue {
+    fn from(qos: Qos) -> Self {
+        crate::ThreadPriorityOsValue(qos.class as u32 | ((-qos.relative_priority) as u32) << 8)
+    }
+}
+
+impl TryFrom<crate::ThreadPriorityOsValue> for Qos {
+    type Error = Error;
+
+    fn try_from(value: crate::ThreadPriorityOsValue) -> Result<Self, Self::Error> {
+        let crate::ThreadPriorityOsValue(value) = value;
+        if value >> 16 != 0 {
+            return Err(Error::Priority("The value is not a QoS."));
+        }
+        Qos::new(QosClass::try_from(value & 0xff)?, -((value >> 8) as i32))
+    }
+}
+
+impl TryFrom<ThreadPriority> for Qos {
+    type Error = Error;
+
+    fn try_from(priority: ThreadPriority) -> Result<Self, Self::Error> {
+        Ok(match priority {
+            ThreadPriority::Min => Qos {
+                class: QosClass::Background,
+                relative_priority: MIN_QOS_RELATIVE_PRIORITY,
+            },
+            ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => {
+                let class = match p {
+                    0..=19 => QosClass::Background,
+                    20..=39 => QosClass::Utility,
+                    40..=59 => QosClass::Default,
+                    60..=79 => QosClass::UserInitiated,
+                    80..=99 => QosClass::UserInteractive,
+                    _ => return Err(Error::Priority("The value is out of range [0; 99].")),
+                };
+                // Every class takes 20 values, spread over its relative priorities.
+                let offset = i32::from(p % 20) * -MIN_QOS_RELATIVE_PRIORITY / 19;
+                Qos {
+                    class,
+                    relative_priority: MIN_QOS_RELATIVE_PRIORITY + offset,
+                }
+            }
+            ThreadPriority::Os(value) => Qos::try_from(value)?,
+            ThreadPriority::Max => Qos {
+                class: QosClass::UserInteractive,
+                relative_priority: 0,
+            },
+        })
+    }
+}
+
+/// Sets the QoS class of the current thread. The class can only be set for the current
+/// thread, the other threads can be only queried.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::darwin::*;
+/// use std::convert::TryFrom;
+///
+/// let class = QosClass::try_from(ThreadPriority::Min).unwrap();
+/// assert!(set_current_thread_qos_class(class).is_ok());
+/// ```
+pub fn set_current_thread_qos_class(class: QosClass) -> Result<(), Error> {
+    set_current_thread_qos(Qos {
+        class,
+        relative_priority: 0,
+    })
+}
+
+/// Sets the QoS class and the relative priority of the current thread.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::darwin::*;
+///
+/// let qos = Qos::new(QosClass::Utility, -5).unwrap();
+/// assert!(set_current_thread_qos(qos).is_ok());
+/// assert_eq!(current_thread_qos().unwrap(), qos);
+/// ```
+pub fn set_current_thread_qos(qos: Qos) -> Result<(), Error> {
+    unsafe {
+        match pthread_set_qos_class_self_np(
+            qos.class as libc::c_uint,
+            qos.relative_priority as libc::c_int,
+        ) {
+            0 => Ok(()),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+/// Returns the QoS class of the thread.
+pub fn thread_qos_class(native: ThreadId) -> Result<QosClass, Error> {
+    thread_qos(native).map(|qos| qos.class)
+}
+
+/// Returns the QoS class and the relative priority of the thread.
+pub fn thread_qos(native: ThreadId) -> Result<Qos, Error> {
+    let mut class = 0;
+    let mut relative_priority = 0;
+    unsafe {
+        match pthread_get_qos_class_np(native, &mut class, &mut relative_priority) {
+            0 => Ok(Qos {
+                class: QosClass::try_from(class)?,
+                relative_priority,
+            }),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+/// Returns the QoS class and the relative priority of the current thread.
+/// For more info read [`thread_qos`].
+pub fn current_thread_qos() -> Result<Qos, Error> {
+    thread_qos(crate::thread_native_id())
+}
+
+/// Returns the QoS class of the current thread.
+/// For more info read [`thread_qos_class`].
+pub fn current_thread_qos_class() -> Result<QosClass, Error> {
+    thread_qos_class(crate::thread_native_id())
+}
+
+/// The time-constraint (realtime) policy of a thread: the thread needs `computation`
+/// of CPU time within `constraint` from the start of every `period`. This is the
+/// scheduling audio and video threads get genuine realtime service with on macOS, the
+/// analogue of Linux's `SCHED_DEADLINE`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TimeConstraintPolicy {
+    /// The nominal time between the starts of two consecutive computations, zero if
+    /// the work isn't periodic.
+    pub period: Duration,
+    /// The CPU time needed by every computation.
+    pub computation: Duration,
+    /// The maximum time between the start and the end of every computation.
+    pub constraint: Duration,
+    /// Whether the computation may be interrupted by other threads.
+    pub preemptible: bool,
+}
+
+/// Converts the Mach error to the closest `errno` value.
+fn kern_error(e: libc::kern_return_t) -> Error {
+    match e {
+        libc::KERN_INVALID_ARGUMENT => Error::OS(libc::EINVAL),
+        libc::KERN_PROTECTION_FAILURE | libc::KERN_NO_ACCESS => Error::OS(libc::EPERM),
+        libc::KERN_NOT_SUPPORTED => Error::OS(libc::ENOTSUP),
+        libc::KERN_RESOURCE_SHORTAGE => Error::OS(libc::ENOMEM),
+        _ => Error::Ffi("The Mach call failed"),
+    }
+}
+
+/// Sets the Mach policy of the `flavor` of the thread. The `policy` must point to the
+/// structure of the flavor, `count` integers long.
+unsafe fn thread_policy_set(
+    native: ThreadId,
+    flavor: libc::c_int,
+    policy: libc::thread_policy_t,
+    count: libc::mach_msg_type_number_t,
+) -> Result<(), Error> {
+    match libc::thread_policy_set(
+        libc::pthread_mach_thread_np(native),
+        flavor as libc::thread_policy_flavor_t,
+        policy,
+        count,
+    ) {
+        libc::KERN_SUCCESS => Ok(()),
+        e => Err(kern_error(e)),
+    }
+}
+
+/// Gets the Mach policy of the `flavor` of the thread into the structure `policy` points
+/// to, `count` integers long. Returns whether the policy is the default one, i.e. the
+/// flavor hasn't been set for the thread.
+unsafe fn thread_policy_get(
+    native: ThreadId,
+    flavor: libc::c_int,
+    policy: libc::thread_policy_t,
+    mut count: libc::mach_msg_type_number_t,
+) -> Result<bool, Error> {
+    let mut get_default: libc::boolean_t = 0;
+    match libc::thread_policy_get(
+        libc::pthread_mach_thread_np(native),
+        flavor as libc::thread_policy_flavor_t,
+        policy,
+        &mut count,
+        &mut get_default,
+    ) {
+        libc::KERN_SUCCESS => Ok(get_default != 0),
+        e => Err(kern_error(e)),
+    }
+}
+
+fn timebase() -> Result<MachTimebaseInfo, Error> {
+    let mut timebase = MachTimebaseInfo { numer: 0, denom: 0 };
+    unsafe {
+        match mach_timebase_info(&mut timebase) {
+            libc::KERN_SUCCESS => Ok(timebase),
+            e => Err(kern_error(e)),
+        }
+    }
+}
+
+/// Converts the duration to the Mach absolute time units.
+fn to_absolute_time(duration: Duration) -> Result<u32, Error> {
+    let timebase = timebase()?;
+    let units = duration.as_nanos() * timebase.denom as u128 / timebase.numer as u128;
+    u32::try_from(units).map_err(|_| Error::Priority("The duration is too long."))
+}
+
+/// Converts the Mach absolute time units to the duration.
+fn from_absolute_time(units: u32) -> Result<Duration, Error> {
+    let timebase = timebase()?;
+    let nanos = units as u64 * timebase.numer as u64 / timebase.denom as u64;
+    Ok(Duration::from_nanos(nanos))
+}
+
+/// Sets the time-constraint policy of the thread. The policy is cleared by setting a
+/// schedule policy or a QoS class.
+///
+/// Based on `thread_policy_set(THREAD_TIME_CONSTRAINT_POLICY)`.
+///
+/// # Usage
+///
+/// ```rust
+/// use std::time::Duration;
+/// use thread_priority::*;
+/// use thread_priority::darwin::*;
+///
+/// let policy = TimeConstraintPolicy {
+///     period: Duration::from_millis(10),
+///     computation: Duration::from_millis(2),
+///     constraint: Duration::from_millis(5),
+///     preemptible: true,
+/// };
+/// assert!(set_thread_time_constraint_policy(thread_native_id(), policy).is_ok());
+/// ```
+pub fn set_thread_time_constraint_policy(
+    native: ThreadId,
+    policy: TimeConstraintPolicy,
+) -> Result<(), Error> {
+    let mut native_policy = libc::thread_time_constraint_policy {
+        period: to_absolute_time(policy.period)?,
+        computation: to_absolute_time(policy.computation)?,
+        constraint: to_absolute_time(policy.constraint)?,
+        preemptible: policy.preemptible as libc::boolean_t,
+    };
+    unsafe {
+        thread_policy_set(
+            native,
+            libc::THREAD_TIME_CONSTRAINT_POLICY,
+            &mut native_policy as *mut _ as libc::thread_policy_t,
+            libc::THREAD_TIME_CONSTRAINT_POLICY_COUNT,
+        )
+    }
+}
+
+/// Sets the time-constraint policy of the current thread.
+/// For more info read [`set_thread_time_constraint_policy`].
+pub fn set_current_thread_time_constraint_policy(
+    policy: TimeConstraintPolicy,
+) -> Result<(), Error> {
+    set_thread_time_constraint_policy(crate::thread_native_id(), policy)
+}
+
+/// Returns the time-constraint policy of the thread, or `None` if the thread isn't
+/// time-constrained.
+///
+/// Based on `thread_policy_get(THREAD_TIME_CONSTRAINT_POLICY)`.
+pub fn thread_time_constraint_policy(
+    native: ThreadId,
+) -> Result<Option<TimeConstraintPolicy>, Error> {
+    let mut policy = libc::thread_time_constraint_policy {
+        period: 0,
+        computation: 0,
+        constraint: 0,
+        preemptible: 0,
+    };
+    let is_default = unsafe {
+        thread_policy_get(
+            native,
+            libc::THREAD_TIME_CONSTRAINT_POLICY,
+            &mut policy as *mut _ as libc::thread_policy_t,
+            libc::THREAD_TIME_CONSTRAINT_POLICY_COUNT,
+        )?
+    };
+    if is_default {
+        return Ok(None);
+    }
+    Ok(Some(TimeConstraintPolicy {
+        period: from_absolute_time(policy.period)?,
+        computation: from_absolute_time(policy.computation)?,
+        constraint: from_absolute_time(policy.constraint)?,
+        preemptible: policy.preemptible != 0,
+    }))
+}
+
+/// Sets whether the thread is time-shared, i.e. whether its priority decays as it uses
+/// the CPU, like the thread of the normal schedule policy, or it stays fixed.
+///
+/// Based on `thread_policy_set(THREAD_EXTENDED_POLICY)`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::darwin::*;
+///
+/// assert!(set_thread_extended_policy(thread_native_id(), true).is_ok());
+/// ```
+#[cfg(target_os = "macos")]
+pub fn set_thread_extended_policy(native: ThreadId, timeshare: bool) -> Result<(), Error> {
+    let mut policy = libc::thread_extended_policy {
+        timeshare: timeshare as libc::boolean_t,
+    };
+    unsafe {
+        thread_policy_set(
+            native,
+            libc::THREAD_EXTENDED_POLICY,
+            &mut policy as *mut _ as libc::thread_policy_t,
+            libc::THREAD_EXTENDED_POLICY_COUNT,
+        )
+    }
+}
+
+/// The lowest importance of a thread, relative to the base priority of its task.
+#[cfg(target_os = "macos")]
+pub const MIN_THREAD_IMPORTANCE: i32 = -31;
+/// The highest importance of a thread, relative to the base priority of its task.
+#[cfg(target_os = "macos")]
+pub const MAX_THREAD_IMPORTANCE: i32 = 32;
+
+/// Sets the importance of the thread, relative to the other threads of the same task.
+/// The importance is added to the base priority of the task, the result is clamped by
+/// the kernel into the range the task may use.
+///
+/// Based on `thread_policy_set(THREAD_PRECEDENCE_POLICY)`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::darwin::*;
+///
+/// assert!(set_thread_precedence(thread_native_id(), -10).is_ok());
+/// ```
+#[cfg(target_os = "macos")]
+pub fn set_thread_precedence(native: ThreadId, importance: i32) -> Result<(), Error> {
+    if !(MIN_THREAD_IMPORTANCE..=MAX_THREAD_IMPORTANCE).contains(&importance) {
+        return Err(Error::Priority("The importance is out of range [-31; 32]."));
+    }
+    let mut policy = libc::thread_precedence_policy {
+        importance: importance as libc::integer_t,
+    };
+    unsafe {
+        thread_policy_set(
+            native,
+            libc::THREAD_PRECEDENCE_POLICY,
+            &mut policy as *mut _ as libc::thread_policy_t,
+            libc::THREAD_PRECEDENCE_POLICY_COUNT,
+        )
+    }
+}
+
+/// Returns whether the thread is time-shared, i.e. whether its priority decays as it
+/// uses the CPU.
+///
+/// Based on `thread_policy_get(THREAD_EXTENDED_POLICY)`.
+pub fn thread_extended_policy(native: ThreadId) -> Result<bool, Error> {
+    let mut policy = libc::thread_extended_policy { timeshare: 0 };
+    unsafe {
+        thread_policy_get(
+            native,
+            libc::THREAD_EXTENDED_POLICY,
+            &mut policy as *mut _ as libc::thread_policy_t,
+            libc::THREAD_EXTENDED_POLICY_COUNT,
+        )?;
+    }
+    Ok(policy.timeshare != 0)
+}
+
+/// Returns the importance of the thread, relative to the base priority of its task.
+///
+/// Based on `thread_policy_get(THREAD_PRECEDENCE_POLICY)`.
+pub fn thread_precedence(native: ThreadId) -> Result<i32, Error> {
+    let mut policy = libc::thread_precedence_policy { importance: 0 };
+    unsafe {
+        thread_policy_get(
+            native,
+            libc::THREAD_PRECEDENCE_POLICY,
+            &mut policy as *mut _ as libc::thread_policy_t,
+            libc::THREAD_PRECEDENCE_POLICY_COUNT,
+        )?;
+    }
+    Ok(policy.importance)
+}
+
+/// Sets the cross-platform priority of the thread of the normal schedule policy. The
+/// POSIX parameters only allow a single priority for the normal schedule policy, so
+/// the thread is made time-shared and the value is spread over the importance range.
+#[cfg(target_os = "macos")]
+pub(crate) fn set_thread_crossplatform_priority(
+    native: ThreadId,
+    value: ThreadPriorityValue,
+) -> Result<(), Error> {
+    set_thread_extended_policy(native, true)?;
+    set_thread_precedence(native, crossplatform_importance(value))
+}
+
+/// Sets the cross-platform priority of the thread of the normal schedule policy. The
+/// mobile platforms don't let the applications set the Mach thread precedences, so the
+/// value is applied as a QoS, which is only possible for the current thread.
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn set_thread_crossplatform_priority(
+    native: ThreadId,
+    value: ThreadPriorityValue,
+) -> Result<(), Error> {
+    if unsafe { libc::pthread_equal(native, libc::pthread_self()) } == 0 {
+        return Err(Error::Priority(
+            "The QoS can only be set for the current thread.",
+        ));
+    }
+    set_current_thread_qos(Qos::try_from(ThreadPriority::Crossplatform(value))?)
+}
+
+#[cfg(target_os = "macos")]
+fn crossplatform_importance(ThreadPriorityValue(p): ThreadPriorityValue) -> i32 {
+    let range = MAX_THREAD_IMPORTANCE - MIN_THREAD_IMPORTANCE;
+    MIN_THREAD_IMPORTANCE + i32::from(p) * range / 99
+}
+
+/// Moves the current thread into or out of the background band: a background thread
+/// has its CPU and I/O throttled, so it only runs when nothing more important does.
+/// This is the Apple counterpart of the idle and batch schedule policies of Linux.
+///
+/// Based on `setpriority(PRIO_DARWIN_THREAD, 0, PRIO_DARWIN_BG)`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::darwin::*;
+///
+/// assert!(set_current_thread_background(true).is_ok());
+/// assert!(current_thread_background().unwrap());
+/// assert!(set_current_thread_background(false).is_ok());
+/// ```
+pub fn set_current_thread_background(enabled: bool) -> Result<(), Error> {
+    let value = if enabled { libc::PRIO_DARWIN_BG } else { 0 };
+    unsafe {
+        match libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, value) {
+            0 => Ok(()),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Returns whether the current thread is in the background band.
+/// For more info read [`set_current_thread_background`].
+pub fn current_thread_background() -> Result<bool, Error> {
+    unsafe {
+        match libc::getpriority(libc::PRIO_DARWIN_THREAD, 0) {
+            -1 => Err(Error::OS(errno())),
+            value => Ok(value != 0),
+        }
+    }
+}
+
+const IOPOL_TYPE_DISK: libc::c_int = 0;
+const IOPOL_SCOPE_THREAD: libc::c_int = 1;
+
+/// The disk I/O policies, as defined in `sys/resource.h`.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum IoPolicy {
+    /// The policy inherited from the process.
+    Default = 0,
+    /// The I/O is critical to the system, it is never throttled.
+    Important = 1,
+    /// The I/O doesn't cause the throttling of the other I/O, like the one of backups.
+    Passive = 2,
+    /// The I/O is throttled by the more important one, for the background work.
+    Throttle = 3,
+    /// The I/O is throttled less than the `Throttle` one, for the utility work.
+    Utility = 4,
+    /// The I/O is throttled the least, for the interactive work.
+    Standard = 5,
+}
+
+impl TryFrom<i32> for IoPolicy {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => IoPolicy::Default,
+            1 => IoPolicy::Important,
+            2 => IoPolicy::Passive,
+            3 => IoPolicy::Throttle,
+            4 => IoPolicy::Utility,
+            5 => IoPolicy::Standard,
+            _ => return Err(Error::Priority("The value is not an I/O policy.")),
+        })
+    }
+}
+
+/// Sets the disk I/O policy of the current thread.
+///
+/// Based on `setiopolicy_np(IOPOL_TYPE_DISK, IOPOL_SCOPE_THREAD)`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::darwin::*;
+///
+/// assert!(set_current_thread_io_policy(IoPolicy::Throttle).is_ok());
+/// assert_eq!(current_thread_io_policy().unwrap(), IoPolicy::Throttle);
+/// ```
+pub fn set_current_thread_io_policy(policy: IoPolicy) -> Result<(), Error> {
+    unsafe {
+        match setiopolicy_np(IOPOL_TYPE_DISK, IOPOL_SCOPE_THREAD, policy as libc::c_int) {
+            0 => Ok(()),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Returns the disk I/O policy of the current thread.
+pub fn current_thread_io_policy() -> Result<IoPolicy, Error> {
+    unsafe {
+        match getiopolicy_np(IOPOL_TYPE_DISK, IOPOL_SCOPE_THREAD) {
+            -1 => Err(Error::OS(errno())),
+            policy => IoPolicy::try_from(policy),
+        }
+    }
+}
+
+/// The `os_workgroup_t` handle of a workgroup, like the one Core Audio reports for an
+/// audio device by the `kAudioDevicePropertyIOThreadOSWorkgroup` property.
+pub type OsWorkgroup = *mut libc::c_void;
+
+/// The membership of the current thread in a workgroup. The threads doing the work of
+/// a workgroup together, like the real-time audio ones, have to join it to get scheduled
+/// in time on Apple Silicon, whatever their priority is.
+///
+/// The thread leaves the workgroup when the membership is dropped.
+///
+/// Based on `os_workgroup_join` and `os_workgroup_leave`, available since macOS 11.
+#[derive(Debug)]
+pub struct WorkgroupMembership {
+    workgroup: OsWorkgroup,
+    // The kernel may keep the address of the token, so it doesn't move.
+    token: Box<WorkgroupJoinToken>,
+}
+
+impl WorkgroupMembership {
+    /// Joins the current thread to the workgroup. Fails with `EINVAL` if the workgroup
+    /// has been cancelled and with `EALREADY` if the thread is already in a workgroup.
+    ///
+    /// # Safety
+    ///
+    /// The workgroup must be a valid `os_workgroup_t`, retained until the membership is
+    /// dropped.
+    pub unsafe fn join(workgroup: OsWorkgroup) -> Result<Self, Error> {
+        let mut token = Box::new(WorkgroupJoinToken {
+            sig: 0,
+            opaque: [0; 36],
+        });
+        match os_workgroup_join(workgroup, &mut *token) {
+            0 => Ok(WorkgroupMembership { workgroup, token }),
+            e => Err(Error::OS(e)),
+        }
+    }
+
+    /// Returns the workgroup the thread is in.
+    pub fn workgroup(&self) -> OsWorkgroup {
+        self.workgroup
+    }
+}
+
+impl Drop for WorkgroupMembership {
+    fn drop(&mut self) {
+        unsafe { os_workgroup_leave(self.workgroup, &mut *self.token) }
+    }
+}
+
+/// The Mach scheduling state of a thread, which tells much more about how the thread
+/// is scheduled than its POSIX priority does.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct DarwinThreadPolicy {
+    /// The QoS of the thread.
+    pub qos: Qos,
+    /// The time-constraint policy, if the thread is time-constrained.
+    pub time_constraint: Option<TimeConstraintPolicy>,
+    /// Whether the thread is time-shared.
+    pub timeshare: bool,
+    /// The importance of the thread.
+    pub importance: i32,
+}
+
+/// Returns the Mach scheduling state of the thread.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::darwin::*;
+///
+/// let policy = thread_policy(thread_native_id()).unwrap();
+/// assert!(policy.time_constraint.is_none());
+/// ```
+pub fn thread_policy(native: ThreadId) -> Result<DarwinThreadPolicy, Error> {
+    Ok(DarwinThreadPolicy {
+        qos: thread_qos(native)?,
+        time_constraint: thread_time_constraint_policy(native)?,
+        timeshare: thread_extended_policy(native)?,
+        importance: thread_precedence(native)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn crossplatform_importance_test() {
+        let importance =
+            |p| crossplatform_importance(ThreadPriorityValue::try_from(p as u8).unwrap());
+        assert_eq!(importance(0), MIN_THREAD_IMPORTANCE);
+        assert_eq!(importance(99), MAX_THREAD_IMPORTANCE);
+        assert!(importance(40) < importance(60));
+    }
+
+    #[test]
+    fn qos_os_value_test() {
+        let qos = Qos::new(QosClass::UserInitiated, -7).unwrap();
+        let value = crate::ThreadPriorityOsValue::from(qos);
+        assert_eq!(Qos::try_from(value).unwrap(), qos);
+        assert!(Qos::new(QosClass::Utility, 1).is_err());
+        assert!(Qos::new(QosClass::Utility, -16).is_err());
+    }
+
+    #[test]
+    fn crossplatform_qos_test() {
+        let qos = |p| {
+            Qos::try_from(ThreadPriority::Crossplatform(
+                ThreadPriorityValue::try_from(p as u8).unwrap(),
+            ))
+        };
+        assert_eq!(qos(40).unwrap(), Qos::new(QosClass::Default, -15).unwrap());
+        assert_eq!(qos(59).unwrap(), Qos::new(QosClass::Default, 0).unwrap());
+    }
+}
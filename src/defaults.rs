@@ -0,0 +1,127 @@
+//! This module defines the default properties of the spawned threads.
+//!
+//! A framework usually wants all its workers configured the same way, but the
+//! threads are spawned all over its code. The default [`ThreadProperties`]
+//! are applied to every thread spawned by the crate's spawn functions and
+//! builders, before the priority and the policy given to the spawn call, so
+//! these still win. The defaults are set for the whole process with
+//! [`set_default_properties`], or for the threads spawned by the current
+//! thread within a closure with [`with_default_properties`], the scoped ones
+//! taking precedence. The properties are read when the thread is spawned.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::defaults::*;
+//! use thread_priority::properties::ThreadProperties;
+//!
+//! set_default_properties(ThreadProperties::default().priority(ThreadPriority::Min));
+//! let thread = ThreadBuilder::default()
+//!     .name("worker")
+//!     .spawn(|result| {
+//!         assert!(result.is_ok());
+//!     })
+//!     .unwrap();
+//! thread.join().unwrap();
+//! clear_default_properties();
+//! ```
+
+use std::cell::RefCell;
+use std::sync::Mutex;
+
+use crate::properties::ThreadProperties;
+use crate::Error;
+
+static DEFAULTS: Mutex<Option<ThreadProperties>> = Mutex::new(None);
+
+thread_local! {
+    static SCOPED_DEFAULTS: RefCell<Option<ThreadProperties>> = const { RefCell::new(None) };
+}
+
+fn defaults() -> std::sync::MutexGuard<'static, Option<ThreadProperties>> {
+    // The defaults are replaced at once, a panic can't poison them.
+    DEFAULTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets the properties applied to all the threads spawned by the crate from now on,
+/// replacing the previous ones.
+pub fn set_default_properties(properties: ThreadProperties) {
+    *defaults() = Some(properties);
+}
+
+/// Stops applying default properties to the spawned threads, the scoped ones aside.
+pub fn clear_default_properties() {
+    *defaults() = None;
+}
+
+/// Returns the properties applied to the threads the current thread spawns: the scoped
+/// ones if any, the ones of the process otherwise.
+pub fn default_properties() -> Option<ThreadProperties> {
+    SCOPED_DEFAULTS
+        .with(|scoped| scoped.borrow().clone())
+        .or_else(|| defaults().clone())
+}
+
+/// Restores the previous scoped properties, even if the closure panics.
+struct ScopeGuard(Option<ThreadProperties>);
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SCOPED_DEFAULTS.with(|scoped| *scoped.borrow_mut() = previous);
+    }
+}
+
+/// Calls the closure with the properties applied to the threads the current thread
+/// spawns in the meantime, instead of the ones of the process. The scopes can be nested.
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::defaults::*;
+/// use thread_priority::properties::ThreadProperties;
+///
+/// let thread = with_default_properties(
+///     ThreadProperties::default().priority(ThreadPriority::Min),
+///     || spawn_careless(ThreadPriority::Min, || println!("A worker")),
+/// );
+/// thread.join().unwrap();
+/// ```
+pub fn with_default_properties<F, R>(properties: ThreadProperties, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let previous = SCOPED_DEFAULTS.with(|scoped| scoped.borrow_mut().replace(properties));
+    let _guard = ScopeGuard(previous);
+    f()
+}
+
+/// Applies the default properties read when the thread has been spawned to the current
+/// thread.
+pub(crate) fn apply_inherited(properties: Option<ThreadProperties>) -> Result<(), Error> {
+    match properties {
+        Some(properties) => properties.apply_to_current(),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::affinity::CpuSet;
+
+    #[test]
+    fn scoped_defaults_test() {
+        assert!(default_properties().is_none());
+        // An empty set of CPUs can't be applied.
+        let result =
+            with_default_properties(ThreadProperties::default().affinity(CpuSet::new()), || {
+                assert!(default_properties().is_some());
+                crate::ThreadBuilder::default()
+                    .spawn(|result| result.is_err())
+                    .unwrap()
+            });
+        assert!(default_properties().is_none());
+        assert!(result.join().unwrap());
+    }
+}
@@ -0,0 +1,187 @@
+//! This module defines the efficiency mode of the current thread.
+//!
+//! The battery-conscious background work, like the syncing or the indexing,
+//! should run where and when it costs the least energy, but each platform has
+//! its own knobs for it. [`enter_efficiency_mode`] turns the right ones on
+//! for the current thread and returns a guard which turns them off when it is
+//! dropped.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::efficiency::*;
+//!
+//! let thread = std::thread::spawn(|| {
+//!     # #[cfg(any(unix, windows))] {
+//!     let _efficiency = enter_efficiency_mode().unwrap();
+//!     // The background work.
+//!     # }
+//! });
+//! thread.join().unwrap();
+//! ```
+
+use crate::Error;
+
+/// The efficiency mode of the current thread, left when the guard is dropped.
+///
+/// The guard restores the thread it has been created on, so it can't be sent to another
+/// thread. A drop can't fail, the errors of the restoration are logged instead.
+#[derive(Debug)]
+pub struct EfficiencyModeGuard {
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    _scheduling: Option<crate::ScopedScheduling>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    nice: Option<(crate::ThreadTid, i32)>,
+    #[cfg(target_vendor = "apple")]
+    qos: crate::darwin::Qos,
+    #[cfg(windows)]
+    throttling: crate::windows::ThreadPowerThrottling,
+    #[cfg(windows)]
+    affinity: crate::affinity::CpuSet,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+/// Puts the current thread in the efficiency mode until the returned guard is dropped.
+///
+/// * On Windows, the thread is constrained to the efficiency cores and throttled with
+///   EcoQoS.
+/// * On Apple platforms, the `Utility` QoS class.
+/// * On Linux and Android, the idle policy, or the highest niceness if it can't be set.
+/// * On the other Unix platforms, the normal policy at its lowest priority.
+/// * May require privileges to be left on Linux and Android, as the niceness can't be
+///   lowered back without them.
+///
+/// For more info read [`crate::topology::prefer_efficiency_cores`] and
+/// `ScopedScheduling`.
+pub fn enter_efficiency_mode() -> Result<EfficiencyModeGuard, Error> {
+    enter_native()
+}
+
+#[cfg(target_vendor = "apple")]
+fn enter_native() -> Result<EfficiencyModeGuard, Error> {
+    use crate::darwin::{current_thread_qos, set_current_thread_qos_class, QosClass};
+
+    let qos = current_thread_qos()?;
+    set_current_thread_qos_class(QosClass::Utility)?;
+    Ok(EfficiencyModeGuard {
+        qos,
+        _not_send: std::marker::PhantomData,
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn enter_native() -> Result<EfficiencyModeGuard, Error> {
+    use crate::{
+        NormalThreadSchedulePolicy, ScopedScheduling, ThreadPriority, ThreadSchedulePolicy,
+    };
+
+    let idle = ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle);
+    let (scheduling, nice) = match ScopedScheduling::elevate(ThreadPriority::Min, idle) {
+        Ok(scheduling) => (Some(scheduling), None),
+        Err(_) => {
+            let tid = crate::thread_native_tid();
+            let previous = crate::process::getpriority(tid)?;
+            crate::process::setpriority(tid, crate::ThreadPriorityOsValue::NICE_LOWEST)?;
+            (None, Some((tid, previous)))
+        }
+    };
+    Ok(EfficiencyModeGuard {
+        _scheduling: scheduling,
+        nice,
+        _not_send: std::marker::PhantomData,
+    })
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "linux", target_os = "android", target_vendor = "apple"))
+))]
+fn enter_native() -> Result<EfficiencyModeGuard, Error> {
+    use crate::{
+        NormalThreadSchedulePolicy, ScopedScheduling, ThreadPriority, ThreadSchedulePolicy,
+    };
+
+    let scheduling = ScopedScheduling::elevate(
+        ThreadPriority::Min,
+        ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+    )?;
+    Ok(EfficiencyModeGuard {
+        _scheduling: Some(scheduling),
+        _not_send: std::marker::PhantomData,
+    })
+}
+
+#[cfg(windows)]
+fn enter_native() -> Result<EfficiencyModeGuard, Error> {
+    use crate::windows::{
+        current_thread_power_throttling, set_current_thread_power_throttling, ThreadPowerThrottling,
+    };
+
+    let throttling = current_thread_power_throttling()?;
+    let affinity = crate::topology::prefer_efficiency_cores()?;
+    if let Err(e) = set_current_thread_power_throttling(ThreadPowerThrottling::EcoQoS) {
+        let _ = crate::affinity::set_current_thread_affinity(&affinity);
+        return Err(e);
+    }
+    Ok(EfficiencyModeGuard {
+        throttling,
+        affinity,
+        _not_send: std::marker::PhantomData,
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn enter_native() -> Result<EfficiencyModeGuard, Error> {
+    Err(Error::Unsupported(
+        "The efficiency mode is not supported on this platform",
+    ))
+}
+
+impl Drop for EfficiencyModeGuard {
+    fn drop(&mut self) {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if let Some((tid, nice)) = self.nice {
+            if let Err(e) = crate::process::setpriority(tid, nice) {
+                log::warn!("Couldn't restore the niceness of the thread: {:?}", e);
+            }
+        }
+        #[cfg(target_vendor = "apple")]
+        if let Err(e) = crate::darwin::set_current_thread_qos(self.qos) {
+            log::warn!("Couldn't restore the QoS of the thread: {:?}", e);
+        }
+        #[cfg(windows)]
+        {
+            if let Err(e) = crate::windows::set_current_thread_power_throttling(self.throttling) {
+                log::warn!(
+                    "Couldn't restore the power throttling of the thread: {:?}",
+                    e
+                );
+            }
+            if let Err(e) = crate::affinity::set_current_thread_affinity(&self.affinity) {
+                log::warn!("Couldn't restore the affinity of the thread: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn efficiency_mode_test() {
+        let thread = std::thread::spawn(|| {
+            let native = crate::thread_native_id();
+            let policy = crate::thread_schedule_policy_for(native).unwrap();
+            {
+                let _efficiency = enter_efficiency_mode().unwrap();
+                assert_eq!(
+                    crate::thread_schedule_policy_for(native).unwrap(),
+                    crate::ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Idle)
+                );
+            }
+            assert_eq!(crate::thread_schedule_policy_for(native).unwrap(), policy);
+        });
+        thread.join().unwrap();
+    }
+}
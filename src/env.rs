@@ -0,0 +1,133 @@
+//! This module defines the loading of the scheduling settings from the
+//! environment variables.
+//!
+//! The deployments tune the threads without recompiling the application by
+//! setting the variables it reads, in the textual forms of the [`crate::parse`]
+//! module. A [`ThreadProperties`] is loaded from a set of variables sharing a
+//! prefix, see [`properties_from_env`].
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//!
+//! std::env::set_var("MYAPP_WORKER_PRIORITY", "max");
+//! assert_eq!(
+//!     ThreadPriority::from_env("MYAPP_WORKER_PRIORITY").unwrap(),
+//!     Some(ThreadPriority::Max)
+//! );
+//! assert_eq!(ThreadPriority::from_env("MYAPP_UNSET_PRIORITY").unwrap(), None);
+//! ```
+
+use std::str::FromStr;
+
+use crate::properties::ThreadProperties;
+use crate::{Error, ThreadPriority};
+
+/// Reads and parses the environment variable, `None` if it isn't set.
+///
+/// The values which don't parse, or aren't valid unicode, are an [`Error::Priority`].
+pub fn var<T: FromStr<Err = &'static str>>(name: &str) -> Result<Option<T>, Error> {
+    match std::env::var(name) {
+        Ok(value) => value.parse().map(Some).map_err(Error::Priority),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(std::env::VarError::NotUnicode(_)) => Err(Error::Priority(
+            "The environment variable is not valid unicode.",
+        )),
+    }
+}
+
+impl ThreadPriority {
+    /// Reads the priority from the environment variable, `None` if it isn't set.
+    /// For more info read [`var`].
+    pub fn from_env(name: &str) -> Result<Option<Self>, Error> {
+        var(name)
+    }
+}
+
+#[cfg(unix)]
+impl crate::parse::Scheduling {
+    /// Reads the scheduling, like `fifo:80`, from the environment variable, `None` if it
+    /// isn't set.
+    /// For more info read [`var`].
+    pub fn from_env(name: &str) -> Result<Option<Self>, Error> {
+        var(name)
+    }
+}
+
+/// Loads the properties from the environment variables of the prefix, the unset ones
+/// being left unset in the properties:
+///
+/// * `<prefix>_PRIORITY`, the priority, like `max` or `80`.
+/// * `<prefix>_POLICY`, the unix policy, like `fifo`.
+/// * `<prefix>_AFFINITY`, the list of CPUs, like `0-3,8`.
+/// * `<prefix>_NICE`, the niceness, on Linux and Android.
+/// * `<prefix>_IO_PRIORITY`, the I/O priority, like `low`.
+///
+/// ```rust
+/// use thread_priority::env::properties_from_env;
+///
+/// std::env::set_var("MYAPP_WORKER_AFFINITY", "0");
+/// std::env::set_var("MYAPP_WORKER_PRIORITY", "min");
+/// let properties = properties_from_env("MYAPP_WORKER").unwrap();
+/// assert!(properties.apply_to_current().is_ok());
+/// ```
+pub fn properties_from_env(prefix: &str) -> Result<ThreadProperties, Error> {
+    let name = |suffix: &str| format!("{}_{}", prefix, suffix);
+    let mut properties = ThreadProperties::default();
+    if let Some(priority) = var::<ThreadPriority>(&name("PRIORITY"))? {
+        properties = properties.priority(priority);
+    }
+    #[cfg(unix)]
+    if let Some(policy) = var::<crate::ThreadSchedulePolicy>(&name("POLICY"))? {
+        properties = properties.policy(policy);
+    }
+    if let Some(cpus) = var(&name("AFFINITY"))? {
+        properties = properties.affinity(cpus);
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if let Some(nice) = var::<Nice>(&name("NICE"))? {
+        properties = properties.nice(nice.0);
+    }
+    if let Some(priority) = var(&name("IO_PRIORITY"))? {
+        properties = properties.io_priority(priority);
+    }
+    Ok(properties)
+}
+
+impl ThreadProperties {
+    /// Loads the properties from the environment variables of the prefix.
+    /// For more info read [`properties_from_env`].
+    pub fn from_env(prefix: &str) -> Result<Self, Error> {
+        properties_from_env(prefix)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct Nice(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl FromStr for Nice {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse() {
+            Ok(nice) if (-20..=19).contains(&nice) => Ok(Nice(nice)),
+            _ => Err("The niceness is not a number from -20 to 19"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties_from_env_test() {
+        std::env::set_var("THREAD_PRIORITY_TEST_PRIORITY", "min");
+        std::env::set_var("THREAD_PRIORITY_TEST_IO_PRIORITY", "fastest");
+        assert!(properties_from_env("THREAD_PRIORITY_TEST").is_err());
+        std::env::set_var("THREAD_PRIORITY_TEST_IO_PRIORITY", "normal");
+        assert!(properties_from_env("THREAD_PRIORITY_TEST").is_ok());
+    }
+}
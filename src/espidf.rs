@@ -0,0 +1,127 @@
+//! This module defines the ESP-IDF task priority control.
+//!
+//! The ESP-IDF threads are FreeRTOS tasks, scheduled preemptively by a native
+//! priority from `0`, the priority of the idle task, up to
+//! `configMAX_PRIORITIES - 1`, set with `vTaskPrioritySet`. The pthread layer of
+//! ESP-IDF doesn't change them after a task is created, this module provides the
+//! native priority of the current task and maps the crate's priorities onto it,
+//! the OS values being the native priorities.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::espidf::*;
+//!
+//! assert!(set_current_task_priority(ThreadPriority::Min).is_ok());
+//! assert_eq!(current_task_priority(), TASK_PRIORITY_IDLE);
+//! ```
+
+use std::convert::TryFrom;
+
+use crate::{
+    Error, NormalThreadSchedulePolicy, ScheduleParams, ThreadPriority, ThreadPriorityValue,
+    ThreadSchedulePolicy,
+};
+
+/// An alias type for a FreeRTOS task handle.
+pub type TaskHandle = *mut libc::c_void;
+
+extern "C" {
+    fn xTaskGetCurrentTaskHandle() -> TaskHandle;
+    fn vTaskPrioritySet(task: TaskHandle, priority: libc::c_uint);
+    fn uxTaskPriorityGet(task: TaskHandle) -> libc::c_uint;
+}
+
+/// The priority of the idle task, the lowest native priority.
+pub const TASK_PRIORITY_IDLE: u32 = 0;
+/// The highest native priority of the default `configMAX_PRIORITIES` of ESP-IDF, `25`.
+pub const TASK_PRIORITY_MAX: u32 = 24;
+
+/// Returns the handle of the current task.
+pub fn current_task_handle() -> TaskHandle {
+    unsafe { xTaskGetCurrentTaskHandle() }
+}
+
+/// Maps the priority onto a native task priority. The cross-platform values are
+/// spread over the whole native range, the OS values are the native priorities.
+pub fn task_priority_from(priority: ThreadPriority) -> Result<u32, Error> {
+    Ok(match priority {
+        ThreadPriority::Min => TASK_PRIORITY_IDLE,
+        ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => {
+            if p > 99 {
+                return Err(Error::Priority("The value is out of range [0; 99]."));
+            }
+            u32::from(p) * TASK_PRIORITY_MAX / 99
+        }
+        ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => {
+            if p > TASK_PRIORITY_MAX {
+                return Err(Error::Priority("The value is out of range [0; 24]."));
+            }
+            p
+        }
+        ThreadPriority::Max => TASK_PRIORITY_MAX,
+    })
+}
+
+/// Sets the native priority of the current task.
+///
+/// Based on `vTaskPrioritySet`.
+pub fn set_current_task_priority(priority: ThreadPriority) -> Result<(), Error> {
+    let priority = task_priority_from(priority)?;
+    unsafe { vTaskPrioritySet(std::ptr::null_mut(), priority as libc::c_uint) };
+    Ok(())
+}
+
+/// Returns the native priority of the current task.
+///
+/// Based on `uxTaskPriorityGet`.
+pub fn current_task_priority() -> u32 {
+    unsafe { uxTaskPriorityGet(std::ptr::null_mut()) as u32 }
+}
+
+fn ensure_current_thread(native: crate::ThreadId) -> Result<(), Error> {
+    match unsafe { libc::pthread_equal(native, libc::pthread_self()) } {
+        0 => Err(Error::Priority(
+            "The task priority can only be accessed for the current thread.",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Sets the native priority of the current task, the FreeRTOS tasks all share a single
+/// preemptive policy.
+pub(crate) fn set_thread_task_priority(
+    native: crate::ThreadId,
+    _policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    ensure_current_thread(native)?;
+    set_current_task_priority(priority)
+}
+
+pub(crate) fn thread_schedule_policy_param(
+    native: crate::ThreadId,
+) -> Result<(ThreadSchedulePolicy, ScheduleParams), Error> {
+    ensure_current_thread(native)?;
+    Ok((
+        ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+        ScheduleParams {
+            sched_priority: libc::c_int::try_from(current_task_priority())
+                .map_err(|_| Error::Ffi("Invalid task priority"))?,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_priority_from_test() {
+        let value = |p| ThreadPriority::Crossplatform(ThreadPriorityValue(p));
+        assert_eq!(task_priority_from(value(0)).unwrap(), TASK_PRIORITY_IDLE);
+        assert_eq!(task_priority_from(value(99)).unwrap(), TASK_PRIORITY_MAX);
+        assert!(task_priority_from(value(100)).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! This module defines the control of threads attached to an EVL real-time core
+//! (Xenomai 4).
+//!
+//! Threads attached to the core are scheduled by it instead of the Linux
+//! kernel. Their scheduling attributes are addressed by the file
+//! descriptor returned when attaching, and are set through the very same
+//! [`ThreadPriority`] and [`ThreadSchedulePolicy`] types the plain Linux
+//! threads use.
+//!
+//! Enabled by the `evl` feature, requires `libevl` to link against.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use thread_priority::*;
+//! use thread_priority::evl::*;
+//!
+//! let thread = attach_current_thread("rt-worker").unwrap();
+//! assert!(set_evl_thread_priority_and_policy(
+//!     thread,
+//!     ThreadPriority::Max,
+//!     ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo),
+//! )
+//! .is_ok());
+//! assert!(detach_current_thread().is_ok());
+//! ```
+
+use std::ffi::CString;
+
+use crate::unix::{
+    NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ScheduleParams, ThreadSchedulePolicy,
+};
+use crate::{Error, ThreadPriority};
+
+/// An alias type for the file descriptor of a thread attached to the EVL core.
+pub type EvlThreadFd = libc::c_int;
+
+const EVL_CLONE_PRIVATE: libc::c_int = 0;
+const SCHED_WEAK: libc::c_int = 43;
+
+/// Copy of libevl's `evl_sched_attrs` type
+#[repr(C)]
+#[derive(Debug, Default)]
+struct EvlSchedAttrs {
+    sched_policy: libc::c_int,
+    sched_priority: libc::c_int,
+    /// Policy-specific parameters (round-robin quantum, quota group or TP partition).
+    sched_u: [i64; 2],
+}
+
+#[link(name = "evl")]
+extern "C" {
+    fn evl_attach_thread(flags: libc::c_int, fmt: *const libc::c_char, ...) -> libc::c_int;
+    fn evl_detach_self() -> libc::c_int;
+    fn evl_get_self() -> libc::c_int;
+    fn evl_set_schedattr(efd: libc::c_int, attrs: *const EvlSchedAttrs) -> libc::c_int;
+    fn evl_get_schedattr(efd: libc::c_int, attrs: *mut EvlSchedAttrs) -> libc::c_int;
+}
+
+fn evl_result(ret: libc::c_int) -> Result<libc::c_int, Error> {
+    if ret < 0 {
+        Err(Error::OS(-ret))
+    } else {
+        Ok(ret)
+    }
+}
+
+fn policy_to_evl(policy: ThreadSchedulePolicy) -> Result<libc::c_int, Error> {
+    match policy {
+        ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo) => Ok(libc::SCHED_FIFO),
+        ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::RoundRobin) => {
+            Ok(libc::SCHED_RR)
+        }
+        ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => Err(
+            Error::Priority("Deadline scheduling is not supported by the EVL core."),
+        ),
+        ThreadSchedulePolicy::Normal(_) => Ok(SCHED_WEAK),
+    }
+}
+
+fn policy_from_evl(policy: libc::c_int) -> Result<ThreadSchedulePolicy, Error> {
+    match policy {
+        libc::SCHED_FIFO => Ok(ThreadSchedulePolicy::Realtime(
+            RealtimeThreadSchedulePolicy::Fifo,
+        )),
+        libc::SCHED_RR => Ok(ThreadSchedulePolicy::Realtime(
+            RealtimeThreadSchedulePolicy::RoundRobin,
+        )),
+        SCHED_WEAK => Ok(ThreadSchedulePolicy::Normal(
+            NormalThreadSchedulePolicy::Normal,
+        )),
+        _ => Err(Error::Ffi("Can't parse schedule policy from EVL")),
+    }
+}
+
+/// Attaches the current thread to the EVL core under the given name, returning the
+/// file descriptor the thread is addressed by.
+///
+/// The name must not contain null bytes (`\0`).
+pub fn attach_current_thread(name: &str) -> Result<EvlThreadFd, Error> {
+    let name = CString::new(name).map_err(|_| Error::Ffi("The name contains a null byte"))?;
+    let format = b"%s\0";
+    unsafe {
+        evl_result(evl_attach_thread(
+            EVL_CLONE_PRIVATE,
+            format.as_ptr() as *const libc::c_char,
+            name.as_ptr(),
+        ))
+    }
+}
+
+/// Detaches the current thread from the EVL core, so it is scheduled by the Linux
+/// kernel again.
+pub fn detach_current_thread() -> Result<(), Error> {
+    unsafe { evl_result(evl_detach_self()).map(|_| ()) }
+}
+
+/// Returns the file descriptor of the current thread if it is attached to the EVL core.
+pub fn evl_thread_native_id() -> Result<EvlThreadFd, Error> {
+    unsafe { evl_result(evl_get_self()) }
+}
+
+/// Sets the priority and schedule policy of a thread attached to the EVL core.
+///
+/// The normal policies are all mapped onto EVL's `SCHED_WEAK`, the deadline policy is
+/// not supported by the core.
+pub fn set_evl_thread_priority_and_policy(
+    efd: EvlThreadFd,
+    priority: ThreadPriority,
+    policy: ThreadSchedulePolicy,
+) -> Result<(), Error> {
+    let attrs = EvlSchedAttrs {
+        sched_policy: policy_to_evl(policy)?,
+        sched_priority: priority.to_posix(policy)?,
+        ..Default::default()
+    };
+    unsafe { evl_result(evl_set_schedattr(efd, &attrs)).map(|_| ()) }
+}
+
+/// Returns the schedule policy and parameters of a thread attached to the EVL core.
+pub fn evl_thread_schedule_policy_param(
+    efd: EvlThreadFd,
+) -> Result<(ThreadSchedulePolicy, ScheduleParams), Error> {
+    let mut attrs = EvlSchedAttrs::default();
+    unsafe {
+        evl_result(evl_get_schedattr(efd, &mut attrs))?;
+    }
+    Ok((
+        policy_from_evl(attrs.sched_policy)?,
+        ScheduleParams {
+            sched_priority: attrs.sched_priority,
+        },
+    ))
+}
@@ -0,0 +1,283 @@
+//! This module defines the FreeBSD thread control.
+//!
+//! Besides the POSIX schedule policies, FreeBSD schedules the threads by their
+//! priority class: realtime, time-sharing or idle, set with `rtprio_thread(2)`.
+//! The idle class, which runs a thread only when nothing else is runnable, is
+//! not reachable through the POSIX interface at all. This module provides the
+//! classes and maps the crate's policies and priorities onto them, as well as
+//! the `cpuset(2)` affinity of the threads addressed by their ids.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::freebsd::*;
+//!
+//! let rtprio = RtPrio::new(RtPrioClass::Idle, 20).unwrap();
+//! assert!(set_current_thread_rtprio(rtprio).is_ok());
+//! assert_eq!(current_thread_rtprio().unwrap(), rtprio);
+//! ```
+
+use std::convert::TryFrom;
+
+use crate::affinity::CpuSet;
+use crate::unix::errno;
+use crate::{
+    Error, NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ThreadPriority,
+    ThreadPriorityValue, ThreadSchedulePolicy,
+};
+
+/// The `RTP_PRIO_FIFO` class of `sys/rtprio.h`, the realtime class without the time
+/// slicing between the threads of the same priority.
+const RTP_PRIO_FIFO: libc::c_ushort = 8 | libc::RTP_PRIO_REALTIME;
+
+/// An alias type for the kernel id of a thread (lwpid).
+pub type LwpId = libc::lwpid_t;
+
+/// Returns the kernel id of the current thread.
+pub fn thread_lwpid() -> LwpId {
+    unsafe { libc::pthread_getthreadid_np() as LwpId }
+}
+
+/// The FreeBSD priority classes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RtPrioClass {
+    /// The realtime class, round-robin among the threads of the same priority.
+    Realtime,
+    /// The realtime class, first-in first-out among the threads of the same priority.
+    Fifo,
+    /// The time-sharing class of the normal threads.
+    Normal,
+    /// The idle class, for the threads that only run when nothing else is runnable.
+    Idle,
+}
+
+impl RtPrioClass {
+    fn to_native(self) -> libc::c_ushort {
+        match self {
+            RtPrioClass::Realtime => libc::RTP_PRIO_REALTIME,
+            RtPrioClass::Fifo => RTP_PRIO_FIFO,
+            RtPrioClass::Normal => libc::RTP_PRIO_NORMAL,
+            RtPrioClass::Idle => libc::RTP_PRIO_IDLE,
+        }
+    }
+
+    fn from_native(class: libc::c_ushort) -> Result<Self, Error> {
+        Ok(match class {
+            libc::RTP_PRIO_REALTIME => RtPrioClass::Realtime,
+            RTP_PRIO_FIFO => RtPrioClass::Fifo,
+            libc::RTP_PRIO_NORMAL => RtPrioClass::Normal,
+            libc::RTP_PRIO_IDLE => RtPrioClass::Idle,
+            _ => return Err(Error::Ffi("Can't parse the rtprio class")),
+        })
+    }
+}
+
+/// The priority class of a thread with the priority within the class. Note that unlike
+/// the POSIX priorities, `0` is the highest priority and `31` is the lowest one.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RtPrio {
+    /// The priority class.
+    pub class: RtPrioClass,
+    /// The priority within the class, from `0` (the highest) to `31` (the lowest).
+    pub priority: u16,
+}
+
+impl RtPrio {
+    /// Creates the class priority, checking the priority is in the range of the classes.
+    pub fn new(class: RtPrioClass, priority: u16) -> Result<Self, Error> {
+        if priority > libc::RTP_PRIO_MAX {
+            return Err(Error::Priority("The value is out of range [0; 31]."));
+        }
+        Ok(RtPrio { class, priority })
+    }
+
+    /// Maps the schedule policy and the priority onto a class priority: the realtime
+    /// policies onto the realtime classes, the idle policy onto the idle class and the
+    /// other normal policies onto the time-sharing class.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    /// use thread_priority::freebsd::*;
+    ///
+    /// let policy = ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle);
+    /// let rtprio = RtPrio::from_policy(policy, ThreadPriority::Max).unwrap();
+    /// assert_eq!(rtprio, RtPrio::new(RtPrioClass::Idle, 0).unwrap());
+    /// ```
+    pub fn from_policy(
+        policy: ThreadSchedulePolicy,
+        priority: ThreadPriority,
+    ) -> Result<Self, Error> {
+        let class = match policy {
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo) => RtPrioClass::Fifo,
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::RoundRobin) => {
+                RtPrioClass::Realtime
+            }
+            ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle) => RtPrioClass::Idle,
+            ThreadSchedulePolicy::Normal(_) => return RtPrio::new(RtPrioClass::Normal, 0),
+        };
+        let priority = match priority {
+            ThreadPriority::Min => libc::RTP_PRIO_MAX,
+            ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => {
+                if p > 99 {
+                    return Err(Error::Priority("The value is out of range [0; 99]."));
+                }
+                libc::RTP_PRIO_MAX - u16::from(p) * libc::RTP_PRIO_MAX / 99
+            }
+            ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => u16::try_from(p)
+                .map_err(|_| Error::Priority("The value is out of range [0; 31]."))?,
+            ThreadPriority::Max => libc::RTP_PRIO_MIN,
+        };
+        RtPrio::new(class, priority)
+    }
+}
+
+/// Sets the class priority of the thread with the given kernel id, `0` being the
+/// current thread.
+///
+/// * May require privileges
+///
+/// Based on `rtprio_thread(RTP_SET)`.
+pub fn set_thread_rtprio(lwpid: LwpId, rtprio: RtPrio) -> Result<(), Error> {
+    let mut native = libc::rtprio {
+        type_: rtprio.class.to_native(),
+        prio: rtprio.priority,
+    };
+    unsafe {
+        match libc::rtprio_thread(libc::RTP_SET, lwpid, &mut native) {
+            0 => Ok(()),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Sets the class priority of the current thread.
+/// For more info read [`set_thread_rtprio`].
+pub fn set_current_thread_rtprio(rtprio: RtPrio) -> Result<(), Error> {
+    set_thread_rtprio(0, rtprio)
+}
+
+/// Returns the class priority of the thread with the given kernel id, `0` being the
+/// current thread.
+///
+/// Based on `rtprio_thread(RTP_LOOKUP)`.
+pub fn thread_rtprio(lwpid: LwpId) -> Result<RtPrio, Error> {
+    let mut native = libc::rtprio { type_: 0, prio: 0 };
+    unsafe {
+        match libc::rtprio_thread(libc::RTP_LOOKUP, lwpid, &mut native) {
+            0 => Ok(RtPrio {
+                class: RtPrioClass::from_native(native.type_)?,
+                priority: native.prio,
+            }),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Returns the class priority of the current thread.
+/// For more info read [`thread_rtprio`].
+pub fn current_thread_rtprio() -> Result<RtPrio, Error> {
+    thread_rtprio(0)
+}
+
+/// Sets the class priority of the current thread from the schedule policy and the
+/// priority, the pthread handles don't tell the kernel ids of the other threads.
+pub(crate) fn set_thread_class_priority(
+    native: crate::ThreadId,
+    policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    if unsafe { libc::pthread_equal(native, libc::pthread_self()) } == 0 {
+        return Err(Error::Priority(
+            "The priority class can only be set for the current thread.",
+        ));
+    }
+    set_current_thread_rtprio(RtPrio::from_policy(policy, priority)?)
+}
+
+pub(crate) fn cpuset_cpus(
+    level: libc::cpulevel_t,
+    which: libc::cpuwhich_t,
+    id: libc::id_t,
+) -> Result<CpuSet, Error> {
+    unsafe {
+        let mut set: libc::cpuset_t = std::mem::zeroed();
+        match libc::cpuset_getaffinity(
+            level,
+            which,
+            id,
+            std::mem::size_of::<libc::cpuset_t>(),
+            &mut set,
+        ) {
+            0 => Ok(CpuSet::from_native(&set)),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Sets the set of CPUs the thread with the given kernel id is allowed to run on. Unlike
+/// [`set_thread_affinity`](crate::affinity::set_thread_affinity), the thread may belong
+/// to any process.
+///
+/// * May require privileges
+///
+/// Based on `cpuset_setaffinity(CPU_LEVEL_WHICH, CPU_WHICH_TID)`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::affinity::*;
+/// use thread_priority::freebsd::*;
+///
+/// assert!(set_thread_cpuset(thread_lwpid(), &CpuSet::single(0)).is_ok());
+/// assert_eq!(thread_cpuset(thread_lwpid()).unwrap(), CpuSet::single(0));
+/// ```
+pub fn set_thread_cpuset(lwpid: LwpId, cpus: &CpuSet) -> Result<(), Error> {
+    let set = cpus.to_native()?;
+    unsafe {
+        match libc::cpuset_setaffinity(
+            libc::CPU_LEVEL_WHICH,
+            libc::CPU_WHICH_TID,
+            lwpid as libc::id_t,
+            std::mem::size_of::<libc::cpuset_t>(),
+            &set,
+        ) {
+            0 => Ok(()),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Returns the set of CPUs the thread with the given kernel id is allowed to run on.
+///
+/// Based on `cpuset_getaffinity(CPU_LEVEL_WHICH, CPU_WHICH_TID)`.
+pub fn thread_cpuset(lwpid: LwpId) -> Result<CpuSet, Error> {
+    cpuset_cpus(
+        libc::CPU_LEVEL_WHICH,
+        libc::CPU_WHICH_TID,
+        lwpid as libc::id_t,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_policy_test() {
+        let fifo = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+        let value = |p| ThreadPriority::Crossplatform(ThreadPriorityValue(p));
+        assert_eq!(
+            RtPrio::from_policy(fifo, value(99)).unwrap(),
+            RtPrio::new(RtPrioClass::Fifo, 0).unwrap()
+        );
+        assert_eq!(
+            RtPrio::from_policy(fifo, value(0)).unwrap(),
+            RtPrio::new(RtPrioClass::Fifo, 31).unwrap()
+        );
+        assert!(RtPrio::from_policy(fifo, value(100)).is_err());
+        assert!(RtPrio::new(RtPrioClass::Idle, 32).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! This module defines the Fuchsia thread control.
+//!
+//! Fuchsia doesn't implement the POSIX thread scheduling, its threads are
+//! scheduled by the profiles applied to them, either of a fair priority or
+//! of deadline parameters. The components get the profiles from the
+//! `fuchsia.scheduler.ProfileProvider` protocol, and this module applies them
+//! to the threads and maps the crate's priorities onto the profile parameters.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use thread_priority::fuchsia::*;
+//!
+//! // Obtained from `fuchsia.scheduler.ProfileProvider`.
+//! let handle: ZxHandle = 0;
+//! let profile = unsafe { Profile::from_raw(handle) };
+//! assert!(set_current_thread_profile(&profile).is_ok());
+//! ```
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::{Error, ThreadPriority, ThreadPriorityValue};
+
+/// An alias type for a kernel object handle.
+pub type ZxHandle = u32;
+
+type ZxStatus = i32;
+
+const ZX_OK: ZxStatus = 0;
+const ZX_ERR_NOT_SUPPORTED: ZxStatus = -2;
+const ZX_ERR_NO_MEMORY: ZxStatus = -4;
+const ZX_ERR_INVALID_ARGS: ZxStatus = -10;
+const ZX_ERR_BAD_HANDLE: ZxStatus = -11;
+const ZX_ERR_ACCESS_DENIED: ZxStatus = -30;
+const ZX_HANDLE_INVALID: ZxHandle = 0;
+const ZX_PROFILE_INFO_FLAG_PRIORITY: u32 = 1 << 0;
+const ZX_PROFILE_INFO_FLAG_DEADLINE: u32 = 1 << 2;
+
+/// The lowest fair priority of a thread.
+pub const ZX_PRIORITY_LOWEST: u8 = 0;
+/// The default fair priority of a thread.
+pub const ZX_PRIORITY_DEFAULT: u8 = 16;
+/// The highest fair priority of a thread.
+pub const ZX_PRIORITY_HIGHEST: u8 = 31;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+union ZxProfileParams {
+    priority: [i32; 6],
+    // The capacity, the relative deadline and the period, in nanoseconds.
+    deadline: [i64; 3],
+}
+
+/// The `zx_profile_info_t` structure of `zircon/syscalls/profile.h`.
+#[repr(C)]
+struct ZxProfileInfo {
+    flags: u32,
+    padding: [u8; 4],
+    params: ZxProfileParams,
+    cpu_affinity_mask: [u64; 8],
+}
+
+#[link(name = "zircon")]
+extern "C" {
+    fn zx_thread_self() -> ZxHandle;
+    fn zx_object_set_profile(handle: ZxHandle, profile: ZxHandle, options: u32) -> ZxStatus;
+    fn zx_profile_create(
+        profile_resource: ZxHandle,
+        options: u32,
+        profile: *const ZxProfileInfo,
+        out: *mut ZxHandle,
+    ) -> ZxStatus;
+    fn zx_handle_close(handle: ZxHandle) -> ZxStatus;
+}
+
+/// Converts the Zircon status to the closest `errno` value.
+fn zx_error(status: ZxStatus) -> Error {
+    match status {
+        ZX_ERR_NOT_SUPPORTED => Error::OS(libc::ENOTSUP),
+        ZX_ERR_NO_MEMORY => Error::OS(libc::ENOMEM),
+        ZX_ERR_INVALID_ARGS => Error::OS(libc::EINVAL),
+        ZX_ERR_BAD_HANDLE => Error::OS(libc::EBADF),
+        ZX_ERR_ACCESS_DENIED => Error::OS(libc::EACCES),
+        _ => Error::Ffi("The Zircon call failed"),
+    }
+}
+
+/// The scheduling parameters of a profile.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ProfileParams {
+    /// The fair scheduling with the priority, from [`ZX_PRIORITY_LOWEST`] to
+    /// [`ZX_PRIORITY_HIGHEST`].
+    Priority(u8),
+    /// The deadline scheduling: the thread gets `capacity` of CPU time within
+    /// `relative_deadline` from the start of every `period`.
+    Deadline {
+        /// The CPU time needed in every period.
+        capacity: Duration,
+        /// The time the work has to be done in.
+        relative_deadline: Duration,
+        /// The period of the work.
+        period: Duration,
+    },
+}
+
+impl TryFrom<ThreadPriority> for ProfileParams {
+    type Error = Error;
+
+    fn try_from(priority: ThreadPriority) -> Result<Self, Self::Error> {
+        Ok(ProfileParams::Priority(match priority {
+            ThreadPriority::Min => ZX_PRIORITY_LOWEST,
+            ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => {
+                if p > 99 {
+                    return Err(Error::Priority("The value is out of range [0; 99]."));
+                }
+                (u16::from(p) * u16::from(ZX_PRIORITY_HIGHEST) / 99) as u8
+            }
+            ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => match u8::try_from(p) {
+                Ok(p) if p <= ZX_PRIORITY_HIGHEST => p,
+                _ => return Err(Error::Priority("The value is out of range [0; 31].")),
+            },
+            ThreadPriority::Max => ZX_PRIORITY_HIGHEST,
+        }))
+    }
+}
+
+impl ProfileParams {
+    fn to_native(self) -> Result<ZxProfileInfo, Error> {
+        let nanos = |duration: Duration| {
+            i64::try_from(duration.as_nanos())
+                .map_err(|_| Error::Priority("The duration is too long."))
+        };
+        let (flags, params) = match self {
+            ProfileParams::Priority(priority) => {
+                if priority > ZX_PRIORITY_HIGHEST {
+                    return Err(Error::Priority("The value is out of range [0; 31]."));
+                }
+                let mut params = [0; 6];
+                params[0] = i32::from(priority);
+                (
+                    ZX_PROFILE_INFO_FLAG_PRIORITY,
+                    ZxProfileParams { priority: params },
+                )
+            }
+            ProfileParams::Deadline {
+                capacity,
+                relative_deadline,
+                period,
+            } => (
+                ZX_PROFILE_INFO_FLAG_DEADLINE,
+                ZxProfileParams {
+                    deadline: [nanos(capacity)?, nanos(relative_deadline)?, nanos(period)?],
+                },
+            ),
+        };
+        Ok(ZxProfileInfo {
+            flags,
+            padding: [0; 4],
+            params,
+            cpu_affinity_mask: [0; 8],
+        })
+    }
+}
+
+/// An owned profile handle, closed when dropped.
+#[derive(Debug)]
+pub struct Profile {
+    handle: ZxHandle,
+}
+
+impl Profile {
+    /// Takes the ownership of the profile handle, like the one returned by
+    /// `fuchsia.scheduler.ProfileProvider`.
+    ///
+    /// # Safety
+    ///
+    /// The handle must be a valid profile handle, not owned by anything else.
+    pub unsafe fn from_raw(handle: ZxHandle) -> Self {
+        Profile { handle }
+    }
+
+    /// Creates a profile of the parameters. Needs the profile resource, which is only
+    /// given to the privileged components, the others get their profiles from
+    /// `fuchsia.scheduler.ProfileProvider`.
+    ///
+    /// # Safety
+    ///
+    /// The resource must be a valid handle.
+    pub unsafe fn create(profile_resource: ZxHandle, params: ProfileParams) -> Result<Self, Error> {
+        let info = params.to_native()?;
+        let mut handle = ZX_HANDLE_INVALID;
+        match zx_profile_create(profile_resource, 0, &info, &mut handle) {
+            ZX_OK => Ok(Profile { handle }),
+            e => Err(zx_error(e)),
+        }
+    }
+
+    /// Returns the raw profile handle, still owned by the profile.
+    pub fn as_raw(&self) -> ZxHandle {
+        self.handle
+    }
+}
+
+impl Drop for Profile {
+    fn drop(&mut self) {
+        unsafe {
+            zx_handle_close(self.handle);
+        }
+    }
+}
+
+/// Returns the handle of the current thread, owned by the runtime.
+pub fn thread_self() -> ZxHandle {
+    unsafe { zx_thread_self() }
+}
+
+/// Applies the profile to the thread.
+///
+/// Based on `zx_object_set_profile`.
+///
+/// # Safety
+///
+/// The thread must be a valid thread handle.
+pub unsafe fn set_thread_profile(thread: ZxHandle, profile: &Profile) -> Result<(), Error> {
+    match zx_object_set_profile(thread, profile.handle, 0) {
+        ZX_OK => Ok(()),
+        e => Err(zx_error(e)),
+    }
+}
+
+/// Applies the profile to the current thread.
+/// For more info read [`set_thread_profile`].
+pub fn set_current_thread_profile(profile: &Profile) -> Result<(), Error> {
+    unsafe { set_thread_profile(thread_self(), profile) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_params_test() {
+        let value = |p| ThreadPriority::Crossplatform(ThreadPriorityValue(p));
+        assert_eq!(
+            ProfileParams::try_from(value(0)).unwrap(),
+            ProfileParams::Priority(ZX_PRIORITY_LOWEST)
+        );
+        assert_eq!(
+            ProfileParams::try_from(value(99)).unwrap(),
+            ProfileParams::Priority(ZX_PRIORITY_HIGHEST)
+        );
+        assert!(ProfileParams::Priority(32).to_native().is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! This module defines the Haiku thread control.
+//!
+//! Haiku schedules the threads by a single native priority from `0` to `120`:
+//! the idle priority `0`, the time-sharing priorities up to `99` and the
+//! realtime priorities from `100` on, set with `set_thread_priority`. Its POSIX
+//! emulation doesn't reach all of them, this module provides the native
+//! priorities and maps the crate's policies and priorities onto them.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::haiku::*;
+//!
+//! assert!(set_current_native_thread_priority(libc::B_LOW_PRIORITY).is_ok());
+//! assert_eq!(current_native_thread_priority().unwrap(), libc::B_LOW_PRIORITY);
+//! ```
+
+use crate::{
+    Error, NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ScheduleParams,
+    ThreadPriority, ThreadPriorityValue, ThreadSchedulePolicy,
+};
+
+/// An alias type for the kernel id of a thread.
+pub type KernelThreadId = libc::thread_id;
+
+/// Returns the kernel id of the thread.
+pub fn kernel_thread_id(native: crate::ThreadId) -> KernelThreadId {
+    unsafe { libc::get_pthread_thread_id(native) }
+}
+
+/// Returns the kernel id of the current thread.
+pub fn current_kernel_thread_id() -> KernelThreadId {
+    unsafe { libc::find_thread(std::ptr::null()) }
+}
+
+/// Maps the schedule policy and the priority onto a native priority: the realtime
+/// policies onto the realtime priorities, the idle policy onto
+/// [`B_IDLE_PRIORITY`](libc::B_IDLE_PRIORITY) and the other normal policies onto the
+/// time-sharing priorities. The cross-platform values are spread over the range of the
+/// policy, the OS values are the native priorities.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::haiku::*;
+///
+/// let policy = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+/// let priority = native_priority_from(policy, ThreadPriority::Max).unwrap();
+/// assert_eq!(priority, libc::B_REAL_TIME_PRIORITY);
+/// ```
+pub fn native_priority_from(
+    policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<i32, Error> {
+    let (min, max) = match policy {
+        ThreadSchedulePolicy::Realtime(_) => {
+            (libc::B_FIRST_REAL_TIME_PRIORITY, libc::B_REAL_TIME_PRIORITY)
+        }
+        ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle) => {
+            return Ok(libc::B_IDLE_PRIORITY)
+        }
+        ThreadSchedulePolicy::Normal(_) => (
+            libc::B_LOWEST_ACTIVE_PRIORITY,
+            libc::B_FIRST_REAL_TIME_PRIORITY - 1,
+        ),
+    };
+    Ok(match priority {
+        ThreadPriority::Min => min,
+        ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => {
+            if p > 99 {
+                return Err(Error::Priority("The value is out of range [0; 99]."));
+            }
+            min + i32::from(p) * (max - min) / 99
+        }
+        ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => match p as i32 {
+            p if (libc::B_IDLE_PRIORITY..=libc::B_REAL_TIME_PRIORITY).contains(&p) => p,
+            _ => return Err(Error::Priority("The value is out of range [0; 120].")),
+        },
+        ThreadPriority::Max => max,
+    })
+}
+
+/// Sets the native priority of the thread with the given kernel id.
+///
+/// * May require privileges
+///
+/// Based on `set_thread_priority`.
+pub fn set_native_thread_priority(thread: KernelThreadId, priority: i32) -> Result<(), Error> {
+    if !(libc::B_IDLE_PRIORITY..=libc::B_REAL_TIME_PRIORITY).contains(&priority) {
+        return Err(Error::Priority("The value is out of range [0; 120]."));
+    }
+    unsafe {
+        // The previous priority is returned on success.
+        match libc::set_thread_priority(thread, priority) {
+            e if e < 0 => Err(Error::OS(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Sets the native priority of the current thread.
+/// For more info read [`set_native_thread_priority`].
+pub fn set_current_native_thread_priority(priority: i32) -> Result<(), Error> {
+    set_native_thread_priority(current_kernel_thread_id(), priority)
+}
+
+/// Returns the native priority of the thread with the given kernel id.
+///
+/// Based on `get_thread_info`.
+pub fn native_thread_priority(thread: KernelThreadId) -> Result<i32, Error> {
+    unsafe {
+        let mut info: libc::thread_info = std::mem::zeroed();
+        match libc::get_thread_info(thread, &mut info) {
+            libc::B_OK => Ok(info.priority),
+            e => Err(Error::OS(e)),
+        }
+    }
+}
+
+/// Returns the native priority of the current thread.
+/// For more info read [`native_thread_priority`].
+pub fn current_native_thread_priority() -> Result<i32, Error> {
+    native_thread_priority(current_kernel_thread_id())
+}
+
+/// Returns the schedule policy a native priority belongs to. The realtime threads aren't
+/// time-sliced, they are reported with the FIFO policy.
+pub fn policy_from_native(priority: i32) -> ThreadSchedulePolicy {
+    match priority {
+        libc::B_IDLE_PRIORITY => ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle),
+        p if p >= libc::B_FIRST_REAL_TIME_PRIORITY => {
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo)
+        }
+        _ => ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+    }
+}
+
+pub(crate) fn set_thread_policy_priority(
+    native: crate::ThreadId,
+    policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    set_native_thread_priority(
+        kernel_thread_id(native),
+        native_priority_from(policy, priority)?,
+    )
+}
+
+pub(crate) fn thread_schedule_policy_param(
+    native: crate::ThreadId,
+) -> Result<(ThreadSchedulePolicy, ScheduleParams), Error> {
+    let priority = native_thread_priority(kernel_thread_id(native))?;
+    Ok((
+        policy_from_native(priority),
+        ScheduleParams {
+            sched_priority: priority,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_priority_from_test() {
+        let normal = ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal);
+        let value = |p| ThreadPriority::Crossplatform(ThreadPriorityValue(p));
+        assert_eq!(
+            native_priority_from(normal, value(0)).unwrap(),
+            libc::B_LOWEST_ACTIVE_PRIORITY
+        );
+        assert_eq!(native_priority_from(normal, value(99)).unwrap(), 99);
+        assert!(native_priority_from(normal, value(100)).is_err());
+        assert_eq!(
+            policy_from_native(native_priority_from(normal, ThreadPriority::Max).unwrap()),
+            normal
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! This module defines the hooks notified of the scheduling changes.
+//!
+//! The frameworks embedding the crate may want to audit the changes made by
+//! their plugins, or to forbid some of them. The registered hooks are called
+//! before every change the crate makes to a thread, and can veto it, and after
+//! it, with its result. The changes are the ones of the setters of the
+//! priorities, the policies, the affinities, the niceness and the I/O
+//! priorities; the restorations of the scoped and rolled back changes aren't
+//! notified.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::hooks::*;
+//! use std::sync::Arc;
+//!
+//! struct NoAffinity;
+//!
+//! impl ChangeHook for NoAffinity {
+//!     fn before(&self, change: &Change<'_>) -> Result<(), Error> {
+//!         match change {
+//!             Change::Affinity { .. } => Err(Error::Priority("The affinity is managed elsewhere.")),
+//!             _ => Ok(()),
+//!         }
+//!     }
+//! }
+//!
+//! let id = register_hook(Arc::new(NoAffinity));
+//! let cpus = thread_priority::affinity::CpuSet::single(0);
+//! assert!(thread_priority::affinity::set_current_thread_affinity(&cpus).is_err());
+//! assert!(unregister_hook(id));
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::affinity::CpuSet;
+use crate::io_priority::IoPriority;
+use crate::{Error, ThreadId};
+
+/// A change the crate is about to make, or has made, to a thread.
+#[derive(Debug, Copy, Clone)]
+pub enum Change<'a> {
+    /// The priority and the policy of a thread, see
+    /// [`crate::set_thread_priority_and_policy`].
+    #[cfg(unix)]
+    Scheduling {
+        /// The thread.
+        thread: ThreadId,
+        /// The priority.
+        priority: crate::ThreadPriority,
+        /// The policy.
+        policy: crate::ThreadSchedulePolicy,
+    },
+    /// The priority and the policy of a process, see
+    /// `set_process_schedule_policy`.
+    #[cfg(unix)]
+    ProcessScheduling {
+        /// The process, `0` for the calling one.
+        pid: crate::ProcessId,
+        /// The priority.
+        priority: crate::ThreadPriority,
+        /// The policy.
+        policy: crate::ThreadSchedulePolicy,
+    },
+    /// The priority of a thread, see [`crate::set_winapi_thread_priority`].
+    #[cfg(windows)]
+    Priority {
+        /// The thread.
+        thread: ThreadId,
+        /// The priority.
+        priority: crate::WinAPIThreadPriority,
+    },
+    /// The priority and the policy of a thread of any process addressed by its tid, see
+    /// `set_thread_priority_and_policy_for_tid`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    TidScheduling {
+        /// The thread.
+        tid: crate::ThreadTid,
+        /// The priority.
+        priority: crate::ThreadPriority,
+        /// The policy.
+        policy: crate::ThreadSchedulePolicy,
+    },
+    /// The affinity of a thread, see [`crate::affinity::set_thread_affinity`].
+    Affinity {
+        /// The thread.
+        thread: ThreadId,
+        /// The CPUs.
+        cpus: &'a CpuSet,
+    },
+    /// The affinity of a thread addressed by its tid, see
+    /// [`crate::properties::ThreadProperties::apply_to_tid`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    TidAffinity {
+        /// The thread.
+        tid: crate::ThreadTid,
+        /// The CPUs.
+        cpus: &'a CpuSet,
+    },
+    /// The niceness of a thread or of a process, addressed by its tid or pid.
+    #[cfg(unix)]
+    Nice {
+        /// The thread or the process.
+        who: crate::ProcessId,
+        /// The niceness.
+        nice: i32,
+    },
+    /// The I/O priority of a thread, see [`crate::io_priority::set_thread_io_priority`].
+    IoPriority {
+        /// The thread.
+        thread: ThreadId,
+        /// The I/O priority.
+        priority: IoPriority,
+    },
+    /// The I/O priority of a thread addressed by its tid, see
+    /// [`crate::properties::ThreadProperties::apply_to_tid`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    TidIoPriority {
+        /// The thread.
+        tid: crate::ThreadTid,
+        /// The I/O priority.
+        priority: IoPriority,
+    },
+}
+
+/// A hook notified of the scheduling changes.
+pub trait ChangeHook: Send + Sync {
+    /// Called before the change. An error vetoes the change, which fails with it, and
+    /// the hooks registered after this one aren't called.
+    fn before(&self, change: &Change<'_>) -> Result<(), Error> {
+        let _ = change;
+        Ok(())
+    }
+
+    /// Called after the change, with its result.
+    fn after(&self, change: &Change<'_>, result: &Result<(), Error>) {
+        let _ = (change, result);
+    }
+}
+
+/// The id of a registered hook, to unregister it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct HookId(u64);
+
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(0);
+static HOOKS: Mutex<Vec<(HookId, Arc<dyn ChangeHook>)>> = Mutex::new(Vec::new());
+
+/// Registers the hook, called after the ones registered before.
+pub fn register_hook(hook: Arc<dyn ChangeHook>) -> HookId {
+    let id = HookId(NEXT_HOOK_ID.fetch_add(1, Ordering::SeqCst));
+    hooks().push((id, hook));
+    id
+}
+
+/// Unregisters the hook. Returns whether it was registered.
+pub fn unregister_hook(id: HookId) -> bool {
+    let mut hooks = hooks();
+    let count = hooks.len();
+    hooks.retain(|(hook_id, _)| *hook_id != id);
+    hooks.len() != count
+}
+
+fn hooks() -> std::sync::MutexGuard<'static, Vec<(HookId, Arc<dyn ChangeHook>)>> {
+    // The hooks are called out of the lock, so a panicking one can't poison it.
+    HOOKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Applies the change, notifying the hooks before and after it.
+pub(crate) fn notified<F: FnOnce() -> Result<(), Error>>(
+    change: Change<'_>,
+    apply: F,
+) -> Result<(), Error> {
+    // The hooks may change the scheduling themselves, so they're called out of the lock.
+    let hooks: Vec<Arc<dyn ChangeHook>> = hooks().iter().map(|(_, hook)| hook.clone()).collect();
+    for hook in &hooks {
+        hook.before(&change)?;
+    }
+    let result = apply();
+    for hook in &hooks {
+        hook.after(&change, &result);
+    }
+    result
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    struct Counter(AtomicUsize);
+
+    impl ChangeHook for Counter {
+        fn after(&self, change: &Change<'_>, result: &Result<(), Error>) {
+            if let (Change::IoPriority { .. }, Ok(())) = (change, result) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[test]
+    fn notified_test() {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let id = register_hook(counter.clone());
+        let thread = std::thread::spawn(|| {
+            crate::io_priority::set_current_thread_io_priority(IoPriority::Normal)
+        });
+        thread.join().unwrap().unwrap();
+        assert!(unregister_hook(id));
+        assert!(counter.0.load(Ordering::SeqCst) >= 1);
+    }
+}
@@ -0,0 +1,322 @@
+//! This module defines the illumos and Solaris thread control.
+//!
+//! The Solaris-derived kernels schedule the threads by their scheduling class,
+//! each with its own priority range: the time-sharing (TS) class of the normal
+//! threads, the fixed-priority (FX) one and the realtime (RT) one. The classes
+//! are set with `priocntl(2)`, this module provides them and maps the crate's
+//! policies and priorities onto them.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::illumos::*;
+//!
+//! let info = class_info(SchedulingClass::FixedPriority).unwrap();
+//! assert!(set_lwp_class_priority(P_MYID, SchedulingClass::FixedPriority, info.max_priority).is_ok());
+//! ```
+
+use std::convert::TryFrom;
+
+use crate::unix::errno;
+use crate::{
+    Error, NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ThreadPriority,
+    ThreadPriorityValue, ThreadSchedulePolicy,
+};
+
+const PC_GETCID: libc::c_int = 0;
+const PC_SETPARMS: libc::c_int = 2;
+const PC_GETPARMS: libc::c_int = 3;
+const PC_CLNMSZ: usize = 16;
+const PC_CLINFOSZ: usize = 8;
+const PC_CLPARMSZ: usize = 8;
+
+const RT_TQINF: libc::c_int = -2;
+const RT_TQDEF: libc::c_int = -3;
+const FX_TQDEF: libc::c_int = -3;
+const TS_NOCHANGE: i16 = -32768;
+
+/// The id of the calling process or lightweight process (lwp).
+pub const P_MYID: libc::id_t = -1;
+
+/// An alias type for the id of a lightweight process, the kernel thread of a pthread,
+/// which number it shares.
+pub type LwpId = libc::id_t;
+
+#[repr(C)]
+struct PcInfo {
+    pc_cid: libc::id_t,
+    pc_clname: [libc::c_char; PC_CLNMSZ],
+    pc_clinfo: [libc::c_int; PC_CLINFOSZ],
+}
+
+#[repr(C)]
+struct PcParms {
+    pc_cid: libc::id_t,
+    pc_clparms: [libc::c_int; PC_CLPARMSZ],
+}
+
+#[repr(C)]
+struct RtParms {
+    rt_pri: i16,
+    rt_tqsecs: libc::c_uint,
+    rt_tqnsecs: libc::c_int,
+}
+
+#[repr(C)]
+struct TsParms {
+    ts_uprilim: i16,
+    ts_upri: i16,
+}
+
+#[repr(C)]
+struct FxParms {
+    fx_upri: i16,
+    fx_uprilim: i16,
+    fx_tqsecs: libc::c_uint,
+    fx_tqnsecs: libc::c_int,
+}
+
+extern "C" {
+    fn priocntl(idtype: libc::idtype_t, id: libc::id_t, cmd: libc::c_int, ...) -> libc::c_long;
+}
+
+/// The scheduling classes of the threads.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SchedulingClass {
+    /// The time-sharing class of the normal threads, priorities from `-max_priority` to
+    /// `max_priority`.
+    TimeSharing,
+    /// The fixed-priority class, priorities from `0` to `max_priority`.
+    FixedPriority,
+    /// The realtime class, priorities from `0` to `max_priority`.
+    ///
+    /// * Requires privileges
+    Realtime,
+}
+
+impl SchedulingClass {
+    fn name(self) -> &'static [u8] {
+        match self {
+            SchedulingClass::TimeSharing => b"TS",
+            SchedulingClass::FixedPriority => b"FX",
+            SchedulingClass::Realtime => b"RT",
+        }
+    }
+
+    fn min_priority(self, max_priority: i16) -> i16 {
+        match self {
+            SchedulingClass::TimeSharing => -max_priority,
+            _ => 0,
+        }
+    }
+}
+
+/// The system-wide properties of a scheduling class.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ClassInfo {
+    /// The id of the class.
+    pub id: libc::id_t,
+    /// The highest priority of the class.
+    pub max_priority: i16,
+}
+
+/// Returns the id and the priority range of the scheduling class.
+///
+/// Based on `priocntl(PC_GETCID)`.
+pub fn class_info(class: SchedulingClass) -> Result<ClassInfo, Error> {
+    let mut info = PcInfo {
+        pc_cid: 0,
+        pc_clname: [0; PC_CLNMSZ],
+        pc_clinfo: [0; PC_CLINFOSZ],
+    };
+    for (dst, src) in info.pc_clname.iter_mut().zip(class.name()) {
+        *dst = *src as libc::c_char;
+    }
+    unsafe {
+        if priocntl(0, 0, PC_GETCID, &mut info as *mut PcInfo) == -1 {
+            return Err(Error::OS(errno()));
+        }
+    }
+    // The class information of the three classes starts with the highest priority.
+    Ok(ClassInfo {
+        id: info.pc_cid,
+        max_priority: info.pc_clinfo[0] as i16,
+    })
+}
+
+/// Sets the scheduling class and the priority within the class of the lightweight
+/// process, [`P_MYID`] being the current one.
+///
+/// * May require privileges
+///
+/// Based on `priocntl(PC_SETPARMS)`.
+pub fn set_lwp_class_priority(
+    lwpid: LwpId,
+    class: SchedulingClass,
+    priority: i16,
+) -> Result<(), Error> {
+    set_class_parms(lwpid, class, priority, false)
+}
+
+fn set_class_parms(
+    lwpid: LwpId,
+    class: SchedulingClass,
+    priority: i16,
+    infinite_quantum: bool,
+) -> Result<(), Error> {
+    let info = class_info(class)?;
+    if priority < class.min_priority(info.max_priority) || priority > info.max_priority {
+        return Err(Error::Priority(
+            "The value is out of the range of the scheduling class.",
+        ));
+    }
+    let mut parms = PcParms {
+        pc_cid: info.id,
+        pc_clparms: [0; PC_CLPARMSZ],
+    };
+    unsafe {
+        let clparms = parms.pc_clparms.as_mut_ptr();
+        match class {
+            SchedulingClass::TimeSharing => {
+                *(clparms as *mut TsParms) = TsParms {
+                    ts_uprilim: TS_NOCHANGE,
+                    ts_upri: priority,
+                }
+            }
+            SchedulingClass::FixedPriority => {
+                *(clparms as *mut FxParms) = FxParms {
+                    fx_upri: priority,
+                    fx_uprilim: priority,
+                    fx_tqsecs: 0,
+                    fx_tqnsecs: FX_TQDEF,
+                }
+            }
+            SchedulingClass::Realtime => {
+                *(clparms as *mut RtParms) = RtParms {
+                    rt_pri: priority,
+                    rt_tqsecs: 0,
+                    rt_tqnsecs: if infinite_quantum { RT_TQINF } else { RT_TQDEF },
+                }
+            }
+        }
+        match priocntl(
+            libc::P_LWPID,
+            lwpid,
+            PC_SETPARMS,
+            &mut parms as *mut PcParms,
+        ) {
+            -1 => Err(Error::OS(errno())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the scheduling class and the priority within the class of the lightweight
+/// process, [`P_MYID`] being the current one.
+///
+/// Based on `priocntl(PC_GETPARMS)`.
+pub fn lwp_class_priority(lwpid: LwpId) -> Result<(SchedulingClass, i16), Error> {
+    let mut parms = PcParms {
+        // Any class.
+        pc_cid: -1,
+        pc_clparms: [0; PC_CLPARMSZ],
+    };
+    unsafe {
+        if priocntl(
+            libc::P_LWPID,
+            lwpid,
+            PC_GETPARMS,
+            &mut parms as *mut PcParms,
+        ) == -1
+        {
+            return Err(Error::OS(errno()));
+        }
+        let clparms = parms.pc_clparms.as_ptr();
+        for &class in &[
+            SchedulingClass::TimeSharing,
+            SchedulingClass::FixedPriority,
+            SchedulingClass::Realtime,
+        ] {
+            if class_info(class)?.id != parms.pc_cid {
+                continue;
+            }
+            let priority = match class {
+                SchedulingClass::TimeSharing => (*(clparms as *const TsParms)).ts_upri,
+                SchedulingClass::FixedPriority => (*(clparms as *const FxParms)).fx_upri,
+                SchedulingClass::Realtime => (*(clparms as *const RtParms)).rt_pri,
+            };
+            return Ok((class, priority));
+        }
+    }
+    Err(Error::Ffi("The scheduling class is not supported"))
+}
+
+/// Maps the schedule policy onto a scheduling class: the realtime policies onto the
+/// realtime class, the idle policy onto the lowest priority of the fixed-priority class
+/// and the other normal policies onto the time-sharing class.
+pub fn scheduling_class_for(policy: ThreadSchedulePolicy) -> SchedulingClass {
+    match policy {
+        ThreadSchedulePolicy::Realtime(_) => SchedulingClass::Realtime,
+        ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle) => {
+            SchedulingClass::FixedPriority
+        }
+        ThreadSchedulePolicy::Normal(_) => SchedulingClass::TimeSharing,
+    }
+}
+
+/// Maps the priority onto the priority range of the class, the cross-platform values
+/// are spread over the whole range, the OS values are the class priorities.
+fn class_priority(
+    class: SchedulingClass,
+    max_priority: i16,
+    priority: ThreadPriority,
+) -> Result<i16, Error> {
+    let min_priority = class.min_priority(max_priority);
+    Ok(match priority {
+        ThreadPriority::Min => min_priority,
+        ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => {
+            if p > 99 {
+                return Err(Error::Priority("The value is out of range [0; 99]."));
+            }
+            let range = i32::from(max_priority) - i32::from(min_priority);
+            (i32::from(min_priority) + i32::from(p) * range / 99) as i16
+        }
+        ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => i16::try_from(p as i32)
+            .map_err(|_| Error::Priority("The value is out of the range of the class."))?,
+        ThreadPriority::Max => max_priority,
+    })
+}
+
+/// Sets the scheduling class and priority of the thread from the schedule policy and
+/// the priority. The FIFO policy gets an infinite time quantum.
+pub(crate) fn set_thread_class_priority(
+    native: crate::ThreadId,
+    policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    let class = scheduling_class_for(policy);
+    let priority = match policy {
+        ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle) => 0,
+        _ => class_priority(class, class_info(class)?.max_priority, priority)?,
+    };
+    let infinite_quantum =
+        policy == ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+    set_class_parms(native as LwpId, class, priority, infinite_quantum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_priority_test() {
+        let value = |p| ThreadPriority::Crossplatform(ThreadPriorityValue(p));
+        let ts = SchedulingClass::TimeSharing;
+        assert_eq!(class_priority(ts, 60, value(0)).unwrap(), -60);
+        assert_eq!(class_priority(ts, 60, value(99)).unwrap(), 60);
+        let rt = SchedulingClass::Realtime;
+        assert_eq!(class_priority(rt, 59, ThreadPriority::Min).unwrap(), 0);
+        assert!(class_priority(rt, 59, value(100)).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! This module defines the snapshot of the scheduling of a thread.
+//!
+//! The bug reports about the latency are only useful with the scheduling the
+//! threads actually had, which is spread over several calls, each failing on
+//! its own. A [`ThreadSchedInfo`] captures all of it at once, every setting
+//! being `None` when it couldn't be read, to be logged with its `Debug` form or
+//! serialized with the `serde` feature.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::info::ThreadSchedInfo;
+//!
+//! let info = ThreadSchedInfo::capture_current();
+//! assert!(info.priority.is_some());
+//! println!("{:?}", info);
+//! ```
+//!
+//! The threads of the whole process, including the ones of the dependencies, are
+//! captured by [`list_process_threads`].
+
+use crate::affinity::CpuSet;
+use crate::io_priority::IoPriority;
+use crate::{Error, ThreadId, ThreadPriority};
+
+/// The scheduling of a thread, the settings which couldn't be read being `None`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreadSchedInfo {
+    /// The name of the thread, known for the current thread and the listed ones.
+    pub name: Option<String>,
+    /// The kernel thread id.
+    #[cfg(any(target_os = "linux", target_os = "android", windows))]
+    pub tid: Option<crate::ThreadTid>,
+    /// The schedule policy.
+    #[cfg(unix)]
+    pub policy: Option<crate::ThreadSchedulePolicy>,
+    /// The priority, with the deadline parameters under the deadline policy on Linux.
+    pub priority: Option<ThreadPriority>,
+    /// The niceness.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub nice: Option<i32>,
+    /// The CPUs the thread may run on.
+    pub affinity: Option<CpuSet>,
+    /// The I/O priority.
+    pub io_priority: Option<IoPriority>,
+    /// The QoS class and the relative priority.
+    #[cfg(target_vendor = "apple")]
+    pub qos: Option<crate::darwin::Qos>,
+    /// Whether the dynamic priority boost is enabled. The MMCSS task of a thread can't be
+    /// read back, so it isn't captured.
+    #[cfg(windows)]
+    pub priority_boost: Option<bool>,
+}
+
+impl ThreadSchedInfo {
+    /// Captures the scheduling of the thread.
+    ///
+    /// * On Linux, the tid, and so the niceness, the I/O priority and the deadline
+    ///   parameters, are only known for the current thread.
+    pub fn capture(native: ThreadId) -> Self {
+        #[cfg(unix)]
+        let (policy, priority) = match crate::get_thread_priority_and_policy(native) {
+            Ok((priority, policy)) => (Some(policy), Some(priority)),
+            Err(_) => (None, None),
+        };
+        #[cfg(not(unix))]
+        let priority = crate::get_thread_priority(native).ok();
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let tid = crate::unix::thread_tid_of(native).ok();
+        #[cfg(target_os = "linux")]
+        let priority = match (tid, policy) {
+            (
+                Some(tid),
+                Some(crate::ThreadSchedulePolicy::Realtime(
+                    crate::RealtimeThreadSchedulePolicy::Deadline,
+                )),
+            ) => match crate::unix::deadline_params_for_tid(tid) {
+                Ok(Some((runtime, deadline, period))) => {
+                    Some(ThreadPriority::Deadline(runtime, deadline, period))
+                }
+                _ => None,
+            },
+            _ => priority,
+        };
+        ThreadSchedInfo {
+            name: None,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            tid,
+            #[cfg(windows)]
+            tid: crate::windows::thread_tid_of(native).ok(),
+            #[cfg(unix)]
+            policy,
+            priority,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            nice: tid.and_then(|tid| crate::process::getpriority(tid).ok()),
+            affinity: crate::affinity::get_thread_affinity(native).ok(),
+            io_priority: crate::io_priority::thread_io_priority(native).ok(),
+            #[cfg(target_vendor = "apple")]
+            qos: crate::darwin::thread_qos(native).ok(),
+            #[cfg(windows)]
+            priority_boost: priority_boost(native),
+        }
+    }
+
+    /// Captures the scheduling of the current thread.
+    /// For more info read [`ThreadSchedInfo::capture`].
+    pub fn capture_current() -> Self {
+        ThreadSchedInfo {
+            name: std::thread::current().name().map(ToOwned::to_owned),
+            ..Self::capture(crate::thread_native_id())
+        }
+    }
+
+    /// Captures the scheduling of the thread of the current process addressed by its tid.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn capture_tid(tid: crate::ThreadTid) -> Self {
+        let pid = std::process::id() as crate::ProcessId;
+        let (policy, priority) = match crate::thread_schedule_policy_param_for_tid(pid, tid) {
+            Ok((policy, params)) => (Some(policy), Some(ThreadPriority::from_posix(params))),
+            Err(_) => (None, None),
+        };
+        #[cfg(target_os = "linux")]
+        let priority = match policy {
+            Some(crate::ThreadSchedulePolicy::Realtime(
+                crate::RealtimeThreadSchedulePolicy::Deadline,
+            )) => match crate::unix::deadline_params_for_tid(tid) {
+                Ok(Some((runtime, deadline, period))) => {
+                    Some(ThreadPriority::Deadline(runtime, deadline, period))
+                }
+                _ => None,
+            },
+            _ => priority,
+        };
+        ThreadSchedInfo {
+            name: None,
+            tid: Some(tid),
+            policy,
+            priority,
+            nice: crate::process::getpriority(tid).ok(),
+            affinity: crate::affinity::tid_affinity(tid).ok(),
+            io_priority: crate::io_priority::tid_io_priority(tid).ok(),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn priority_boost(native: ThreadId) -> Option<bool> {
+    // The handle is taken on trust, as by the other cross-platform functions.
+    unsafe { crate::windows::thread_priority_boost(native) }.ok()
+}
+
+/// Captures the scheduling of all the threads of the current process, with their names.
+///
+/// * On Linux and Android, the threads listed in `/proc/self/task`.
+/// * On Windows, based on `CreateToolhelp32Snapshot`, only the tid and the name are known
+///   for the threads which have exited since they have been listed.
+///
+/// For more info read [`crate::names::process_threads`].
+///
+/// ```rust
+/// use thread_priority::info::list_process_threads;
+///
+/// # #[cfg(any(target_os = "linux", target_os = "android", windows))]
+/// for info in list_process_threads().unwrap() {
+///     println!("{:?}: {:?} {:?}", info.name, info.policy, info.priority);
+/// }
+/// ```
+pub fn list_process_threads() -> Result<Vec<ThreadSchedInfo>, Error> {
+    Ok(crate::names::process_threads()?
+        .into_iter()
+        .map(|thread| {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let info = ThreadSchedInfo::capture_tid(thread.tid);
+            #[cfg(windows)]
+            let info = ThreadSchedInfo {
+                tid: Some(thread.tid),
+                ..crate::OwnedThreadHandle::open(std::process::id(), thread.tid)
+                    .map(|handle| ThreadSchedInfo::capture(handle.as_thread_id()))
+                    .unwrap_or_default()
+            };
+            #[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+            let info = ThreadSchedInfo::default();
+            ThreadSchedInfo {
+                name: Some(thread.name),
+                ..info
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_current_test() {
+        let info = ThreadSchedInfo::capture_current();
+        assert_eq!(
+            info.affinity,
+            crate::affinity::get_thread_affinity(crate::thread_native_id()).ok()
+        );
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(info.tid, Some(crate::thread_native_tid()));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn list_process_threads_test() {
+        let threads = list_process_threads().unwrap();
+        let current = threads
+            .iter()
+            .find(|info| info.tid == Some(crate::thread_native_tid()))
+            .unwrap();
+        assert_eq!(current.policy, ThreadSchedInfo::capture_current().policy);
+    }
+}
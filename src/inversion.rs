@@ -0,0 +1,284 @@
+//! This module defines the detection of the priority inversions.
+//!
+//! A high priority thread waiting for a lock held by a lower priority one
+//! runs at the priority of the holder, which undoes the configuration of the
+//! priorities without a trace. An [`InstrumentedMutex`] is a
+//! [`std::sync::Mutex`] which remembers the priority of the thread holding
+//! it, and reports an [`Inversion`] when a thread of a higher priority has to
+//! wait for it: as a warning of the `tracing` events with the `tracing`
+//! feature, of the logs otherwise, and to the handler set with
+//! [`set_inversion_handler`].
+//!
+//! Each locking reads the scheduling of the thread, so the instrumentation is
+//! meant for the debug builds and the tests.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::inversion::*;
+//!
+//! set_inversion_handler(|inversion| println!("{:?}", inversion));
+//! let mutex = InstrumentedMutex::new(0);
+//! *mutex.lock().unwrap() += 1;
+//! assert_eq!(mutex.into_inner().unwrap(), 1);
+//! ```
+
+use std::sync::{Arc, LockResult, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::time::{Duration, Instant};
+
+/// A thread of a higher priority having waited for a lock held by one of a lower priority.
+#[derive(Debug, Clone)]
+pub struct Inversion {
+    /// The name of the waiting thread.
+    pub waiter: Option<String>,
+    /// The name of the thread holding the lock.
+    pub holder: Option<String>,
+    /// How long the waiting thread has waited for the lock.
+    pub waited: Duration,
+}
+
+type InversionHandler = Arc<dyn Fn(&Inversion) + Send + Sync>;
+
+static HANDLER: Mutex<Option<InversionHandler>> = Mutex::new(None);
+
+fn handler() -> std::sync::MutexGuard<'static, Option<InversionHandler>> {
+    // The handler is replaced at once, a panic can't poison it.
+    HANDLER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets the closure called with the inversions detected by all the instrumented mutexes,
+/// replacing the previous one. It is called on the waiting thread, once it holds the lock.
+pub fn set_inversion_handler<F>(f: F)
+where
+    F: Fn(&Inversion) + Send + Sync + 'static,
+{
+    *handler() = Some(Arc::new(f));
+}
+
+/// Removes the handler of the inversions, which are still reported to the logs.
+pub fn clear_inversion_handler() {
+    *handler() = None;
+}
+
+fn report(inversion: &Inversion) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        waiter = ?inversion.waiter,
+        holder = ?inversion.holder,
+        waited = ?inversion.waited,
+        "Priority inversion"
+    );
+    #[cfg(not(feature = "tracing"))]
+    log::warn!("Priority inversion: {:?}", inversion);
+    let handler = handler().clone();
+    if let Some(handler) = handler {
+        handler(inversion);
+    }
+}
+
+/// The rank of the scheduling of the current thread, the higher the more it is favoured,
+/// `None` if it is unknown.
+#[cfg(unix)]
+fn current_rank() -> Option<i64> {
+    use crate::ThreadSchedulePolicy;
+
+    let (policy, params) = crate::thread_schedule_policy_param(crate::thread_native_id()).ok()?;
+    Some(match policy {
+        #[cfg(target_os = "linux")]
+        ThreadSchedulePolicy::Realtime(crate::RealtimeThreadSchedulePolicy::Deadline) => 2_000,
+        ThreadSchedulePolicy::Realtime(_) => 1_000 + i64::from(params.sched_priority),
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Idle) => -100,
+        // The niceness orders the normal threads.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        ThreadSchedulePolicy::Normal(_) => {
+            -i64::from(crate::process::getpriority(crate::thread_native_tid()).ok()?)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        ThreadSchedulePolicy::Normal(_) => i64::from(params.sched_priority),
+    })
+}
+
+#[cfg(windows)]
+fn current_rank() -> Option<i64> {
+    match crate::thread_priority().ok()? {
+        // The relative priorities are signed.
+        crate::ThreadPriority::Os(value) => Some(i64::from(value.as_raw() as i32)),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn current_rank() -> Option<i64> {
+    None
+}
+
+/// The thread holding an instrumented mutex.
+struct Holder {
+    name: Option<String>,
+    rank: Option<i64>,
+}
+
+impl Holder {
+    fn current() -> Self {
+        Holder {
+            name: std::thread::current().name().map(str::to_owned),
+            rank: current_rank(),
+        }
+    }
+}
+
+/// A [`std::sync::Mutex`] reporting the priority inversions.
+pub struct InstrumentedMutex<T: ?Sized> {
+    holder: Mutex<Option<Holder>>,
+    inner: Mutex<T>,
+}
+
+impl<T: ?Sized + std::fmt::Debug> std::fmt::Debug for InstrumentedMutex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstrumentedMutex")
+            .field("inner", &&self.inner)
+            .finish()
+    }
+}
+
+impl<T: Default> Default for InstrumentedMutex<T> {
+    fn default() -> Self {
+        InstrumentedMutex::new(T::default())
+    }
+}
+
+impl<T> InstrumentedMutex<T> {
+    /// Creates the mutex holding the value.
+    pub fn new(value: T) -> Self {
+        InstrumentedMutex {
+            holder: Mutex::new(None),
+            inner: Mutex::new(value),
+        }
+    }
+
+    /// Consumes the mutex and returns its value.
+    /// For more info read [`std::sync::Mutex::into_inner`].
+    pub fn into_inner(self) -> LockResult<T> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> InstrumentedMutex<T> {
+    fn holder(&self) -> MutexGuard<'_, Option<Holder>> {
+        // The holder is replaced at once, a panic can't poison it.
+        self.holder.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Acquires the mutex, blocking the current thread until it is able to do so, and
+    /// reports an inversion if it had to wait for a thread of a lower priority.
+    /// For more info read [`std::sync::Mutex::lock`].
+    pub fn lock(&self) -> LockResult<InstrumentedMutexGuard<'_, T>> {
+        let (result, current) = match self.inner.try_lock() {
+            Ok(guard) => (Ok(guard), None),
+            Err(TryLockError::Poisoned(e)) => (Err(e), None),
+            Err(TryLockError::WouldBlock) => {
+                let waiter = Holder::current();
+                let holder = self
+                    .holder()
+                    .as_ref()
+                    .map(|holder| (holder.name.clone(), holder.rank));
+                let started = Instant::now();
+                let result = self.inner.lock();
+                if let (Some(waiter_rank), Some((holder, Some(holder_rank)))) =
+                    (waiter.rank, holder)
+                {
+                    if waiter_rank > holder_rank {
+                        report(&Inversion {
+                            waiter: waiter.name.clone(),
+                            holder,
+                            waited: started.elapsed(),
+                        });
+                    }
+                }
+                (result, Some(waiter))
+            }
+        };
+        *self.holder() = Some(current.unwrap_or_else(Holder::current));
+        match result {
+            Ok(guard) => Ok(InstrumentedMutexGuard { mutex: self, guard }),
+            Err(e) => Err(PoisonError::new(InstrumentedMutexGuard {
+                mutex: self,
+                guard: e.into_inner(),
+            })),
+        }
+    }
+
+    /// Returns a mutable reference to the value, the mutex being borrowed mutably.
+    /// For more info read [`std::sync::Mutex::get_mut`].
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.inner.get_mut()
+    }
+}
+
+/// The guard of an [`InstrumentedMutex`], unlocking it when it is dropped.
+pub struct InstrumentedMutexGuard<'a, T: ?Sized> {
+    mutex: &'a InstrumentedMutex<T>,
+    guard: MutexGuard<'a, T>,
+}
+
+impl<T: ?Sized> std::ops::Deref for InstrumentedMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> std::ops::DerefMut for InstrumentedMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized + std::fmt::Debug> std::fmt::Debug for InstrumentedMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.guard, f)
+    }
+}
+
+impl<T: ?Sized> Drop for InstrumentedMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // The holder is cleared before the inner guard unlocks the mutex.
+        *self.mutex.holder() = None;
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inversion_test() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        set_inversion_handler(move |inversion| {
+            let _ = sender.send(inversion.clone());
+        });
+        let mutex = Arc::new(InstrumentedMutex::new(()));
+        let (locked_sender, locked) = std::sync::mpsc::channel();
+        let holder = std::thread::Builder::new()
+            .name("low".to_owned())
+            .spawn({
+                let mutex = mutex.clone();
+                move || {
+                    // Lowering the niceness doesn't need privileges.
+                    crate::process::setpriority(crate::thread_native_tid(), 19).unwrap();
+                    let _guard = mutex.lock().unwrap();
+                    locked_sender.send(()).unwrap();
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            })
+            .unwrap();
+        locked.recv().unwrap();
+        drop(mutex.lock().unwrap());
+        holder.join().unwrap();
+        clear_inversion_handler();
+        let inversion = receiver.try_recv().unwrap();
+        assert_eq!(inversion.holder.as_deref(), Some("low"));
+    }
+}
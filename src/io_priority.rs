@@ -0,0 +1,328 @@
+//! This module defines the thread I/O priority control.
+//!
+//! The background threads deprioritized for the CPU usually need their disk
+//! I/O demoted too, otherwise they still slow the foreground activity down.
+//! This module maps a few cross-platform I/O priorities onto the native ones:
+//! the I/O scheduling classes of `ioprio_set(2)` on Linux and Android, the
+//! I/O priority hints on Windows and the disk I/O policies on macOS and iOS.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use thread_priority::*;
+//! use thread_priority::io_priority::*;
+//!
+//! assert!(set_current_thread_io_priority(IoPriority::Idle).is_ok());
+//! assert_eq!(current_thread_io_priority().unwrap(), IoPriority::Idle);
+//! ```
+
+use crate::{Error, ThreadId};
+
+/// The I/O priority of a thread.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IoPriority {
+    /// The I/O is only served when there is no other I/O.
+    ///
+    /// * On Linux, the `IOPRIO_CLASS_IDLE` class.
+    /// * On Windows, the `IoPriorityVeryLow` hint.
+    /// * On macOS, the `IOPOL_THROTTLE` policy.
+    Idle,
+    /// The I/O is served after the normal one.
+    ///
+    /// * On Linux, the lowest level of the `IOPRIO_CLASS_BE` class.
+    /// * On Windows, the `IoPriorityLow` hint.
+    /// * On macOS, the `IOPOL_UTILITY` policy.
+    Low,
+    /// The default I/O priority.
+    ///
+    /// * On Linux, no class, the I/O priority follows the niceness of the thread.
+    /// * On Windows, the `IoPriorityNormal` hint.
+    /// * On macOS, the `IOPOL_DEFAULT` policy, the one of the process.
+    Normal,
+    /// The I/O is served before the normal one.
+    ///
+    /// * May require privileges
+    ///
+    /// * On Linux, the middle level of the `IOPRIO_CLASS_RT` class.
+    /// * On Windows, the `IoPriorityHigh` hint.
+    /// * On macOS, the `IOPOL_IMPORTANT` policy.
+    High,
+}
+
+/// Sets the I/O priority of the thread.
+///
+/// * May require privileges
+///
+/// * On Linux, based on `ioprio_set(IOPRIO_WHO_PROCESS)`, for the current thread only,
+///   as there is no way to get the tid of another thread from its pthread handle.
+/// * On Android, based on `ioprio_set(IOPRIO_WHO_PROCESS)`.
+/// * On Windows, based on `NtSetInformationThread(ThreadIoPriority)`.
+/// * On macOS and iOS, based on `setiopolicy_np(IOPOL_TYPE_DISK, IOPOL_SCOPE_THREAD)`, for
+///   the current thread only.
+pub fn set_thread_io_priority(native: ThreadId, priority: IoPriority) -> Result<(), Error> {
+    traced!(
+        DEBUG,
+        crate::hooks::notified(
+            crate::hooks::Change::IoPriority {
+                thread: native,
+                priority
+            },
+            || set_native_thread_io_priority(native, priority)
+        ),
+        "the I/O priority",
+        thread = ?native,
+        ?priority
+    )
+}
+
+/// Sets the I/O priority of the current thread.
+/// For more info read [`set_thread_io_priority`].
+pub fn set_current_thread_io_priority(priority: IoPriority) -> Result<(), Error> {
+    set_thread_io_priority(crate::thread_native_id(), priority)
+}
+
+/// Returns the I/O priority of the thread. The native priorities between the
+/// cross-platform ones are reported with the closest lower one, the Linux `IOPRIO_CLASS_RT`
+/// class with [`IoPriority::High`] at all the levels.
+/// For more info read [`set_thread_io_priority`].
+pub fn thread_io_priority(native: ThreadId) -> Result<IoPriority, Error> {
+    native_thread_io_priority(native)
+}
+
+/// Returns the I/O priority of the current thread.
+/// For more info read [`thread_io_priority`].
+pub fn current_thread_io_priority() -> Result<IoPriority, Error> {
+    thread_io_priority(crate::thread_native_id())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) mod ioprio {
+    pub const WHO_PROCESS: libc::c_int = 1;
+    pub const CLASS_SHIFT: libc::c_int = 13;
+    pub const CLASS_NONE: libc::c_int = 0;
+    pub const CLASS_RT: libc::c_int = 1;
+    pub const CLASS_BE: libc::c_int = 2;
+    pub const CLASS_IDLE: libc::c_int = 3;
+    pub const LEVEL_LOWEST: libc::c_int = 7;
+    pub const LEVEL_DEFAULT: libc::c_int = 4;
+}
+
+/// Encodes the I/O priority as an `ioprio` value, the class and the level within it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn ioprio_from(priority: IoPriority) -> libc::c_int {
+    let (class, level) = match priority {
+        IoPriority::Idle => (ioprio::CLASS_IDLE, 0),
+        IoPriority::Low => (ioprio::CLASS_BE, ioprio::LEVEL_LOWEST),
+        IoPriority::Normal => (ioprio::CLASS_NONE, 0),
+        IoPriority::High => (ioprio::CLASS_RT, ioprio::LEVEL_DEFAULT),
+    };
+    class << ioprio::CLASS_SHIFT | level
+}
+
+/// Decodes an `ioprio` value.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn io_priority_from(ioprio: libc::c_int) -> Result<IoPriority, Error> {
+    let level = ioprio & ((1 << ioprio::CLASS_SHIFT) - 1);
+    Ok(match ioprio >> ioprio::CLASS_SHIFT {
+        ioprio::CLASS_NONE => IoPriority::Normal,
+        ioprio::CLASS_RT => IoPriority::High,
+        ioprio::CLASS_BE if level > ioprio::LEVEL_DEFAULT => IoPriority::Low,
+        ioprio::CLASS_BE => IoPriority::Normal,
+        ioprio::CLASS_IDLE => IoPriority::Idle,
+        _ => return Err(Error::Ffi("Invalid I/O priority")),
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_native_thread_io_priority(native: ThreadId, priority: IoPriority) -> Result<(), Error> {
+    set_tid_io_priority(crate::unix::thread_tid_of(native)?, priority)
+}
+
+/// Sets the I/O priority of the thread addressed by its tid.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn set_tid_io_priority(
+    tid: crate::ThreadTid,
+    priority: IoPriority,
+) -> Result<(), Error> {
+    crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::IoprioSet)?;
+    unsafe {
+        match libc::syscall(
+            libc::SYS_ioprio_set,
+            ioprio::WHO_PROCESS,
+            tid,
+            ioprio_from(priority),
+        ) {
+            0 => Ok(()),
+            _ => Err(Error::OS(crate::unix::errno())),
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn native_thread_io_priority(native: ThreadId) -> Result<IoPriority, Error> {
+    tid_io_priority(crate::unix::thread_tid_of(native)?)
+}
+
+/// Returns the I/O priority of the thread addressed by its tid.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn tid_io_priority(tid: crate::ThreadTid) -> Result<IoPriority, Error> {
+    unsafe {
+        match libc::syscall(libc::SYS_ioprio_get, ioprio::WHO_PROCESS, tid) {
+            -1 => Err(Error::OS(crate::unix::errno())),
+            ioprio => io_priority_from(ioprio as libc::c_int),
+        }
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+fn ensure_current_thread(native: ThreadId) -> Result<(), Error> {
+    match unsafe { libc::pthread_equal(native, libc::pthread_self()) } {
+        0 => Err(Error::Priority(
+            "The I/O priority can only be accessed for the current thread.",
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+fn set_native_thread_io_priority(native: ThreadId, priority: IoPriority) -> Result<(), Error> {
+    use crate::darwin::IoPolicy;
+
+    ensure_current_thread(native)?;
+    crate::darwin::set_current_thread_io_policy(match priority {
+        IoPriority::Idle => IoPolicy::Throttle,
+        IoPriority::Low => IoPolicy::Utility,
+        IoPriority::Normal => IoPolicy::Default,
+        IoPriority::High => IoPolicy::Important,
+    })
+}
+
+#[cfg(target_vendor = "apple")]
+fn native_thread_io_priority(native: ThreadId) -> Result<IoPriority, Error> {
+    use crate::darwin::IoPolicy;
+
+    ensure_current_thread(native)?;
+    Ok(match crate::darwin::current_thread_io_policy()? {
+        IoPolicy::Throttle | IoPolicy::Passive => IoPriority::Idle,
+        IoPolicy::Utility => IoPriority::Low,
+        IoPolicy::Default | IoPolicy::Standard => IoPriority::Normal,
+        IoPolicy::Important => IoPriority::High,
+    })
+}
+
+/// The `THREADINFOCLASS` value of the I/O priority hint.
+#[cfg(windows)]
+const THREAD_IO_PRIORITY: u32 = 22;
+
+#[cfg(windows)]
+#[link(name = "ntdll")]
+extern "system" {
+    fn NtSetInformationThread(
+        thread: winapi::um::winnt::HANDLE,
+        class: u32,
+        information: *const libc::c_void,
+        length: u32,
+    ) -> i32;
+    fn NtQueryInformationThread(
+        thread: winapi::um::winnt::HANDLE,
+        class: u32,
+        information: *mut libc::c_void,
+        length: u32,
+        return_length: *mut u32,
+    ) -> i32;
+    fn RtlNtStatusToDosError(status: i32) -> u32;
+}
+
+#[cfg(windows)]
+fn set_native_thread_io_priority(native: ThreadId, priority: IoPriority) -> Result<(), Error> {
+    // The `IO_PRIORITY_HINT` values.
+    let hint: u32 = match priority {
+        IoPriority::Idle => 0,
+        IoPriority::Low => 1,
+        IoPriority::Normal => 2,
+        IoPriority::High => 3,
+    };
+    unsafe {
+        match NtSetInformationThread(
+            native,
+            THREAD_IO_PRIORITY,
+            &hint as *const u32 as *const libc::c_void,
+            std::mem::size_of::<u32>() as u32,
+        ) {
+            0 => Ok(()),
+            status => Err(Error::OS(RtlNtStatusToDosError(status) as i32)),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn native_thread_io_priority(native: ThreadId) -> Result<IoPriority, Error> {
+    let mut hint: u32 = 0;
+    unsafe {
+        match NtQueryInformationThread(
+            native,
+            THREAD_IO_PRIORITY,
+            &mut hint as *mut u32 as *mut libc::c_void,
+            std::mem::size_of::<u32>() as u32,
+            std::ptr::null_mut(),
+        ) {
+            0 => {}
+            status => return Err(Error::OS(RtlNtStatusToDosError(status) as i32)),
+        }
+    }
+    Ok(match hint {
+        0 => IoPriority::Idle,
+        1 => IoPriority::Low,
+        2 => IoPriority::Normal,
+        3 => IoPriority::High,
+        _ => return Err(Error::Ffi("Invalid I/O priority hint")),
+    })
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    windows
+)))]
+fn set_native_thread_io_priority(_native: ThreadId, _priority: IoPriority) -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "Setting the I/O priority is not supported on this platform",
+    ))
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    windows
+)))]
+fn native_thread_io_priority(_native: ThreadId) -> Result<IoPriority, Error> {
+    Err(Error::Unsupported(
+        "The I/O priority is not supported on this platform",
+    ))
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ioprio_roundtrip_test() {
+        for priority in [
+            IoPriority::Idle,
+            IoPriority::Low,
+            IoPriority::Normal,
+            IoPriority::High,
+        ]
+        .iter()
+        {
+            assert_eq!(io_priority_from(ioprio_from(*priority)).unwrap(), *priority);
+        }
+        assert_eq!(
+            io_priority_from(ioprio::CLASS_BE << ioprio::CLASS_SHIFT | 2).unwrap(),
+            IoPriority::Normal
+        );
+    }
+}
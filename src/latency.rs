@@ -0,0 +1,138 @@
+//! This module defines the measurement of the wake-up latency.
+//!
+//! How much a policy or a priority helps depends on the machine, its kernel
+//! and its load, so it is best measured. A thread spawned with the requested
+//! scheduling sleeps for a short interval over and over, and records how late
+//! it wakes up each time; the percentiles of these latencies tell the
+//! configurations apart.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::latency::*;
+//!
+//! let stats = measure_wakeup_latency_for_priority(ThreadPriority::Min, 10).unwrap();
+//! println!("p99: {:?}, max: {:?}", stats.p99, stats.max);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::{Error, ThreadBuilder, ThreadPriority};
+
+/// The interval the measuring thread sleeps for between the samples.
+pub const SLEEP_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The statistics of the wake-up latencies: how late the thread woke up after its sleeps.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyStats {
+    /// The number of samples.
+    pub samples: usize,
+    /// The lowest latency.
+    pub min: Duration,
+    /// The mean latency.
+    pub mean: Duration,
+    /// The median latency.
+    pub p50: Duration,
+    /// The 90th percentile.
+    pub p90: Duration,
+    /// The 99th percentile.
+    pub p99: Duration,
+    /// The highest latency.
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Computes the statistics of the latencies, `None` if there are none.
+    ///
+    /// ```rust
+    /// use thread_priority::latency::LatencyStats;
+    /// use std::time::Duration;
+    ///
+    /// let latencies = (1..=100).map(Duration::from_micros).collect();
+    /// let stats = LatencyStats::from_latencies(latencies).unwrap();
+    /// assert_eq!(stats.p90, Duration::from_micros(90));
+    /// assert_eq!(stats.max, Duration::from_micros(100));
+    /// ```
+    pub fn from_latencies(mut latencies: Vec<Duration>) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort_unstable();
+        // The nearest-rank percentile.
+        let percentile = |p: usize| latencies[(latencies.len() * p).div_ceil(100).max(1) - 1];
+        Some(LatencyStats {
+            samples: latencies.len(),
+            min: latencies[0],
+            mean: latencies.iter().sum::<Duration>() / latencies.len() as u32,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: latencies[latencies.len() - 1],
+        })
+    }
+}
+
+/// Measures the wake-up latency of a thread spawned with the policy and the priority,
+/// over the number of samples, each one sleeping for [`SLEEP_INTERVAL`]. The error of
+/// setting the scheduling of the thread is returned.
+///
+/// * May require privileges
+///
+/// For more info read [`crate::set_thread_priority_and_policy`].
+#[cfg(unix)]
+pub fn measure_wakeup_latency(
+    policy: crate::ThreadSchedulePolicy,
+    priority: ThreadPriority,
+    samples: usize,
+) -> Result<LatencyStats, Error> {
+    measure(
+        ThreadBuilder::default().policy(policy).priority(priority),
+        samples,
+    )
+}
+
+/// Measures the wake-up latency of a thread spawned with the priority.
+/// For more info read [`ThreadPriority::set_for_current`] and `measure_wakeup_latency`.
+pub fn measure_wakeup_latency_for_priority(
+    priority: ThreadPriority,
+    samples: usize,
+) -> Result<LatencyStats, Error> {
+    measure(ThreadBuilder::default().priority(priority), samples)
+}
+
+fn measure(builder: ThreadBuilder, samples: usize) -> Result<LatencyStats, Error> {
+    if samples == 0 {
+        return Err(Error::Priority("The number of samples must be positive."));
+    }
+    let thread = builder
+        .name("wakeup-latency")
+        .spawn(move |result| {
+            result?;
+            let latencies = (0..samples)
+                .map(|_| {
+                    let deadline = Instant::now() + SLEEP_INTERVAL;
+                    std::thread::sleep(SLEEP_INTERVAL);
+                    Instant::now().saturating_duration_since(deadline)
+                })
+                .collect();
+            Ok(LatencyStats::from_latencies(latencies).unwrap_or_default())
+        })?;
+    thread
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_wakeup_latency_test() {
+        let stats = measure_wakeup_latency_for_priority(ThreadPriority::Min, 5).unwrap();
+        assert_eq!(stats.samples, 5);
+        assert!(stats.min <= stats.p50 && stats.p50 <= stats.max);
+        assert!(measure_wakeup_latency_for_priority(ThreadPriority::Min, 0).is_err());
+    }
+}
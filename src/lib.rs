@@ -87,33 +87,217 @@
 #![warn(missing_docs)]
 #![deny(warnings)]
 
+// Emits an event of the result of a change with the `tracing` feature, at the level on
+// success and as a warning on failure, and evaluates to the result.
+#[cfg(feature = "tracing")]
+macro_rules! traced {
+    ($level:ident, $result:expr, $change:literal, $($field:tt)+) => {{
+        let result = $result;
+        match &result {
+            Ok(_) => tracing::event!(tracing::Level::$level, $($field)+, "Changed {}", $change),
+            Err(e) => tracing::event!(
+                tracing::Level::WARN,
+                $($field)+,
+                error = ?e,
+                "Couldn't change {}",
+                $change
+            ),
+        }
+        result
+    }};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! traced {
+    ($level:ident, $result:expr, $($rest:tt)+) => {
+        $result
+    };
+}
+
 #[cfg(unix)]
 pub mod unix;
 #[cfg(unix)]
 pub use unix::*;
 
+pub mod affinity;
+pub mod autotune;
+pub mod backend;
+pub mod cpu_time;
+pub mod defaults;
+pub mod efficiency;
+pub mod env;
+pub mod hooks;
+pub mod info;
+pub mod inversion;
+pub mod io_priority;
+pub mod latency;
+pub mod mapping;
+pub mod names;
+pub mod parse;
+pub mod pool;
+pub mod process;
+pub mod profiles;
+pub mod properties;
+pub mod realtime;
+pub mod registry;
+pub mod sync;
+pub mod topology;
+pub mod verify;
+pub mod watchdog;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod sandbox;
+
+#[cfg(target_os = "android")]
+pub mod android;
+#[cfg(target_vendor = "apple")]
+pub mod darwin;
+#[cfg(target_os = "espidf")]
+pub mod espidf;
+#[cfg(target_os = "freebsd")]
+pub mod freebsd;
+#[cfg(target_os = "fuchsia")]
+pub mod fuchsia;
+#[cfg(target_os = "haiku")]
+pub mod haiku;
+#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+pub mod illumos;
+#[cfg(any(
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "espidf",
+    target_os = "rtems"
+))]
+mod posix;
+#[cfg(target_os = "redox")]
+pub mod redox;
+#[cfg(target_os = "vxworks")]
+pub mod vxworks;
+
+#[cfg(all(target_os = "linux", feature = "evl"))]
+pub mod evl;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "crossbeam")]
+pub mod crossbeam;
+
+#[cfg(feature = "rayon")]
+pub mod rayon;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "macros")]
+pub use thread_priority_macros::{main, prioritized_thread};
+
 #[cfg(windows)]
 pub mod windows;
 #[cfg(windows)]
 pub use windows::*;
 
+#[cfg(not(any(unix, windows)))]
+pub mod unsupported;
+#[cfg(not(any(unix, windows)))]
+pub use unsupported::*;
+
 /// A error type
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let error = Error::Priority("The value is out of range [0; 99]");
+/// assert_eq!(error.to_string(), "Invalid priority: The value is out of range [0; 99]");
+/// let error: std::io::Error = Error::OS(1).into();
+/// assert_eq!(error.raw_os_error(), Some(1));
+/// let error: Error = std::io::Error::from_raw_os_error(1).into();
+/// assert!(matches!(error, Error::OS(1)));
+/// let error: Error = std::io::Error::new(std::io::ErrorKind::InvalidData, "Not UTF-8").into();
+/// assert!(matches!(error, Error::Ffi(_)));
+/// ```
 #[derive(Debug, Copy, Clone)]
 pub enum Error {
     /// A value which describes why it is impossible to use such a priority.
     Priority(&'static str),
-    /// Target OS' error type. In most systems it is an integer which
-    /// later should be used with target OS' API for understanding the value.
+    /// Target OS' error code: the `errno` value on Unix, the `GetLastError` value on Windows,
+    /// whether the failed call has returned it or has set it. Its description is the one of
+    /// [`std::io::Error::from_raw_os_error`].
     OS(i32),
     /// FFI failure.
     Ffi(&'static str),
+    /// The operation isn't supported on the target platform.
+    Unsupported(&'static str),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Priority(s) => write!(f, "Invalid priority: {}", s),
+            Error::OS(code) => write!(f, "{}", std::io::Error::from_raw_os_error(*code)),
+            Error::Ffi(s) => write!(f, "FFI failure: {}", s),
+            Error::Unsupported(s) => write!(f, "Unsupported: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::OS(code) => std::io::Error::from_raw_os_error(code),
+            Error::Priority(_) => std::io::Error::new(std::io::ErrorKind::InvalidInput, error),
+            Error::Ffi(_) => std::io::Error::other(error),
+            Error::Unsupported(_) => std::io::Error::new(std::io::ErrorKind::Unsupported, error),
+        }
+    }
+}
+
+/// The errors of the standard library calls, like reading the `/proc` files or spawning the
+/// threads, are [`Error::OS`] when they carry an OS error code, so that the code is never
+/// made up, and [`Error::Ffi`] or [`Error::Unsupported`] otherwise.
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        match (error.raw_os_error(), error.kind()) {
+            (Some(code), _) => Error::OS(code),
+            (None, std::io::ErrorKind::Unsupported) => {
+                Error::Unsupported("The operation is not supported by the OS")
+            }
+            (None, std::io::ErrorKind::InvalidData) => Error::Ffi("The OS has returned invalid data"),
+            (None, _) => Error::Ffi("The operation has failed without an OS error code"),
+        }
+    }
+}
+
+impl Error {
+    /// Explains a permission error with the hints on how to grant the permission, the other
+    /// errors have no hints.
+    ///
+    /// # Usage
+    ///
+    /// ```rust,no_run
+    /// use thread_priority::*;
+    ///
+    /// if let Err(e) = set_current_thread_priority(ThreadPriority::Max) {
+    ///     for hint in e.diagnose() {
+    ///         println!("{}", hint);
+    ///     }
+    /// }
+    /// ```
+    pub fn diagnose(&self) -> Vec<realtime::PermissionHint> {
+        realtime::diagnose(self)
+    }
 }
 
 /// Platform-independent thread priority value.
-/// Should be in `[0; 100)` range. The higher the number is - the higher
+/// Should be in `[0; 99]` range. The higher the number is - the higher
 /// the priority.
 ///
-/// The only way to create such a value is a safe conversion from an 8-byte
+/// The value is created with [`ThreadPriorityValue::new`], at compile time with the
+/// [`thread_priority_value!`] macro, or with a safe conversion from an 8-byte
 /// unsigned integer ([`u8`]):
 ///
 /// ```rust
@@ -139,18 +323,71 @@ pub enum Error {
 /// assert_eq!(raw_value, 0);
 /// ```
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
 pub struct ThreadPriorityValue(u8);
 
-impl std::convert::TryFrom<u8> for ThreadPriorityValue {
-    type Error = &'static str;
+impl ThreadPriorityValue {
+    /// The lowest value.
+    pub const MIN: Self = ThreadPriorityValue(0);
+    /// The highest value.
+    pub const MAX: Self = ThreadPriorityValue(99);
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        if (0..=99).contains(&value) {
-            Ok(Self(value))
+    /// Creates the value, which must be from `0` to `99`.
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// assert!(ThreadPriorityValue::new(99).is_ok());
+    /// assert!(ThreadPriorityValue::new(100).is_err());
+    /// ```
+    pub const fn new(value: u8) -> Result<Self, &'static str> {
+        if value <= 99 {
+            Ok(ThreadPriorityValue(value))
         } else {
             Err("The value is not in the range of [0;99]")
         }
     }
+
+    /// Creates the value, panicking if it isn't from `0` to `99`, which fails the build
+    /// when evaluated at compile time, see [`thread_priority_value!`].
+    pub const fn new_const(value: u8) -> Self {
+        match Self::new(value) {
+            Ok(value) => value,
+            Err(_) => panic!("The value is not in the range of [0;99]"),
+        }
+    }
+}
+
+/// Creates a [`ThreadPriorityValue`] at compile time, so the values out of the `[0; 99]`
+/// range fail the build.
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let priority = ThreadPriority::Crossplatform(thread_priority_value!(50));
+/// ```
+///
+/// ```rust,compile_fail
+/// let value = thread_priority::thread_priority_value!(100);
+/// ```
+#[macro_export]
+macro_rules! thread_priority_value {
+    ($value:expr) => {{
+        const VALUE: $crate::ThreadPriorityValue = $crate::ThreadPriorityValue::new_const($value);
+        VALUE
+    }};
+}
+
+impl std::convert::TryFrom<u8> for ThreadPriorityValue {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
 }
 
 // The From<u8> is unsafe, so there is a TryFrom instead.
@@ -163,11 +400,54 @@ impl std::convert::Into<u8> for ThreadPriorityValue {
 }
 
 /// Platform-specific thread priority value.
+///
+/// On the Apple platforms the value encodes a QoS class with its relative priority, see
+/// `darwin::Qos`.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct ThreadPriorityOsValue(u32);
 
+impl ThreadPriorityOsValue {
+    /// Returns the raw value, as the OS APIs take it. On Windows, the levels below the
+    /// normal one are negative, reinterpreted as unsigned.
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// assert_eq!(ThreadPriorityOsValue::default().as_raw(), 0);
+    /// ```
+    pub const fn as_raw(self) -> u32 {
+        self.0
+    }
+}
+
 /// Thread priority enumeration.
+///
+/// The priorities are ordered as `Min`, the cross-platform values by their value, the
+/// OS-specific values by their value, the deadline parameters and `Max`, so that
+/// `Min < Crossplatform(n) < Max` holds for any `n`. The OS-specific values can't be
+/// compared with the cross-platform ones without the policy they are for, see
+/// `ThreadPriority::cmp_with_policy` on unix.
+///
+/// ```rust
+/// use thread_priority::*;
+/// use std::convert::TryInto;
+///
+/// let mut priorities = vec![
+///     ThreadPriority::Max,
+///     ThreadPriority::Crossplatform(50u8.try_into().unwrap()),
+///     ThreadPriority::Min,
+/// ];
+/// priorities.sort();
+/// assert_eq!(priorities[0], ThreadPriority::Min);
+/// assert_eq!(priorities[2], ThreadPriority::Max);
+/// ```
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ThreadPriority {
     /// Holds a value representing the minimum possible priority.
     Min,
@@ -226,6 +506,7 @@ impl Thread {
 }
 
 /// A copy of the [`std::thread::Builder`] builder allowing to set priority settings.
+/// The default properties of [`defaults`] are applied to the spawned threads first.
 ///
 /// ```rust
 /// use thread_priority::*;
@@ -253,23 +534,26 @@ impl Thread {
 #[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ThreadBuilder {
     name: Option<String>,
+    os_name: Option<String>,
     stack_size: Option<usize>,
     priority: Option<ThreadPriority>,
 
     #[cfg(unix)]
     policy: Option<ThreadSchedulePolicy>,
+    affinity: Option<affinity::CpuSet>,
 
     #[cfg(windows)]
     winapi_priority: Option<WinAPIThreadPriority>,
     #[cfg(windows)]
-    boost_enabled: bool,
+    boost_enabled: Option<bool>,
     #[cfg(windows)]
     ideal_processor: Option<IdealProcessor>,
 }
 
 impl ThreadBuilder {
-    /// Names the thread-to-be. Currently the name is used for identification
-    /// only in panic messages.
+    /// Names the thread-to-be. The name is used for identification in panic
+    /// messages, and is set as the name of the OS thread where the standard
+    /// library supports it, unless a [`ThreadBuilder::os_name`] is set.
     ///
     /// The name must not contain null bytes (`\0`).
     ///
@@ -280,6 +564,31 @@ impl ThreadBuilder {
         self
     }
 
+    /// Sets the name of the OS thread, shown by the debuggers and the profilers, when the
+    /// thread starts, like a short one for the 15 bytes Linux keeps. Failing to set it
+    /// fails the thread like failing to set the priority does.
+    ///
+    /// For more information, see [`crate::names::set_thread_os_name`].
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// # #[cfg(any(target_os = "linux", target_os = "android", windows))] {
+    /// let thread = ThreadBuilder::default()
+    ///     .name("audio-render-thread-0")
+    ///     .os_name("audio-0")
+    ///     .stack_size(1024 * 1024usize)
+    ///     .priority(ThreadPriority::Min)
+    ///     .spawn(|result| assert!(result.is_ok()))
+    ///     .unwrap();
+    /// thread.join().unwrap();
+    /// # }
+    /// ```
+    pub fn os_name<VALUE: Into<String>>(mut self, value: VALUE) -> Self {
+        self.os_name = Some(value.into());
+        self
+    }
+
     /// Sets the size of the stack (in bytes) for the new thread.
     ///
     /// The actual stack size may be greater than this value if
@@ -311,6 +620,14 @@ impl ThreadBuilder {
         self
     }
 
+    /// The CPUs the thread may run on, set after the scheduling.
+    ///
+    /// For more information, see [`crate::affinity::set_current_thread_affinity`].
+    pub fn affinity(mut self, value: affinity::CpuSet) -> Self {
+        self.affinity = Some(value);
+        self
+    }
+
     /// The WinAPI priority representation.
     ///
     /// For more information, see
@@ -330,7 +647,7 @@ impl ThreadBuilder {
     /// [`crate::windows::set_thread_priority_boost`].
     #[cfg(windows)]
     pub fn boost_enabled(mut self, value: bool) -> Self {
-        self.boost_enabled = value;
+        self.boost_enabled = Some(value);
         self
     }
 
@@ -345,66 +662,143 @@ impl ThreadBuilder {
         self
     }
 
-    /// Spawns a new thread by taking ownership of the `Builder`, and returns an
-    /// [`std::io::Result`] to its [`std::thread::JoinHandle`].
-    ///
-    /// See [`std::thread::Builder::spawn`]
+    /// Returns the closure applying the scheduling of the builder to the thread it runs
+    /// on, the default properties first.
     #[cfg(unix)]
-    pub fn spawn<F, T>(mut self, f: F) -> std::io::Result<std::thread::JoinHandle<T>>
-    where
-        F: FnOnce(Result<(), Error>) -> T,
-        F: Send + 'static,
-        T: Send + 'static,
-    {
+    fn scheduling(&self) -> impl FnOnce() -> Result<(), Error> + Send + 'static {
         let priority = self.priority;
         let policy = self.policy;
+        let defaults = defaults::default_properties();
 
-        self.build_std().spawn(move || match (priority, policy) {
-            (Some(priority), Some(policy)) => f(set_thread_priority_and_policy(
-                thread_native_id(),
-                priority,
-                policy,
-            )),
-            (Some(priority), None) => f(priority.set_for_current()),
-            (None, Some(_policy)) => {
-                unimplemented!("Setting the policy separately isn't currently supported.");
-            }
-            _ => f(Ok(())),
-        })
+        move || {
+            defaults::apply_inherited(defaults).and_then(|()| match (priority, policy) {
+                (Some(priority), Some(policy)) => {
+                    set_thread_priority_and_policy(thread_native_id(), priority, policy)
+                }
+                (Some(priority), None) => priority.set_for_current(),
+                (None, Some(_policy)) => {
+                    unimplemented!("Setting the policy separately isn't currently supported.");
+                }
+                _ => Ok(()),
+            })
+        }
     }
 
-    /// Spawns a new thread by taking ownership of the `Builder`, and returns an
-    /// [`std::io::Result`] to its [`std::thread::JoinHandle`].
-    ///
-    /// See [`std::thread::Builder::spawn`]
+    /// Returns the closure applying the scheduling of the builder to the thread it runs
+    /// on, the default properties first.
     #[cfg(windows)]
-    pub fn spawn<F, T>(mut self, f: F) -> std::io::Result<std::thread::JoinHandle<T>>
-    where
-        F: FnOnce(Result<(), Error>) -> T,
-        F: Send + 'static,
-        T: Send + 'static,
-    {
+    fn scheduling(&self) -> impl FnOnce() -> Result<(), Error> + Send + 'static {
         let thread_priority = self.priority;
         let winapi_priority = self.winapi_priority;
         let boost_enabled = self.boost_enabled;
         let ideal_processor = self.ideal_processor;
+        let defaults = defaults::default_properties();
 
-        self.build_std().spawn(move || {
-            let mut result = match (thread_priority, winapi_priority) {
-                (Some(priority), None) => set_thread_priority(thread_native_id(), priority),
-                (_, Some(priority)) => set_winapi_thread_priority(thread_native_id(), priority),
-                _ => Ok(()),
-            };
-            if result.is_ok() && boost_enabled {
-                result = set_current_thread_priority_boost(boost_enabled);
+        move || {
+            let mut result = defaults::apply_inherited(defaults).and_then(|()| {
+                match (thread_priority, winapi_priority) {
+                    (Some(priority), None) => set_thread_priority(thread_native_id(), priority),
+                    (_, Some(priority)) => set_winapi_thread_priority(thread_native_id(), priority),
+                    _ => Ok(()),
+                }
+            });
+            if result.is_ok() {
+                if let Some(boost_enabled) = boost_enabled {
+                    result = set_current_thread_priority_boost(boost_enabled);
+                }
             }
             if result.is_ok() {
                 if let Some(ideal_processor) = ideal_processor {
                     result = set_current_thread_ideal_processor(ideal_processor).map(|_| ());
                 }
             }
-            f(result)
-        })
+            result
+        }
+    }
+
+    /// Returns the closure applying the scheduling of the builder to the thread it runs
+    /// on, the default properties first.
+    #[cfg(not(any(unix, windows)))]
+    fn scheduling(&self) -> impl FnOnce() -> Result<(), Error> + Send + 'static {
+        let priority = self.priority;
+        let defaults = defaults::default_properties();
+
+        move || {
+            defaults::apply_inherited(defaults).and_then(|()| match priority {
+                Some(priority) => priority.set_for_current(),
+                None => Ok(()),
+            })
+        }
+    }
+
+    /// Returns the closure preparing the thread it runs on: registering it, setting its
+    /// OS name, its scheduling and its affinity.
+    fn start(&self) -> impl FnOnce() -> Result<(), Error> + Send + 'static {
+        let os_name = self.os_name.clone();
+        let scheduling = self.scheduling();
+        let cpus = self.affinity.clone();
+
+        move || {
+            registry::register_spawned_thread();
+            os_name
+                .map_or(Ok(()), |name| names::set_current_thread_os_name(&name))
+                .and_then(|()| scheduling())
+                .and_then(|()| {
+                    cpus.map_or(Ok(()), |cpus| affinity::set_current_thread_affinity(&cpus))
+                })
+        }
+    }
+
+    /// Spawns a new thread by taking ownership of the `Builder`, and returns an
+    /// [`std::io::Result`] to its [`std::thread::JoinHandle`].
+    ///
+    /// See [`std::thread::Builder::spawn`]
+    pub fn spawn<F, T>(mut self, f: F) -> std::io::Result<std::thread::JoinHandle<T>>
+    where
+        F: FnOnce(Result<(), Error>) -> T,
+        F: Send + 'static,
+        T: Send + 'static,
+    {
+        let start = self.start();
+
+        self.build_std().spawn(move || f(start()))
+    }
+
+    /// Spawns a new scoped thread by taking ownership of the `Builder`, and returns an
+    /// [`std::io::Result`] to its [`std::thread::ScopedJoinHandle`]. The scheduling is
+    /// applied like in [`ThreadBuilder::spawn`].
+    ///
+    /// See [`std::thread::Builder::spawn_scoped`]
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// let mut total = 0;
+    /// std::thread::scope(|scope| {
+    ///     ThreadBuilder::default()
+    ///         .name("MyScopedThread")
+    ///         .priority(ThreadPriority::Min)
+    ///         .spawn_scoped(scope, |result| {
+    ///             assert!(result.is_ok());
+    ///             total += 1;
+    ///         })
+    ///         .unwrap();
+    /// });
+    /// assert_eq!(total, 1);
+    /// ```
+    pub fn spawn_scoped<'scope, 'env, F, T>(
+        mut self,
+        scope: &'scope std::thread::Scope<'scope, 'env>,
+        f: F,
+    ) -> std::io::Result<std::thread::ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce(Result<(), Error>) -> T,
+        F: Send + 'scope,
+        T: Send + 'scope,
+    {
+        let start = self.start();
+
+        self.build_std().spawn_scoped(scope, move || f(start()))
     }
 
     fn build_std(&mut self) -> std::thread::Builder {
@@ -433,17 +827,144 @@ impl ThreadBuilder {
     {
         self.spawn(|priority_set_result| {
             if let Err(e) = priority_set_result {
-                log::warn!(
-                    "Couldn't set the priority for the thread with Rust Thread ID {:?} named {:?}: {:?}",
-                    std::thread::current().id(),
-                    std::thread::current().name(),
-                    e,
-                );
+                warn_priority_not_set(&e);
             }
 
             f()
         })
     }
+
+    /// Spawns a new scoped thread by taking ownership of the `Builder`, and returns an
+    /// [`std::io::Result`] to its [`std::thread::ScopedJoinHandle`].
+    ///
+    /// See [`ThreadBuilder::spawn_scoped`] and [`ThreadBuilder::spawn_careless`]
+    pub fn spawn_scoped_careless<'scope, 'env, F, T>(
+        self,
+        scope: &'scope std::thread::Scope<'scope, 'env>,
+        f: F,
+    ) -> std::io::Result<std::thread::ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce() -> T,
+        F: Send + 'scope,
+        T: Send + 'scope,
+    {
+        self.spawn_scoped(scope, |priority_set_result| {
+            if let Err(e) = priority_set_result {
+                warn_priority_not_set(&e);
+            }
+
+            f()
+        })
+    }
+
+    /// Spawns a new thread by taking ownership of the `Builder`, waits until the thread
+    /// is configured, and returns its [`std::thread::JoinHandle`] with the report of the
+    /// configuration. The closure runs once the report is sent, whether the configuration
+    /// has succeeded or not.
+    ///
+    /// See [`ThreadBuilder::spawn`]
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// let (thread, report) = ThreadBuilder::default()
+    ///     .name("MyWorker")
+    ///     .priority(ThreadPriority::Min)
+    ///     .spawn_reported(|| println!("Working"))
+    ///     .unwrap();
+    /// assert!(report.result.is_ok());
+    /// println!("Running with {:?}", report.applied);
+    /// thread.join().unwrap();
+    /// ```
+    pub fn spawn_reported<F, T>(
+        self,
+        f: F,
+    ) -> std::io::Result<(std::thread::JoinHandle<T>, SpawnReport)>
+    where
+        F: FnOnce() -> T,
+        F: Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let thread = self.spawn(move |result| {
+            let _ = sender.send(SpawnReport {
+                result,
+                applied: info::ThreadSchedInfo::capture_current(),
+            });
+            f()
+        })?;
+        // The report is sent before the closure runs, the thread can't exit without it.
+        let report = receiver
+            .recv()
+            .map_err(|_| std::io::Error::other("The thread has exited before its report"))?;
+        Ok((thread, report))
+    }
+}
+
+/// The outcome of the configuration of a thread spawned with
+/// [`ThreadBuilder::spawn_reported`].
+#[derive(Clone, Debug)]
+pub struct SpawnReport {
+    /// The result of configuring the thread, the first failure stopping it.
+    pub result: Result<(), Error>,
+    /// The scheduling of the thread once configured.
+    pub applied: info::ThreadSchedInfo,
+}
+
+/// Spawns a thread with the name, the priority, the policy and the affinity applied
+/// before the closure runs, and returns its handle with the report of the configuration.
+/// For more info read [`ThreadBuilder::spawn_reported`].
+///
+/// * May require privileges
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::affinity::CpuSet;
+///
+/// # #[cfg(any(target_os = "linux", target_os = "android"))] {
+/// let (thread, report) = spawn_prioritized(
+///     "rt-worker",
+///     ThreadPriority::Min,
+///     ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Other),
+///     CpuSet::single(0),
+///     || println!("Working"),
+/// )
+/// .unwrap();
+/// assert!(report.result.is_ok());
+/// assert_eq!(report.applied.affinity, Some(CpuSet::single(0)));
+/// thread.join().unwrap();
+/// # }
+/// ```
+#[cfg(unix)]
+pub fn spawn_prioritized<N, F, T>(
+    name: N,
+    priority: ThreadPriority,
+    policy: ThreadSchedulePolicy,
+    cpus: affinity::CpuSet,
+    f: F,
+) -> std::io::Result<(std::thread::JoinHandle<T>, SpawnReport)>
+where
+    N: Into<String>,
+    F: FnOnce() -> T,
+    F: Send + 'static,
+    T: Send + 'static,
+{
+    ThreadBuilder::default()
+        .name(name)
+        .priority(priority)
+        .policy(policy)
+        .affinity(cpus)
+        .spawn_reported(f)
+}
+
+/// Logs the failure to set the priority of the current thread.
+fn warn_priority_not_set(e: &Error) {
+    log::warn!(
+        "Couldn't set the priority for the thread with Rust Thread ID {:?} named {:?}: {:?}",
+        std::thread::current().id(),
+        std::thread::current().name(),
+        e,
+    );
 }
 
 /// Adds thread building functions using the priority.
@@ -489,7 +1010,132 @@ impl ThreadBuilderExt for std::thread::Builder {
         F: Send + 'static,
         T: Send + 'static,
     {
-        self.spawn(move || f(priority.set_for_current()))
+        let defaults = defaults::default_properties();
+        self.spawn(move || {
+            registry::register_spawned_thread();
+            f(defaults::apply_inherited(defaults).and_then(|()| priority.set_for_current()))
+        })
+    }
+}
+
+/// Adds scoped thread spawning functions using the priority or the properties.
+pub trait ThreadScopeExt<'scope> {
+    /// Spawns a scoped thread with set priority. The passed functor `f` is executed in the
+    /// spawned thread and receives as the only argument the result of setting the thread
+    /// priority.
+    /// See [`std::thread::Scope::spawn`] and [`ThreadPriority::set_for_current`] for more info.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// let data = vec![1, 2, 3];
+    /// std::thread::scope(|scope| {
+    ///     scope.spawn_with_priority(ThreadPriority::Min, |result| {
+    ///         assert!(result.is_ok());
+    ///         println!("{:?}", data);
+    ///     });
+    /// });
+    /// ```
+    fn spawn_with_priority<F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(Result<(), Error>) -> T,
+        F: Send + 'scope,
+        T: Send + 'scope;
+
+    /// Spawns a scoped thread with set priority, logging the failure to set it.
+    /// See [`ThreadScopeExt::spawn_with_priority`] and [`spawn_careless`] for more info.
+    fn spawn_careless_with_priority<F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T,
+        F: Send + 'scope,
+        T: Send + 'scope;
+
+    /// Spawns a scoped thread with the properties, like a policy or an affinity, applied.
+    /// The passed functor `f` receives the result of applying them.
+    /// See [`properties::ThreadProperties::apply_to_current`] for more info.
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    /// use thread_priority::properties::ThreadProperties;
+    ///
+    /// std::thread::scope(|scope| {
+    ///     let properties = ThreadProperties::default().priority(ThreadPriority::Min);
+    ///     scope.spawn_with_properties(properties, |result| assert!(result.is_ok()));
+    /// });
+    /// ```
+    fn spawn_with_properties<F, T>(
+        &'scope self,
+        properties: properties::ThreadProperties,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(Result<(), Error>) -> T,
+        F: Send + 'scope,
+        T: Send + 'scope;
+}
+
+impl<'scope, 'env> ThreadScopeExt<'scope> for std::thread::Scope<'scope, 'env> {
+    fn spawn_with_priority<F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(Result<(), Error>) -> T,
+        F: Send + 'scope,
+        T: Send + 'scope,
+    {
+        let defaults = defaults::default_properties();
+        self.spawn(move || {
+            registry::register_spawned_thread();
+            f(defaults::apply_inherited(defaults).and_then(|()| priority.set_for_current()))
+        })
+    }
+
+    fn spawn_careless_with_priority<F, T>(
+        &'scope self,
+        priority: ThreadPriority,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T,
+        F: Send + 'scope,
+        T: Send + 'scope,
+    {
+        self.spawn_with_priority(priority, |result| {
+            if let Err(e) = result {
+                warn_priority_not_set(&e);
+            }
+
+            f()
+        })
+    }
+
+    fn spawn_with_properties<F, T>(
+        &'scope self,
+        properties: properties::ThreadProperties,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce(Result<(), Error>) -> T,
+        F: Send + 'scope,
+        T: Send + 'scope,
+    {
+        let defaults = defaults::default_properties();
+        self.spawn(move || {
+            registry::register_spawned_thread();
+            f(defaults::apply_inherited(defaults).and_then(|()| properties.apply_to_current()))
+        })
     }
 }
 
@@ -513,7 +1159,11 @@ where
     F: Send + 'static,
     T: Send + 'static,
 {
-    std::thread::spawn(move || f(priority.set_for_current()))
+    let defaults = defaults::default_properties();
+    std::thread::spawn(move || {
+        registry::register_spawned_thread();
+        f(defaults::apply_inherited(defaults).and_then(|()| priority.set_for_current()))
+    })
 }
 
 /// Spawns a thread with the specified priority.
@@ -538,14 +1188,13 @@ where
     F: Send + 'static,
     T: Send + 'static,
 {
+    let defaults = defaults::default_properties();
     std::thread::spawn(move || {
-        if let Err(e) = priority.set_for_current() {
-            log::warn!(
-                "Couldn't set the priority for the thread with Rust Thread ID {:?} named {:?}: {:?}",
-                std::thread::current().id(),
-                std::thread::current().name(),
-                e,
-            );
+        registry::register_spawned_thread();
+        if let Err(e) =
+            defaults::apply_inherited(defaults).and_then(|()| priority.set_for_current())
+        {
+            warn_priority_not_set(&e);
         }
 
         f()
@@ -0,0 +1,144 @@
+//! This module defines how the cross-platform priorities are mapped onto the
+//! native ones.
+//!
+//! A [`ThreadPriorityValue`] is in the `[0; 99]` range, while the native ranges
+//! differ per platform and per policy: `[1; 99]` for the Linux realtime policies,
+//! `[15; 47]` on macOS, `[1; 255]` on QNX. The [`MappingStrategy`] decides how the
+//! value is brought into the native range of the POSIX policies. It is selected
+//! globally with [`set_mapping_strategy`], or for the calls made within a closure
+//! with [`with_mapping_strategy`]. The platforms with their own native scales, like
+//! Windows, FreeBSD, Android or Fuchsia, keep their mappings.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::mapping::*;
+//! use std::convert::TryFrom;
+//!
+//! let value = ThreadPriorityValue::try_from(0u8).unwrap();
+//! assert_eq!(MappingStrategy::Linear.map(value, 1, 99).ok(), Some(1));
+//! assert_eq!(MappingStrategy::Clamp.map(value, 1, 99).ok(), Some(1));
+//! assert!(MappingStrategy::Exact.map(value, 1, 99).is_err());
+//!
+//! let result = with_mapping_strategy(MappingStrategy::Clamp, || {
+//!     ThreadPriority::Crossplatform(value).to_posix(ThreadSchedulePolicy::Realtime(
+//!         RealtimeThreadSchedulePolicy::Fifo,
+//!     ))
+//! });
+//! # #[cfg(target_os = "linux")]
+//! assert_eq!(result.ok(), Some(1));
+//! ```
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{Error, ThreadPriorityValue};
+
+/// How a [`ThreadPriorityValue`] is mapped onto a native priority range.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum MappingStrategy {
+    /// The value is used as the native priority, and it is an error if it is out of
+    /// the native range. This is how the crate has always mapped the values.
+    #[default]
+    Exact,
+    /// The value is used as the native priority, brought into the native range.
+    Clamp,
+    /// The value is scaled into the native range: `0` is the lowest native priority
+    /// and `99` the highest.
+    Linear,
+}
+
+impl MappingStrategy {
+    /// Maps the value onto the native range `[min; max]`.
+    pub fn map(self, value: ThreadPriorityValue, min: i32, max: i32) -> Result<i32, Error> {
+        if min > max {
+            return Err(Error::Priority("The native range is empty."));
+        }
+        let value = i32::from(value.0);
+        match self {
+            MappingStrategy::Exact if value < min || value > max => Err(Error::Priority(
+                "The value is out of the native priority range.",
+            )),
+            MappingStrategy::Exact => Ok(value),
+            MappingStrategy::Clamp => Ok(value.clamp(min, max)),
+            MappingStrategy::Linear => Ok(min + value * (max - min) / 99),
+        }
+    }
+
+    fn to_bits(self) -> u8 {
+        match self {
+            MappingStrategy::Exact => 0,
+            MappingStrategy::Clamp => 1,
+            MappingStrategy::Linear => 2,
+        }
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        match bits {
+            1 => MappingStrategy::Clamp,
+            2 => MappingStrategy::Linear,
+            _ => MappingStrategy::Exact,
+        }
+    }
+}
+
+static MAPPING_STRATEGY: AtomicU8 = AtomicU8::new(0);
+
+thread_local! {
+    static SCOPED_STRATEGY: Cell<Option<MappingStrategy>> = const { Cell::new(None) };
+}
+
+/// Sets the strategy used by all the threads, unless overridden by
+/// [`with_mapping_strategy`].
+pub fn set_mapping_strategy(strategy: MappingStrategy) {
+    MAPPING_STRATEGY.store(strategy.to_bits(), Ordering::SeqCst);
+}
+
+/// Returns the strategy in effect on the current thread.
+pub fn mapping_strategy() -> MappingStrategy {
+    SCOPED_STRATEGY
+        .with(Cell::get)
+        .unwrap_or_else(|| MappingStrategy::from_bits(MAPPING_STRATEGY.load(Ordering::SeqCst)))
+}
+
+/// Runs the closure with the strategy in effect for the calls it makes on the current
+/// thread, whatever the global strategy is.
+pub fn with_mapping_strategy<T, F: FnOnce() -> T>(strategy: MappingStrategy, f: F) -> T {
+    struct Restore(Option<MappingStrategy>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED_STRATEGY.with(|scoped| scoped.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SCOPED_STRATEGY.with(|scoped| scoped.replace(Some(strategy))));
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_test() {
+        let value = |p| ThreadPriorityValue(p);
+        assert_eq!(
+            MappingStrategy::Linear.map(value(99), 15, 47).ok(),
+            Some(47)
+        );
+        assert_eq!(
+            MappingStrategy::Linear.map(value(50), 1, 255).ok(),
+            Some(129)
+        );
+        assert_eq!(MappingStrategy::Clamp.map(value(99), 15, 47).ok(), Some(47));
+        assert_eq!(MappingStrategy::Exact.map(value(20), 15, 47).ok(), Some(20));
+        assert!(MappingStrategy::Exact.map(value(50), 15, 47).is_err());
+        assert_eq!(
+            with_mapping_strategy(MappingStrategy::Linear, mapping_strategy),
+            MappingStrategy::Linear
+        );
+        assert_eq!(mapping_strategy(), MappingStrategy::Exact);
+    }
+}
@@ -0,0 +1,506 @@
+//! This module defines the adjustment of the threads of the process by their
+//! names.
+//!
+//! The threads created by the libraries the application doesn't control, like
+//! the runtimes and the drivers, can't be configured when they're spawned, but
+//! they're usually named. [`apply_by_name`] scans the threads of the current
+//! process and applies the properties of the first rule whose pattern matches
+//! the name of each thread. The patterns are matched as globs, with `*` for any
+//! characters and `?` for a single one. [`apply_to_all_threads`] applies the
+//! same properties to all the threads, or to the ones a [`ThreadFilter`]
+//! selects, to demote the whole process at once when it moves to the
+//! background. [`set_thread_os_name`] sets the name the OS knows a thread by.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::names::*;
+//! use thread_priority::properties::ThreadProperties;
+//!
+//! # #[cfg(any(target_os = "linux", target_os = "android", windows))] {
+//! let outcomes = apply_by_name(&[(
+//!     "tokio-runtime-*",
+//!     ThreadProperties::default().priority(ThreadPriority::Min),
+//! )])
+//! .unwrap();
+//! for outcome in outcomes {
+//!     println!("{}: {:?}", outcome.thread.name, outcome.result);
+//! }
+//! # }
+//! ```
+
+use crate::properties::ThreadProperties;
+use crate::Error;
+
+/// A thread of the current process.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ProcessThread {
+    /// The kernel thread id.
+    #[cfg(any(target_os = "linux", target_os = "android", windows))]
+    pub tid: crate::ThreadTid,
+    /// The name of the thread, empty if it has none.
+    ///
+    /// * On Linux and Android, the `comm` name, truncated to 15 bytes.
+    /// * On Windows, the thread description.
+    pub name: String,
+}
+
+/// The outcome of applying the properties to a thread.
+#[derive(Debug)]
+pub struct ThreadOutcome {
+    /// The thread.
+    pub thread: ProcessThread,
+    /// The index of the rule whose pattern matched the name of the thread.
+    pub rule: usize,
+    /// The result of applying the properties of the rule.
+    pub result: Result<(), Error>,
+}
+
+/// The result of applying the properties to a thread.
+#[derive(Debug)]
+pub struct ThreadResult {
+    /// The thread.
+    pub thread: ProcessThread,
+    /// The result of applying the properties.
+    pub result: Result<(), Error>,
+}
+
+/// The threads of the current process to apply the properties to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ThreadFilter<'a> {
+    /// All the threads.
+    All,
+    /// The threads whose name matches the glob pattern, see [`matches_pattern`].
+    Name(&'a str),
+    /// The thread with the kernel thread id.
+    #[cfg(any(target_os = "linux", target_os = "android", windows))]
+    Tid(crate::ThreadTid),
+}
+
+impl ThreadFilter<'_> {
+    /// Returns whether the thread is selected by the filter.
+    pub fn matches(&self, thread: &ProcessThread) -> bool {
+        match self {
+            ThreadFilter::All => true,
+            ThreadFilter::Name(pattern) => matches_pattern(pattern, &thread.name),
+            #[cfg(any(target_os = "linux", target_os = "android", windows))]
+            ThreadFilter::Tid(tid) => thread.tid == *tid,
+        }
+    }
+}
+
+/// Returns whether the name matches the glob pattern, with `*` for any characters and
+/// `?` for a single one.
+///
+/// ```rust
+/// use thread_priority::names::matches_pattern;
+///
+/// assert!(matches_pattern("worker-*", "worker-12"));
+/// assert!(matches_pattern("gc?", "gc1"));
+/// assert!(!matches_pattern("worker-*", "io-worker-1"));
+/// ```
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // The position after the last `*`, and the position in the name it stands up to.
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            p += 1;
+            star = Some((p, n));
+        } else if let Some((after_star, matched)) = star {
+            p = after_star;
+            n = matched + 1;
+            star = Some((after_star, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Returns the threads of the current process.
+///
+/// * On Linux and Android, read from `/proc/self/task`.
+/// * On Windows, based on `CreateToolhelp32Snapshot` and `GetThreadDescription`, the
+///   threads which can't be opened are left out.
+pub fn process_threads() -> Result<Vec<ProcessThread>, Error> {
+    native_process_threads()
+}
+
+/// Applies the properties of the first rule whose pattern matches the name of each
+/// thread of the current process, and returns the outcome for each matched thread; the
+/// threads matched by no rule are left as they are. The failures are reported in the
+/// outcomes, only a failure to list the threads is an error.
+///
+/// * May require privileges
+///
+/// For more info read [`process_threads`] and [`ThreadProperties::apply`].
+pub fn apply_by_name<P: AsRef<str>>(
+    rules: &[(P, ThreadProperties)],
+) -> Result<Vec<ThreadOutcome>, Error> {
+    Ok(process_threads()?
+        .into_iter()
+        .filter_map(|thread| {
+            let rule = rules
+                .iter()
+                .position(|(pattern, _)| matches_pattern(pattern.as_ref(), &thread.name))?;
+            let result = apply_to_thread(&rules[rule].1, &thread);
+            Some(ThreadOutcome {
+                thread,
+                rule,
+                result,
+            })
+        })
+        .collect())
+}
+
+/// Applies the properties to the threads of the current process selected by the filter,
+/// and returns the result for each of them. The failures are reported in the results,
+/// only a failure to list the threads is an error.
+///
+/// * May require privileges
+///
+/// For more info read [`process_threads`] and [`ThreadProperties::apply`].
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::names::*;
+/// use thread_priority::properties::ThreadProperties;
+///
+/// # #[cfg(any(target_os = "linux", target_os = "android", windows))] {
+/// let results = apply_to_all_threads(
+///     &ThreadProperties::default().priority(ThreadPriority::Min),
+///     ThreadFilter::All,
+/// )
+/// .unwrap();
+/// for failed in results.iter().filter(|r| r.result.is_err()) {
+///     println!("Couldn't demote {}: {:?}", failed.thread.name, failed.result);
+/// }
+/// # }
+/// ```
+pub fn apply_to_all_threads(
+    properties: &ThreadProperties,
+    filter: ThreadFilter<'_>,
+) -> Result<Vec<ThreadResult>, Error> {
+    Ok(process_threads()?
+        .into_iter()
+        .filter(|thread| filter.matches(thread))
+        .map(|thread| {
+            let result = apply_to_thread(properties, &thread);
+            ThreadResult { thread, result }
+        })
+        .collect())
+}
+
+/// Sets the name of the thread known to the OS, shown by the debuggers, the profilers
+/// and the process monitors. The standard library only sets it when spawning a named
+/// thread.
+///
+/// * On Linux and Android, with `pthread_setname_np`, truncated to 15 bytes.
+/// * On Apple platforms, with `pthread_setname_np`, only for the current thread.
+/// * On FreeBSD and OpenBSD, with `pthread_set_name_np`.
+/// * On NetBSD, illumos, Solaris, Fuchsia and Haiku, with `pthread_setname_np`.
+/// * On Windows, with `SetThreadDescription`.
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::names::*;
+///
+/// # #[cfg(any(target_os = "linux", target_os = "android", windows))]
+/// assert!(set_thread_os_name(thread_native_id(), "worker").is_ok());
+/// ```
+///
+/// If there's an error, a result of the native call is returned.
+pub fn set_thread_os_name(native: crate::ThreadId, name: &str) -> Result<(), Error> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let name = {
+        // The name is truncated on a character boundary, the kernel rejects longer ones.
+        let mut end = name.len().min(15);
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        &name[..end]
+    };
+    #[cfg(not(windows))]
+    let name = std::ffi::CString::new(name)
+        .map_err(|_| Error::Priority("The name can't contain a null byte."))?;
+    #[cfg(not(windows))]
+    let name = name.as_c_str();
+    native_set_thread_os_name(native, name)
+}
+
+/// Sets the name of the current thread known to the OS.
+/// For more info read [`set_thread_os_name`].
+pub fn set_current_thread_os_name(name: &str) -> Result<(), Error> {
+    set_thread_os_name(crate::thread_native_id(), name)
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "fuchsia",
+    target_os = "haiku"
+))]
+fn native_set_thread_os_name(native: crate::ThreadId, name: &std::ffi::CStr) -> Result<(), Error> {
+    match unsafe { libc::pthread_setname_np(native, name.as_ptr()) } {
+        0 => Ok(()),
+        e => Err(Error::OS(e)),
+    }
+}
+
+#[cfg(target_os = "netbsd")]
+fn native_set_thread_os_name(native: crate::ThreadId, name: &std::ffi::CStr) -> Result<(), Error> {
+    // The name is a format with a single argument.
+    match unsafe {
+        libc::pthread_setname_np(
+            native,
+            b"%s\0".as_ptr() as *const libc::c_char,
+            name.as_ptr() as *mut libc::c_void,
+        )
+    } {
+        0 => Ok(()),
+        e => Err(Error::OS(e)),
+    }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+fn native_set_thread_os_name(native: crate::ThreadId, name: &std::ffi::CStr) -> Result<(), Error> {
+    unsafe { libc::pthread_set_name_np(native, name.as_ptr()) };
+    Ok(())
+}
+
+#[cfg(target_vendor = "apple")]
+fn native_set_thread_os_name(native: crate::ThreadId, name: &std::ffi::CStr) -> Result<(), Error> {
+    if unsafe { libc::pthread_equal(native, libc::pthread_self()) } == 0 {
+        return Err(Error::Unsupported(
+            "Naming another thread is not supported on this platform",
+        ));
+    }
+    match unsafe { libc::pthread_setname_np(name.as_ptr()) } {
+        0 => Ok(()),
+        e => Err(Error::OS(e)),
+    }
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetThreadDescription(thread: winapi::um::winnt::HANDLE, description: *const u16) -> i32;
+}
+
+#[cfg(windows)]
+fn native_set_thread_os_name(native: crate::ThreadId, name: &str) -> Result<(), Error> {
+    if name.contains('\0') {
+        return Err(Error::Priority("The name can't contain a null byte."));
+    }
+    let description: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    match unsafe { SetThreadDescription(native, description.as_ptr()) } {
+        // The result is an `HRESULT`.
+        e if e < 0 => Err(Error::OS(e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "fuchsia",
+    target_os = "haiku",
+    target_os = "netbsd",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_vendor = "apple",
+    windows
+)))]
+fn native_set_thread_os_name(
+    _native: crate::ThreadId,
+    _name: &std::ffi::CStr,
+) -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "Setting the name of the threads is not supported on this platform",
+    ))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn native_process_threads() -> Result<Vec<ProcessThread>, Error> {
+    let tasks = std::fs::read_dir("/proc/self/task")?;
+    Ok(tasks
+        .flatten()
+        .filter_map(|task| {
+            let tid = task.file_name().to_str()?.parse().ok()?;
+            // The thread may have exited since the directory has been read.
+            let name = std::fs::read_to_string(task.path().join("comm")).ok()?;
+            Some(ProcessThread {
+                tid,
+                name: name.trim_end_matches('\n').to_owned(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(windows)]
+fn native_process_threads() -> Result<Vec<ProcessThread>, Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use winapi::um::tlhelp32::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+
+    let pid = std::process::id();
+    let mut threads = Vec::new();
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+        let mut entry: THREADENTRY32 = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<THREADENTRY32>() as u32;
+        let mut more = Thread32First(snapshot, &mut entry);
+        while more != 0 {
+            // The snapshot holds the threads of all the processes.
+            if entry.th32OwnerProcessID == pid {
+                if let Ok(handle) = crate::OwnedThreadHandle::open(pid, entry.th32ThreadID) {
+                    threads.push(ProcessThread {
+                        tid: entry.th32ThreadID,
+                        name: thread_description(&handle).unwrap_or_default(),
+                    });
+                }
+            }
+            more = Thread32Next(snapshot, &mut entry);
+        }
+        CloseHandle(snapshot);
+    }
+    Ok(threads)
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetThreadDescription(thread: winapi::um::winnt::HANDLE, description: *mut *mut u16) -> i32;
+}
+
+#[cfg(windows)]
+fn thread_description(handle: &crate::OwnedThreadHandle) -> Option<String> {
+    unsafe {
+        let mut description = std::ptr::null_mut();
+        if GetThreadDescription(handle.as_thread_id(), &mut description) < 0 {
+            return None;
+        }
+        let length = (0..).take_while(|i| *description.add(*i) != 0).count();
+        let name = String::from_utf16_lossy(std::slice::from_raw_parts(description, length));
+        winapi::um::winbase::LocalFree(description as _);
+        Some(name)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn native_process_threads() -> Result<Vec<ProcessThread>, Error> {
+    Err(Error::Unsupported(
+        "Listing the threads of the process is not supported on this platform",
+    ))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn apply_to_thread(properties: &ThreadProperties, thread: &ProcessThread) -> Result<(), Error> {
+    properties.apply_to_tid(thread.tid)
+}
+
+#[cfg(windows)]
+fn apply_to_thread(properties: &ThreadProperties, thread: &ProcessThread) -> Result<(), Error> {
+    let handle = crate::OwnedThreadHandle::open(std::process::id(), thread.tid)?;
+    properties.apply(handle.as_thread_id())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn apply_to_thread(_properties: &ThreadProperties, _thread: &ProcessThread) -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "Listing the threads of the process is not supported on this platform",
+    ))
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android", windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_by_name_test() {
+        let (ready_sender, ready) = std::sync::mpsc::channel::<()>();
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("names-test".to_owned())
+            .spawn(move || {
+                ready_sender.send(()).unwrap();
+                receiver.recv()
+            })
+            .unwrap();
+        ready.recv().unwrap();
+        let outcomes = apply_by_name(&[
+            ("names-t?st", ThreadProperties::default()),
+            (
+                "*",
+                ThreadProperties::default().affinity(Default::default()),
+            ),
+        ])
+        .unwrap();
+        let outcome = outcomes
+            .iter()
+            .find(|outcome| outcome.thread.name == "names-test")
+            .unwrap();
+        assert_eq!(outcome.rule, 0);
+        assert!(outcome.result.is_ok());
+        sender.send(()).unwrap();
+        thread.join().unwrap().unwrap();
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn set_thread_os_name_test() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (done, wait) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            assert!(set_current_thread_os_name("null\0byte").is_err());
+            set_current_thread_os_name("os-name-tests-é").unwrap();
+            sender.send(crate::thread_native_tid()).unwrap();
+            wait.recv()
+        });
+        let tid = receiver.recv().unwrap();
+        let threads = process_threads().unwrap();
+        // The name is truncated under 15 bytes, on a character boundary.
+        assert_eq!(
+            threads
+                .iter()
+                .find(|thread| thread.tid == tid)
+                .unwrap()
+                .name,
+            "os-name-tests-"
+        );
+        drop(done);
+        assert!(thread.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn apply_to_all_threads_test() {
+        let tid = process_threads().unwrap().into_iter().next().unwrap().tid;
+        let results =
+            apply_to_all_threads(&ThreadProperties::default(), ThreadFilter::Tid(tid)).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].thread.tid, tid);
+        assert!(results[0].result.is_ok());
+        assert!(apply_to_all_threads(
+            &ThreadProperties::default(),
+            ThreadFilter::Name("no thread has this name")
+        )
+        .unwrap()
+        .is_empty());
+    }
+}
@@ -0,0 +1,326 @@
+//! This module defines the textual form of the priorities and the policies.
+//!
+//! The command line tools and the configuration files name the scheduling
+//! settings rather than build them, so the priorities and, on unix, the policies
+//! and their combination, `Scheduling`, implement both [`std::str::FromStr`] and
+//! [`std::fmt::Display`], the latter producing what the former parses:
+//!
+//! * The priorities are `min`, `max`, a cross-platform value from `0` to `99`,
+//!   `os:<value>` for a native value and, on Linux, `<runtime>/<deadline>/<period>`
+//!   for the deadline parameters, the durations having one of the `s`, `ms`, `us` or
+//!   `ns` units.
+//! * The unix policies are `other`, `normal`, `idle`, `batch`, `fifo`, `rr`, and
+//!   `deadline` on Linux or `sporadic` on QNX and RTEMS.
+//! * The I/O priorities are `idle`, `low`, `normal` and `high`.
+//! * The scheduling is `<policy>:<priority>`, like `fifo:80` or
+//!   `deadline:1ms/10ms/100ms`, or only the policy, with the minimum priority.
+//!
+//! The names are case-insensitive.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//!
+//! let priority: ThreadPriority = "max".parse().unwrap();
+//! assert_eq!(priority, ThreadPriority::Max);
+//! assert_eq!(priority.to_string(), "max");
+//!
+//! # #[cfg(unix)] {
+//! use thread_priority::parse::Scheduling;
+//!
+//! let scheduling: Scheduling = "fifo:80".parse().unwrap();
+//! assert_eq!(
+//!     scheduling.policy,
+//!     ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo)
+//! );
+//! assert_eq!(scheduling.to_string(), "fifo:80");
+//! # }
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::io_priority::IoPriority;
+#[cfg(unix)]
+use crate::{
+    Error, NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ThreadId, ThreadSchedulePolicy,
+};
+use crate::{ThreadPriority, ThreadPriorityOsValue, ThreadPriorityValue};
+
+impl FromStr for ThreadPriority {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "min" => return Ok(ThreadPriority::Min),
+            "max" => return Ok(ThreadPriority::Max),
+            _ => {}
+        }
+        if let Some(value) = s.strip_prefix("os:") {
+            return value
+                .trim()
+                .parse()
+                .map(|value| ThreadPriority::Os(ThreadPriorityOsValue(value)))
+                .map_err(|_| "The OS priority is not a number");
+        }
+        #[cfg(target_os = "linux")]
+        if s.contains('/') {
+            let mut durations = s.split('/').map(parse_nanos);
+            return match (
+                durations.next(),
+                durations.next(),
+                durations.next(),
+                durations.next(),
+            ) {
+                (Some(runtime), Some(deadline), Some(period), None) => {
+                    Ok(ThreadPriority::Deadline(runtime?, deadline?, period?))
+                }
+                _ => Err("The deadline priority is not <runtime>/<deadline>/<period>"),
+            };
+        }
+        let value = s
+            .parse::<u8>()
+            .map_err(|_| "The priority is not min, max or a number")?;
+        ThreadPriorityValue::try_from(value).map(ThreadPriority::Crossplatform)
+    }
+}
+
+impl fmt::Display for ThreadPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThreadPriority::Min => f.write_str("min"),
+            ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => write!(f, "{}", p),
+            ThreadPriority::Os(ThreadPriorityOsValue(p)) => write!(f, "os:{}", p),
+            #[cfg(target_os = "linux")]
+            ThreadPriority::Deadline(runtime, deadline, period) => write!(
+                f,
+                "{}/{}/{}",
+                Nanos(*runtime),
+                Nanos(*deadline),
+                Nanos(*period)
+            ),
+            ThreadPriority::Max => f.write_str("max"),
+        }
+    }
+}
+
+/// Parses a duration with its unit into nanoseconds.
+#[cfg(target_os = "linux")]
+fn parse_nanos(s: &str) -> Result<u64, &'static str> {
+    let s = s.trim();
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let multiplier = match &s[digits..] {
+        "s" => 1_000_000_000,
+        "ms" => 1_000_000,
+        "us" => 1_000,
+        "ns" => 1,
+        _ => return Err("The duration unit is not one of s, ms, us or ns"),
+    };
+    s[..digits]
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or("The duration is not a number of its unit")
+}
+
+/// Formats nanoseconds with the largest unit they are a whole number of.
+#[cfg(target_os = "linux")]
+struct Nanos(u64);
+
+#[cfg(target_os = "linux")]
+impl fmt::Display for Nanos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = [(1_000_000_000, "s"), (1_000_000, "ms"), (1_000, "us")];
+        match units
+            .iter()
+            .find(|(multiplier, _)| self.0 != 0 && self.0 % multiplier == 0)
+        {
+            Some((multiplier, unit)) => write!(f, "{}{}", self.0 / multiplier, unit),
+            None => write!(f, "{}ns", self.0),
+        }
+    }
+}
+
+impl FromStr for IoPriority {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "idle" => Ok(IoPriority::Idle),
+            "low" => Ok(IoPriority::Low),
+            "normal" => Ok(IoPriority::Normal),
+            "high" => Ok(IoPriority::High),
+            _ => Err("The I/O priority is not one of idle, low, normal or high"),
+        }
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IoPriority::Idle => "idle",
+            IoPriority::Low => "low",
+            IoPriority::Normal => "normal",
+            IoPriority::High => "high",
+        })
+    }
+}
+
+#[cfg(unix)]
+impl FromStr for NormalThreadSchedulePolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "idle" => Ok(NormalThreadSchedulePolicy::Idle),
+            "batch" => Ok(NormalThreadSchedulePolicy::Batch),
+            "other" => Ok(NormalThreadSchedulePolicy::Other),
+            "normal" => Ok(NormalThreadSchedulePolicy::Normal),
+            _ => Err("The normal policy is not one of idle, batch, other or normal"),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Display for NormalThreadSchedulePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NormalThreadSchedulePolicy::Idle => "idle",
+            NormalThreadSchedulePolicy::Batch => "batch",
+            NormalThreadSchedulePolicy::Other => "other",
+            NormalThreadSchedulePolicy::Normal => "normal",
+        })
+    }
+}
+
+#[cfg(unix)]
+impl FromStr for RealtimeThreadSchedulePolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fifo" => Ok(RealtimeThreadSchedulePolicy::Fifo),
+            "rr" | "roundrobin" | "round-robin" | "round_robin" => {
+                Ok(RealtimeThreadSchedulePolicy::RoundRobin)
+            }
+            #[cfg(target_os = "linux")]
+            "deadline" => Ok(RealtimeThreadSchedulePolicy::Deadline),
+            #[cfg(any(target_os = "nto", target_os = "rtems"))]
+            "sporadic" => Ok(RealtimeThreadSchedulePolicy::Sporadic(Default::default())),
+            _ => Err("The realtime policy is not supported on this platform"),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Display for RealtimeThreadSchedulePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RealtimeThreadSchedulePolicy::Fifo => "fifo",
+            RealtimeThreadSchedulePolicy::RoundRobin => "rr",
+            #[cfg(target_os = "linux")]
+            RealtimeThreadSchedulePolicy::Deadline => "deadline",
+            #[cfg(any(target_os = "nto", target_os = "rtems"))]
+            RealtimeThreadSchedulePolicy::Sporadic(_) => "sporadic",
+        })
+    }
+}
+
+#[cfg(unix)]
+impl FromStr for ThreadSchedulePolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(ThreadSchedulePolicy::Normal)
+            .or_else(|_| s.parse().map(ThreadSchedulePolicy::Realtime))
+            .map_err(|_| "The policy is not supported on this platform")
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Display for ThreadSchedulePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThreadSchedulePolicy::Normal(policy) => policy.fmt(f),
+            ThreadSchedulePolicy::Realtime(policy) => policy.fmt(f),
+        }
+    }
+}
+
+/// A unix policy together with its priority, as in `fifo:80`.
+#[cfg(unix)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Scheduling {
+    /// The policy.
+    pub policy: ThreadSchedulePolicy,
+    /// The priority.
+    pub priority: ThreadPriority,
+}
+
+#[cfg(unix)]
+impl Scheduling {
+    /// Applies the scheduling to the thread.
+    /// For more info read [`crate::set_thread_priority_and_policy`].
+    pub fn apply(&self, native: ThreadId) -> Result<(), Error> {
+        crate::set_thread_priority_and_policy(native, self.priority, self.policy)
+    }
+
+    /// Applies the scheduling to the current thread.
+    /// For more info read [`crate::set_thread_priority_and_policy`].
+    pub fn apply_to_current(&self) -> Result<(), Error> {
+        self.apply(crate::thread_native_id())
+    }
+}
+
+#[cfg(unix)]
+impl FromStr for Scheduling {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let policy = parts.next().unwrap_or_default().parse()?;
+        let priority = match parts.next() {
+            Some(priority) => priority.parse()?,
+            None => ThreadPriority::Min,
+        };
+        Ok(Scheduling { policy, priority })
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Display for Scheduling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.policy, self.priority)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_test() {
+        for s in ["min", "max", "42", "os:7"] {
+            assert_eq!(s.parse::<ThreadPriority>().unwrap().to_string(), s);
+        }
+        assert!("100".parse::<ThreadPriority>().is_err());
+        #[cfg(target_os = "linux")]
+        {
+            let scheduling: Scheduling = "Deadline:1ms/10ms/100ms".parse().unwrap();
+            assert_eq!(
+                scheduling.priority,
+                ThreadPriority::Deadline(1_000_000, 10_000_000, 100_000_000)
+            );
+            assert_eq!(scheduling.to_string(), "deadline:1ms/10ms/100ms");
+            assert_eq!(
+                "other".parse::<Scheduling>().unwrap().to_string(),
+                "other:min"
+            );
+            assert!("fifo:1ms".parse::<Scheduling>().is_err());
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! This module defines the priority-aware thread pool.
+//!
+//! A [`ThreadPool`] spawns its workers with the configured properties, like a
+//! policy and a priority, and runs the queued tasks by their [`TaskPriority`]:
+//! the tasks of a higher priority run first, the ones of the same priority in
+//! the order they've been queued.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::pool::*;
+//! use thread_priority::properties::ThreadProperties;
+//!
+//! let pool = ThreadPool::new(
+//!     PoolConfig::new(2)
+//!         .name("worker")
+//!         .properties(ThreadProperties::default().priority(ThreadPriority::Min)),
+//! )
+//! .unwrap();
+//! pool.execute(|| println!("A normal task"));
+//! pool.execute_with_priority(TaskPriority::High, || println!("An urgent task"));
+//! // The queued tasks are run before the workers exit.
+//! drop(pool);
+//! ```
+
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::properties::ThreadProperties;
+use crate::{Error, ThreadBuilder};
+
+/// The priority of a task in the queue of the pool.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum TaskPriority {
+    /// Run when nothing else is queued.
+    Low,
+    /// The priority of [`ThreadPool::execute`].
+    #[default]
+    Normal,
+    /// Run before the normal tasks.
+    High,
+    /// Run before all the others.
+    Critical,
+}
+
+/// The configuration of a [`ThreadPool`].
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    workers: usize,
+    name: Option<String>,
+    stack_size: Option<usize>,
+    properties: ThreadProperties,
+}
+
+impl PoolConfig {
+    /// Creates the configuration of a pool of the number of workers, which keep their
+    /// scheduling.
+    pub fn new(workers: usize) -> Self {
+        PoolConfig {
+            workers,
+            name: None,
+            stack_size: None,
+            properties: ThreadProperties::default(),
+        }
+    }
+
+    /// The prefix of the names of the workers, followed by their index.
+    pub fn name<VALUE: Into<String>>(mut self, value: VALUE) -> Self {
+        self.name = Some(value.into());
+        self
+    }
+
+    /// The size of the stack of the workers.
+    ///
+    /// For more information, see [`ThreadBuilder::stack_size`].
+    pub fn stack_size(mut self, value: usize) -> Self {
+        self.stack_size = Some(value);
+        self
+    }
+
+    /// The properties applied to the workers when they start. If they can't be applied,
+    /// the error is logged and the worker runs as it is.
+    ///
+    /// For more information, see [`ThreadProperties::apply`].
+    pub fn properties(mut self, value: ThreadProperties) -> Self {
+        self.properties = value;
+        self
+    }
+}
+
+type Task = Box<dyn FnOnce() + Send + 'static>;
+
+/// A queued task, ordered by its priority, then by its order in the queue.
+struct QueuedTask {
+    priority: TaskPriority,
+    sequence: u64,
+    task: Task,
+}
+
+impl PartialEq for QueuedTask {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for QueuedTask {}
+
+impl PartialOrd for QueuedTask {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedTask {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // The heap pops the greatest task: the highest priority, the oldest one first.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    tasks: BinaryHeap<QueuedTask>,
+    sequence: u64,
+    shutdown: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    available: Condvar,
+}
+
+impl Shared {
+    fn queue(&self) -> std::sync::MutexGuard<'_, Queue> {
+        // The tasks run outside of the lock, a panic can't poison the queue.
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A pool of workers running the tasks by their priority. When it is dropped, the queued
+/// tasks are run and the workers are joined.
+pub struct ThreadPool {
+    shared: Arc<Shared>,
+    workers: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThreadPool")
+            .field("workers", &self.workers.len())
+            .field("queued", &self.queued())
+            .finish()
+    }
+}
+
+impl ThreadPool {
+    /// Spawns the workers of the pool.
+    pub fn new(config: PoolConfig) -> Result<Self, Error> {
+        if config.workers == 0 {
+            return Err(Error::Priority("The pool must have at least a worker."));
+        }
+        let shared = Arc::new(Shared::default());
+        let mut pool = ThreadPool {
+            shared,
+            workers: Vec::with_capacity(config.workers),
+        };
+        for index in 0..config.workers {
+            let mut builder = ThreadBuilder::default();
+            if let Some(name) = &config.name {
+                builder = builder.name(format!("{}-{}", name, index));
+            }
+            if let Some(stack_size) = config.stack_size {
+                builder = builder.stack_size(stack_size);
+            }
+            let shared = pool.shared.clone();
+            let properties = config.properties.clone();
+            // The workers spawned so far are joined when the pool is dropped.
+            let worker = builder
+                .spawn_careless(move || {
+                    if let Err(e) = properties.apply_to_current() {
+                        log::warn!("Couldn't apply the properties to the worker: {:?}", e);
+                    }
+                    work(&shared);
+                })?;
+            pool.workers.push(worker);
+        }
+        Ok(pool)
+    }
+
+    /// Queues the task at the normal priority.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.execute_with_priority(TaskPriority::Normal, f)
+    }
+
+    /// Queues the task at the priority.
+    pub fn execute_with_priority<F>(&self, priority: TaskPriority, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut queue = self.shared.queue();
+        let sequence = queue.sequence;
+        queue.sequence += 1;
+        queue.tasks.push(QueuedTask {
+            priority,
+            sequence,
+            task: Box::new(f),
+        });
+        drop(queue);
+        self.shared.available.notify_one();
+    }
+
+    /// Returns the number of workers.
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Returns the number of tasks waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.shared.queue().tasks.len()
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shared.queue().shutdown = true;
+        self.shared.available.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn work(shared: &Shared) {
+    loop {
+        let mut queue = shared.queue();
+        let task = loop {
+            if let Some(queued) = queue.tasks.pop() {
+                break queued.task;
+            }
+            if queue.shutdown {
+                return;
+            }
+            queue = shared
+                .available
+                .wait(queue)
+                .unwrap_or_else(|e| e.into_inner());
+        };
+        drop(queue);
+        if std::panic::catch_unwind(std::panic::AssertUnwindSafe(task)).is_err() {
+            log::warn!("A task of the pool has panicked");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_priority_order_test() {
+        let pool = ThreadPool::new(PoolConfig::new(1)).unwrap();
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // The worker is kept busy until all the tasks are queued.
+        pool.execute(move || released.recv().unwrap());
+        for (priority, name) in [
+            (TaskPriority::Low, "low"),
+            (TaskPriority::Critical, "critical"),
+            (TaskPriority::Normal, "normal 1"),
+            (TaskPriority::Normal, "normal 2"),
+        ] {
+            let sender = sender.clone();
+            pool.execute_with_priority(priority, move || sender.send(name).unwrap());
+        }
+        release.send(()).unwrap();
+        drop(pool);
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            ["critical", "normal 1", "normal 2", "low"]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! The POSIX scheduling items missing from the `libc` crate on some platforms,
+//! which the `unix` module uses in place of the `libc` crate. On Redox, Haiku
+//! and ESP-IDF the policies are never passed to the kernel, the calls fail with
+//! `ENOSYS`: the platform modules apply the priorities instead. On RTEMS the
+//! items are bound to its POSIX implementation.
+
+#![allow(non_camel_case_types)]
+
+pub use ::libc::*;
+
+#[cfg(target_os = "rtems")]
+pub use self::rtems::*;
+#[cfg(not(target_os = "rtems"))]
+pub use self::unsupported::*;
+
+#[cfg(not(target_os = "rtems"))]
+mod unsupported {
+    use ::libc::{c_int, pid_t, pthread_t, ENOSYS};
+
+    #[repr(C)]
+    pub struct sched_param {
+        pub sched_priority: c_int,
+    }
+
+    pub const SCHED_OTHER: c_int = 0;
+    pub const SCHED_FIFO: c_int = 1;
+    pub const SCHED_RR: c_int = 2;
+
+    unsafe fn unsupported() -> c_int {
+        crate::unix::set_errno(ENOSYS);
+        -1
+    }
+
+    pub unsafe fn sched_getscheduler(_pid: pid_t) -> c_int {
+        unsupported()
+    }
+
+    pub unsafe fn sched_setscheduler(
+        _pid: pid_t,
+        _policy: c_int,
+        _params: *const sched_param,
+    ) -> c_int {
+        unsupported()
+    }
+
+    pub unsafe fn pthread_setschedparam(
+        _native: pthread_t,
+        _policy: c_int,
+        _params: *const sched_param,
+    ) -> c_int {
+        ENOSYS
+    }
+
+    // Haiku and ESP-IDF read the native priorities instead.
+    #[cfg(target_os = "redox")]
+    pub unsafe fn pthread_getschedparam(
+        _native: pthread_t,
+        _policy: *mut c_int,
+        _params: *mut sched_param,
+    ) -> c_int {
+        ENOSYS
+    }
+}
+
+#[cfg(target_os = "rtems")]
+mod rtems {
+    use ::libc::{c_int, pid_t, pthread_t, timespec};
+
+    /// The `sched_param` structure of `sys/sched.h`, with the sporadic server parameters.
+    #[repr(C)]
+    pub struct sched_param {
+        pub sched_priority: c_int,
+        pub sched_ss_low_priority: c_int,
+        pub sched_ss_repl_period: timespec,
+        pub sched_ss_init_budget: timespec,
+        pub sched_ss_max_repl: c_int,
+    }
+
+    pub const SCHED_OTHER: c_int = 0;
+    pub const SCHED_FIFO: c_int = 1;
+    pub const SCHED_RR: c_int = 2;
+    pub const SCHED_SPORADIC: c_int = 4;
+
+    extern "C" {
+        pub fn sched_getscheduler(pid: pid_t) -> c_int;
+        pub fn sched_setscheduler(pid: pid_t, policy: c_int, params: *const sched_param) -> c_int;
+        pub fn pthread_setschedparam(
+            native: pthread_t,
+            policy: c_int,
+            params: *const sched_param,
+        ) -> c_int;
+        pub fn pthread_getschedparam(
+            native: pthread_t,
+            policy: *mut c_int,
+            params: *mut sched_param,
+        ) -> c_int;
+    }
+}
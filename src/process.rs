@@ -0,0 +1,425 @@
+//! This module defines the process priority control.
+//!
+//! Lowering a whole helper process, like a background indexer, is usually
+//! done with the priority of the process rather than with the priorities of
+//! its threads. On Unix it is the niceness set with `setpriority(2)`, on
+//! Windows the priority class set with `SetPriorityClass`. This module maps
+//! the crate's priorities onto them, the OS values being the native ones: the
+//! niceness reinterpreted as signed on Unix, the priority class on Windows.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use thread_priority::*;
+//! use thread_priority::process::*;
+//!
+//! assert!(set_current_process_priority(ThreadPriority::Min).is_ok());
+//! assert!(get_current_process_priority().is_ok());
+//! ```
+
+use crate::{Error, ThreadPriority};
+
+#[cfg(unix)]
+pub use crate::unix::ProcessId;
+#[cfg(windows)]
+pub use crate::windows::ProcessId;
+/// An alias type for a process id.
+#[cfg(not(any(unix, windows)))]
+pub type ProcessId = u32;
+
+/// The lowest niceness, the highest priority.
+#[cfg(unix)]
+pub const NICE_HIGHEST: i32 = -20;
+/// The highest niceness, the lowest priority.
+#[cfg(unix)]
+pub const NICE_LOWEST: i32 = 19;
+
+/// Maps the priority onto a niceness. The cross-platform values are spread over the whole
+/// niceness range, the OS values are the niceness reinterpreted as signed.
+#[cfg(unix)]
+pub fn nice_from(priority: ThreadPriority) -> Result<i32, Error> {
+    Ok(match priority {
+        ThreadPriority::Min => NICE_LOWEST,
+        ThreadPriority::Crossplatform(crate::ThreadPriorityValue(p)) => {
+            if p > 99 {
+                return Err(Error::Priority("The value is out of range [0; 99]."));
+            }
+            NICE_LOWEST - i32::from(p) * (NICE_LOWEST - NICE_HIGHEST) / 99
+        }
+        ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => match p as i32 {
+            nice if (NICE_HIGHEST..=NICE_LOWEST).contains(&nice) => nice,
+            _ => return Err(Error::Priority("The value is out of range [-20; 19].")),
+        },
+        #[cfg(target_os = "linux")]
+        ThreadPriority::Deadline(_, _, _) => {
+            return Err(Error::Priority("Deadline is not a niceness."))
+        }
+        ThreadPriority::Max => NICE_HIGHEST,
+    })
+}
+
+/// Maps the priority onto a priority class. The cross-platform values are spread over
+/// the classes from `Idle` to `High`, the OS values are the priority classes.
+/// [`ProcessPriorityClass::Realtime`](crate::windows::ProcessPriorityClass::Realtime) is
+/// only reached with its OS value.
+#[cfg(windows)]
+pub fn priority_class_from(
+    priority: ThreadPriority,
+) -> Result<crate::windows::ProcessPriorityClass, Error> {
+    use crate::windows::ProcessPriorityClass;
+    use std::convert::TryFrom;
+
+    Ok(match priority {
+        ThreadPriority::Min => ProcessPriorityClass::Idle,
+        ThreadPriority::Crossplatform(crate::ThreadPriorityValue(p)) => match p {
+            0..=19 => ProcessPriorityClass::Idle,
+            20..=39 => ProcessPriorityClass::BelowNormal,
+            40..=59 => ProcessPriorityClass::Normal,
+            60..=79 => ProcessPriorityClass::AboveNormal,
+            80..=99 => ProcessPriorityClass::High,
+            _ => return Err(Error::Priority("The value is out of range [0; 99].")),
+        },
+        ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => ProcessPriorityClass::try_from(p)?,
+        ThreadPriority::Max => ProcessPriorityClass::High,
+    })
+}
+
+/// The failure of an operation applied to all the threads of a process.
+///
+/// # Usage
+///
+/// ```rust,no_run
+/// use thread_priority::*;
+/// use thread_priority::process::*;
+///
+/// match set_current_process_priority(ThreadPriority::Max) {
+///     Ok(()) => {}
+///     Err(BatchError::Failed(e)) => println!("Nothing has been changed: {}", e),
+///     Err(BatchError::Partial { succeeded, failed }) => {
+///         println!("Changed {} threads, failed for {:?}", succeeded.len(), failed)
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub enum BatchError {
+    /// The operation has failed for all the threads, nothing has been changed.
+    Failed(Error),
+    /// The operation has only succeeded for some of the threads.
+    Partial {
+        /// The kernel ids of the threads the operation has succeeded for.
+        succeeded: Vec<ProcessId>,
+        /// The kernel ids of the threads the operation has failed for, with the errors.
+        failed: Vec<(ProcessId, Error)>,
+    },
+}
+
+impl From<Error> for BatchError {
+    fn from(error: Error) -> Self {
+        BatchError::Failed(error)
+    }
+}
+
+impl std::fmt::Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchError::Failed(e) => e.fmt(f),
+            BatchError::Partial { succeeded, failed } => write!(
+                f,
+                "Succeeded for {} threads, failed for {} threads",
+                succeeded.len(),
+                failed.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// Sets the priority of the process with the given id.
+///
+/// * May require privileges
+///
+/// * On Unix, based on `setpriority(PRIO_PROCESS)`, `0` being the current process.
+/// * On Linux and Android, the niceness belongs to the threads, so it is set for all
+///   the threads the process has at the moment, the threads created later inherit it
+///   from their creator. A failure for some of the threads doesn't stop the others, it is
+///   reported with [`BatchError::Partial`].
+/// * On Windows, based on `SetPriorityClass`.
+pub fn set_process_priority(pid: ProcessId, priority: ThreadPriority) -> Result<(), BatchError> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return set_native_process_priority(pid, priority);
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    set_native_process_priority(pid, priority).map_err(BatchError::Failed)
+}
+
+/// Sets the priority of the current process.
+/// For more info read [`set_process_priority`].
+pub fn set_current_process_priority(priority: ThreadPriority) -> Result<(), BatchError> {
+    #[cfg(unix)]
+    let pid = 0;
+    #[cfg(not(unix))]
+    let pid = std::process::id() as ProcessId;
+    set_process_priority(pid, priority)
+}
+
+/// Returns the priority of the process with the given id, as an OS value.
+///
+/// * On Unix, based on `getpriority(PRIO_PROCESS)`, `0` being the current process. On
+///   Linux and Android, this is the niceness of the main thread of the process.
+/// * On Windows, based on `GetPriorityClass`.
+pub fn get_process_priority(pid: ProcessId) -> Result<ThreadPriority, Error> {
+    get_native_process_priority(pid).map(|p| ThreadPriority::Os(crate::ThreadPriorityOsValue(p)))
+}
+
+/// Returns the priority of the current process.
+/// For more info read [`get_process_priority`].
+pub fn get_current_process_priority() -> Result<ThreadPriority, Error> {
+    #[cfg(unix)]
+    let pid = 0;
+    #[cfg(not(unix))]
+    let pid = std::process::id() as ProcessId;
+    get_process_priority(pid)
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "nto"
+))]
+pub(crate) fn setpriority(who: ProcessId, nice: i32) -> Result<(), Error> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::Setpriority)?;
+    let result = crate::hooks::notified(crate::hooks::Change::Nice { who, nice }, || unsafe {
+        match libc::setpriority(libc::PRIO_PROCESS, who as _, nice) {
+            0 => Ok(()),
+            _ => Err(Error::OS(crate::unix::errno())),
+        }
+    });
+    traced!(DEBUG, result, "the niceness", who, nice)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_native_process_priority(pid: ProcessId, priority: ThreadPriority) -> Result<(), BatchError> {
+    let nice = nice_from(priority)?;
+    let tasks = match pid {
+        0 => std::fs::read_dir("/proc/self/task"),
+        pid => std::fs::read_dir(format!("/proc/{}/task", pid)),
+    }
+    .map_err(Error::from)?;
+    let mut succeeded = Vec::new();
+    let mut failed = Vec::new();
+    for task in tasks {
+        let task = task.map_err(Error::from)?;
+        let tid = match task.file_name().to_str().and_then(|t| t.parse().ok()) {
+            Some(tid) => tid,
+            None => continue,
+        };
+        match setpriority(tid, nice) {
+            Ok(()) => succeeded.push(tid),
+            // The thread has exited in the meantime.
+            Err(Error::OS(libc::ESRCH)) => {}
+            Err(e) => failed.push((tid, e)),
+        }
+    }
+    match failed.first() {
+        None => Ok(()),
+        Some((_, e)) if succeeded.is_empty() => Err(BatchError::Failed(*e)),
+        Some(_) => Err(BatchError::Partial { succeeded, failed }),
+    }
+}
+
+#[cfg(any(
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "nto"
+))]
+fn set_native_process_priority(pid: ProcessId, priority: ThreadPriority) -> Result<(), Error> {
+    setpriority(pid, nice_from(priority)?)
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "nto"
+))]
+pub(crate) fn getpriority(who: ProcessId) -> Result<i32, Error> {
+    use crate::unix::{errno, set_errno};
+
+    set_errno(0);
+    match unsafe { libc::getpriority(libc::PRIO_PROCESS, who as _) } {
+        // The niceness may be `-1` itself, only the errno tells the failures.
+        -1 if errno() != 0 => Err(Error::OS(errno())),
+        nice => Ok(nice),
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "nto"
+))]
+fn get_native_process_priority(pid: ProcessId) -> Result<u32, Error> {
+    // On Linux the process id addresses the main thread, `0` the calling one.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let pid = match pid {
+        0 => unsafe { libc::getpid() },
+        pid => pid,
+    };
+    getpriority(pid).map(|nice| nice as u32)
+}
+
+#[cfg(windows)]
+fn set_native_process_priority(pid: ProcessId, priority: ThreadPriority) -> Result<(), Error> {
+    use winapi::shared::minwindef::DWORD;
+    use winapi::um::processthreadsapi::SetPriorityClass;
+    use winapi::um::winnt::PROCESS_SET_INFORMATION;
+
+    let class = priority_class_from(priority)?;
+    with_process(pid, PROCESS_SET_INFORMATION, |handle| unsafe {
+        SetPriorityClass(handle, class as DWORD) as DWORD
+    })
+    .map(|_| ())
+}
+
+#[cfg(windows)]
+fn get_native_process_priority(pid: ProcessId) -> Result<u32, Error> {
+    use winapi::um::processthreadsapi::GetPriorityClass;
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    with_process(pid, PROCESS_QUERY_LIMITED_INFORMATION, |handle| unsafe {
+        GetPriorityClass(handle)
+    })
+}
+
+/// Opens the process, calls the function with its handle and closes it. A result of `0`
+/// is a failure, reported by `GetLastError`.
+#[cfg(windows)]
+fn with_process(
+    pid: ProcessId,
+    access: winapi::shared::minwindef::DWORD,
+    f: impl FnOnce(winapi::um::winnt::HANDLE) -> u32,
+) -> Result<u32, Error> {
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::OpenProcess;
+
+    unsafe {
+        let handle = OpenProcess(access, 0, pid);
+        if handle.is_null() {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+        let result = match f(handle) {
+            0 => Err(Error::OS(GetLastError() as i32)),
+            value => Ok(value),
+        };
+        CloseHandle(handle);
+        result
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "nto",
+    windows
+)))]
+fn set_native_process_priority(_pid: ProcessId, _priority: ThreadPriority) -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "Setting the process priority is not supported on this platform",
+    ))
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "nto",
+    windows
+)))]
+fn get_native_process_priority(_pid: ProcessId) -> Result<u32, Error> {
+    Err(Error::Unsupported(
+        "The process priority is not supported on this platform",
+    ))
+}
+
+#[cfg(all(test, any(unix, windows)))]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn nice_from_test() {
+        let value = |p| ThreadPriority::Crossplatform(crate::ThreadPriorityValue(p));
+        assert_eq!(nice_from(value(0)).unwrap(), NICE_LOWEST);
+        assert_eq!(nice_from(value(99)).unwrap(), NICE_HIGHEST);
+        assert!(nice_from(value(100)).is_err());
+        let os = ThreadPriority::Os(crate::ThreadPriorityOsValue(-5i32 as u32));
+        assert_eq!(nice_from(os).unwrap(), -5);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn priority_class_from_test() {
+        use crate::windows::ProcessPriorityClass;
+
+        let value = |p| ThreadPriority::Crossplatform(crate::ThreadPriorityValue(p));
+        assert_eq!(
+            priority_class_from(value(0)).unwrap(),
+            ProcessPriorityClass::Idle
+        );
+        assert_eq!(
+            priority_class_from(value(50)).unwrap(),
+            ProcessPriorityClass::Normal
+        );
+        assert_eq!(
+            priority_class_from(ThreadPriority::Max).unwrap(),
+            ProcessPriorityClass::High
+        );
+        assert!(priority_class_from(value(100)).is_err());
+    }
+}
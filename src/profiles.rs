@@ -0,0 +1,181 @@
+//! This module defines the workload profiles.
+//!
+//! Each platform has its own way of telling the scheduler what a thread is
+//! for: the policies and priorities on Linux, the quality of service classes
+//! on the Apple platforms, MMCSS and the background mode on Windows. A
+//! [`Profile`] names a common kind of workload and expands to the right
+//! settings on each platform, so that the applications don't need to learn
+//! the nuances of all the schedulers.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use thread_priority::profiles::*;
+//!
+//! let thread = std::thread::spawn(|| {
+//!     let _profile = Profile::BackgroundBatch.apply_to_current().unwrap();
+//!     // The batch work.
+//! });
+//! thread.join().unwrap();
+//! ```
+
+use crate::Error;
+
+/// A kind of workload, expanding to the scheduling settings which suit it on each platform.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Profile {
+    /// The low-latency audio processing, which mustn't miss its deadlines.
+    ///
+    /// * May require privileges
+    ///
+    /// * On Apple platforms, the `UserInteractive` QoS class.
+    /// * On Windows, the `Pro Audio` MMCSS task.
+    /// * On the other Unix platforms, the FIFO realtime policy at the cross-platform
+    ///   priority `80`.
+    Audio,
+    /// The simulation or the rendering loop of a game, which has to keep its frame rate.
+    ///
+    /// * On Apple platforms, the `UserInitiated` QoS class.
+    /// * On Windows, the `Games` MMCSS task.
+    /// * On the other Unix platforms, the normal policy at its highest priority.
+    GameSimulation,
+    /// The background work nobody is waiting for, like indexing or backups.
+    ///
+    /// * On Apple platforms, the `Background` QoS class, which throttles the I/O too.
+    /// * On Windows, the background processing mode, which lowers the I/O and the memory
+    ///   priorities too.
+    /// * On Linux and Android, the batch policy with the low I/O priority.
+    /// * On the other Unix platforms, the normal policy at its lowest priority.
+    BackgroundBatch,
+}
+
+/// A profile applied to the current thread. On Windows, the MMCSS registration and the
+/// background mode are reverted when it is dropped, so it must be dropped on the thread
+/// which has applied it. On the other platforms, the settings stay applied.
+#[derive(Debug)]
+pub struct AppliedProfile {
+    profile: Profile,
+    #[cfg(windows)]
+    _characteristics: Option<crate::windows::WindowsThreadCharacteristics>,
+    #[cfg(windows)]
+    _background_mode: Option<crate::windows::BackgroundModeGuard>,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl AppliedProfile {
+    /// Returns the applied profile.
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+}
+
+impl Profile {
+    /// Applies the profile to the current thread.
+    pub fn apply_to_current(self) -> Result<AppliedProfile, Error> {
+        #[cfg(windows)]
+        let (characteristics, background_mode) = apply_native(self)?;
+        #[cfg(not(windows))]
+        apply_native(self)?;
+        Ok(AppliedProfile {
+            profile: self,
+            #[cfg(windows)]
+            _characteristics: characteristics,
+            #[cfg(windows)]
+            _background_mode: background_mode,
+            _not_send: std::marker::PhantomData,
+        })
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+fn apply_native(profile: Profile) -> Result<(), Error> {
+    use crate::darwin::{set_current_thread_qos_class, QosClass};
+
+    set_current_thread_qos_class(match profile {
+        Profile::Audio => QosClass::UserInteractive,
+        Profile::GameSimulation => QosClass::UserInitiated,
+        Profile::BackgroundBatch => QosClass::Background,
+    })
+}
+
+#[cfg(windows)]
+fn apply_native(
+    profile: Profile,
+) -> Result<
+    (
+        Option<crate::windows::WindowsThreadCharacteristics>,
+        Option<crate::windows::BackgroundModeGuard>,
+    ),
+    Error,
+> {
+    use crate::windows::{BackgroundModeGuard, MmcssTask, WindowsThreadCharacteristics};
+
+    Ok(match profile {
+        Profile::Audio => (
+            Some(WindowsThreadCharacteristics::new(MmcssTask::ProAudio)?),
+            None,
+        ),
+        Profile::GameSimulation => (
+            Some(WindowsThreadCharacteristics::new(MmcssTask::Games)?),
+            None,
+        ),
+        Profile::BackgroundBatch => (None, Some(BackgroundModeGuard::begin()?)),
+    })
+}
+
+#[cfg(all(unix, not(target_vendor = "apple")))]
+fn apply_native(profile: Profile) -> Result<(), Error> {
+    use crate::{
+        NormalThreadSchedulePolicy, RealtimeThreadSchedulePolicy, ThreadPriority,
+        ThreadPriorityValue, ThreadSchedulePolicy,
+    };
+
+    let (priority, policy) = match profile {
+        Profile::Audio => (
+            ThreadPriority::Crossplatform(ThreadPriorityValue(80)),
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo),
+        ),
+        Profile::GameSimulation => (
+            ThreadPriority::Max,
+            ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+        ),
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        Profile::BackgroundBatch => {
+            crate::io_priority::set_current_thread_io_priority(
+                crate::io_priority::IoPriority::Low,
+            )?;
+            (
+                ThreadPriority::Min,
+                ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Batch),
+            )
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        Profile::BackgroundBatch => (
+            ThreadPriority::Min,
+            ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+        ),
+    };
+    crate::set_thread_priority_and_policy(crate::thread_native_id(), priority, policy)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn apply_native(profile: Profile) -> Result<(), Error> {
+    match profile {
+        Profile::BackgroundBatch => crate::ThreadPriority::Min.set_for_current(),
+        _ => crate::ThreadPriority::Max.set_for_current(),
+    }
+}
+
+#[cfg(all(test, any(unix, windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn background_batch_profile_test() {
+        let thread = std::thread::spawn(|| {
+            let applied = Profile::BackgroundBatch.apply_to_current().unwrap();
+            assert_eq!(applied.profile(), Profile::BackgroundBatch);
+        });
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,470 @@
+//! This module defines the multi-property thread configuration.
+//!
+//! Configuring a thread usually takes a few calls: the policy and the
+//! priority, the affinity, the niceness, the I/O priority. When one of them
+//! fails half-way, the thread is left with a mix of the old and the new
+//! settings. [`ThreadProperties::apply`] captures the previous state of each
+//! property before changing it, and rolls all the changed properties back
+//! if any step fails.
+//!
+//! The hooks of [`ApplyOnThreadStart`] apply the properties to the threads of
+//! the executors and the pools when they start.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::affinity::CpuSet;
+//! use thread_priority::properties::*;
+//!
+//! let properties = ThreadProperties::default()
+//!     .priority(ThreadPriority::Min)
+//!     .affinity(CpuSet::single(0));
+//! assert!(properties.apply(thread_native_id()).is_ok());
+//! ```
+
+use crate::affinity::CpuSet;
+use crate::io_priority::IoPriority;
+use crate::{Error, ThreadId, ThreadPriority};
+
+/// The properties to apply to a thread, the ones left unset are kept.
+#[derive(Clone, Debug, Default)]
+pub struct ThreadProperties {
+    priority: Option<ThreadPriority>,
+    #[cfg(unix)]
+    policy: Option<crate::ThreadSchedulePolicy>,
+    affinity: Option<CpuSet>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    nice: Option<i32>,
+    io_priority: Option<IoPriority>,
+}
+
+/// The thread the properties are applied to.
+#[derive(Copy, Clone)]
+enum Target {
+    Native(ThreadId),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Tid(crate::ThreadTid),
+}
+
+/// The state of a property before it has been changed.
+enum Previous {
+    #[cfg(unix)]
+    Scheduling(crate::ThreadSchedulePolicy, libc::c_int),
+    #[cfg(not(unix))]
+    Priority(ThreadPriority),
+    Affinity(CpuSet),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Nice(crate::ThreadTid, i32),
+    IoPriority(IoPriority),
+}
+
+impl Previous {
+    fn restore(&self, target: Target) -> Result<(), Error> {
+        match (self, target) {
+            #[cfg(unix)]
+            (Previous::Scheduling(policy, sched_priority), Target::Native(native)) => {
+                crate::unix::restore_scheduling(native, *policy, *sched_priority)
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (Previous::Scheduling(policy, sched_priority), Target::Tid(tid)) => {
+                crate::set_process_schedule_policy(
+                    tid,
+                    *policy,
+                    ThreadPriority::Os(crate::ThreadPriorityOsValue(*sched_priority as u32)),
+                )
+            }
+            #[cfg(not(unix))]
+            (Previous::Priority(priority), Target::Native(native)) => {
+                crate::set_thread_priority(native, *priority)
+            }
+            (Previous::Affinity(cpus), Target::Native(native)) => {
+                crate::affinity::set_thread_affinity(native, cpus)
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (Previous::Affinity(cpus), Target::Tid(tid)) => {
+                crate::affinity::set_tid_affinity(tid, cpus)
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (Previous::Nice(tid, nice), _) => crate::process::setpriority(*tid, *nice),
+            (Previous::IoPriority(priority), Target::Native(native)) => {
+                crate::io_priority::set_thread_io_priority(native, *priority)
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (Previous::IoPriority(priority), Target::Tid(tid)) => {
+                crate::io_priority::set_tid_io_priority(tid, *priority)
+            }
+        }
+    }
+}
+
+impl ThreadProperties {
+    /// The thread's priority. Without a policy, it is applied with the current policy of
+    /// the thread.
+    ///
+    /// For more information, see [`ThreadPriority`].
+    pub fn priority<VALUE: Into<ThreadPriority>>(mut self, value: VALUE) -> Self {
+        self.priority = Some(value.into());
+        self
+    }
+
+    /// The thread's unix scheduling policy. Without a priority, it is applied with
+    /// [`ThreadPriority::Min`].
+    ///
+    /// For more information, see
+    /// [`crate::unix::ThreadSchedulePolicy`] and [`crate::unix::set_thread_priority_and_policy`].
+    #[cfg(unix)]
+    pub fn policy<VALUE: Into<crate::ThreadSchedulePolicy>>(mut self, value: VALUE) -> Self {
+        self.policy = Some(value.into());
+        self
+    }
+
+    /// The CPUs to pin the thread to.
+    ///
+    /// For more information, see [`crate::affinity::set_thread_affinity`].
+    pub fn affinity(mut self, value: CpuSet) -> Self {
+        self.affinity = Some(value);
+        self
+    }
+
+    /// The thread's niceness, from `-20` to `19`.
+    ///
+    /// On Linux, it can only be applied to the current thread.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn nice(mut self, value: i32) -> Self {
+        self.nice = Some(value);
+        self
+    }
+
+    /// The thread's I/O priority.
+    ///
+    /// For more information, see [`crate::io_priority::set_thread_io_priority`].
+    pub fn io_priority(mut self, value: IoPriority) -> Self {
+        self.io_priority = Some(value);
+        self
+    }
+
+    /// Applies the properties to the thread: the scheduling, then the affinity, the
+    /// niceness and the I/O priority. If any step fails, the properties changed before are
+    /// restored and the error of the step is returned; the errors of the restoration are
+    /// logged.
+    ///
+    /// * May require privileges
+    ///
+    /// The threads under the deadline policy can't be rolled back, so their scheduling
+    /// can't be changed with the properties.
+    pub fn apply(&self, native: ThreadId) -> Result<(), Error> {
+        self.apply_to(Target::Native(native))
+    }
+
+    /// Applies the properties to the current thread.
+    /// For more info read [`ThreadProperties::apply`].
+    pub fn apply_to_current(&self) -> Result<(), Error> {
+        self.apply(crate::thread_native_id())
+    }
+
+    /// Applies the properties to the thread of the current process addressed by its
+    /// kernel thread id, which is how the threads not created by the application are
+    /// known.
+    /// For more info read [`ThreadProperties::apply`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn apply_to_tid(&self, tid: crate::ThreadTid) -> Result<(), Error> {
+        self.apply_to(Target::Tid(tid))
+    }
+
+    fn apply_to(&self, target: Target) -> Result<(), Error> {
+        let mut applied = Vec::new();
+        let result = self.apply_steps(target, &mut applied);
+        if result.is_err() {
+            for previous in applied.iter().rev() {
+                if let Err(e) = previous.restore(target) {
+                    log::warn!("Couldn't roll back a property of the thread: {:?}", e);
+                }
+            }
+        }
+        result
+    }
+
+    fn apply_steps(&self, target: Target, applied: &mut Vec<Previous>) -> Result<(), Error> {
+        #[cfg(unix)]
+        if self.priority.is_some() || self.policy.is_some() {
+            let priority = self.priority.unwrap_or(ThreadPriority::Min);
+            match target {
+                Target::Native(native) => {
+                    let (policy, sched_priority) = crate::unix::restorable_scheduling(native)?;
+                    crate::set_thread_priority_and_policy(
+                        native,
+                        priority,
+                        self.policy.unwrap_or(policy),
+                    )?;
+                    applied.push(Previous::Scheduling(policy, sched_priority));
+                }
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                Target::Tid(tid) => {
+                    let pid = std::process::id() as crate::ProcessId;
+                    let (policy, params) = crate::thread_schedule_policy_param_for_tid(pid, tid)?;
+                    #[cfg(target_os = "linux")]
+                    if let crate::ThreadSchedulePolicy::Realtime(
+                        crate::RealtimeThreadSchedulePolicy::Deadline,
+                    ) = policy
+                    {
+                        return Err(Error::Priority(
+                            "The deadline policy parameters can't be restored.",
+                        ));
+                    }
+                    crate::set_thread_priority_and_policy_for_tid(
+                        pid,
+                        tid,
+                        priority,
+                        self.policy.unwrap_or(policy),
+                    )?;
+                    applied.push(Previous::Scheduling(policy, params.sched_priority));
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        if let (Some(priority), Target::Native(native)) = (self.priority, target) {
+            let previous = crate::get_thread_priority(native)?;
+            crate::set_thread_priority(native, priority)?;
+            applied.push(Previous::Priority(previous));
+        }
+
+        if let Some(cpus) = &self.affinity {
+            let previous = match target {
+                Target::Native(native) => {
+                    let previous = crate::affinity::get_thread_affinity(native)?;
+                    crate::affinity::set_thread_affinity(native, cpus)?;
+                    previous
+                }
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                Target::Tid(tid) => {
+                    let previous = crate::affinity::tid_affinity(tid)?;
+                    traced!(
+                        DEBUG,
+                        crate::affinity::validate_affinity(cpus)
+                            .and_then(|()| crate::hooks::notified(
+                                crate::hooks::Change::TidAffinity { tid, cpus },
+                                || crate::affinity::set_tid_affinity(tid, cpus)
+                            )),
+                        "the affinity",
+                        tid,
+                        cpus = %cpus
+                    )?;
+                    previous
+                }
+            };
+            applied.push(Previous::Affinity(previous));
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if let Some(nice) = self.nice {
+            let tid = match target {
+                Target::Native(native) => crate::unix::thread_tid_of(native)?,
+                Target::Tid(tid) => tid,
+            };
+            let previous = crate::process::getpriority(tid)?;
+            crate::process::setpriority(tid, nice)?;
+            applied.push(Previous::Nice(tid, previous));
+        }
+
+        if let Some(priority) = self.io_priority {
+            let previous = match target {
+                Target::Native(native) => {
+                    let previous = crate::io_priority::thread_io_priority(native)?;
+                    crate::io_priority::set_thread_io_priority(native, priority)?;
+                    previous
+                }
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                Target::Tid(tid) => {
+                    let previous = crate::io_priority::tid_io_priority(tid)?;
+                    traced!(
+                        DEBUG,
+                        crate::hooks::notified(
+                            crate::hooks::Change::TidIoPriority { tid, priority },
+                            || crate::io_priority::set_tid_io_priority(tid, priority)
+                        ),
+                        "the I/O priority",
+                        tid,
+                        ?priority
+                    )?;
+                    previous
+                }
+            };
+            applied.push(Previous::IoPriority(previous));
+        }
+        Ok(())
+    }
+}
+
+/// A list of configurations to try in order, the first one which applies wins. This
+/// covers the usual degradation: a realtime priority if the permissions allow it, a high
+/// niceness otherwise, and no change as the last resort.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::properties::*;
+/// use std::convert::TryInto;
+///
+/// # #[cfg(any(target_os = "linux", target_os = "android"))] {
+/// let request = PriorityRequest::preferred(
+///     ThreadProperties::default()
+///         .policy(ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo))
+///         .priority(ThreadPriority::Crossplatform(80u8.try_into().unwrap())),
+/// )
+/// .fallback(ThreadProperties::default().nice(-20))
+/// .fallback(ThreadProperties::default());
+/// let applied = request.apply_to_current().unwrap();
+/// println!("Applied the configuration {}", applied);
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PriorityRequest {
+    configurations: Vec<ThreadProperties>,
+}
+
+impl PriorityRequest {
+    /// Creates the request with the configuration to try first.
+    pub fn preferred(properties: ThreadProperties) -> Self {
+        PriorityRequest {
+            configurations: vec![properties],
+        }
+    }
+
+    /// Adds the configuration to try if the previous ones fail. An empty
+    /// [`ThreadProperties`] always applies, it stands for leaving the thread as it is.
+    pub fn fallback(mut self, properties: ThreadProperties) -> Self {
+        self.configurations.push(properties);
+        self
+    }
+
+    /// Applies the first configuration which succeeds to the thread, and returns its
+    /// index, `0` being the preferred one. The failed configurations are rolled back, see
+    /// [`ThreadProperties::apply`]. If all of them fail, the error of the last one is
+    /// returned.
+    pub fn apply(&self, native: ThreadId) -> Result<usize, Error> {
+        let mut error = None;
+        for (index, properties) in self.configurations.iter().enumerate() {
+            match properties.apply(native) {
+                Ok(()) => return Ok(index),
+                Err(e) => error = Some(e),
+            }
+        }
+        // There is always the preferred configuration.
+        Err(error.unwrap_or(Error::Priority("No configuration to apply.")))
+    }
+
+    /// Applies the first configuration which succeeds to the current thread.
+    /// For more info read [`PriorityRequest::apply`].
+    pub fn apply_to_current(&self) -> Result<usize, Error> {
+        self.apply(crate::thread_native_id())
+    }
+}
+
+/// A closure run by an executor or a pool on each of its threads when it starts.
+pub type ThreadStartHook = Box<dyn Fn() + Send + Sync + 'static>;
+
+/// Produces the thread start hooks applying the configuration, for the executors and the
+/// pools exposing such a hook, like `async-std`, `smol` or `glommio`. The hooks register
+/// the threads in the [`crate::registry`] when it is enabled, and log the failures to
+/// apply the configuration, the threads running as they are.
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::properties::*;
+///
+/// let hook = ThreadProperties::default()
+///     .priority(ThreadPriority::Min)
+///     .on_thread_start();
+/// std::thread::spawn(move || hook()).join().unwrap();
+/// ```
+pub trait ApplyOnThreadStart {
+    /// Returns the hook applying the configuration to the current thread.
+    fn on_thread_start(&self) -> ThreadStartHook;
+}
+
+fn warn_not_applied(e: &Error) {
+    log::warn!(
+        "Couldn't apply the properties to the thread named {:?}: {:?}",
+        std::thread::current().name(),
+        e,
+    );
+}
+
+impl ApplyOnThreadStart for ThreadProperties {
+    fn on_thread_start(&self) -> ThreadStartHook {
+        let properties = self.clone();
+        Box::new(move || {
+            crate::registry::register_spawned_thread();
+            if let Err(e) = properties.apply_to_current() {
+                warn_not_applied(&e);
+            }
+        })
+    }
+}
+
+impl ApplyOnThreadStart for PriorityRequest {
+    fn on_thread_start(&self) -> ThreadStartHook {
+        let request = self.clone();
+        Box::new(move || {
+            crate::registry::register_spawned_thread();
+            if let Err(e) = request.apply_to_current() {
+                warn_not_applied(&e);
+            }
+        })
+    }
+}
+
+impl ApplyOnThreadStart for ThreadPriority {
+    fn on_thread_start(&self) -> ThreadStartHook {
+        ThreadProperties::default()
+            .priority(*self)
+            .on_thread_start()
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::{NormalThreadSchedulePolicy, ThreadSchedulePolicy};
+
+    #[test]
+    fn rollback_test() {
+        let thread = std::thread::spawn(|| {
+            let policy = crate::thread_schedule_policy_for(crate::thread_native_id()).unwrap();
+            let result = ThreadProperties::default()
+                .policy(ThreadSchedulePolicy::Normal(
+                    NormalThreadSchedulePolicy::Batch,
+                ))
+                // The scheduling is valid, the affinity is not.
+                .affinity(CpuSet::new())
+                .apply_to_current();
+
+            assert!(result.is_err());
+            assert_eq!(
+                crate::thread_schedule_policy_for(crate::thread_native_id()).unwrap(),
+                policy
+            );
+        });
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn priority_request_fallback_test() {
+        let request =
+            PriorityRequest::preferred(ThreadProperties::default().affinity(CpuSet::new()))
+                .fallback(ThreadProperties::default());
+        assert_eq!(request.apply_to_current().unwrap(), 1);
+    }
+
+    #[test]
+    fn on_thread_start_test() {
+        let hook = ThreadProperties::default().nice(19).on_thread_start();
+        let nice = std::thread::spawn(move || {
+            hook();
+            crate::process::getpriority(crate::thread_native_tid()).unwrap()
+        });
+        assert_eq!(nice.join().unwrap(), 19);
+    }
+}
@@ -0,0 +1,79 @@
+//! This module defines the extension of the `rayon` thread pool builders.
+//!
+//! The data-parallel background work usually shouldn't compete with the
+//! latency-sensitive threads. [`ThreadPoolBuilderExt`] applies the
+//! [`ThreadProperties`], like a priority, a policy or an affinity, to every
+//! worker of a `rayon` pool when it starts, with the start handler of the
+//! builder. The properties can depend on the index of the worker, to pin each
+//! of them to its own CPU.
+//!
+//! The failures to apply the properties are logged, the workers run as they
+//! are.
+//!
+//! Enabled by the `rayon` feature.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::affinity::CpuSet;
+//! use thread_priority::properties::ThreadProperties;
+//! use thread_priority::rayon::ThreadPoolBuilderExt;
+//!
+//! let background = ::rayon::ThreadPoolBuilder::new()
+//!     .num_threads(2)
+//!     .worker_priority(ThreadPriority::Min)
+//!     .build()
+//!     .unwrap();
+//! background.install(|| println!("A background task"));
+//!
+//! let pinned = ::rayon::ThreadPoolBuilder::new()
+//!     .num_threads(1)
+//!     .worker_properties_with(|index| ThreadProperties::default().affinity(CpuSet::single(index)))
+//!     .build()
+//!     .unwrap();
+//! pinned.install(|| println!("A task on the first CPU"));
+//! ```
+
+use crate::properties::ThreadProperties;
+use crate::ThreadPriority;
+
+/// Adds the configuration of the scheduling of the workers to the `rayon` thread pool
+/// builders. The methods set the start handler of the builder, replacing the one set
+/// before, and a start handler set after them replaces theirs.
+pub trait ThreadPoolBuilderExt: Sized {
+    /// Applies the priority to every worker when it starts.
+    fn worker_priority(self, priority: ThreadPriority) -> Self {
+        self.worker_properties(ThreadProperties::default().priority(priority))
+    }
+
+    /// Applies the properties to every worker when it starts.
+    /// For more info read [`ThreadProperties::apply_to_current`].
+    fn worker_properties(self, properties: ThreadProperties) -> Self {
+        self.worker_properties_with(move |_| properties.clone())
+    }
+
+    /// Applies the properties returned for the index of every worker when it starts.
+    /// For more info read [`ThreadProperties::apply_to_current`].
+    fn worker_properties_with<F>(self, f: F) -> Self
+    where
+        F: Fn(usize) -> ThreadProperties + Send + Sync + 'static;
+}
+
+impl<S> ThreadPoolBuilderExt for ::rayon::ThreadPoolBuilder<S> {
+    fn worker_properties_with<F>(self, f: F) -> Self
+    where
+        F: Fn(usize) -> ThreadProperties + Send + Sync + 'static,
+    {
+        self.start_handler(move |index| {
+            crate::registry::register_spawned_thread();
+            if let Err(e) = f(index).apply_to_current() {
+                log::warn!(
+                    "Couldn't apply the properties to the rayon worker {}: {:?}",
+                    index,
+                    e
+                );
+            }
+        })
+    }
+}
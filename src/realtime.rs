@@ -0,0 +1,675 @@
+//! This module defines the one-shot realtime setup of a thread.
+//!
+//! Making a thread realtime takes more than its priority: the thread is
+//! usually pinned to dedicated CPUs, the memory of the process is locked so
+//! that no page fault reaches the disk, and the stack is touched in advance
+//! so that its first use doesn't fault either. [`realtime_setup`] walks
+//! through this checklist in one call and reports which steps succeeded, as
+//! some of them may fail without privileges while the others still help.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use thread_priority::*;
+//! use thread_priority::affinity::CpuSet;
+//! use thread_priority::realtime::*;
+//!
+//! let report = realtime_setup(
+//!     RealtimeConfig::new(ThreadPriority::Max)
+//!         .affinity(CpuSet::single(0))
+//!         .prefault_stack(64 * 1024),
+//! );
+//! if !report.is_complete() {
+//!     println!("The realtime setup is incomplete: {:?}", report);
+//! }
+//! ```
+
+use crate::affinity::CpuSet;
+use crate::{Error, ThreadPriority};
+
+/// The steps of the realtime setup of the current thread, applied by [`realtime_setup`].
+#[derive(Debug, Clone)]
+pub struct RealtimeConfig {
+    priority: ThreadPriority,
+    #[cfg(unix)]
+    policy: crate::ThreadSchedulePolicy,
+    affinity: Option<CpuSet>,
+    lock_memory: bool,
+    prefault_stack: Option<usize>,
+}
+
+impl RealtimeConfig {
+    /// Creates the configuration with the priority, which locks the memory. On Unix, the
+    /// priority is used with the FIFO realtime policy.
+    pub fn new(priority: ThreadPriority) -> Self {
+        Self {
+            priority,
+            #[cfg(unix)]
+            policy: crate::ThreadSchedulePolicy::Realtime(
+                crate::RealtimeThreadSchedulePolicy::Fifo,
+            ),
+            affinity: None,
+            lock_memory: true,
+            prefault_stack: None,
+        }
+    }
+
+    /// The thread's unix scheduling policy.
+    ///
+    /// For more information, see [`crate::unix::set_thread_priority_and_policy`].
+    #[cfg(unix)]
+    pub fn policy(mut self, value: crate::ThreadSchedulePolicy) -> Self {
+        self.policy = value;
+        self
+    }
+
+    /// Pins the thread to the CPUs.
+    ///
+    /// For more information, see [`crate::affinity::set_current_thread_affinity`].
+    pub fn affinity(mut self, value: CpuSet) -> Self {
+        self.affinity = Some(value);
+        self
+    }
+
+    /// Enables or disables locking all the current and the future memory of the process.
+    pub fn lock_memory(mut self, value: bool) -> Self {
+        self.lock_memory = value;
+        self
+    }
+
+    /// Touches the bytes of the stack below the current frame, so that they are mapped
+    /// before the realtime work needs them. The size must fit into the stack of the thread,
+    /// otherwise the step fails with [`Error::Priority`].
+    pub fn prefault_stack(mut self, bytes: usize) -> Self {
+        self.prefault_stack = Some(bytes);
+        self
+    }
+}
+
+/// The outcome of each step of [`realtime_setup`], the skipped steps are `None`.
+#[derive(Debug, Clone)]
+pub struct RealtimeReport {
+    /// Setting the priority, and the policy on Unix.
+    pub scheduling: Result<(), Error>,
+    /// Pinning the thread to the CPUs.
+    pub affinity: Option<Result<(), Error>>,
+    /// Locking the memory of the process.
+    pub memory_lock: Option<Result<(), Error>>,
+    /// Touching the stack in advance.
+    pub stack_prefault: Option<Result<(), Error>>,
+}
+
+impl RealtimeReport {
+    /// Returns whether all the configured steps succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.scheduling.is_ok()
+            && [&self.affinity, &self.memory_lock, &self.stack_prefault]
+                .iter()
+                .all(|step| !matches!(step, Some(Err(_))))
+    }
+}
+
+/// Applies the realtime setup to the current thread. All the steps are attempted even if
+/// the previous ones fail, the report tells which of them succeeded.
+///
+/// * May require privileges
+///
+/// * The memory is locked with `mlockall(MCL_CURRENT | MCL_FUTURE)`, on the platforms having
+///   it, otherwise the step fails with [`Error::Unsupported`].
+/// * The memory lock affects the whole process, not just the current thread.
+/// * The stack is prefaulted on Linux, Android, FreeBSD, the Apple platforms and Windows,
+///   where its size is known, otherwise the step fails with [`Error::Unsupported`].
+pub fn realtime_setup(config: RealtimeConfig) -> RealtimeReport {
+    #[cfg(unix)]
+    let scheduling = crate::set_thread_priority_and_policy(
+        crate::thread_native_id(),
+        config.priority,
+        config.policy,
+    );
+    #[cfg(not(unix))]
+    let scheduling = config.priority.set_for_current();
+
+    RealtimeReport {
+        scheduling,
+        affinity: config
+            .affinity
+            .as_ref()
+            .map(crate::affinity::set_current_thread_affinity),
+        memory_lock: if config.lock_memory {
+            Some(lock_all_memory())
+        } else {
+            None
+        },
+        stack_prefault: config.prefault_stack.map(prefault_current_stack),
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "nto"
+))]
+fn lock_all_memory() -> Result<(), Error> {
+    match unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) } {
+        0 => Ok(()),
+        _ => Err(Error::OS(crate::unix::errno())),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "nto"
+)))]
+fn lock_all_memory() -> Result<(), Error> {
+    Err(Error::Unsupported(
+        "Locking the memory is not supported on this platform",
+    ))
+}
+
+const PREFAULT_CHUNK: usize = 4096;
+/// The stack left to the code running after the prefault.
+const PREFAULT_MARGIN: usize = 64 * 1024;
+
+fn prefault_current_stack(bytes: usize) -> Result<(), Error> {
+    // The frame of each chunk takes up to an eighth more than the chunk itself.
+    let needed = bytes
+        .saturating_add(bytes / 8)
+        .saturating_add(PREFAULT_MARGIN);
+    if needed > free_stack()? {
+        return Err(Error::Priority(
+            "The stack prefault doesn't fit into the stack of the thread.",
+        ));
+    }
+    prefault_stack(bytes);
+    Ok(())
+}
+
+/// Returns the address of the caller's frame, roughly.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_vendor = "apple",
+    windows
+))]
+#[inline(always)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    &marker as *const u8 as usize
+}
+
+/// Returns the bytes of the current thread's stack below the caller's frame.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn free_stack() -> Result<usize, Error> {
+    unsafe {
+        let mut attr: libc::pthread_attr_t = std::mem::zeroed();
+        #[cfg(target_os = "freebsd")]
+        let ret = match libc::pthread_attr_init(&mut attr) {
+            0 => libc::pthread_attr_get_np(libc::pthread_self(), &mut attr),
+            ret => return Err(Error::OS(ret)),
+        };
+        #[cfg(not(target_os = "freebsd"))]
+        let ret = libc::pthread_getattr_np(libc::pthread_self(), &mut attr);
+        let (mut low, mut size) = (std::ptr::null_mut(), 0);
+        let ret = match ret {
+            0 => libc::pthread_attr_getstack(&attr, &mut low, &mut size),
+            ret => ret,
+        };
+        libc::pthread_attr_destroy(&mut attr);
+        match ret {
+            0 => Ok(stack_position().saturating_sub(low as usize)),
+            ret => Err(Error::OS(ret)),
+        }
+    }
+}
+
+/// Returns the bytes of the current thread's stack below the caller's frame.
+#[cfg(target_vendor = "apple")]
+fn free_stack() -> Result<usize, Error> {
+    unsafe {
+        let thread = libc::pthread_self();
+        let high = libc::pthread_get_stackaddr_np(thread) as usize;
+        let low = high.saturating_sub(libc::pthread_get_stacksize_np(thread));
+        Ok(stack_position().saturating_sub(low))
+    }
+}
+
+/// Returns the bytes of the current thread's stack below the caller's frame.
+#[cfg(windows)]
+fn free_stack() -> Result<usize, Error> {
+    use winapi::um::processthreadsapi::GetCurrentThreadStackLimits;
+
+    let (mut low, mut high) = (0, 0);
+    unsafe { GetCurrentThreadStackLimits(&mut low, &mut high) };
+    Ok(stack_position().saturating_sub(low))
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_vendor = "apple",
+    windows
+)))]
+fn free_stack() -> Result<usize, Error> {
+    Err(Error::Unsupported(
+        "The stack size is unknown on this platform",
+    ))
+}
+
+/// Touches the stack chunk by chunk, each call occupying another chunk below the previous.
+#[inline(never)]
+fn prefault_stack(bytes: usize) {
+    let mut chunk = [0u8; PREFAULT_CHUNK];
+    for byte in chunk.iter_mut().step_by(256) {
+        // The volatile writes can't be optimized away with the chunk.
+        unsafe { std::ptr::write_volatile(byte, 1) };
+    }
+    if bytes > PREFAULT_CHUNK {
+        prefault_stack(bytes - PREFAULT_CHUNK);
+    }
+    unsafe { std::ptr::read_volatile(&chunk[0]) };
+}
+
+/// The sources of the permission to use the realtime scheduling, as found by
+/// [`realtime_permissions`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RealtimePermissions {
+    /// Whether the process bypasses the limits: on Unix, it runs as root or, on Linux and
+    /// Android, has `CAP_SYS_NICE`; on Windows, it runs elevated, having the privilege to
+    /// raise the process to `REALTIME_PRIORITY_CLASS`.
+    pub privileged: bool,
+    /// The soft `RLIMIT_RTPRIO` limit on Linux and Android, the highest realtime priority
+    /// allowed without the privileges; `None` when unlimited or on the other platforms.
+    pub rtprio_limit: Option<u64>,
+    /// Whether the user belongs to the `realtime` or the `audio` group on Linux, which the
+    /// distributions usually give a realtime limit. The limit is only applied by the next
+    /// login, so this only explains why it is still missing.
+    pub realtime_group: bool,
+}
+
+/// Inspects the permissions of the process to use the realtime scheduling.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::realtime::*;
+///
+/// let permissions = realtime_permissions().unwrap();
+/// println!("Privileged: {}", permissions.privileged);
+/// ```
+pub fn realtime_permissions() -> Result<RealtimePermissions, Error> {
+    native_realtime_permissions()
+}
+
+/// Returns the highest realtime priority the current thread may use, `None` if it may not
+/// use the realtime scheduling at all.
+///
+/// * On Linux and Android, [`ThreadPriority::Max`] with the privileges or an unlimited
+///   `RLIMIT_RTPRIO`, otherwise the limit as [`ThreadPriority::Os`].
+/// * On macOS and iOS, always [`ThreadPriority::Max`], the realtime policies need no
+///   privileges.
+/// * On Windows, [`ThreadPriority::Max`] for the elevated processes.
+/// * On the other Unix platforms, [`ThreadPriority::Max`] for root.
+pub fn max_allowed_realtime_priority() -> Result<Option<ThreadPriority>, Error> {
+    #[cfg(target_vendor = "apple")]
+    return Ok(Some(ThreadPriority::Max));
+
+    #[cfg(not(target_vendor = "apple"))]
+    {
+        let permissions = realtime_permissions()?;
+        Ok(match permissions.rtprio_limit {
+            _ if permissions.privileged => Some(ThreadPriority::Max),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            None => Some(ThreadPriority::Max),
+            Some(0) => None,
+            Some(limit) => Some(ThreadPriority::Os(crate::ThreadPriorityOsValue(
+                limit.min(99) as u32,
+            ))),
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            None => None,
+        })
+    }
+}
+
+/// Returns whether the current thread may use the realtime scheduling, so that the
+/// applications can decide up front whether to offer a realtime mode.
+/// For more info read [`max_allowed_realtime_priority`].
+pub fn can_set_realtime() -> bool {
+    matches!(max_allowed_realtime_priority(), Ok(Some(_)))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn native_realtime_permissions() -> Result<RealtimePermissions, Error> {
+    const CAP_SYS_NICE: u32 = 23;
+
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    let capabilities = status
+        .lines()
+        .find(|line| line.starts_with("CapEff:"))
+        .and_then(|line| u64::from_str_radix(line["CapEff:".len()..].trim(), 16).ok())
+        .unwrap_or(0);
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_RTPRIO, &mut limit) } != 0 {
+        return Err(Error::OS(crate::unix::errno()));
+    }
+
+    Ok(RealtimePermissions {
+        privileged: unsafe { libc::geteuid() } == 0 || capabilities & 1 << CAP_SYS_NICE != 0,
+        rtprio_limit: match limit.rlim_cur {
+            libc::RLIM_INFINITY => None,
+            // `rlim_t` is narrower on the 32-bit Android.
+            #[allow(clippy::unnecessary_cast)]
+            limit => Some(limit as u64),
+        },
+        realtime_group: in_realtime_group(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn in_realtime_group() -> bool {
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if count <= 0 {
+        return false;
+    }
+    let mut groups = vec![0 as libc::gid_t; count as usize];
+    let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+    groups.truncate(count.max(0) as usize);
+    groups.push(unsafe { libc::getegid() });
+
+    groups.into_iter().any(|gid| {
+        let mut group: libc::group = unsafe { std::mem::zeroed() };
+        let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+        let mut result = std::ptr::null_mut();
+        let found = unsafe {
+            libc::getgrgid_r(
+                gid,
+                &mut group,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        } == 0
+            && !result.is_null();
+        found && {
+            let name = unsafe { std::ffi::CStr::from_ptr(group.gr_name) };
+            name.to_bytes() == b"realtime" || name.to_bytes() == b"audio"
+        }
+    })
+}
+
+#[cfg(target_os = "android")]
+fn in_realtime_group() -> bool {
+    false
+}
+
+#[cfg(target_vendor = "apple")]
+fn native_realtime_permissions() -> Result<RealtimePermissions, Error> {
+    Ok(RealtimePermissions {
+        privileged: unsafe { libc::geteuid() } == 0,
+        rtprio_limit: None,
+        realtime_group: false,
+    })
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "linux", target_os = "android", target_vendor = "apple"))
+))]
+fn native_realtime_permissions() -> Result<RealtimePermissions, Error> {
+    Ok(RealtimePermissions {
+        privileged: unsafe { libc::geteuid() } == 0,
+        rtprio_limit: None,
+        realtime_group: false,
+    })
+}
+
+#[cfg(windows)]
+fn native_realtime_permissions() -> Result<RealtimePermissions, Error> {
+    use winapi::um::{
+        errhandlingapi::GetLastError,
+        handleapi::CloseHandle,
+        processthreadsapi::{GetCurrentProcess, OpenProcessToken},
+        securitybaseapi::GetTokenInformation,
+        winnt::{TokenElevation, HANDLE, TOKEN_ELEVATION, TOKEN_QUERY},
+    };
+
+    unsafe {
+        let mut token: HANDLE = std::ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+        let mut elevation = TOKEN_ELEVATION { TokenIsElevated: 0 };
+        let mut length = 0;
+        let ret = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut TOKEN_ELEVATION as *mut _,
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut length,
+        );
+        let error = GetLastError();
+        CloseHandle(token);
+        if ret == 0 {
+            return Err(Error::OS(error as i32));
+        }
+        Ok(RealtimePermissions {
+            privileged: elevation.TokenIsElevated != 0,
+            rtprio_limit: None,
+            realtime_group: false,
+        })
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn native_realtime_permissions() -> Result<RealtimePermissions, Error> {
+    Err(Error::Unsupported(
+        "The realtime scheduling is not supported on this platform",
+    ))
+}
+
+/// A way to grant the permission a failed call was missing, found by [`Error::diagnose`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum PermissionHint {
+    /// The process runs without `CAP_SYS_NICE` on Linux or Android.
+    MissingCapSysNice,
+    /// The `RLIMIT_RTPRIO` limit on Linux or Android is lower than the highest realtime
+    /// priority, it may be lower than the requested one.
+    RtprioLimitTooLow {
+        /// The current soft limit.
+        limit: u64,
+    },
+    /// The user belongs to the `realtime` or the `audio` group, but the process was started
+    /// before the limit of the group was applied.
+    RealtimeGroupLimitNotApplied,
+    /// The cgroup of the process has no realtime runtime, `cpu.rt_runtime_us` is `0`, which
+    /// blocks the realtime policies even for root.
+    CgroupRtRuntimeZero,
+    /// The process doesn't run as root.
+    NotRoot,
+    /// The process doesn't run elevated on Windows.
+    NotElevated,
+}
+
+impl std::fmt::Display for PermissionHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionHint::MissingCapSysNice => write!(
+                f,
+                "Grant CAP_SYS_NICE to the executable with `setcap cap_sys_nice+ep <executable>`"
+            ),
+            PermissionHint::RtprioLimitTooLow { limit } => write!(
+                f,
+                "Raise the RLIMIT_RTPRIO limit of {} with an `rtprio` line in \
+                 /etc/security/limits.conf or `LimitRTPRIO=` of the systemd unit",
+                limit
+            ),
+            PermissionHint::RealtimeGroupLimitNotApplied => write!(
+                f,
+                "Log in again to apply the realtime limit of the user's group"
+            ),
+            PermissionHint::CgroupRtRuntimeZero => write!(
+                f,
+                "Give the cgroup of the process a realtime runtime in its `cpu.rt_runtime_us`"
+            ),
+            PermissionHint::NotRoot => write!(f, "Run the process as root"),
+            PermissionHint::NotElevated => write!(f, "Run the process as administrator"),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_permission_error(error: &Error) -> bool {
+    matches!(error, Error::OS(code) if *code == libc::EPERM || *code == libc::EACCES)
+}
+
+#[cfg(windows)]
+fn is_permission_error(error: &Error) -> bool {
+    use winapi::shared::winerror::{ERROR_ACCESS_DENIED, ERROR_PRIVILEGE_NOT_HELD};
+
+    matches!(
+        error,
+        Error::OS(code) if *code == ERROR_ACCESS_DENIED as i32
+            || *code == ERROR_PRIVILEGE_NOT_HELD as i32
+    )
+}
+
+#[cfg(any(unix, windows))]
+pub(crate) fn diagnose(error: &Error) -> Vec<PermissionHint> {
+    let permissions = match realtime_permissions() {
+        Ok(permissions) if is_permission_error(error) => permissions,
+        _ => return Vec::new(),
+    };
+    let mut hints = Vec::new();
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        if !permissions.privileged {
+            hints.push(PermissionHint::MissingCapSysNice);
+            match permissions.rtprio_limit {
+                Some(0) if permissions.realtime_group => {
+                    hints.push(PermissionHint::RealtimeGroupLimitNotApplied)
+                }
+                Some(limit) if limit < 99 => {
+                    hints.push(PermissionHint::RtprioLimitTooLow { limit })
+                }
+                _ => {}
+            }
+        }
+        if cgroup_rt_runtime_zero() {
+            hints.push(PermissionHint::CgroupRtRuntimeZero);
+        }
+    }
+    #[cfg(windows)]
+    {
+        if !permissions.privileged {
+            hints.push(PermissionHint::NotElevated);
+        }
+    }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+    {
+        if !permissions.privileged {
+            hints.push(PermissionHint::NotRoot);
+        }
+    }
+    hints
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn diagnose(_error: &Error) -> Vec<PermissionHint> {
+    Vec::new()
+}
+
+/// Returns whether the v1 `cpu` cgroup of the process has no realtime runtime. The unified
+/// hierarchy has no realtime runtime control.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn cgroup_rt_runtime_zero() -> bool {
+    let cgroups = match std::fs::read_to_string("/proc/self/cgroup") {
+        Ok(cgroups) => cgroups,
+        Err(_) => return false,
+    };
+    cgroups
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ':');
+            let controllers = fields.nth(1)?;
+            let path = fields.next()?;
+            if controllers.split(',').any(|controller| controller == "cpu") {
+                Some((controllers, path))
+            } else {
+                None
+            }
+        })
+        .any(|(controllers, path)| {
+            let file = format!("/sys/fs/cgroup/{}{}/cpu.rt_runtime_us", controllers, path);
+            matches!(std::fs::read_to_string(file), Ok(runtime) if runtime.trim() == "0")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn realtime_report_test() {
+        let report = realtime_setup(
+            RealtimeConfig::new(ThreadPriority::Min)
+                .lock_memory(false)
+                .prefault_stack(16 * 1024),
+        );
+        assert!(report.affinity.is_none());
+        assert!(report.memory_lock.is_none());
+        assert!(matches!(report.stack_prefault, Some(Ok(()))));
+        assert_eq!(report.is_complete(), report.scheduling.is_ok());
+
+        let report = realtime_setup(
+            RealtimeConfig::new(ThreadPriority::Min)
+                .lock_memory(false)
+                .prefault_stack(usize::MAX),
+        );
+        assert!(matches!(
+            report.stack_prefault,
+            Some(Err(Error::Priority(_)))
+        ));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn max_allowed_realtime_priority_test() {
+        let permissions = realtime_permissions().unwrap();
+        let max = max_allowed_realtime_priority().unwrap();
+        if permissions.privileged || permissions.rtprio_limit.is_none() {
+            assert_eq!(max, Some(ThreadPriority::Max));
+        } else if permissions.rtprio_limit == Some(0) {
+            assert_eq!(max, None);
+        }
+        assert_eq!(can_set_realtime(), max.is_some());
+    }
+
+    #[test]
+    fn diagnose_test() {
+        assert!(Error::Priority("The value is out of range")
+            .diagnose()
+            .is_empty());
+        assert!(Error::OS(0).diagnose().is_empty());
+    }
+}
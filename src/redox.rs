@@ -0,0 +1,71 @@
+//! This module defines the Redox thread control.
+//!
+//! Redox doesn't implement the POSIX thread scheduling yet, the only priority
+//! it knows is the niceness of the processes, set with `setpriority(2)`. This
+//! module provides the niceness and maps the crate's normal priorities onto it,
+//! the realtime policies and the POSIX scheduling calls fail with `ENOSYS`.
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use thread_priority::redox::*;
+//!
+//! assert!(set_process_nice(0, NICE_LOWEST).is_ok());
+//! assert_eq!(process_nice(0).unwrap(), NICE_LOWEST);
+//! ```
+
+use crate::unix::{errno, set_errno};
+use crate::{Error, ThreadPriority, ThreadSchedulePolicy};
+
+/// An alias type for a process id.
+pub type ProcessId = libc::id_t;
+
+pub use crate::process::{nice_from, NICE_HIGHEST, NICE_LOWEST};
+
+/// Sets the niceness of the process with the given id, `0` being the current process.
+///
+/// * May require privileges
+///
+/// Based on `setpriority(PRIO_PROCESS)`.
+pub fn set_process_nice(pid: ProcessId, nice: i32) -> Result<(), Error> {
+    unsafe {
+        match libc::setpriority(libc::PRIO_PROCESS, pid, nice) {
+            0 => Ok(()),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Returns the niceness of the process with the given id, `0` being the current process.
+///
+/// Based on `getpriority(PRIO_PROCESS)`.
+pub fn process_nice(pid: ProcessId) -> Result<i32, Error> {
+    unsafe {
+        set_errno(0);
+        match libc::getpriority(libc::PRIO_PROCESS, pid) {
+            // The niceness may be `-1` itself, only the errno tells the failures.
+            -1 if errno() != 0 => Err(Error::OS(errno())),
+            nice => Ok(nice),
+        }
+    }
+}
+
+/// Sets the niceness of the current process from the normal policies' priority. The
+/// niceness is shared by all the threads of the process.
+pub(crate) fn set_thread_nice_priority(
+    native: crate::ThreadId,
+    policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    if let ThreadSchedulePolicy::Realtime(_) = policy {
+        return Err(Error::Unsupported(
+            "The realtime policies are not supported on this platform",
+        ));
+    }
+    if unsafe { libc::pthread_equal(native, libc::pthread_self()) } == 0 {
+        return Err(Error::Priority(
+            "The priority can only be set for the current thread.",
+        ));
+    }
+    set_process_nice(0, nice_from(priority)?)
+}
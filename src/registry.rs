@@ -0,0 +1,221 @@
+//! This module defines the registry of the native thread ids.
+//!
+//! A [`std::thread::Thread`] doesn't know its native id, so the code holding
+//! only a `Thread`, or its name, can't adjust that specific thread. The
+//! threads registered here can be looked up by their standard library's id or
+//! by their name. The registry is opt-in: once it is enabled, the threads
+//! spawned by the crate's spawn functions and builders register themselves,
+//! the other threads can register with [`register_current_thread`]. The threads
+//! are unregistered when they exit.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::registry::*;
+//!
+//! enable_registry();
+//! let (sender, receiver) = std::sync::mpsc::channel::<()>();
+//! let thread = ThreadBuilder::default()
+//!     .name("worker")
+//!     .spawn_careless(move || receiver.recv())
+//!     .unwrap();
+//! while native_id_by_name("worker").is_none() {
+//!     std::thread::yield_now();
+//! }
+//! assert!(native_id_of(thread.thread().id()).is_some());
+//! sender.send(()).unwrap();
+//! thread.join().unwrap();
+//! ```
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::{Error, ThreadId};
+
+static REGISTRY_ENABLED: AtomicBool = AtomicBool::new(false);
+static REGISTRY: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static REGISTRATION: RefCell<Option<Registration>> = const { RefCell::new(None) };
+}
+
+/// The native id of a registered or watched thread. On Windows, it is a real handle of the
+/// thread, as the one of [`crate::thread_native_id`] only addresses the current
+/// thread.
+#[cfg(windows)]
+pub(crate) struct NativeId(crate::windows::OwnedThreadHandle);
+
+#[cfg(windows)]
+impl NativeId {
+    pub(crate) fn current() -> Result<Self, Error> {
+        crate::windows::OwnedThreadHandle::current().map(NativeId)
+    }
+
+    pub(crate) fn get(&self) -> ThreadId {
+        self.0.as_thread_id()
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) struct NativeId(ThreadId);
+
+// The native ids are only used as the ids, like the values of `thread_native_id`.
+#[cfg(not(windows))]
+unsafe impl Send for NativeId {}
+
+#[cfg(not(windows))]
+impl NativeId {
+    pub(crate) fn current() -> Result<Self, Error> {
+        Ok(NativeId(crate::thread_native_id()))
+    }
+
+    pub(crate) fn get(&self) -> ThreadId {
+        self.0
+    }
+}
+
+struct Entry {
+    id: std::thread::ThreadId,
+    name: Option<String>,
+    native: NativeId,
+}
+
+/// Unregisters the thread when the thread-local storage is destroyed, at the exit.
+struct Registration(std::thread::ThreadId);
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        remove(self.0);
+    }
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Vec<Entry>> {
+    // The entries are always left consistent, a panic can't poison them.
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn remove(id: std::thread::ThreadId) {
+    registry().retain(|entry| entry.id != id);
+}
+
+/// Makes the threads spawned by the crate's spawn functions and builders register
+/// themselves.
+pub fn enable_registry() {
+    REGISTRY_ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stops the registration of the spawned threads. The registered threads stay
+/// registered.
+pub fn disable_registry() {
+    REGISTRY_ENABLED.store(false, Ordering::SeqCst);
+}
+
+/// Returns whether the spawned threads register themselves.
+pub fn registry_enabled() -> bool {
+    REGISTRY_ENABLED.load(Ordering::SeqCst)
+}
+
+/// Registers the current thread, with its name, until it exits or is unregistered.
+/// Registering a thread again updates its entry.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::registry::*;
+///
+/// assert!(register_current_thread().is_ok());
+/// assert!(native_id_of(std::thread::current().id()).is_some());
+/// unregister_current_thread();
+/// assert!(native_id_of(std::thread::current().id()).is_none());
+/// ```
+pub fn register_current_thread() -> Result<(), Error> {
+    let thread = std::thread::current();
+    let entry = Entry {
+        id: thread.id(),
+        name: thread.name().map(str::to_owned),
+        native: NativeId::current()?,
+    };
+    {
+        let mut registry = registry();
+        registry.retain(|e| e.id != entry.id);
+        registry.push(entry);
+    }
+    REGISTRATION.with(|registration| {
+        registration
+            .borrow_mut()
+            .get_or_insert_with(|| Registration(thread.id()));
+    });
+    Ok(())
+}
+
+/// Unregisters the current thread, if it is registered.
+pub fn unregister_current_thread() {
+    // Dropping the registration removes the entry.
+    REGISTRATION.with(|registration| drop(registration.borrow_mut().take()));
+}
+
+/// Registers the spawned thread if the registry is enabled, the failures are only logged
+/// not to fail the spawning.
+pub(crate) fn register_spawned_thread() {
+    if registry_enabled() {
+        if let Err(e) = register_current_thread() {
+            log::warn!(
+                "Couldn't register the thread with Rust Thread ID {:?} named {:?}: {:?}",
+                std::thread::current().id(),
+                std::thread::current().name(),
+                e,
+            );
+        }
+    }
+}
+
+/// Returns the native id of the registered thread with the standard library's id.
+///
+/// The native id is only valid as long as the thread is registered: once the thread
+/// exits, it may be reused by another thread.
+pub fn native_id_of(id: std::thread::ThreadId) -> Option<ThreadId> {
+    registry()
+        .iter()
+        .find(|entry| entry.id == id)
+        .map(|entry| entry.native.get())
+}
+
+/// Returns the native id of the first registered thread with the name.
+/// For more info read [`native_id_of`].
+pub fn native_id_by_name(name: &str) -> Option<ThreadId> {
+    registry()
+        .iter()
+        .find(|entry| entry.name.as_deref() == Some(name))
+        .map(|entry| entry.native.get())
+}
+
+/// Returns the standard library's ids and the names of the registered threads.
+pub fn registered_threads() -> Vec<(std::thread::ThreadId, Option<String>)> {
+    registry()
+        .iter()
+        .map(|entry| (entry.id, entry.name.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_unregisters_exited_threads_test() {
+        let thread = std::thread::Builder::new()
+            .name("registry-test".to_owned())
+            .spawn(|| {
+                register_current_thread().unwrap();
+                std::thread::current().id()
+            })
+            .unwrap();
+        let id = thread.join().unwrap();
+
+        assert!(native_id_of(id).is_none());
+        assert!(native_id_by_name("registry-test").is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! This module defines the sandbox-aware operation mode.
+//!
+//! Inside sandboxes like Flatpak or Chromium's, a seccomp filter may block
+//! `sched_setattr`, `sched_setscheduler`, `setpriority` or `ioprio_set`, so that the
+//! calls fail with `EPERM` (or worse, kill the process) not because of the
+//! scheduler but because of the filter. This module allows to find out
+//! up front which of the calls are available, and to make the crate
+//! restrict itself to just those.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::sandbox::*;
+//!
+//! let capabilities = probe();
+//! println!("Can use SCHED_DEADLINE: {}", capabilities.sched_setattr);
+//! enable_sandbox_safe_mode(capabilities);
+//! assert_eq!(sandbox_safe_mode(), Some(capabilities));
+//! disable_sandbox_safe_mode();
+//! ```
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::Error;
+
+/// The seccomp mode of the current thread, as reported in `/proc/self/status`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum SeccompMode {
+    /// No seccomp restrictions are in place.
+    Disabled,
+    /// Only `read`, `write`, `_exit` and `sigreturn` are allowed.
+    Strict,
+    /// A BPF filter decides which system calls are allowed.
+    Filter,
+}
+
+/// Returns the seccomp mode of the current thread.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::sandbox::*;
+///
+/// assert!(seccomp_mode().is_ok());
+/// ```
+pub fn seccomp_mode() -> Result<SeccompMode, Error> {
+    let status = std::fs::read_to_string("/proc/thread-self/status")
+        .or_else(|_| std::fs::read_to_string("/proc/self/status"))?;
+
+    match status
+        .lines()
+        .find(|line| line.starts_with("Seccomp:"))
+        .map(|line| line["Seccomp:".len()..].trim())
+    {
+        Some("0") | None => Ok(SeccompMode::Disabled),
+        Some("1") => Ok(SeccompMode::Strict),
+        Some("2") => Ok(SeccompMode::Filter),
+        Some(_) => Err(Error::Ffi("Can't parse the seccomp mode")),
+    }
+}
+
+/// The scheduling-related system calls the crate is able to use in the current sandbox.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SandboxCapabilities {
+    /// Whether `sched_setattr` is allowed. Required for the deadline policy.
+    pub sched_setattr: bool,
+    /// Whether `sched_setscheduler` is allowed. Required for the policies
+    /// set through `pthread_setschedparam` and `sched_setscheduler`.
+    pub sched_setscheduler: bool,
+    /// Whether `setpriority` is allowed. Required for changing the niceness.
+    pub setpriority: bool,
+    /// Whether `ioprio_set` is allowed. Required for changing the I/O priority.
+    pub ioprio_set: bool,
+}
+
+impl SandboxCapabilities {
+    /// Capabilities with every system call allowed.
+    pub const fn all() -> Self {
+        SandboxCapabilities {
+            sched_setattr: true,
+            sched_setscheduler: true,
+            setpriority: true,
+            ioprio_set: true,
+        }
+    }
+
+    /// Capabilities with every system call blocked.
+    pub const fn none() -> Self {
+        SandboxCapabilities {
+            sched_setattr: false,
+            sched_setscheduler: false,
+            setpriority: false,
+            ioprio_set: false,
+        }
+    }
+
+    fn to_bits(self) -> u8 {
+        PROBED
+            | (self.sched_setattr as u8) << 1
+            | (self.sched_setscheduler as u8) << 2
+            | (self.setpriority as u8) << 3
+            | (self.ioprio_set as u8) << 4
+    }
+
+    fn from_bits(bits: u8) -> Option<Self> {
+        if bits & PROBED == 0 {
+            return None;
+        }
+        Some(SandboxCapabilities {
+            sched_setattr: bits & (1 << 1) != 0,
+            sched_setscheduler: bits & (1 << 2) != 0,
+            setpriority: bits & (1 << 3) != 0,
+            ioprio_set: bits & (1 << 4) != 0,
+        })
+    }
+}
+
+/// Finds out which of the scheduling system calls are allowed for the current thread.
+///
+/// Each call is probed by re-applying the thread's current settings, which the scheduler
+/// itself never refuses, so an `EPERM` or `ENOSYS` can only come from a filter.
+///
+/// # Warning
+/// Filters which kill the process instead of returning an error can't be probed safely.
+/// If such a filter may be in place, construct the [`SandboxCapabilities`] manually instead.
+/// In [`SeccompMode::Strict`] nothing is probed and every call is reported as blocked.
+pub fn probe() -> SandboxCapabilities {
+    match seccomp_mode() {
+        Ok(SeccompMode::Disabled) => return SandboxCapabilities::all(),
+        Ok(SeccompMode::Strict) => return SandboxCapabilities::none(),
+        _ => {}
+    }
+
+    SandboxCapabilities {
+        sched_setattr: probe_sched_setattr(),
+        sched_setscheduler: probe_sched_setscheduler(),
+        setpriority: probe_setpriority(),
+        ioprio_set: probe_ioprio_set(),
+    }
+}
+
+fn is_filtered(ret: libc::c_long) -> bool {
+    ret != 0
+        && matches!(
+            crate::unix::errno(),
+            libc::EPERM | libc::ENOSYS | libc::EACCES
+        )
+}
+
+#[cfg(target_os = "linux")]
+fn probe_sched_setattr() -> bool {
+    let mut sched_attr = crate::unix::SchedAttr::default();
+    unsafe {
+        let ret = libc::syscall(
+            libc::SYS_sched_getattr,
+            0,
+            &mut sched_attr as *mut _,
+            std::mem::size_of::<crate::unix::SchedAttr>() as u32,
+            0,
+        );
+        if ret != 0 {
+            return false;
+        }
+        !is_filtered(libc::syscall(
+            libc::SYS_sched_setattr,
+            0,
+            &sched_attr as *const _,
+            0,
+        ))
+    }
+}
+
+#[cfg(target_os = "android")]
+fn probe_sched_setattr() -> bool {
+    false
+}
+
+fn probe_sched_setscheduler() -> bool {
+    unsafe {
+        let mut policy = 0;
+        let mut params: libc::sched_param = std::mem::zeroed();
+        if libc::pthread_getschedparam(libc::pthread_self(), &mut policy, &mut params) != 0 {
+            return false;
+        }
+        !is_filtered(libc::sched_setscheduler(0, policy, &params) as libc::c_long)
+    }
+}
+
+fn probe_setpriority() -> bool {
+    unsafe {
+        crate::unix::set_errno(0);
+        let nice = libc::getpriority(libc::PRIO_PROCESS, 0);
+        if nice == -1 && crate::unix::errno() != 0 {
+            return false;
+        }
+        !is_filtered(libc::setpriority(libc::PRIO_PROCESS, 0, nice) as libc::c_long)
+    }
+}
+
+fn probe_ioprio_set() -> bool {
+    unsafe {
+        let priority = libc::syscall(
+            libc::SYS_ioprio_get,
+            crate::io_priority::ioprio::WHO_PROCESS,
+            0,
+        );
+        if priority == -1 {
+            return false;
+        }
+        !is_filtered(libc::syscall(
+            libc::SYS_ioprio_set,
+            crate::io_priority::ioprio::WHO_PROCESS,
+            0,
+            priority,
+        ))
+    }
+}
+
+const PROBED: u8 = 1;
+
+static SANDBOX_SAFE_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Enables the sandbox-safe mode: from now on, the crate doesn't issue the system calls
+/// which are not allowed by the passed capabilities, and returns an [`Error::Priority`]
+/// instead.
+pub fn enable_sandbox_safe_mode(capabilities: SandboxCapabilities) {
+    SANDBOX_SAFE_MODE.store(capabilities.to_bits(), Ordering::SeqCst);
+}
+
+/// Disables the sandbox-safe mode, so that all the system calls are issued again.
+pub fn disable_sandbox_safe_mode() {
+    SANDBOX_SAFE_MODE.store(0, Ordering::SeqCst);
+}
+
+/// Returns the capabilities the sandbox-safe mode has been enabled with, if it is enabled.
+pub fn sandbox_safe_mode() -> Option<SandboxCapabilities> {
+    SandboxCapabilities::from_bits(SANDBOX_SAFE_MODE.load(Ordering::SeqCst))
+}
+
+/// A scheduling system call the crate may issue.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum SchedSyscall {
+    #[cfg(target_os = "linux")]
+    SchedSetattr,
+    SchedSetscheduler,
+    Setpriority,
+    IoprioSet,
+}
+
+/// Checks whether the system call is allowed in the sandbox-safe mode.
+pub(crate) fn ensure_allowed(syscall: SchedSyscall) -> Result<(), Error> {
+    let capabilities = match sandbox_safe_mode() {
+        Some(capabilities) => capabilities,
+        None => return Ok(()),
+    };
+    let allowed = match syscall {
+        #[cfg(target_os = "linux")]
+        SchedSyscall::SchedSetattr => capabilities.sched_setattr,
+        SchedSyscall::SchedSetscheduler => capabilities.sched_setscheduler,
+        SchedSyscall::Setpriority => capabilities.setpriority,
+        SchedSyscall::IoprioSet => capabilities.ioprio_set,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(Error::Priority(
+            "The system call is blocked by the sandbox (sandbox-safe mode is enabled).",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_bits_roundtrip() {
+        let capabilities = SandboxCapabilities {
+            sched_setattr: false,
+            sched_setscheduler: true,
+            setpriority: false,
+            ioprio_set: true,
+        };
+
+        assert_eq!(
+            SandboxCapabilities::from_bits(capabilities.to_bits()),
+            Some(capabilities)
+        );
+        assert_eq!(SandboxCapabilities::from_bits(0), None);
+    }
+}
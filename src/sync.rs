@@ -0,0 +1,900 @@
+//! This module defines the priority inheritance mutex.
+//!
+//! A realtime thread waiting for a lock held by a normal one waits as long as
+//! the normal thread doesn't run, which may be forever under load. With the
+//! priority inheritance, the holder of a [`PiMutex`] runs at the priority of
+//! its highest waiter until it unlocks it.
+//!
+//! * On Linux, Apple platforms and FreeBSD, a `pthread` mutex with the
+//!   `PTHREAD_PRIO_INHERIT` protocol.
+//! * On Windows, which has no priority inheritance, the priority ceiling: the
+//!   holder runs at least at the ceiling priority while it holds the mutex.
+//! * On the other platforms, a plain mutex.
+//!
+//! A [`PiCondvar`] waits with a [`PiMutex`], as [`std::sync::Condvar`] only
+//! does with the standard mutexes.
+//!
+//! On the `pthread` platforms, a `CeilingMutex` implements the priority
+//! ceiling protocol instead, with `PTHREAD_PRIO_PROTECT`.
+//!
+//! The mutexes aren't poisoned by the panics of their holders.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::sync::PiMutex;
+//! use std::sync::Arc;
+//!
+//! let counter = Arc::new(PiMutex::new(0).unwrap());
+//! let thread = std::thread::spawn({
+//!     let counter = counter.clone();
+//!     move || *counter.lock() += 1
+//! });
+//! thread.join().unwrap();
+//! assert_eq!(*counter.lock(), 1);
+//! ```
+
+use std::cell::UnsafeCell;
+
+use crate::Error;
+
+#[cfg(any(target_os = "freebsd", target_vendor = "apple"))]
+const PTHREAD_PRIO_INHERIT: libc::c_int = 1;
+#[cfg(any(target_os = "freebsd", target_vendor = "apple"))]
+const PTHREAD_PRIO_PROTECT: libc::c_int = 2;
+#[cfg(target_os = "linux")]
+use libc::{PTHREAD_PRIO_INHERIT, PTHREAD_PRIO_PROTECT};
+
+// The bindings are missing from `libc` on these platforms, their C library has them.
+#[cfg(any(target_os = "freebsd", target_vendor = "apple"))]
+extern "C" {
+    fn pthread_mutexattr_setprotocol(
+        attr: *mut libc::pthread_mutexattr_t,
+        protocol: libc::c_int,
+    ) -> libc::c_int;
+}
+#[cfg(target_os = "linux")]
+use libc::pthread_mutexattr_setprotocol;
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+extern "C" {
+    fn pthread_mutexattr_setprioceiling(
+        attr: *mut libc::pthread_mutexattr_t,
+        prioceiling: libc::c_int,
+    ) -> libc::c_int;
+}
+
+#[cfg(any(
+    all(target_os = "linux", not(target_env = "ohos")),
+    target_os = "freebsd"
+))]
+unsafe fn set_robust(attr: *mut libc::pthread_mutexattr_t) -> libc::c_int {
+    libc::pthread_mutexattr_setrobust(attr, libc::PTHREAD_MUTEX_ROBUST)
+}
+
+#[cfg(any(target_vendor = "apple", target_env = "ohos"))]
+unsafe fn set_robust(_attr: *mut libc::pthread_mutexattr_t) -> libc::c_int {
+    libc::ENOTSUP
+}
+
+/// The `pthread` mutex, boxed as it can't be moved once initialized.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+struct RawMutex(Box<UnsafeCell<libc::pthread_mutex_t>>);
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl RawMutex {
+    fn new() -> Result<Self, Error> {
+        RawMutex::with_protocol(PTHREAD_PRIO_INHERIT, None, false)
+    }
+
+    fn with_protocol(
+        protocol: libc::c_int,
+        ceiling: Option<libc::c_int>,
+        robust: bool,
+    ) -> Result<Self, Error> {
+        unsafe {
+            let mut attr: libc::pthread_mutexattr_t = std::mem::zeroed();
+            match libc::pthread_mutexattr_init(&mut attr) {
+                0 => {}
+                e => return Err(Error::OS(e)),
+            }
+            let mutex = Box::new(UnsafeCell::new(std::mem::zeroed()));
+            let mut result = pthread_mutexattr_setprotocol(&mut attr, protocol);
+            if let (0, Some(ceiling)) = (result, ceiling) {
+                result = pthread_mutexattr_setprioceiling(&mut attr, ceiling);
+            }
+            if result == 0 && robust {
+                result = set_robust(&mut attr);
+            }
+            if result == 0 {
+                result = libc::pthread_mutex_init(mutex.get(), &attr);
+            }
+            libc::pthread_mutexattr_destroy(&mut attr);
+            match result {
+                0 => Ok(RawMutex(mutex)),
+                e => Err(Error::OS(e)),
+            }
+        }
+    }
+
+    fn acquire(&self) -> Result<RawGuard<'_>, Error> {
+        match self.recover(unsafe { libc::pthread_mutex_lock(self.0.get()) }) {
+            0 => Ok(RawGuard(self)),
+            e => Err(Error::OS(e)),
+        }
+    }
+
+    fn try_acquire(&self) -> Result<Option<RawGuard<'_>>, Error> {
+        match self.recover(unsafe { libc::pthread_mutex_trylock(self.0.get()) }) {
+            0 => Ok(Some(RawGuard(self))),
+            libc::EBUSY => Ok(None),
+            e => Err(Error::OS(e)),
+        }
+    }
+
+    /// Marks a robust mutex, whose holder has exited without unlocking it, consistent
+    /// again, as the panics don't poison the mutexes either.
+    fn recover(&self, result: libc::c_int) -> libc::c_int {
+        #[cfg(any(
+            all(target_os = "linux", not(target_env = "ohos")),
+            target_os = "freebsd"
+        ))]
+        if result == libc::EOWNERDEAD {
+            log::warn!("The holder of a robust mutex has exited without unlocking it");
+            return unsafe { libc::pthread_mutex_consistent(self.0.get()) };
+        }
+        result
+    }
+
+    fn lock(&self) -> RawGuard<'_> {
+        self.acquire()
+            .expect("Couldn't lock the priority inheritance mutex")
+    }
+
+    fn try_lock(&self) -> Option<RawGuard<'_>> {
+        self.try_acquire().ok().flatten()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl Drop for RawMutex {
+    fn drop(&mut self) {
+        unsafe {
+            libc::pthread_mutex_destroy(self.0.get());
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+struct RawGuard<'a>(&'a RawMutex);
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl Drop for RawGuard<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            libc::pthread_mutex_unlock((self.0).0.get());
+        }
+    }
+}
+
+/// The plain mutex, with the priority ceiling on Windows.
+#[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+struct RawMutex {
+    mutex: std::sync::Mutex<()>,
+    #[cfg(windows)]
+    ceiling: crate::WinAPIThreadPriority,
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+impl RawMutex {
+    fn new() -> Result<Self, Error> {
+        Ok(RawMutex {
+            mutex: std::sync::Mutex::new(()),
+            #[cfg(windows)]
+            ceiling: crate::WinAPIThreadPriority::Highest,
+        })
+    }
+
+    fn lock(&self) -> RawGuard<'_> {
+        self.guard(self.mutex.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn try_lock(&self) -> Option<RawGuard<'_>> {
+        match self.mutex.try_lock() {
+            Ok(guard) => Some(self.guard(guard)),
+            Err(std::sync::TryLockError::Poisoned(e)) => Some(self.guard(e.into_inner())),
+            Err(std::sync::TryLockError::WouldBlock) => None,
+        }
+    }
+
+    #[cfg(windows)]
+    fn guard<'a>(&self, guard: std::sync::MutexGuard<'a, ()>) -> RawGuard<'a> {
+        // The relative priorities are signed.
+        let rank = |priority: crate::WinAPIThreadPriority| priority as u32 as i32;
+        let previous = crate::thread_priority().ok().filter(|previous| {
+            matches!(
+                <crate::WinAPIThreadPriority as std::convert::TryFrom<_>>::try_from(*previous),
+                Ok(previous) if rank(previous) < rank(self.ceiling)
+            )
+        });
+        if previous.is_some() {
+            raise(self.ceiling);
+        }
+        RawGuard {
+            guard: Some(guard),
+            ceiling: self.ceiling,
+            previous,
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn guard<'a>(&self, guard: std::sync::MutexGuard<'a, ()>) -> RawGuard<'a> {
+        RawGuard { guard: Some(guard) }
+    }
+}
+
+#[cfg(windows)]
+fn raise(ceiling: crate::WinAPIThreadPriority) {
+    if let Err(e) = crate::set_winapi_thread_priority(crate::thread_native_id(), ceiling) {
+        log::warn!("Couldn't raise the thread to the ceiling priority: {:?}", e);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+struct RawGuard<'a> {
+    // Taken out while a condition variable waits.
+    guard: Option<std::sync::MutexGuard<'a, ()>>,
+    #[cfg(windows)]
+    ceiling: crate::WinAPIThreadPriority,
+    // The priority of the thread raised to the ceiling.
+    #[cfg(windows)]
+    previous: Option<crate::ThreadPriority>,
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+impl<'a> RawGuard<'a> {
+    /// Takes the guard out for a condition variable to wait, the thread back at its
+    /// priority as it doesn't hold the mutex while waiting.
+    fn unlock(&mut self) -> std::sync::MutexGuard<'a, ()> {
+        let guard = self.guard.take().expect("The mutex is locked");
+        #[cfg(windows)]
+        self.restore();
+        guard
+    }
+
+    /// Puts the guard back once a condition variable has waited, the thread at the
+    /// ceiling again.
+    fn relock(&mut self, guard: std::sync::MutexGuard<'a, ()>) {
+        #[cfg(windows)]
+        if self.previous.is_some() {
+            raise(self.ceiling);
+        }
+        self.guard = Some(guard);
+    }
+
+    #[cfg(windows)]
+    fn restore(&self) {
+        if let Some(previous) = self.previous {
+            if let Err(e) = crate::set_current_thread_priority(previous) {
+                log::warn!("Couldn't restore the priority of the thread: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for RawGuard<'_> {
+    fn drop(&mut self) {
+        // Unlocked first, so that the thread doesn't hold the mutex at its lower priority.
+        if let Some(guard) = self.guard.take() {
+            drop(guard);
+            self.restore();
+        }
+    }
+}
+
+/// A mutex whose holder inherits the priority of its waiters.
+pub struct PiMutex<T: ?Sized> {
+    raw: RawMutex,
+    value: UnsafeCell<T>,
+}
+
+// The value is only reached through the lock.
+unsafe impl<T: ?Sized + Send> Send for PiMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for PiMutex<T> {}
+
+impl<T: ?Sized> std::fmt::Debug for PiMutex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PiMutex").finish_non_exhaustive()
+    }
+}
+
+impl<T> PiMutex<T> {
+    /// Creates the mutex holding the value.
+    ///
+    /// If there's an error, the result of `pthread_mutex_init` is returned.
+    pub fn new(value: T) -> Result<Self, Error> {
+        Ok(PiMutex {
+            raw: RawMutex::new()?,
+            value: UnsafeCell::new(value),
+        })
+    }
+
+    /// Creates the robust mutex holding the value: if a thread exits without unlocking
+    /// it, the next locking succeeds instead of blocking forever.
+    ///
+    /// If there's an error, the result of `pthread_mutex_init` is returned.
+    #[cfg(any(
+        all(target_os = "linux", not(target_env = "ohos")),
+        target_os = "freebsd"
+    ))]
+    pub fn robust(value: T) -> Result<Self, Error> {
+        Ok(PiMutex {
+            raw: RawMutex::with_protocol(PTHREAD_PRIO_INHERIT, None, true)?,
+            value: UnsafeCell::new(value),
+        })
+    }
+
+    /// Creates the mutex holding the value, its holders running at least at the ceiling
+    /// priority, [`crate::WinAPIThreadPriority::Highest`] by default.
+    #[cfg(windows)]
+    pub fn with_ceiling(value: T, ceiling: crate::WinAPIThreadPriority) -> Result<Self, Error> {
+        let mut mutex = PiMutex::new(value)?;
+        mutex.raw.ceiling = ceiling;
+        Ok(mutex)
+    }
+
+    /// Consumes the mutex and returns its value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> PiMutex<T> {
+    /// Acquires the mutex, blocking the current thread until it is able to do so.
+    pub fn lock(&self) -> PiMutexGuard<'_, T> {
+        PiMutexGuard {
+            _raw: self.raw.lock(),
+            value: unsafe { &mut *self.value.get() },
+            _not_send: std::marker::PhantomData,
+        }
+    }
+
+    /// Acquires the mutex if it isn't held.
+    pub fn try_lock(&self) -> Option<PiMutexGuard<'_, T>> {
+        self.raw.try_lock().map(|raw| PiMutexGuard {
+            _raw: raw,
+            value: unsafe { &mut *self.value.get() },
+            _not_send: std::marker::PhantomData,
+        })
+    }
+
+    /// Returns a mutable reference to the value, the mutex being borrowed mutably.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+/// The guard of a [`PiMutex`], unlocking it when it is dropped. The mutex must be
+/// unlocked by the thread which has locked it, so the guard can't be sent to another
+/// thread.
+pub struct PiMutexGuard<'a, T: ?Sized> {
+    _raw: RawGuard<'a>,
+    value: &'a mut T,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl<T: ?Sized> std::ops::Deref for PiMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T: ?Sized> std::ops::DerefMut for PiMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+impl<T: ?Sized + std::fmt::Debug> std::fmt::Debug for PiMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.value, f)
+    }
+}
+
+/// The clock measuring the timeouts of a [`PiCondvar`].
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CondvarClock {
+    /// `CLOCK_REALTIME`, following the changes of the time of the system.
+    Realtime,
+    /// `CLOCK_MONOTONIC`, not supported on the Apple platforms.
+    Monotonic,
+}
+
+/// A condition variable waiting with a [`PiMutex`]. The waiting thread unlocks the
+/// mutex, which keeps the priority inheritance, and locks it back once it is woken up.
+///
+/// A condition variable can only be used with a single mutex: waiting with another one
+/// panics.
+///
+/// ```rust
+/// use thread_priority::sync::{PiCondvar, PiMutex};
+/// use std::sync::Arc;
+///
+/// let pair = Arc::new((PiMutex::new(false).unwrap(), PiCondvar::new().unwrap()));
+/// std::thread::spawn({
+///     let pair = pair.clone();
+///     move || {
+///         *pair.0.lock() = true;
+///         pair.1.notify_one();
+///     }
+/// });
+/// let (ready, condvar) = &*pair;
+/// let ready = condvar.wait_while(ready.lock(), |ready| !*ready);
+/// assert!(*ready);
+/// ```
+pub struct PiCondvar {
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    cond: Box<UnsafeCell<libc::pthread_cond_t>>,
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    clock: CondvarClock,
+    // The address of the mutex the condition variable is used with.
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    mutex: std::sync::atomic::AtomicUsize,
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+    cond: std::sync::Condvar,
+}
+
+// The `pthread` condition variables are shared by design.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+unsafe impl Send for PiCondvar {}
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+unsafe impl Sync for PiCondvar {}
+
+impl std::fmt::Debug for PiCondvar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PiCondvar").finish_non_exhaustive()
+    }
+}
+
+impl PiCondvar {
+    /// Creates the condition variable, measuring the timeouts with the monotonic clock
+    /// where it is supported, and with the realtime one otherwise.
+    ///
+    /// If there's an error, the result of `pthread_cond_init` is returned.
+    pub fn new() -> Result<Self, Error> {
+        #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+        return PiCondvar::with_clock(CondvarClock::Monotonic);
+        #[cfg(target_vendor = "apple")]
+        return PiCondvar::with_clock(CondvarClock::Realtime);
+        #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+        Ok(PiCondvar {
+            cond: std::sync::Condvar::new(),
+        })
+    }
+
+    /// Creates the condition variable, measuring the timeouts with the clock.
+    ///
+    /// If there's an error, the result of `pthread_cond_init` is returned.
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    pub fn with_clock(clock: CondvarClock) -> Result<Self, Error> {
+        #[cfg(target_vendor = "apple")]
+        if clock == CondvarClock::Monotonic {
+            return Err(Error::Unsupported(
+                "The monotonic clock of the condition variables is not supported on this platform",
+            ));
+        }
+        unsafe {
+            let mut attr: libc::pthread_condattr_t = std::mem::zeroed();
+            match libc::pthread_condattr_init(&mut attr) {
+                0 => {}
+                e => return Err(Error::OS(e)),
+            }
+            let cond = Box::new(UnsafeCell::new(std::mem::zeroed()));
+            #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+            let mut result = libc::pthread_condattr_setclock(&mut attr, clock_id(clock));
+            #[cfg(target_vendor = "apple")]
+            let mut result = 0;
+            if result == 0 {
+                result = libc::pthread_cond_init(cond.get(), &attr);
+            }
+            libc::pthread_condattr_destroy(&mut attr);
+            match result {
+                0 => Ok(PiCondvar {
+                    cond,
+                    clock,
+                    mutex: std::sync::atomic::AtomicUsize::new(0),
+                }),
+                e => Err(Error::OS(e)),
+            }
+        }
+    }
+
+    /// Returns the clock measuring the timeouts.
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    pub fn clock(&self) -> CondvarClock {
+        self.clock
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    fn mutex<'a>(&self, raw: &RawGuard<'a>) -> &'a RawMutex {
+        let mutex = raw.0;
+        let address = mutex.0.get() as usize;
+        if let Err(previous) = self.mutex.compare_exchange(
+            0,
+            address,
+            std::sync::atomic::Ordering::Relaxed,
+            std::sync::atomic::Ordering::Relaxed,
+        ) {
+            assert_eq!(
+                previous, address,
+                "A condition variable can't be used with two mutexes"
+            );
+        }
+        mutex
+    }
+
+    /// Blocks the current thread until it is notified, unlocking the mutex meanwhile.
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    pub fn wait<'a, T: ?Sized>(&self, guard: PiMutexGuard<'a, T>) -> PiMutexGuard<'a, T> {
+        let mutex = self.mutex(&guard._raw);
+        let result =
+            mutex.recover(unsafe { libc::pthread_cond_wait(self.cond.get(), mutex.0.get()) });
+        assert_eq!(result, 0, "Couldn't wait for the condition variable");
+        guard
+    }
+
+    /// Blocks the current thread until it is notified, unlocking the mutex meanwhile.
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+    pub fn wait<'a, T: ?Sized>(&self, mut guard: PiMutexGuard<'a, T>) -> PiMutexGuard<'a, T> {
+        let inner = guard._raw.unlock();
+        guard
+            ._raw
+            .relock(self.cond.wait(inner).unwrap_or_else(|e| e.into_inner()));
+        guard
+    }
+
+    /// Blocks the current thread until it is notified or the timeout has elapsed,
+    /// unlocking the mutex meanwhile. Returns whether the wait has timed out.
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: PiMutexGuard<'a, T>,
+        timeout: std::time::Duration,
+    ) -> (PiMutexGuard<'a, T>, bool) {
+        let mutex = self.mutex(&guard._raw);
+        let result = mutex.recover(self.timed_wait(mutex, timeout));
+        match result {
+            0 => (guard, false),
+            libc::ETIMEDOUT => (guard, true),
+            _ => panic!("Couldn't wait for the condition variable"),
+        }
+    }
+
+    /// Blocks the current thread until it is notified or the timeout has elapsed,
+    /// unlocking the mutex meanwhile. Returns whether the wait has timed out.
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        mut guard: PiMutexGuard<'a, T>,
+        timeout: std::time::Duration,
+    ) -> (PiMutexGuard<'a, T>, bool) {
+        let inner = guard._raw.unlock();
+        let (inner, result) = self
+            .cond
+            .wait_timeout(inner, timeout)
+            .unwrap_or_else(|e| e.into_inner());
+        guard._raw.relock(inner);
+        (guard, result.timed_out())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    fn timed_wait(&self, mutex: &RawMutex, timeout: std::time::Duration) -> libc::c_int {
+        unsafe {
+            let mut deadline: libc::timespec = std::mem::zeroed();
+            libc::clock_gettime(clock_id(self.clock), &mut deadline);
+            let nanos = deadline.tv_nsec as u64 + u64::from(timeout.subsec_nanos());
+            // The timeout too far to be represented saturates, the wait is unbounded.
+            deadline.tv_sec = saturating_add_secs(
+                deadline.tv_sec,
+                timeout.as_secs().saturating_add(nanos / 1_000_000_000),
+            );
+            deadline.tv_nsec = (nanos % 1_000_000_000) as _;
+            libc::pthread_cond_timedwait(self.cond.get(), mutex.0.get(), &deadline)
+        }
+    }
+
+    #[cfg(target_vendor = "apple")]
+    fn timed_wait(&self, mutex: &RawMutex, timeout: std::time::Duration) -> libc::c_int {
+        unsafe {
+            let mut relative: libc::timespec = std::mem::zeroed();
+            relative.tv_sec = saturating_add_secs(relative.tv_sec, timeout.as_secs());
+            relative.tv_nsec = timeout.subsec_nanos() as _;
+            libc::pthread_cond_timedwait_relative_np(self.cond.get(), mutex.0.get(), &relative)
+        }
+    }
+
+    /// Blocks the current thread while the condition holds for the value, waiting for the
+    /// notifications.
+    pub fn wait_while<'a, T: ?Sized, F>(
+        &self,
+        mut guard: PiMutexGuard<'a, T>,
+        mut condition: F,
+    ) -> PiMutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    /// Wakes up a waiting thread.
+    pub fn notify_one(&self) {
+        #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+        unsafe {
+            libc::pthread_cond_signal(self.cond.get());
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+        self.cond.notify_one();
+    }
+
+    /// Wakes up all the waiting threads.
+    pub fn notify_all(&self) {
+        #[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+        unsafe {
+            libc::pthread_cond_broadcast(self.cond.get());
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple")))]
+        self.cond.notify_all();
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl Drop for PiCondvar {
+    fn drop(&mut self) {
+        unsafe {
+            libc::pthread_cond_destroy(self.cond.get());
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn clock_id(clock: CondvarClock) -> libc::clockid_t {
+    match clock {
+        CondvarClock::Realtime => libc::CLOCK_REALTIME,
+        CondvarClock::Monotonic => libc::CLOCK_MONOTONIC,
+    }
+}
+
+/// Adds the seconds to the ones of a `timespec`, saturating. The type is the one of its
+/// `tv_sec`, as `time_t` is deprecated on musl, and is 32 or 64 bits wide.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+fn saturating_add_secs<S>(secs: S, add: u64) -> S
+where
+    S: Into<i64> + std::convert::TryFrom<i64> + From<i32>,
+{
+    let add = <i64 as std::convert::TryFrom<u64>>::try_from(add).unwrap_or(i64::MAX);
+    S::try_from(secs.into().saturating_add(add)).unwrap_or_else(|_| S::from(i32::MAX))
+}
+
+/// A mutex whose holder runs at least at the ceiling priority, with the `pthread`
+/// `PTHREAD_PRIO_PROTECT` protocol. Unlike the inheritance, the holder is raised as soon
+/// as it locks the mutex, so the lower priority threads can't preempt it even before a
+/// higher priority one waits for the mutex.
+///
+/// * The threads of a higher priority than the ceiling can't lock the mutex.
+/// * May require privileges to raise the holders.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+pub struct CeilingMutex<T: ?Sized> {
+    raw: RawMutex,
+    ceiling: libc::c_int,
+    value: UnsafeCell<T>,
+}
+
+// The value is only reached through the lock.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+unsafe impl<T: ?Sized + Send> Send for CeilingMutex<T> {}
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+unsafe impl<T: ?Sized + Send> Sync for CeilingMutex<T> {}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl<T: ?Sized> std::fmt::Debug for CeilingMutex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CeilingMutex")
+            .field("ceiling", &self.ceiling)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl<T> CeilingMutex<T> {
+    /// Creates the mutex holding the value, with the ceiling priority of the scheduling
+    /// policy of the current thread.
+    /// For more info read [`CeilingMutex::with_policy`].
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    /// use thread_priority::sync::CeilingMutex;
+    ///
+    /// let fifo = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+    /// let mutex = CeilingMutex::with_policy(0, fifo, ThreadPriority::Max).unwrap();
+    /// assert_eq!(mutex.ceiling(), ThreadPriority::Max.to_posix(fifo).unwrap());
+    /// ```
+    pub fn new(value: T, ceiling: crate::ThreadPriority) -> Result<Self, Error> {
+        let policy = crate::thread_schedule_policy_for(crate::thread_native_id())?;
+        CeilingMutex::with_policy(value, policy, ceiling)
+    }
+
+    /// Creates the mutex holding the value, with the ceiling priority of the scheduling
+    /// policy, which must be in the range of the policy.
+    ///
+    /// If there's an error, the result of `pthread_mutex_init` is returned.
+    pub fn with_policy(
+        value: T,
+        policy: crate::ThreadSchedulePolicy,
+        ceiling: crate::ThreadPriority,
+    ) -> Result<Self, Error> {
+        let ceiling = ceiling.to_posix(policy)?;
+        let (min, max) = crate::unix::native_priority_range(policy);
+        if !(min..=max).contains(&ceiling) {
+            return Err(Error::Priority(
+                "The ceiling is out of the range of the policy.",
+            ));
+        }
+        Ok(CeilingMutex {
+            raw: RawMutex::with_protocol(PTHREAD_PRIO_PROTECT, Some(ceiling), false)?,
+            ceiling,
+            value: UnsafeCell::new(value),
+        })
+    }
+
+    /// Consumes the mutex and returns its value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl<T: ?Sized> CeilingMutex<T> {
+    /// Returns the ceiling, as the POSIX priority.
+    pub fn ceiling(&self) -> libc::c_int {
+        self.ceiling
+    }
+
+    /// Acquires the mutex, blocking the current thread until it is able to do so.
+    ///
+    /// If there's an error, the result of `pthread_mutex_lock` is returned: `EINVAL` if
+    /// the priority of the thread is higher than the ceiling.
+    pub fn lock(&self) -> Result<CeilingMutexGuard<'_, T>, Error> {
+        Ok(CeilingMutexGuard {
+            _raw: self.raw.acquire()?,
+            value: unsafe { &mut *self.value.get() },
+            _not_send: std::marker::PhantomData,
+        })
+    }
+
+    /// Acquires the mutex if it isn't held.
+    /// For more info read [`CeilingMutex::lock`].
+    pub fn try_lock(&self) -> Result<Option<CeilingMutexGuard<'_, T>>, Error> {
+        Ok(self.raw.try_acquire()?.map(|raw| CeilingMutexGuard {
+            _raw: raw,
+            value: unsafe { &mut *self.value.get() },
+            _not_send: std::marker::PhantomData,
+        }))
+    }
+
+    /// Returns a mutable reference to the value, the mutex being borrowed mutably.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+/// The guard of a [`CeilingMutex`], unlocking it and restoring the priority of the thread
+/// when it is dropped. The guard can't be sent to another thread.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+pub struct CeilingMutexGuard<'a, T: ?Sized> {
+    _raw: RawGuard<'a>,
+    value: &'a mut T,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl<T: ?Sized> std::ops::Deref for CeilingMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl<T: ?Sized> std::ops::DerefMut for CeilingMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_vendor = "apple"))]
+impl<T: ?Sized + std::fmt::Debug> std::fmt::Debug for CeilingMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.value, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn pi_mutex_test() {
+        let mutex = PiMutex::new(vec![1]).unwrap();
+        {
+            let mut guard = mutex.lock();
+            guard.push(2);
+            assert!(mutex.try_lock().is_none());
+        }
+        assert_eq!(*mutex.try_lock().unwrap(), [1, 2]);
+        assert_eq!(mutex.into_inner(), [1, 2]);
+        #[cfg(target_os = "linux")]
+        assert_eq!(*PiMutex::robust(1).unwrap().lock(), 1);
+    }
+
+    #[test]
+    fn pi_condvar_test() {
+        let mutex = PiMutex::new(0).unwrap();
+        let condvar = PiCondvar::new().unwrap();
+        let (guard, timed_out) = condvar.wait_timeout(mutex.lock(), Duration::from_millis(10));
+        assert!(timed_out);
+        drop(guard);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                *mutex.lock() = 1;
+                condvar.notify_all();
+            });
+            let guard = condvar.wait_while(mutex.lock(), |value| *value == 0);
+            assert_eq!(*guard, 1);
+        });
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn ceiling_mutex_test() {
+        use crate::{RealtimeThreadSchedulePolicy, ThreadPriority, ThreadSchedulePolicy};
+
+        let fifo = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+        let normal = ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Other);
+        assert!(CeilingMutex::with_policy((), normal, ThreadPriority::Max).is_err());
+        let mutex = CeilingMutex::with_policy(1, fifo, ThreadPriority::Min).unwrap();
+        assert_eq!(mutex.ceiling(), 1);
+        assert_eq!(mutex.into_inner(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn ceiling_mutex_thread_policy_test() {
+        use crate::{RealtimeThreadSchedulePolicy, ThreadPriority, ThreadSchedulePolicy};
+
+        std::thread::spawn(|| {
+            let fifo = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+            // The thread's policy can only be made differ from the process' one with privileges.
+            if crate::set_thread_priority_and_policy(
+                crate::thread_native_id(),
+                ThreadPriority::Min,
+                fifo,
+            )
+            .is_err()
+            {
+                return;
+            }
+            let mutex = CeilingMutex::new((), ThreadPriority::Max).unwrap();
+            assert_eq!(mutex.ceiling(), ThreadPriority::Max.to_posix(fifo).unwrap());
+        })
+        .join()
+        .unwrap();
+    }
+}
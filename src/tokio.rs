@@ -0,0 +1,120 @@
+//! This module defines the scheduling of the threads of the `tokio` runtimes.
+//!
+//! The start hook of a `tokio` runtime runs on all its threads, the workers
+//! driving the tasks and the blocking ones running [`::tokio::task::spawn_blocking`]
+//! alike: the workers are started by the blocking pool. The workers can only
+//! be told apart when they park, which the blocking threads never do. A
+//! [`RuntimeScheduling`] applies the blocking properties to every thread of the
+//! runtime when it starts, and the worker ones to the workers when they park
+//! for the first time, which they do as soon as they're out of tasks.
+//!
+//! The failures to apply the properties are logged, the threads run as they
+//! are.
+//!
+//! Enabled by the `tokio` feature.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::properties::ThreadProperties;
+//! use thread_priority::tokio::*;
+//!
+//! let runtime = ::tokio::runtime::Builder::new_multi_thread()
+//!     .thread_scheduling(
+//!         RuntimeScheduling::new()
+//!             .workers(ThreadProperties::default().priority(ThreadPriority::Max))
+//!             .blocking(ThreadProperties::default().priority(ThreadPriority::Min)),
+//!     )
+//!     .build()
+//!     .unwrap();
+//! runtime.block_on(async { println!("A task on a worker") });
+//! ```
+
+use std::cell::Cell;
+use std::sync::Arc;
+
+use crate::properties::ThreadProperties;
+
+thread_local! {
+    // Whether the current thread has been started by a runtime.
+    static RUNTIME_THREAD: Cell<bool> = const { Cell::new(false) };
+    // Whether the worker properties have been applied to the current thread.
+    static WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The properties of the threads of a runtime.
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeScheduling {
+    workers: ThreadProperties,
+    blocking: ThreadProperties,
+}
+
+impl RuntimeScheduling {
+    /// Creates the scheduling keeping the properties of all the threads.
+    pub fn new() -> Self {
+        RuntimeScheduling::default()
+    }
+
+    /// The properties applied to the workers when they park for the first time. The
+    /// properties left unset keep the blocking ones.
+    pub fn workers(mut self, value: ThreadProperties) -> Self {
+        self.workers = value;
+        self
+    }
+
+    /// The properties applied to all the threads of the runtime when they start, the
+    /// workers included until they park for the first time.
+    pub fn blocking(mut self, value: ThreadProperties) -> Self {
+        self.blocking = value;
+        self
+    }
+
+    /// Returns the closure to pass to [`::tokio::runtime::Builder::on_thread_start`],
+    /// applying the blocking properties.
+    pub fn on_thread_start(&self) -> impl Fn() + Send + Sync + 'static {
+        let blocking = Arc::new(self.blocking.clone());
+        move || {
+            RUNTIME_THREAD.with(|runtime_thread| runtime_thread.set(true));
+            crate::registry::register_spawned_thread();
+            apply(&blocking, "blocking");
+        }
+    }
+
+    /// Returns the closure to pass to [`::tokio::runtime::Builder::on_thread_park`],
+    /// applying the worker properties once per worker. The threads the runtime hasn't
+    /// started, like the one of [`::tokio::runtime::Runtime::block_on`], are kept.
+    pub fn on_thread_park(&self) -> impl Fn() + Send + Sync + 'static {
+        let workers = Arc::new(self.workers.clone());
+        move || {
+            if RUNTIME_THREAD.with(Cell::get) && !WORKER.with(|worker| worker.replace(true)) {
+                apply(&workers, "worker");
+            }
+        }
+    }
+}
+
+fn apply(properties: &ThreadProperties, kind: &str) {
+    if let Err(e) = properties.apply_to_current() {
+        log::warn!(
+            "Couldn't apply the {} properties to the thread named {:?}: {:?}",
+            kind,
+            std::thread::current().name(),
+            e,
+        );
+    }
+}
+
+/// Adds the scheduling of the threads to the `tokio` runtime builders.
+pub trait RuntimeBuilderExt {
+    /// Sets the start and park hooks of the builder applying the scheduling, replacing
+    /// the ones set before.
+    fn thread_scheduling(&mut self, scheduling: RuntimeScheduling) -> &mut Self;
+}
+
+impl RuntimeBuilderExt for ::tokio::runtime::Builder {
+    fn thread_scheduling(&mut self, scheduling: RuntimeScheduling) -> &mut Self {
+        self.on_thread_start(scheduling.on_thread_start())
+            .on_thread_park(scheduling.on_thread_park())
+    }
+}
@@ -0,0 +1,456 @@
+//! This module defines the CPU topology discovery.
+//!
+//! Choosing the right CPUs for a thread requires knowing which logical
+//! CPUs share a physical core, which share a cache, and which cores are
+//! the performance or the efficiency ones on hybrid processors. This
+//! module provides this information as a typed structure.
+//!
+//! * On Linux and Android, read from `/sys/devices/system/cpu`.
+//! * On Windows, based on `GetLogicalProcessorInformationEx`. The CPUs are
+//!   numbered as `group * 64 + processor`.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::topology::*;
+//!
+//! let topology = Topology::discover().unwrap();
+//! for core in &topology.cores {
+//!     println!("{:?} core on package {}: {}", core.core_type, core.package, core.cpus);
+//! }
+//! assert!(!topology.cpus().is_empty());
+//! ```
+
+use crate::affinity::{
+    get_current_thread_affinity, set_current_thread_affinity, set_thread_affinity_clamped, CpuSet,
+};
+use crate::{Error, ThreadPriority};
+
+/// The type of a physical core.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum CoreType {
+    /// A performance core (an Intel P-core or an ARM "big" core). On non-hybrid
+    /// processors all the cores are of this type.
+    Performance,
+    /// An efficiency core (an Intel E-core or an ARM "LITTLE" core).
+    Efficiency,
+}
+
+/// A physical core.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Core {
+    /// The physical package (socket) the core belongs to.
+    pub package: usize,
+    /// The logical CPUs of the core. There is more than one when the core
+    /// supports simultaneous multithreading (SMT, Hyper-Threading).
+    pub cpus: CpuSet,
+    /// The type of the core.
+    pub core_type: CoreType,
+}
+
+/// The type of a CPU cache.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum CacheType {
+    /// A data cache.
+    Data,
+    /// An instruction cache.
+    Instruction,
+    /// A cache for both data and instructions.
+    Unified,
+}
+
+/// A CPU cache.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Cache {
+    /// The cache level, starting from 1.
+    pub level: u8,
+    /// The type of the cache.
+    pub cache_type: CacheType,
+    /// The size of the cache in bytes.
+    pub size: usize,
+    /// The logical CPUs sharing the cache.
+    pub cpus: CpuSet,
+}
+
+/// The CPU topology of the system.
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Topology {
+    /// The physical cores, ordered by their first logical CPU.
+    pub cores: Vec<Core>,
+    /// The caches, ordered by their level and first logical CPU.
+    pub caches: Vec<Cache>,
+}
+
+impl Topology {
+    /// Discovers the topology of the online CPUs.
+    pub fn discover() -> Result<Self, Error> {
+        let mut topology = discover_topology()?;
+        topology.cores.sort_by_key(|core| core.cpus.iter().next());
+        topology
+            .caches
+            .sort_by_key(|cache| (cache.level, cache.cpus.iter().next(), cache.cache_type));
+        Ok(topology)
+    }
+
+    /// Returns all the logical CPUs.
+    pub fn cpus(&self) -> CpuSet {
+        self.cores
+            .iter()
+            .flat_map(|core| core.cpus.iter())
+            .collect()
+    }
+
+    /// Returns the physical core the logical CPU belongs to.
+    pub fn core_of(&self, cpu: usize) -> Option<&Core> {
+        self.cores.iter().find(|core| core.cpus.contains(cpu))
+    }
+
+    /// Returns the other logical CPUs sharing the physical core with the logical CPU.
+    pub fn smt_siblings(&self, cpu: usize) -> CpuSet {
+        let mut siblings = self
+            .core_of(cpu)
+            .map(|core| core.cpus.clone())
+            .unwrap_or_default();
+        siblings.remove(cpu);
+        siblings
+    }
+
+    /// Returns the logical CPUs of the cores of the given type.
+    pub fn cpus_of_type(&self, core_type: CoreType) -> CpuSet {
+        self.cores
+            .iter()
+            .filter(|core| core.core_type == core_type)
+            .flat_map(|core| core.cpus.iter())
+            .collect()
+    }
+
+    /// Returns the first logical CPU of every physical core, which is the set to use
+    /// for threads which must not compete with an SMT sibling.
+    pub fn one_cpu_per_core(&self) -> CpuSet {
+        self.cores
+            .iter()
+            .filter_map(|core| core.cpus.iter().next())
+            .collect()
+    }
+
+    /// Returns whether the processor has cores of different types.
+    pub fn is_hybrid(&self) -> bool {
+        self.cores
+            .iter()
+            .any(|core| core.core_type == CoreType::Efficiency)
+    }
+
+    /// Returns the logical CPUs a thread preferring the cores of the given type should
+    /// run on. On non-hybrid processors, these are all the CPUs.
+    pub fn preferred_cpus(&self, core_type: CoreType) -> CpuSet {
+        match self.cpus_of_type(core_type) {
+            cpus if cpus.is_empty() => self.cpus(),
+            cpus => cpus,
+        }
+    }
+}
+
+/// Constrains the current thread to the cores of the given type, optionally changing
+/// its priority as well, and returns the set of CPUs it was allowed to run on before,
+/// so that it can be restored later.
+///
+/// On non-hybrid processors, the thread is allowed to run on all the CPUs. The CPUs
+/// the process is not allowed to run on are left out. If the priority can't be set,
+/// the previous affinity is restored.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use thread_priority::affinity::*;
+/// use thread_priority::topology::*;
+///
+/// let previous = prefer_cores(CoreType::Efficiency, Some(ThreadPriority::Min)).unwrap();
+/// assert!(set_current_thread_affinity(&previous).is_ok());
+/// ```
+pub fn prefer_cores(
+    core_type: CoreType,
+    priority: Option<ThreadPriority>,
+) -> Result<CpuSet, Error> {
+    let cpus = Topology::discover()?.preferred_cpus(core_type);
+    let previous = get_current_thread_affinity()?;
+    set_thread_affinity_clamped(crate::thread_native_id(), &cpus)?;
+    if let Some(priority) = priority {
+        if let Err(e) = crate::set_current_thread_priority(priority) {
+            let _ = set_current_thread_affinity(&previous);
+            return Err(e);
+        }
+    }
+    Ok(previous)
+}
+
+/// Constrains the current thread to the performance cores.
+/// For more info read [`prefer_cores`].
+pub fn prefer_performance_cores() -> Result<CpuSet, Error> {
+    prefer_cores(CoreType::Performance, None)
+}
+
+/// Constrains the current thread to the efficiency cores.
+/// For more info read [`prefer_cores`].
+pub fn prefer_efficiency_cores() -> Result<CpuSet, Error> {
+    prefer_cores(CoreType::Efficiency, None)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_sysfs(path: &str) -> Result<String, Error> {
+    std::fs::read_to_string(path)
+        .map(|content| content.trim().to_owned())
+        .map_err(Error::from)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_sysfs_list(path: &str) -> Result<CpuSet, Error> {
+    read_sysfs(path)?
+        .parse()
+        .map_err(|_| Error::Ffi("Can't parse the CPU list"))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_sysfs_value<T: std::str::FromStr>(path: &str) -> Result<T, Error> {
+    read_sysfs(path)?
+        .parse()
+        .map_err(|_| Error::Ffi("Can't parse the CPU topology value"))
+}
+
+/// Parses the cache sizes the way sysfs reports them, like `48K`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn parse_cache_size(size: &str) -> Option<usize> {
+    let (number, multiplier) = match size.chars().last()? {
+        'K' => (&size[..size.len() - 1], 1 << 10),
+        'M' => (&size[..size.len() - 1], 1 << 20),
+        'G' => (&size[..size.len() - 1], 1 << 30),
+        _ => (size, 1),
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .map(|number| number * multiplier)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn discover_topology() -> Result<Topology, Error> {
+    const CPU_PATH: &str = "/sys/devices/system/cpu";
+
+    let online = read_sysfs_list(&format!("{}/online", CPU_PATH))?;
+    // Intel hybrid processors expose the core types as separate PMUs.
+    let efficiency_cpus = read_sysfs_list("/sys/bus/event_source/devices/cpu_atom/cpus").ok();
+    // ARM big.LITTLE processors expose the relative capacity of each CPU instead.
+    let capacities: Vec<(usize, u64)> = online
+        .iter()
+        .filter_map(|cpu| {
+            read_sysfs_value(&format!("{}/cpu{}/cpu_capacity", CPU_PATH, cpu))
+                .ok()
+                .map(|capacity| (cpu, capacity))
+        })
+        .collect();
+    let max_capacity = capacities.iter().map(|&(_, capacity)| capacity).max();
+
+    let mut topology = Topology::default();
+    for cpu in online.iter() {
+        let cpu_path = format!("{}/cpu{}", CPU_PATH, cpu);
+
+        if topology.core_of(cpu).is_none() {
+            let cpus = read_sysfs_list(&format!("{}/topology/thread_siblings_list", cpu_path))
+                .unwrap_or_else(|_| CpuSet::single(cpu));
+            let is_efficiency = match (&efficiency_cpus, max_capacity) {
+                (Some(efficiency_cpus), _) => efficiency_cpus.contains(cpu),
+                (None, Some(max_capacity)) => capacities
+                    .iter()
+                    .any(|&(index, capacity)| index == cpu && capacity < max_capacity),
+                (None, None) => false,
+            };
+            topology.cores.push(Core {
+                package: read_sysfs_value(&format!("{}/topology/physical_package_id", cpu_path))
+                    .unwrap_or(0),
+                cpus,
+                core_type: if is_efficiency {
+                    CoreType::Efficiency
+                } else {
+                    CoreType::Performance
+                },
+            });
+        }
+
+        for index in 0.. {
+            let cache_path = format!("{}/cache/index{}", cpu_path, index);
+            let level = match read_sysfs_value(&format!("{}/level", cache_path)) {
+                Ok(level) => level,
+                Err(_) => break,
+            };
+            let cache_type = match read_sysfs(&format!("{}/type", cache_path))?.as_str() {
+                "Data" => CacheType::Data,
+                "Instruction" => CacheType::Instruction,
+                _ => CacheType::Unified,
+            };
+            let cpus = read_sysfs_list(&format!("{}/shared_cpu_list", cache_path))?;
+            let is_known = topology.caches.iter().any(|cache| {
+                cache.level == level && cache.cache_type == cache_type && cache.cpus == cpus
+            });
+            if !is_known {
+                topology.caches.push(Cache {
+                    level,
+                    cache_type,
+                    size: read_sysfs(&format!("{}/size", cache_path))
+                        .ok()
+                        .and_then(|size| parse_cache_size(&size))
+                        .unwrap_or(0),
+                    cpus,
+                });
+            }
+        }
+    }
+    Ok(topology)
+}
+
+#[cfg(windows)]
+fn discover_topology() -> Result<Topology, Error> {
+    use crate::affinity::GroupAffinity;
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::sysinfoapi::GetLogicalProcessorInformationEx;
+    use winapi::um::winnt::{
+        self, RelationAll, GROUP_AFFINITY, SYSTEM_LOGICAL_PROCESSOR_INFORMATION,
+        SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX,
+    };
+
+    fn group_cpus(affinity: &GROUP_AFFINITY) -> CpuSet {
+        GroupAffinity::from_native(affinity).into()
+    }
+
+    let buffer = unsafe {
+        let mut length = 0;
+        GetLogicalProcessorInformationEx(RelationAll, std::ptr::null_mut(), &mut length);
+        let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+        if GetLogicalProcessorInformationEx(
+            RelationAll,
+            // winapi declares the buffer with the non-extended type by mistake.
+            buffer.as_mut_ptr() as *mut SYSTEM_LOGICAL_PROCESSOR_INFORMATION,
+            &mut length,
+        ) == 0
+        {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+        buffer
+    };
+
+    let mut cores = Vec::new();
+    let mut packages = Vec::new();
+    let mut topology = Topology::default();
+    let mut offset = 0;
+    let length = buffer.len() * 8;
+    while offset < length {
+        let info = unsafe {
+            &*((buffer.as_ptr() as *const u8).add(offset)
+                as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX)
+        };
+        if info.Size == 0 {
+            break;
+        }
+        match info.Relationship {
+            winnt::RelationProcessorCore => {
+                let processor = unsafe { info.u.Processor() };
+                cores.push((
+                    group_cpus(&processor.GroupMask[0]),
+                    processor.EfficiencyClass,
+                ));
+            }
+            winnt::RelationProcessorPackage => {
+                let processor = unsafe { info.u.Processor() };
+                packages.push(group_cpus(&processor.GroupMask[0]));
+            }
+            winnt::RelationCache => {
+                let cache = unsafe { info.u.Cache() };
+                topology.caches.push(Cache {
+                    level: cache.Level,
+                    cache_type: match cache.Type {
+                        winnt::CacheData => CacheType::Data,
+                        winnt::CacheInstruction => CacheType::Instruction,
+                        _ => CacheType::Unified,
+                    },
+                    size: cache.CacheSize as usize,
+                    cpus: group_cpus(&cache.GroupMask),
+                });
+            }
+            _ => {}
+        }
+        offset += info.Size as usize;
+    }
+
+    // The higher the efficiency class, the more performant the core is.
+    let max_class = cores.iter().map(|&(_, class)| class).max().unwrap_or(0);
+    topology.cores = cores
+        .into_iter()
+        .map(|(cpus, class)| {
+            let package = cpus
+                .iter()
+                .next()
+                .and_then(|cpu| packages.iter().position(|package| package.contains(cpu)))
+                .unwrap_or(0);
+            Core {
+                package,
+                cpus,
+                core_type: if class < max_class {
+                    CoreType::Efficiency
+                } else {
+                    CoreType::Performance
+                },
+            }
+        })
+        .collect();
+    Ok(topology)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn discover_topology() -> Result<Topology, Error> {
+    Err(Error::Unsupported(
+        "CPU topology discovery is not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topology_test() {
+        let topology = Topology::discover().unwrap();
+        let cpus = topology.cpus();
+
+        assert!(!cpus.is_empty());
+        assert_eq!(
+            cpus.len(),
+            topology
+                .cores
+                .iter()
+                .map(|core| core.cpus.len())
+                .sum::<usize>()
+        );
+        for cpu in cpus.iter() {
+            assert!(!topology.smt_siblings(cpu).contains(cpu));
+        }
+    }
+
+    #[test]
+    fn preferred_cpus_test() {
+        let topology = Topology::discover().unwrap();
+        let performance = topology.preferred_cpus(CoreType::Performance);
+        let efficiency = topology.preferred_cpus(CoreType::Efficiency);
+
+        assert!(!performance.is_empty());
+        assert!(!efficiency.is_empty());
+        if !topology.is_hybrid() {
+            assert_eq!(efficiency, topology.cpus());
+        }
+
+        let previous = prefer_performance_cores().unwrap();
+        assert_eq!(
+            crate::affinity::get_current_thread_affinity().unwrap(),
+            performance.intersection(&crate::affinity::allowed_cpus().unwrap())
+        );
+        assert!(set_current_thread_affinity(&previous).is_ok());
+    }
+}
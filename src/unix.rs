@@ -8,10 +8,66 @@ use std::convert::TryFrom;
 
 use crate::{Error, ThreadPriority, ThreadPriorityValue};
 
+// The `libc` of Redox, Haiku, ESP-IDF and RTEMS lacks the POSIX scheduling, the `posix`
+// module completes it.
+#[cfg(any(
+    target_os = "haiku",
+    target_os = "redox",
+    target_os = "espidf",
+    target_os = "rtems"
+))]
+use crate::posix as libc;
+
+#[cfg(not(any(
+    target_os = "fuchsia",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku",
+    target_os = "espidf"
+)))]
+use libc::pthread_getschedparam;
+#[cfg(not(any(target_os = "fuchsia", target_os = "illumos", target_os = "solaris")))]
+use libc::pthread_setschedparam;
+
+#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+extern "C" {
+    fn pthread_setschedparam(
+        native: ThreadId,
+        policy: libc::c_int,
+        params: *const libc::sched_param,
+    ) -> libc::c_int;
+    fn pthread_getschedparam(
+        native: ThreadId,
+        policy: *mut libc::c_int,
+        params: *mut libc::sched_param,
+    ) -> libc::c_int;
+}
+
+// Fuchsia has no POSIX thread scheduling, its threads are scheduled by the profiles of
+// the `fuchsia` module instead.
+#[cfg(target_os = "fuchsia")]
+unsafe fn pthread_setschedparam(
+    _native: ThreadId,
+    _policy: libc::c_int,
+    _params: *const libc::sched_param,
+) -> libc::c_int {
+    libc::ENOTSUP
+}
+
+#[cfg(target_os = "fuchsia")]
+unsafe fn pthread_getschedparam(
+    _native: ThreadId,
+    _policy: *mut libc::c_int,
+    _params: *mut libc::sched_param,
+) -> libc::c_int {
+    libc::ENOTSUP
+}
+
 /// An alias type for a thread id.
 pub type ThreadId = libc::pthread_t;
 
 /// Proxy structure to maintain compatibility between glibc and musl
+#[derive(Clone, Copy)]
 pub struct ScheduleParams {
     /// Copy of `sched_priority` from `libc::sched_param`
     pub sched_priority: libc::c_int,
@@ -45,30 +101,12 @@ pub struct SchedAttr {
 }
 
 impl ScheduleParams {
-    #[cfg(not(target_env = "musl"))]
     fn into_posix(self) -> libc::sched_param {
-        libc::sched_param {
-            sched_priority: self.sched_priority,
-        }
-    }
-
-    #[cfg(target_env = "musl")]
-    fn into_posix(self) -> libc::sched_param {
-        use libc::timespec as TimeSpec;
-
-        libc::sched_param {
-            sched_priority: self.sched_priority,
-            sched_ss_low_priority: 0,
-            sched_ss_repl_period: TimeSpec {
-                tv_sec: 0,
-                tv_nsec: 0,
-            },
-            sched_ss_init_budget: TimeSpec {
-                tv_sec: 0,
-                tv_nsec: 0,
-            },
-            sched_ss_max_repl: 0,
-        }
+        // The structure has more fields on some platforms, which are either private or
+        // deprecated.
+        let mut sched_param: libc::sched_param = unsafe { std::mem::zeroed() };
+        sched_param.sched_priority = self.sched_priority;
+        sched_param
     }
 
     fn from_posix(sched_param: libc::sched_param) -> Self {
@@ -81,6 +119,7 @@ impl ScheduleParams {
 /// The following "real-time" policies are also supported, for special time-critical applications
 /// that need precise control over the way in which runnable processes are selected for execution
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RealtimeThreadSchedulePolicy {
     /// A first-in, first-out policy
     Fifo,
@@ -91,20 +130,99 @@ pub enum RealtimeThreadSchedulePolicy {
     /// POSIX-compatible, so we only include it for linux targets.
     #[cfg(target_os = "linux")]
     Deadline,
+    /// A sporadic server policy: the thread runs at its priority for a budget of time in
+    /// every replenishment period, and at a low priority once the budget is exhausted.
+    #[cfg(any(target_os = "nto", target_os = "rtems"))]
+    Sporadic(SporadicParams),
+}
+
+/// The parameters of the sporadic server policy.
+#[cfg(any(target_os = "nto", target_os = "rtems"))]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SporadicParams {
+    /// The priority the thread drops to once its budget is exhausted.
+    pub low_priority: libc::c_int,
+    /// The period the budget is replenished in.
+    pub replenishment_period: std::time::Duration,
+    /// The time the thread may run at its priority in every period.
+    pub initial_budget: std::time::Duration,
+    /// The maximum number of the pending replenishments.
+    pub max_replenishments: libc::c_int,
+}
+
+/// The `sched_param` structure of QNX, with the sporadic server parameters `libc` keeps
+/// private.
+#[cfg(target_os = "nto")]
+#[repr(C)]
+struct NtoSchedParam {
+    sched_priority: libc::c_int,
+    sched_curpriority: libc::c_int,
+    sched_ss_low_priority: libc::c_int,
+    sched_ss_max_repl: libc::c_int,
+    sched_ss_repl_period: libc::timespec,
+    sched_ss_init_budget: libc::timespec,
+}
+
+#[cfg(target_os = "nto")]
+type SporadicSchedParam = NtoSchedParam;
+// The RTEMS `sched_param` of the `posix` module has the sporadic server parameters.
+#[cfg(target_os = "rtems")]
+type SporadicSchedParam = libc::sched_param;
+
+#[cfg(any(target_os = "nto", target_os = "rtems"))]
+impl SporadicParams {
+    fn to_posix(self, sched_priority: libc::c_int) -> SporadicSchedParam {
+        let timespec = |duration: std::time::Duration| libc::timespec {
+            tv_sec: duration.as_secs() as libc::time_t,
+            tv_nsec: duration.subsec_nanos() as libc::c_long,
+        };
+        SporadicSchedParam {
+            sched_priority,
+            #[cfg(target_os = "nto")]
+            sched_curpriority: 0,
+            sched_ss_low_priority: self.low_priority,
+            sched_ss_max_repl: self.max_replenishments,
+            sched_ss_repl_period: timespec(self.replenishment_period),
+            sched_ss_init_budget: timespec(self.initial_budget),
+        }
+    }
+
+    fn from_posix(params: &SporadicSchedParam) -> Self {
+        let duration = |timespec: &libc::timespec| {
+            std::time::Duration::new(timespec.tv_sec as u64, timespec.tv_nsec as u32)
+        };
+        SporadicParams {
+            low_priority: params.sched_ss_low_priority,
+            replenishment_period: duration(&params.sched_ss_repl_period),
+            initial_budget: duration(&params.sched_ss_init_budget),
+            max_replenishments: params.sched_ss_max_repl,
+        }
+    }
 }
+
 impl RealtimeThreadSchedulePolicy {
     fn to_posix(self) -> libc::c_int {
         match self {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
             RealtimeThreadSchedulePolicy::Fifo => 1,
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            RealtimeThreadSchedulePolicy::Fifo => libc::SCHED_FIFO,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
             RealtimeThreadSchedulePolicy::RoundRobin => 2,
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            RealtimeThreadSchedulePolicy::RoundRobin => libc::SCHED_RR,
             #[cfg(target_os = "linux")]
             RealtimeThreadSchedulePolicy::Deadline => 6,
+            #[cfg(any(target_os = "nto", target_os = "rtems"))]
+            RealtimeThreadSchedulePolicy::Sporadic(_) => libc::SCHED_SPORADIC,
         }
     }
 }
 
 /// Normal (usual) schedule policies
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NormalThreadSchedulePolicy {
     /// For running very low priority background jobs
     Idle,
@@ -118,15 +236,23 @@ pub enum NormalThreadSchedulePolicy {
 impl NormalThreadSchedulePolicy {
     fn to_posix(self) -> libc::c_int {
         match self {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
             NormalThreadSchedulePolicy::Idle => 5,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
             NormalThreadSchedulePolicy::Batch => 3,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
             NormalThreadSchedulePolicy::Other | NormalThreadSchedulePolicy::Normal => 0,
+            // The other platforms have a single POSIX time-sharing policy, the FreeBSD
+            // idle class is set by `rtprio_thread` instead.
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            _ => libc::SCHED_OTHER,
         }
     }
 }
 
 /// Thread schedule policy definition
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ThreadSchedulePolicy {
     /// Normal thread schedule policies
     Normal(NormalThreadSchedulePolicy),
@@ -134,13 +260,14 @@ pub enum ThreadSchedulePolicy {
     Realtime(RealtimeThreadSchedulePolicy),
 }
 impl ThreadSchedulePolicy {
-    fn to_posix(self) -> libc::c_int {
+    pub(crate) fn to_posix(self) -> libc::c_int {
         match self {
             ThreadSchedulePolicy::Normal(p) => p.to_posix(),
             ThreadSchedulePolicy::Realtime(p) => p.to_posix(),
         }
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
     fn from_posix(policy: libc::c_int) -> Result<ThreadSchedulePolicy, Error> {
         match policy {
             0 => Ok(ThreadSchedulePolicy::Normal(
@@ -165,12 +292,82 @@ impl ThreadSchedulePolicy {
             _ => Err(Error::Ffi("Can't parse schedule policy from posix")),
         }
     }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn from_posix(policy: libc::c_int) -> Result<ThreadSchedulePolicy, Error> {
+        match policy {
+            libc::SCHED_OTHER => Ok(ThreadSchedulePolicy::Normal(
+                NormalThreadSchedulePolicy::Normal,
+            )),
+            libc::SCHED_FIFO => Ok(ThreadSchedulePolicy::Realtime(
+                RealtimeThreadSchedulePolicy::Fifo,
+            )),
+            libc::SCHED_RR => Ok(ThreadSchedulePolicy::Realtime(
+                RealtimeThreadSchedulePolicy::RoundRobin,
+            )),
+            // The parameters are filled from the `sched_param` by the callers.
+            #[cfg(any(target_os = "nto", target_os = "rtems"))]
+            libc::SCHED_SPORADIC => Ok(ThreadSchedulePolicy::Realtime(
+                RealtimeThreadSchedulePolicy::Sporadic(SporadicParams::default()),
+            )),
+            _ => Err(Error::Ffi("Can't parse schedule policy from posix")),
+        }
+    }
+}
+
+/// Returns the range of the POSIX priorities of the policy, the one of the system for
+/// the realtime policies.
+pub(crate) fn native_priority_range(policy: ThreadSchedulePolicy) -> (libc::c_int, libc::c_int) {
+    if let ThreadSchedulePolicy::Normal(_) = policy {
+        return (0, 0);
+    }
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "nto"
+    ))]
+    unsafe {
+        let min = libc::sched_get_priority_min(policy.to_posix());
+        let max = libc::sched_get_priority_max(policy.to_posix());
+        if min >= 0 && max >= min {
+            return (min, max);
+        }
+    }
+    // The ranges `to_posix` accepts for the OS values.
+    if cfg!(target_os = "nto") {
+        (1, 255)
+    } else if cfg!(target_os = "rtems") {
+        (1, 254)
+    } else {
+        (1, 99)
+    }
 }
 
 impl ThreadPriority {
     /// POSIX value can not be known without knowing the scheduling policy
     /// <https://linux.die.net/man/2/sched_get_priority_max>
+    ///
+    /// The cross-platform values are mapped with the [`crate::mapping::mapping_strategy`]
+    /// in effect.
     pub fn to_posix(self, policy: ThreadSchedulePolicy) -> Result<libc::c_int, Error> {
+        self.to_posix_with(policy, crate::mapping::mapping_strategy())
+    }
+
+    /// Converts the priority to the POSIX value, mapping the cross-platform values with
+    /// the strategy.
+    /// For more info read [`ThreadPriority::to_posix`].
+    pub fn to_posix_with(
+        self,
+        policy: ThreadSchedulePolicy,
+        strategy: crate::mapping::MappingStrategy,
+    ) -> Result<libc::c_int, Error> {
         let ret = match self {
             ThreadPriority::Min => match policy {
                 // SCHED_DEADLINE doesn't really have a notion of priority, this is an error
@@ -187,6 +384,12 @@ impl ThreadPriority {
                 ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => Err(
                     Error::Priority("Deadline scheduling must use deadline priority."),
                 ),
+                _ if strategy != crate::mapping::MappingStrategy::Exact => {
+                    let (min, max) = native_priority_range(policy);
+                    strategy
+                        .map(ThreadPriorityValue(p), min, max)
+                        .map(|p| p as u32)
+                }
                 ThreadSchedulePolicy::Realtime(_) if (p == 0 || p > 99) => {
                     Err(Error::Priority("The value is out of range [0; 99]"))
                 }
@@ -202,6 +405,17 @@ impl ThreadPriority {
                 ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => Err(
                     Error::Priority("Deadline scheduling must use deadline priority."),
                 ),
+                // The QNX priorities range up to 255, the ones above 63 need privileges.
+                #[cfg(target_os = "nto")]
+                ThreadSchedulePolicy::Realtime(_) if (p == 0 || p > 255) => {
+                    Err(Error::Priority("The value is out of range [0; 255]"))
+                }
+                // The RTEMS priorities of the default 256 levels range up to 254.
+                #[cfg(target_os = "rtems")]
+                ThreadSchedulePolicy::Realtime(_) if (p == 0 || p > 254) => {
+                    Err(Error::Priority("The value is out of range [0; 254]"))
+                }
+                #[cfg(not(any(target_os = "nto", target_os = "rtems")))]
                 ThreadSchedulePolicy::Realtime(_) if (p == 0 || p > 99) => {
                     Err(Error::Priority("The value is out of range [0; 99]"))
                 }
@@ -233,6 +447,131 @@ impl ThreadPriority {
     pub fn from_posix(params: ScheduleParams) -> ThreadPriority {
         ThreadPriority::Crossplatform(ThreadPriorityValue(params.sched_priority as u8))
     }
+
+    /// Returns the priority raised by the amount, as the OS value for the policy, within
+    /// its range. The cross-platform values are clamped into the range first, the deadline
+    /// parameters and the deadline policy are an error.
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// let fifo = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+    /// let priority = ThreadPriority::Crossplatform(thread_priority_value!(50));
+    /// assert_eq!(priority.raise_by(10, fifo).unwrap(), ThreadPriority::Os(60u32.into()));
+    /// # #[cfg(target_os = "linux")]
+    /// assert_eq!(ThreadPriority::Max.raise_by(10, fifo).unwrap(), ThreadPriority::Os(99u32.into()));
+    /// ```
+    pub fn raise_by(self, amount: u32, policy: ThreadSchedulePolicy) -> Result<Self, Error> {
+        self.adjusted_by(i64::from(amount), policy)
+    }
+
+    /// Returns the priority lowered by the amount, as the OS value for the policy, within
+    /// its range.
+    /// For more info read [`ThreadPriority::raise_by`].
+    pub fn lower_by(self, amount: u32, policy: ThreadSchedulePolicy) -> Result<Self, Error> {
+        self.adjusted_by(-i64::from(amount), policy)
+    }
+
+    /// Returns the priority clamped into the range of the policy, as its OS value.
+    /// For more info read [`ThreadPriority::raise_by`].
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// let normal = ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Other);
+    /// let priority = ThreadPriority::Crossplatform(thread_priority_value!(50));
+    /// assert_eq!(priority.clamp_to(normal).unwrap(), ThreadPriority::Os(0u32.into()));
+    /// ```
+    pub fn clamp_to(self, policy: ThreadSchedulePolicy) -> Result<Self, Error> {
+        self.adjusted_by(0, policy)
+    }
+
+    fn adjusted_by(self, delta: i64, policy: ThreadSchedulePolicy) -> Result<Self, Error> {
+        #[cfg(target_os = "linux")]
+        if policy == ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) {
+            return Err(Error::Priority(
+                "Deadline scheduling must use deadline priority.",
+            ));
+        }
+        let native = match self {
+            ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => i64::from(p),
+            _ => i64::from(self.to_posix_with(policy, crate::mapping::MappingStrategy::Clamp)?),
+        };
+        let (min, max) = native_priority_range(policy);
+        let adjusted = (native + delta).clamp(i64::from(min), i64::from(max));
+        Ok(ThreadPriority::Os(crate::ThreadPriorityOsValue(
+            adjusted as u32,
+        )))
+    }
+
+    /// Compares the priorities by their POSIX values for the policy, unlike the derived
+    /// order, which compares the OS-specific values after all the cross-platform ones.
+    /// Returns `None` if any of them can't be converted, like the deadline parameters.
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    /// use std::cmp::Ordering;
+    /// use std::convert::TryInto;
+    ///
+    /// let policy = ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Fifo);
+    /// let os = ThreadPriority::Os(10u32.into());
+    /// let crossplatform = ThreadPriority::Crossplatform(50u8.try_into().unwrap());
+    /// assert!(os > crossplatform);
+    /// assert_eq!(os.cmp_with_policy(&crossplatform, policy), Some(Ordering::Less));
+    /// ```
+    pub fn cmp_with_policy(
+        &self,
+        other: &ThreadPriority,
+        policy: ThreadSchedulePolicy,
+    ) -> Option<std::cmp::Ordering> {
+        let this = self.to_posix(policy).ok()?;
+        Some(this.cmp(&other.to_posix(policy).ok()?))
+    }
+}
+
+/// The boundaries of the unix priorities.
+///
+/// ```rust
+/// use thread_priority::*;
+/// use std::convert::TryFrom;
+///
+/// let p = ThreadPriorityOsValue::try_from(42).unwrap();
+/// assert!(ThreadPriorityOsValue::REALTIME_MIN <= p && p <= ThreadPriorityOsValue::REALTIME_MAX);
+/// assert!(ThreadPriorityOsValue::try_from(-1).is_err());
+/// ```
+impl crate::ThreadPriorityOsValue {
+    /// The lowest `sched_priority` of the realtime policies.
+    pub const REALTIME_MIN: Self = crate::ThreadPriorityOsValue(1);
+    /// The highest `sched_priority` of the realtime policies the crate accepts.
+    #[cfg(target_os = "nto")]
+    pub const REALTIME_MAX: Self = crate::ThreadPriorityOsValue(255);
+    /// The highest `sched_priority` of the realtime policies the crate accepts.
+    #[cfg(target_os = "rtems")]
+    pub const REALTIME_MAX: Self = crate::ThreadPriorityOsValue(254);
+    /// The highest `sched_priority` of the realtime policies the crate accepts.
+    #[cfg(not(any(target_os = "nto", target_os = "rtems")))]
+    pub const REALTIME_MAX: Self = crate::ThreadPriorityOsValue(99);
+    /// The lowest niceness, the highest priority of the normal policies. The niceness
+    /// isn't an OS value, see [`crate::process::nice_from`].
+    pub const NICE_HIGHEST: i32 = crate::process::NICE_HIGHEST;
+    /// The highest niceness, the lowest priority of the normal policies.
+    pub const NICE_LOWEST: i32 = crate::process::NICE_LOWEST;
+}
+
+impl From<u32> for crate::ThreadPriorityOsValue {
+    fn from(value: u32) -> Self {
+        crate::ThreadPriorityOsValue(value)
+    }
+}
+
+impl TryFrom<i32> for crate::ThreadPriorityOsValue {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        u32::try_from(value)
+            .map(crate::ThreadPriorityOsValue)
+            .map_err(|_| Error::Priority("The value is negative."))
+    }
 }
 
 /// Sets thread's priority and schedule policy
@@ -256,13 +595,86 @@ pub fn set_thread_priority_and_policy(
     priority: ThreadPriority,
     policy: ThreadSchedulePolicy,
 ) -> Result<(), Error> {
-    let params = ScheduleParams {
-        sched_priority: match policy {
-            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => 0,
-            _ => priority.to_posix(policy)?,
-        },
-    };
-    set_thread_schedule_policy(native, policy, params, priority)
+    traced!(
+        DEBUG,
+        crate::hooks::notified(
+            crate::hooks::Change::Scheduling {
+                thread: native,
+                priority,
+                policy
+            },
+            || set_native_thread_priority_and_policy(native, priority, policy)
+        ),
+        "the scheduling",
+        thread = ?native,
+        ?priority,
+        ?policy
+    )
+}
+
+fn set_native_thread_priority_and_policy(
+    native: ThreadId,
+    priority: ThreadPriority,
+    policy: ThreadSchedulePolicy,
+) -> Result<(), Error> {
+    // The POSIX parameters hold a single priority for the normal policy on the Apple
+    // platforms, the cross-platform values are applied by the darwin module instead.
+    #[cfg(target_vendor = "apple")]
+    if let (ThreadPriority::Crossplatform(value), ThreadSchedulePolicy::Normal(_)) =
+        (priority, policy)
+    {
+        return crate::darwin::set_thread_crossplatform_priority(native, value);
+    }
+    // The VxWorks tasks aren't time-shared, the normal policy stands for their native
+    // priorities, which only the current task's id is known for.
+    #[cfg(target_os = "vxworks")]
+    if let ThreadSchedulePolicy::Normal(_) = policy {
+        if unsafe { libc::pthread_equal(native, libc::pthread_self()) } == 0 {
+            return Err(Error::Priority(
+                "The task priority can only be set for the current thread.",
+            ));
+        }
+        return crate::vxworks::set_current_task_priority(priority);
+    }
+    ensure_policy_supported(policy)?;
+    // The POSIX realtime priorities of FreeBSD only range up to 31 and there is no
+    // POSIX idle policy, these policies are applied as priority classes instead.
+    #[cfg(target_os = "freebsd")]
+    if let ThreadSchedulePolicy::Realtime(_)
+    | ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Idle) = policy
+    {
+        return crate::freebsd::set_thread_class_priority(native, policy, priority);
+    }
+    // The Solaris-derived kernels schedule the threads by the scheduling classes, the
+    // POSIX policies are a thin and incomplete layer over them.
+    #[cfg(any(target_os = "illumos", target_os = "solaris"))]
+    return crate::illumos::set_thread_class_priority(native, policy, priority);
+    // Redox only has the niceness of the processes.
+    #[cfg(target_os = "redox")]
+    return crate::redox::set_thread_nice_priority(native, policy, priority);
+    // The POSIX emulation of Haiku doesn't reach all of its native priorities.
+    #[cfg(target_os = "haiku")]
+    return crate::haiku::set_thread_policy_priority(native, policy, priority);
+    // The FreeRTOS tasks of ESP-IDF all share a single preemptive policy.
+    #[cfg(target_os = "espidf")]
+    return crate::espidf::set_thread_task_priority(native, policy, priority);
+    #[cfg(not(any(
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "redox",
+        target_os = "haiku",
+        target_os = "espidf"
+    )))]
+    {
+        let params = ScheduleParams {
+            sched_priority: match policy {
+                #[cfg(target_os = "linux")]
+                ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => 0,
+                _ => priority.to_posix(policy)?,
+            },
+        };
+        set_thread_schedule_policy(native, policy, params, priority)
+    }
 }
 
 /// Set current thread's priority.
@@ -272,6 +684,102 @@ pub fn set_current_thread_priority(priority: ThreadPriority) -> Result<(), Error
     set_thread_priority_and_policy(thread_id, priority, policy)
 }
 
+/// Checks the policy is implemented by the platform.
+fn ensure_policy_supported(policy: ThreadSchedulePolicy) -> Result<(), Error> {
+    // NetBSD and OpenBSD only implement the POSIX policies.
+    #[cfg(any(target_os = "netbsd", target_os = "openbsd"))]
+    if let ThreadSchedulePolicy::Normal(
+        NormalThreadSchedulePolicy::Idle | NormalThreadSchedulePolicy::Batch,
+    ) = policy
+    {
+        return Err(Error::Unsupported(
+            "The idle and batch policies are not supported on this platform",
+        ));
+    }
+    #[cfg(not(any(target_os = "netbsd", target_os = "openbsd")))]
+    let _ = policy;
+    Ok(())
+}
+
+/// Checks whether the priority and the policy could be set, without touching any thread:
+/// the range of the priority for the policy, the platform's support of the policy, the
+/// sandbox-safe mode and the permission to use the realtime policies. This allows to
+/// validate the configuration at startup, before the threads are spawned.
+///
+/// The permission denials are reported as `EPERM`, the realtime priorities above the
+/// `RLIMIT_RTPRIO` limit included. The platform-specific ranges may still reject some of
+/// the OS values.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+/// use std::convert::TryInto;
+///
+/// let policy = ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal);
+/// assert!(validate(ThreadPriority::Min, policy).is_ok());
+/// // The normal policy only takes the priority `0`.
+/// assert!(validate(ThreadPriority::Crossplatform(50u8.try_into().unwrap()), policy).is_err());
+/// ```
+pub fn validate(priority: ThreadPriority, policy: ThreadSchedulePolicy) -> Result<(), Error> {
+    ensure_policy_supported(policy)?;
+    #[cfg(target_os = "linux")]
+    if let ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) = policy {
+        return match priority {
+            ThreadPriority::Deadline(runtime, deadline, period) => {
+                validate_deadline(runtime, deadline, period)
+            }
+            _ => Err(Error::Priority(
+                "Deadline policy given without deadline priority.",
+            )),
+        };
+    }
+    let sched_priority = priority.to_posix(policy)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::SchedSetscheduler)?;
+    if let ThreadSchedulePolicy::Realtime(_) = policy {
+        let allowed = match crate::realtime::max_allowed_realtime_priority()? {
+            Some(ThreadPriority::Os(crate::ThreadPriorityOsValue(limit))) => limit as libc::c_int,
+            Some(_) => libc::c_int::MAX,
+            None => 0,
+        };
+        if sched_priority > allowed {
+            return Err(Error::OS(libc::EPERM));
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether the deadline parameters, in nanoseconds, could be set without touching
+/// any thread: the kernel requires `runtime <= deadline <= period`, a runtime of at least
+/// 1024 ns, and the privileges, as `RLIMIT_RTPRIO` doesn't apply to the deadline policy.
+/// A period of `0` stands for the deadline.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(validate_deadline(2_000_000, 1_000_000, 0).is_err());
+/// ```
+#[cfg(target_os = "linux")]
+pub fn validate_deadline(runtime: u64, deadline: u64, period: u64) -> Result<(), Error> {
+    let period = if period == 0 { deadline } else { period };
+    if runtime < 1024 {
+        return Err(Error::Priority("The runtime must be at least 1024 ns."));
+    }
+    if !(runtime <= deadline && deadline <= period) {
+        return Err(Error::Priority(
+            "The parameters must satisfy runtime <= deadline <= period.",
+        ));
+    }
+    crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::SchedSetattr)?;
+    if !crate::realtime::realtime_permissions()?.privileged {
+        return Err(Error::OS(libc::EPERM));
+    }
+    Ok(())
+}
+
 /// Returns policy parameters (schedule policy and other schedule parameters) for current process
 ///
 /// # Usage
@@ -282,14 +790,248 @@ pub fn set_current_thread_priority(priority: ThreadPriority) -> Result<(), Error
 /// assert!(thread_schedule_policy().is_ok());
 /// ```
 pub fn thread_schedule_policy() -> Result<ThreadSchedulePolicy, Error> {
-    unsafe { ThreadSchedulePolicy::from_posix(libc::sched_getscheduler(libc::getpid())) }
+    #[cfg(not(any(
+        target_vendor = "apple",
+        target_os = "openbsd",
+        target_os = "haiku",
+        target_os = "espidf"
+    )))]
+    unsafe {
+        ThreadSchedulePolicy::from_posix(libc::sched_getscheduler(libc::getpid()))
+    }
+    // There is no process-wide scheduler on the Apple platforms, OpenBSD, Haiku and
+    // ESP-IDF.
+    #[cfg(any(
+        target_vendor = "apple",
+        target_os = "openbsd",
+        target_os = "haiku",
+        target_os = "espidf"
+    ))]
+    thread_schedule_policy_for(thread_native_id())
+}
+
+/// Returns the schedule policy of the given thread. Unlike [`thread_schedule_policy`],
+/// which queries the process, this reports what `pthread_getschedparam` returns for the
+/// thread itself, which is what changes when a thread's policy is set.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(thread_schedule_policy_for(thread_native_id()).is_ok());
+/// ```
+pub fn thread_schedule_policy_for(native: ThreadId) -> Result<ThreadSchedulePolicy, Error> {
+    thread_schedule_policy_param(native).map(|(policy, _)| policy)
+}
+
+/// An alias type for a process id.
+pub type ProcessId = libc::pid_t;
+
+/// Returns the schedule policy of the process with the given id. Passing `0` queries the
+/// calling process.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(process_schedule_policy(0).is_ok());
+/// ```
+#[cfg(not(any(target_vendor = "apple", target_os = "openbsd")))]
+pub fn process_schedule_policy(pid: ProcessId) -> Result<ThreadSchedulePolicy, Error> {
+    unsafe {
+        match libc::sched_getscheduler(pid) {
+            -1 => Err(Error::OS(errno())),
+            policy => ThreadSchedulePolicy::from_posix(policy),
+        }
+    }
+}
+
+/// Sets the schedule policy and priority of the process with the given id. Passing `0`
+/// targets the calling process.
+///
+/// * May require privileges
+/// * On Linux, the scheduler attributes are per-thread, so this only changes the main thread
+///   of the process; threads it creates afterwards inherit the new settings.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(set_process_schedule_policy(
+///     0,
+///     ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+///     ThreadPriority::Min,
+/// )
+/// .is_ok());
+/// ```
+#[cfg(not(any(target_vendor = "apple", target_os = "openbsd")))]
+pub fn set_process_schedule_policy(
+    pid: ProcessId,
+    policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    traced!(
+        DEBUG,
+        crate::hooks::notified(
+            crate::hooks::Change::ProcessScheduling {
+                pid,
+                priority,
+                policy
+            },
+            || set_native_process_schedule_policy(pid, policy, priority)
+        ),
+        "the process scheduling",
+        pid,
+        ?policy,
+        ?priority
+    )
+}
+
+#[cfg(not(any(target_vendor = "apple", target_os = "openbsd")))]
+fn set_native_process_schedule_policy(
+    pid: ProcessId,
+    policy: ThreadSchedulePolicy,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    let ret = match policy {
+        #[cfg(target_os = "linux")]
+        ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => {
+            crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::SchedSetattr)?;
+            set_deadline_sched_attr(pid, priority)?;
+            0
+        }
+        _ => {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::SchedSetscheduler)?;
+            let params = ScheduleParams {
+                sched_priority: priority.to_posix(policy)?,
+            }
+            .into_posix();
+            unsafe { libc::sched_setscheduler(pid, policy.to_posix(), &params) }
+        }
+    };
+    match ret {
+        0 => Ok(()),
+        _ => Err(Error::OS(errno())),
+    }
+}
+
+/// Sets the priority and schedule policy of a thread of any process, addressed by its
+/// kernel thread id (tid) rather than by a pthread handle, which is only valid within the
+/// process which created the thread.
+///
+/// Before changing anything, checks that the thread `tid` belongs to the process `pid`,
+/// returning `ESRCH` otherwise, so that a reused tid of an exited thread isn't adjusted by
+/// mistake. The thread exiting during the change, its tid possibly reused by another
+/// thread meanwhile, also returns `ESRCH`: the change may have reached that other thread.
+///
+/// * May require privileges
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let pid = std::process::id() as ProcessId;
+/// assert!(set_thread_priority_and_policy_for_tid(
+///     pid,
+///     thread_native_tid(),
+///     ThreadPriority::Min,
+///     ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+/// )
+/// .is_ok());
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn set_thread_priority_and_policy_for_tid(
+    pid: ProcessId,
+    tid: ThreadTid,
+    priority: ThreadPriority,
+    policy: ThreadSchedulePolicy,
+) -> Result<(), Error> {
+    let task = TaskDir::open(pid, tid)?;
+    traced!(
+        DEBUG,
+        crate::hooks::notified(
+            crate::hooks::Change::TidScheduling {
+                tid,
+                priority,
+                policy
+            },
+            // Linux scheduler attributes are per-thread, so addressing a tid where a pid
+            // is expected changes exactly that thread.
+            || set_native_process_schedule_policy(tid, policy, priority)
+        ),
+        "the thread scheduling",
+        tid,
+        ?policy,
+        ?priority
+    )?;
+    task.ensure_alive()
+}
+
+/// Returns the schedule policy and parameters of a thread of any process, addressed by
+/// its kernel thread id (tid).
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let pid = std::process::id() as ProcessId;
+/// assert!(thread_schedule_policy_param_for_tid(pid, thread_native_tid()).is_ok());
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn thread_schedule_policy_param_for_tid(
+    pid: ProcessId,
+    tid: ThreadTid,
+) -> Result<(ThreadSchedulePolicy, ScheduleParams), Error> {
+    let task = TaskDir::open(pid, tid)?;
+    let policy = process_schedule_policy(tid)?;
+    let params = unsafe {
+        let mut params = ScheduleParams { sched_priority: 0 }.into_posix();
+        match libc::sched_getparam(tid, &mut params) {
+            0 => ScheduleParams::from_posix(params),
+            _ => return Err(Error::OS(errno())),
+        }
+    };
+    task.ensure_alive()?;
+    Ok((policy, params))
+}
+
+/// The `/proc` directory of a thread of a process, held open while the thread is addressed
+/// by its tid. Its entries are gone once the thread has exited, even if another thread has
+/// got the tid since, which tells whether the tid still named the thread.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct TaskDir(std::fs::File);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl TaskDir {
+    fn open(pid: ProcessId, tid: ThreadTid) -> Result<Self, Error> {
+        std::fs::File::open(format!("/proc/{}/task/{}", pid, tid))
+            .map(TaskDir)
+            .map_err(|_| Error::OS(libc::ESRCH))
+    }
+
+    fn ensure_alive(&self) -> Result<(), Error> {
+        use std::os::unix::io::AsRawFd;
+
+        let stat = b"stat\0".as_ptr() as *const libc::c_char;
+        match unsafe { libc::faccessat(self.0.as_raw_fd(), stat, libc::F_OK, 0) } {
+            0 => Ok(()),
+            _ => Err(Error::OS(libc::ESRCH)),
+        }
+    }
 }
 
 /// Sets thread schedule policy.
 ///
 /// * May require privileges
 /// * Deadline policy requires a tid, not a pthread_t, so invoking this while using a deadline
-/// policy will interpret the given [ThreadId](struct.ThreadId) as a pid_t (thread tid).
+///   policy will interpret the given [ThreadId](struct.ThreadId) as a pid_t (thread tid),
+///   unless it's the one of the current thread.
 ///
 /// # Usage
 /// ```rust,no_run
@@ -301,50 +1043,74 @@ pub fn thread_schedule_policy() -> Result<ThreadSchedulePolicy, Error> {
 /// let priority = ThreadPriority::Min;
 /// assert!(set_thread_schedule_policy(thread_id, policy, params, priority).is_ok());
 /// ```
+// Clippy only trusts the foreign functions with the handles, the `posix` shims ignore them.
+#[cfg_attr(target_os = "redox", allow(clippy::not_unsafe_ptr_arg_deref))]
 pub fn set_thread_schedule_policy(
     native: ThreadId,
     policy: ThreadSchedulePolicy,
     params: ScheduleParams,
     priority: ThreadPriority,
 ) -> Result<(), Error> {
+    traced!(
+        TRACE,
+        set_native_thread_schedule_policy(native, policy, params, priority),
+        "the native scheduling",
+        thread = ?native,
+        posix_policy = policy.to_posix(),
+        sched_priority = params.sched_priority
+    )
+}
+
+fn set_native_thread_schedule_policy(
+    native: ThreadId,
+    policy: ThreadSchedulePolicy,
+    params: ScheduleParams,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    // The priority is only needed for the deadline policy.
+    #[cfg(not(target_os = "linux"))]
+    let _ = priority;
     let params = params.into_posix();
     unsafe {
         let ret = match policy {
             // SCHED_DEADLINE policy requires its own syscall
             #[cfg(target_os = "linux")]
             ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => {
-                let (runtime, deadline, period) = match priority {
-                    ThreadPriority::Deadline(r, d, p) => (r, d, p),
-                    _ => {
-                        return Err(Error::Priority(
-                            "Deadline policy given without deadline priority.",
-                        ))
-                    }
+                crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::SchedSetattr)?;
+                // The handle of the current thread is resolved to its tid, any other
+                // value is taken for a tid.
+                let tid = if libc::pthread_equal(native, libc::pthread_self()) != 0 {
+                    thread_native_tid()
+                } else {
+                    native as libc::pid_t
                 };
-                let tid = native as libc::pid_t;
-                let sched_attr = SchedAttr {
-                    size: std::mem::size_of::<SchedAttr>() as u32,
-                    sched_policy: policy.to_posix() as u32,
-
-                    sched_runtime: runtime as u64,
-                    sched_deadline: deadline as u64,
-                    sched_period: period as u64,
-
-                    ..Default::default()
-                };
-                libc::syscall(
-                    libc::SYS_sched_setattr,
-                    tid,
-                    &sched_attr as *const _,
-                    // we are not setting SCHED_FLAG_RECLAIM nor SCHED_FLAG_DL_OVERRUN
-                    0,
-                ) as i32
+                set_deadline_sched_attr(tid, priority)?;
+                0
+            }
+            // The sporadic server parameters are private in the `libc` structure
+            #[cfg(target_os = "nto")]
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Sporadic(sporadic)) => {
+                let params = sporadic.to_posix(params.sched_priority);
+                pthread_setschedparam(
+                    native,
+                    policy.to_posix(),
+                    &params as *const NtoSchedParam as *const libc::sched_param,
+                )
+            }
+            #[cfg(target_os = "rtems")]
+            ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Sporadic(sporadic)) => {
+                let params = sporadic.to_posix(params.sched_priority);
+                pthread_setschedparam(native, policy.to_posix(), &params)
+            }
+            _ => {
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                crate::sandbox::ensure_allowed(crate::sandbox::SchedSyscall::SchedSetscheduler)?;
+                pthread_setschedparam(
+                    native,
+                    policy.to_posix(),
+                    &params as *const libc::sched_param,
+                )
             }
-            _ => libc::pthread_setschedparam(
-                native,
-                policy.to_posix(),
-                &params as *const libc::sched_param,
-            ),
         };
         match ret {
             0 => Ok(()),
@@ -353,6 +1119,103 @@ pub fn set_thread_schedule_policy(
     }
 }
 
+/// Applies `SCHED_DEADLINE` to the given kernel thread id via the `sched_setattr` syscall.
+#[cfg(target_os = "linux")]
+fn set_deadline_sched_attr(tid: libc::pid_t, priority: ThreadPriority) -> Result<(), Error> {
+    let (runtime, deadline, period) = match priority {
+        ThreadPriority::Deadline(r, d, p) => (r, d, p),
+        _ => {
+            return Err(Error::Priority(
+                "Deadline policy given without deadline priority.",
+            ))
+        }
+    };
+    let sched_attr = SchedAttr {
+        size: std::mem::size_of::<SchedAttr>() as u32,
+        sched_policy: RealtimeThreadSchedulePolicy::Deadline.to_posix() as u32,
+
+        sched_runtime: runtime,
+        sched_deadline: deadline,
+        sched_period: period,
+
+        ..Default::default()
+    };
+    unsafe {
+        match libc::syscall(
+            libc::SYS_sched_setattr,
+            tid,
+            &sched_attr as *const _,
+            // we are not setting SCHED_FLAG_RECLAIM nor SCHED_FLAG_DL_OVERRUN
+            0,
+        ) {
+            0 => Ok(()),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Returns the deadline parameters of the thread, `None` if it isn't scheduled with the
+/// deadline policy.
+#[cfg(target_os = "linux")]
+pub(crate) fn deadline_params_for_tid(tid: libc::pid_t) -> Result<Option<(u64, u64, u64)>, Error> {
+    let mut sched_attr = SchedAttr::default();
+    unsafe {
+        if libc::syscall(
+            libc::SYS_sched_getattr,
+            tid,
+            &mut sched_attr as *mut _,
+            std::mem::size_of::<SchedAttr>() as u32,
+            // flags must be 0
+            0,
+        ) < 0
+        {
+            return Err(Error::OS(errno()));
+        }
+    }
+    Ok(
+        if sched_attr.sched_policy == RealtimeThreadSchedulePolicy::Deadline.to_posix() as u32 {
+            Some((
+                sched_attr.sched_runtime,
+                sched_attr.sched_deadline,
+                sched_attr.sched_period,
+            ))
+        } else {
+            None
+        },
+    )
+}
+
+/// Returns the value of `errno` for the last failed libc call of this thread.
+pub(crate) fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+/// Sets the value of `errno` of this thread, so that the failures of the calls which may
+/// return `-1` on success can be told apart.
+#[cfg(not(any(target_os = "fuchsia", target_os = "vxworks", target_os = "rtems")))]
+pub(crate) fn set_errno(value: i32) {
+    unsafe {
+        #[cfg(any(target_os = "linux", target_os = "redox"))]
+        let errno = ::libc::__errno_location();
+        #[cfg(any(
+            target_os = "android",
+            target_os = "netbsd",
+            target_os = "openbsd",
+            target_os = "espidf"
+        ))]
+        let errno = ::libc::__errno();
+        #[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
+        let errno = ::libc::__error();
+        #[cfg(any(target_os = "illumos", target_os = "solaris"))]
+        let errno = ::libc::___errno();
+        #[cfg(target_os = "haiku")]
+        let errno = ::libc::_errnop();
+        #[cfg(target_os = "nto")]
+        let errno = ::libc::__get_errno_ptr();
+        *errno = value;
+    }
+}
+
 /// Returns policy parameters (schedule policy and other schedule parameters)
 ///
 /// # Usage
@@ -363,21 +1226,61 @@ pub fn set_thread_schedule_policy(
 /// let thread_id = thread_native_id();
 /// assert!(thread_schedule_policy_param(thread_id).is_ok());
 /// ```
+// Clippy only trusts the foreign functions with the handles, the `posix` shims ignore them.
+// The musl handles are pointers, which clippy takes for dereferenced when they're passed.
+#[cfg_attr(
+    any(target_os = "redox", target_env = "musl"),
+    allow(clippy::not_unsafe_ptr_arg_deref)
+)]
 pub fn thread_schedule_policy_param(
     native: ThreadId,
 ) -> Result<(ThreadSchedulePolicy, ScheduleParams), Error> {
+    // The schedule policy of the Haiku threads follows from their native priority.
+    #[cfg(target_os = "haiku")]
+    return crate::haiku::thread_schedule_policy_param(native);
+    // Only the priority of the current task is known on ESP-IDF.
+    #[cfg(target_os = "espidf")]
+    return crate::espidf::thread_schedule_policy_param(native);
+    #[cfg(not(any(target_os = "haiku", target_os = "espidf")))]
     unsafe {
         let mut policy = 0i32;
         let mut params = ScheduleParams { sched_priority: 0 }.into_posix();
 
-        let ret = libc::pthread_getschedparam(
+        #[cfg(not(target_os = "nto"))]
+        let ret = pthread_getschedparam(
             native,
             &mut policy as *mut libc::c_int,
             &mut params as *mut libc::sched_param,
         );
+        #[cfg(target_os = "nto")]
+        let mut nto_params: NtoSchedParam = std::mem::zeroed();
+        #[cfg(target_os = "nto")]
+        let ret = pthread_getschedparam(
+            native,
+            &mut policy as *mut libc::c_int,
+            &mut nto_params as *mut NtoSchedParam as *mut libc::sched_param,
+        );
+        #[cfg(target_os = "nto")]
+        {
+            params.sched_priority = nto_params.sched_priority;
+        }
         match ret {
             0 => Ok((
-                ThreadSchedulePolicy::from_posix(policy)?,
+                match ThreadSchedulePolicy::from_posix(policy)? {
+                    #[cfg(target_os = "nto")]
+                    ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Sporadic(_)) => {
+                        ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Sporadic(
+                            SporadicParams::from_posix(&nto_params),
+                        ))
+                    }
+                    #[cfg(target_os = "rtems")]
+                    ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Sporadic(_)) => {
+                        ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Sporadic(
+                            SporadicParams::from_posix(&params),
+                        ))
+                    }
+                    policy => policy,
+                },
                 ScheduleParams::from_posix(params),
             )),
             e => Err(Error::OS(e)),
@@ -387,27 +1290,194 @@ pub fn thread_schedule_policy_param(
 
 /// Get current thread's priority value.
 pub fn thread_priority() -> Result<ThreadPriority, Error> {
-    Ok(ThreadPriority::from_posix(
-        thread_schedule_policy_param(thread_native_id())?.1,
-    ))
+    get_thread_priority(thread_native_id())
+}
+
+/// Returns the priority of the thread.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(get_thread_priority(thread_native_id()).is_ok());
+/// ```
+pub fn get_thread_priority(native: ThreadId) -> Result<ThreadPriority, Error> {
+    get_thread_priority_and_policy(native).map(|(priority, _)| priority)
+}
+
+/// Returns the priority and the schedule policy of the thread. There are no schedule
+/// policies on Windows, the priority alone is returned by `get_thread_priority` there.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let (_priority, policy) = get_thread_priority_and_policy(thread_native_id()).unwrap();
+/// assert_eq!(policy, thread_schedule_policy_for(thread_native_id()).unwrap());
+/// ```
+pub fn get_thread_priority_and_policy(
+    native: ThreadId,
+) -> Result<(ThreadPriority, ThreadSchedulePolicy), Error> {
+    let (policy, params) = thread_schedule_policy_param(native)?;
+    Ok((ThreadPriority::from_posix(params), policy))
+}
+
+/// A temporary change of the scheduling of the current thread: the previous schedule
+/// policy and priority, and the previous affinity if it has been changed too, are
+/// restored when the guard is dropped, including when the thread panics, so that the
+/// temporary boosts can't leak.
+///
+/// The guard restores the thread it has been created on, so it can't be sent to another
+/// thread. A drop can't fail, the errors of the restoration are logged instead.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let policy = ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal);
+/// {
+///     let _scoped = ScopedScheduling::elevate(ThreadPriority::Min, policy).unwrap();
+///     // The work to do with the temporary scheduling.
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ScopedScheduling {
+    policy: ThreadSchedulePolicy,
+    sched_priority: libc::c_int,
+    affinity: Option<crate::affinity::CpuSet>,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl ScopedScheduling {
+    /// Captures the schedule policy and priority of the current thread and applies the
+    /// new ones.
+    ///
+    /// * May require privileges
+    ///
+    /// The threads under the deadline policy can't be elevated, as their parameters
+    /// can't be restored.
+    ///
+    /// For more info read [`set_thread_priority_and_policy`].
+    pub fn elevate(priority: ThreadPriority, policy: ThreadSchedulePolicy) -> Result<Self, Error> {
+        let native = thread_native_id();
+        let (previous_policy, sched_priority) = restorable_scheduling(native)?;
+        set_thread_priority_and_policy(native, priority, policy)?;
+        Ok(ScopedScheduling {
+            policy: previous_policy,
+            sched_priority,
+            affinity: None,
+            _not_send: std::marker::PhantomData,
+        })
+    }
+
+    /// Captures the affinity of the current thread too and applies the new one, before
+    /// elevating the thread. If the elevation fails, the affinity is restored right away.
+    ///
+    /// For more info read [`ScopedScheduling::elevate`] and
+    /// [`set_thread_affinity`](crate::affinity::set_thread_affinity).
+    pub fn elevate_with_affinity(
+        priority: ThreadPriority,
+        policy: ThreadSchedulePolicy,
+        cpus: &crate::affinity::CpuSet,
+    ) -> Result<Self, Error> {
+        use crate::affinity::{get_current_thread_affinity, set_current_thread_affinity};
+
+        let previous = get_current_thread_affinity()?;
+        set_current_thread_affinity(cpus)?;
+        match ScopedScheduling::elevate(priority, policy) {
+            Ok(mut scoped) => {
+                scoped.affinity = Some(previous);
+                Ok(scoped)
+            }
+            Err(e) => {
+                let _ = set_current_thread_affinity(&previous);
+                Err(e)
+            }
+        }
+    }
+
+    fn restore(&self) -> Result<(), Error> {
+        restore_scheduling(thread_native_id(), self.policy, self.sched_priority)
+    }
+}
+
+/// Captures the schedule policy and the native priority of the thread, to be restored with
+/// [`restore_scheduling`]. The threads under the deadline policy can't be restored.
+pub(crate) fn restorable_scheduling(
+    native: ThreadId,
+) -> Result<(ThreadSchedulePolicy, libc::c_int), Error> {
+    let (policy, params) = thread_schedule_policy_param(native)?;
+    #[cfg(target_os = "linux")]
+    if let ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) = policy {
+        return Err(Error::Priority(
+            "The deadline policy parameters can't be restored.",
+        ));
+    }
+    Ok((policy, params.sched_priority))
+}
+
+/// Restores the scheduling captured by [`restorable_scheduling`].
+pub(crate) fn restore_scheduling(
+    native: ThreadId,
+    policy: ThreadSchedulePolicy,
+    sched_priority: libc::c_int,
+) -> Result<(), Error> {
+    // The native priorities of Haiku and ESP-IDF are only applied by the platform
+    // modules, which take them as the OS values.
+    #[cfg(any(target_os = "haiku", target_os = "espidf"))]
+    return set_thread_priority_and_policy(
+        native,
+        ThreadPriority::Os(crate::ThreadPriorityOsValue(sched_priority as u32)),
+        policy,
+    );
+    #[cfg(not(any(target_os = "haiku", target_os = "espidf")))]
+    {
+        let params = ScheduleParams { sched_priority };
+        let priority = ThreadPriority::from_posix(ScheduleParams { sched_priority });
+        set_thread_schedule_policy(native, policy, params, priority)
+    }
+}
+
+impl Drop for ScopedScheduling {
+    fn drop(&mut self) {
+        if let Err(e) = self.restore() {
+            log::warn!("Couldn't restore the scheduling of the thread: {:?}", e);
+        }
+        if let Some(cpus) = &self.affinity {
+            if let Err(e) = crate::affinity::set_current_thread_affinity(cpus) {
+                log::warn!("Couldn't restore the affinity of the thread: {:?}", e);
+            }
+        }
+    }
 }
 
 /// A helper trait for other threads to implement to be able to call methods
 /// on threads themselves.
 ///
+/// The methods apply to the thread [`ThreadExt::get_native_id`] returns. A
+/// [`std::thread::Thread`] doesn't know its native id, so it stands for the
+/// current thread, while a [`std::thread::JoinHandle`] applies the methods to the
+/// thread it refers to.
+///
 /// ```rust
 /// use thread_priority::*;
 ///
 /// assert!(std::thread::current().get_priority().is_ok());
 ///
-/// let join_handle = std::thread::spawn(|| println!("Hello world!"));
-/// assert!(join_handle.thread().get_priority().is_ok());
+/// let (sender, receiver) = std::sync::mpsc::channel::<()>();
+/// let join_handle = std::thread::spawn(move || receiver.recv());
+/// // The thread is alive until the sender is dropped.
+/// assert!(join_handle.get_priority().is_ok());
 ///
+/// drop(sender);
 /// join_handle.join();
 /// ```
 pub trait ThreadExt {
-    /// Gets the current thread's priority.
-    /// For more info read [`thread_priority`].
+    /// Gets the thread's priority.
+    /// For more info read [`get_thread_priority`].
     ///
     /// ```rust
     /// use thread_priority::*;
@@ -415,10 +1485,10 @@ pub trait ThreadExt {
     /// assert!(std::thread::current().get_priority().is_ok());
     /// ```
     fn get_priority(&self) -> Result<ThreadPriority, Error> {
-        thread_priority()
+        get_thread_priority(self.get_native_id())
     }
 
-    /// Sets the current thread's priority.
+    /// Sets the thread's priority, with the normal schedule policy.
     /// For more info see [`ThreadPriority::set_for_current`].
     ///
     /// ```rust
@@ -427,22 +1497,26 @@ pub trait ThreadExt {
     /// assert!(std::thread::current().set_priority(ThreadPriority::Min).is_ok());
     /// ```
     fn set_priority(&self, priority: ThreadPriority) -> Result<(), Error> {
-        priority.set_for_current()
+        set_thread_priority_and_policy(
+            self.get_native_id(),
+            priority,
+            ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Normal),
+        )
     }
 
-    /// Gets the current thread's schedule policy.
-    /// For more info read [`thread_schedule_policy`].
+    /// Gets the thread's schedule policy.
+    /// For more info read [`thread_schedule_policy_for`].
     fn get_schedule_policy(&self) -> Result<ThreadSchedulePolicy, Error> {
-        thread_schedule_policy()
+        thread_schedule_policy_for(self.get_native_id())
     }
 
-    /// Returns current thread's schedule policy and parameters.
+    /// Returns the thread's schedule policy and parameters.
     /// For more info read [`thread_schedule_policy_param`].
     fn get_schedule_policy_param(&self) -> Result<(ThreadSchedulePolicy, ScheduleParams), Error> {
-        thread_schedule_policy_param(thread_native_id())
+        thread_schedule_policy_param(self.get_native_id())
     }
 
-    /// Sets current thread's schedule policy.
+    /// Sets the thread's schedule policy.
     /// For more info read [`set_thread_schedule_policy`].
     fn set_schedule_policy(
         &self,
@@ -451,11 +1525,12 @@ pub trait ThreadExt {
     ) -> Result<(), Error> {
         let params = ScheduleParams {
             sched_priority: match policy {
+                #[cfg(target_os = "linux")]
                 ThreadSchedulePolicy::Realtime(RealtimeThreadSchedulePolicy::Deadline) => 0,
                 _ => priority.to_posix(policy)?,
             },
         };
-        set_thread_schedule_policy(thread_native_id(), policy, params, priority)
+        set_thread_schedule_policy(self.get_native_id(), policy, params, priority)
     }
 
     /// Returns native unix thread id.
@@ -473,6 +1548,16 @@ pub trait ThreadExt {
 /// Auto-implementation of this trait for the [`std::thread::Thread`].
 impl ThreadExt for std::thread::Thread {}
 
+/// Implementation of this trait for the [`std::thread::JoinHandle`], for the thread it
+/// refers to.
+impl<T> ThreadExt for std::thread::JoinHandle<T> {
+    // The standard library's `pthread_t` is an integer on musl, unlike the one of `libc`.
+    #[allow(clippy::unnecessary_cast)]
+    fn get_native_id(&self) -> ThreadId {
+        std::os::unix::thread::JoinHandleExt::as_pthread_t(self) as ThreadId
+    }
+}
+
 /// Returns current thread id, which is the current OS's native handle.
 /// It may or may not be equal or even related to rust's thread id,
 /// there is absolutely no guarantee for that.
@@ -488,15 +1573,105 @@ pub fn thread_native_id() -> ThreadId {
     unsafe { libc::pthread_self() }
 }
 
+/// An alias type for a kernel thread id (tid). Unlike the [`ThreadId`], which is
+/// a pthread handle valid only within the current process, this value is what the
+/// kernel uses to identify a thread system-wide.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub type ThreadTid = libc::pid_t;
+
+/// Returns current thread's kernel thread id (tid).
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(thread_native_tid() > 0);
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn thread_native_tid() -> ThreadTid {
+    unsafe { libc::syscall(libc::SYS_gettid) as ThreadTid }
+}
+
+/// Returns the kernel thread id of the thread. On Linux, there is no way to get the tid of
+/// another thread from its pthread handle, so only the one of the current thread is known.
+#[cfg(target_os = "linux")]
+pub(crate) fn thread_tid_of(native: ThreadId) -> Result<ThreadTid, Error> {
+    match unsafe { libc::pthread_equal(native, libc::pthread_self()) } {
+        0 => Err(Error::Priority(
+            "The kernel thread id is only known for the current thread.",
+        )),
+        _ => Ok(thread_native_tid()),
+    }
+}
+
+/// Returns the kernel thread id of the thread.
+#[cfg(target_os = "android")]
+pub(crate) fn thread_tid_of(native: ThreadId) -> Result<ThreadTid, Error> {
+    Ok(unsafe { libc::pthread_gettid_np(native) })
+}
+
+/// Scheduler statistics of a thread, as reported by the kernel in
+/// `/proc/self/task/<tid>/schedstat`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SchedStats {
+    /// Time spent running on a CPU.
+    pub run_time: std::time::Duration,
+    /// Time spent waiting on a run queue.
+    pub wait_time: std::time::Duration,
+    /// Number of timeslices run on a CPU.
+    pub timeslices: u64,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl std::str::FromStr for SchedStats {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = s.split_whitespace().map(|v| {
+            v.parse::<u64>()
+                .map_err(|_| Error::Ffi("Can't parse schedstat"))
+        });
+        let mut next = || {
+            values
+                .next()
+                .unwrap_or(Err(Error::Ffi("Can't parse schedstat")))
+        };
+
+        Ok(SchedStats {
+            run_time: std::time::Duration::from_nanos(next()?),
+            wait_time: std::time::Duration::from_nanos(next()?),
+            timeslices: next()?,
+        })
+    }
+}
+
+/// Returns scheduler statistics of a thread of the current process. This can be used to
+/// verify whether a priority change actually reduced the time the thread spends waiting
+/// for a CPU.
+///
+/// Requires the kernel to be built with `CONFIG_SCHED_INFO`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let stats = thread_sched_stats(thread_native_tid()).unwrap();
+/// assert!(stats.run_time > std::time::Duration::from_nanos(0));
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn thread_sched_stats(tid: ThreadTid) -> Result<SchedStats, Error> {
+    std::fs::read_to_string(format!("/proc/self/task/{}/schedstat", tid))?
+        .parse()
+}
+
 impl TryFrom<u8> for ThreadPriority {
     type Error = &'static str;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        if let 0..=100 = value {
-            Ok(ThreadPriority::Crossplatform(ThreadPriorityValue(value)))
-        } else {
-            Err("The thread priority value must be in range of [0; 100].")
-        }
+        ThreadPriorityValue::new(value).map(ThreadPriority::Crossplatform)
     }
 }
 
@@ -511,6 +1686,16 @@ mod tests {
         assert!(thread_schedule_policy_param(thread_id).is_ok());
     }
 
+    #[test]
+    fn thread_schedule_policy_for_test() {
+        let thread_id = thread_native_id();
+
+        assert_eq!(
+            thread_schedule_policy_for(thread_id).unwrap(),
+            thread_schedule_policy_param(thread_id).unwrap().0
+        );
+    }
+
     #[test]
     fn set_thread_priority_test() {
         let thread_id = thread_native_id();
@@ -535,6 +1720,51 @@ mod tests {
         .is_ok());
     }
 
+    #[test]
+    fn join_handle_thread_ext_test() {
+        let (done_sender, done_receiver) = std::sync::mpsc::channel::<()>();
+        let join_handle = std::thread::spawn(move || {
+            let _ = done_receiver.recv();
+        });
+
+        assert!(join_handle.get_native_id() != thread_native_id());
+        assert!(join_handle.get_schedule_policy_param().is_ok());
+        done_sender.send(()).unwrap();
+        join_handle.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn scoped_scheduling_test() {
+        let thread_id = thread_native_id();
+        let (policy, params) = thread_schedule_policy_param(thread_id).unwrap();
+        {
+            let _scoped = ScopedScheduling::elevate(
+                ThreadPriority::Min,
+                ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Batch),
+            )
+            .unwrap();
+            assert_eq!(
+                thread_schedule_policy_for(thread_id).unwrap(),
+                ThreadSchedulePolicy::Normal(NormalThreadSchedulePolicy::Batch)
+            );
+        }
+        let (restored_policy, restored_params) = thread_schedule_policy_param(thread_id).unwrap();
+        assert_eq!(restored_policy, policy);
+        assert_eq!(restored_params.sched_priority, params.sched_priority);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn sched_stats_parse_test() {
+        let stats: SchedStats = "1000 2000 3\n".parse().unwrap();
+
+        assert_eq!(stats.run_time, std::time::Duration::from_nanos(1000));
+        assert_eq!(stats.wait_time, std::time::Duration::from_nanos(2000));
+        assert_eq!(stats.timeslices, 3);
+        assert!("1000 2000".parse::<SchedStats>().is_err());
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn set_deadline_policy() {
@@ -569,4 +1799,26 @@ mod tests {
             assert_eq!(sched_attr.sched_period, 100 * 10_u64.pow(6));
         }
     }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn task_dir_test() {
+        let pid = std::process::id() as ProcessId;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let thread = std::thread::spawn(move || sender.send(thread_native_tid()).unwrap());
+        let tid = receiver.recv().unwrap();
+        let task = TaskDir::open(pid, tid);
+        thread.join().unwrap();
+        // The kernel may release the task a little after the join.
+        if let Ok(task) = task {
+            while task.ensure_alive().is_ok() {
+                std::thread::yield_now();
+            }
+        }
+        assert!(matches!(TaskDir::open(pid, tid), Err(Error::OS(libc::ESRCH))));
+        assert!(TaskDir::open(pid, thread_native_tid())
+            .unwrap()
+            .ensure_alive()
+            .is_ok());
+    }
 }
@@ -0,0 +1,88 @@
+//! This module defines the thread control of the platforms without thread
+//! priorities, like `wasm32`.
+//!
+//! It provides the same cross-platform functions as the other platforms, so
+//! that the crates using them don't need to gate them: setting a priority fails
+//! with [`Error::Unsupported`], the getters report all threads alike.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//!
+//! assert!(matches!(
+//!     set_current_thread_priority(ThreadPriority::Max),
+//!     Err(Error::Unsupported(_))
+//! ));
+//! assert_eq!(thread_priority().unwrap(), ThreadPriority::Min);
+//! ```
+
+use crate::{Error, ThreadPriority};
+
+/// An alias type for a thread id. There are no native thread ids, all threads have the
+/// id `0`.
+pub type ThreadId = usize;
+
+const UNSUPPORTED: Error =
+    Error::Unsupported("Thread priorities are not supported on this platform");
+
+/// Sets the thread's priority, which always fails with [`Error::Unsupported`].
+pub fn set_thread_priority(_native: ThreadId, _priority: ThreadPriority) -> Result<(), Error> {
+    Err(UNSUPPORTED)
+}
+
+/// Sets current thread's priority.
+/// For more info read [`set_thread_priority`].
+pub fn set_current_thread_priority(priority: ThreadPriority) -> Result<(), Error> {
+    set_thread_priority(thread_native_id(), priority)
+}
+
+/// Checks whether the priority could be set, which always fails with
+/// [`Error::Unsupported`].
+pub fn validate(_priority: ThreadPriority) -> Result<(), Error> {
+    Err(UNSUPPORTED)
+}
+
+/// Get current thread's priority value. All the threads are scheduled alike, they are
+/// reported with [`ThreadPriority::Min`].
+pub fn thread_priority() -> Result<ThreadPriority, Error> {
+    Ok(ThreadPriority::Min)
+}
+
+/// Returns the priority of the thread, [`ThreadPriority::Min`] as for all the threads.
+pub fn get_thread_priority(_native: ThreadId) -> Result<ThreadPriority, Error> {
+    thread_priority()
+}
+
+/// Returns current thread id, which is always `0`.
+pub fn thread_native_id() -> ThreadId {
+    0
+}
+
+/// A helper trait for other threads to implement to be able to call methods
+/// on threads themselves.
+pub trait ThreadExt {
+    /// Gets the current thread's priority.
+    /// For more info read [`thread_priority`].
+    fn get_priority(&self) -> Result<ThreadPriority, Error> {
+        thread_priority()
+    }
+
+    /// Sets the current thread's priority.
+    /// For more info see [`ThreadPriority::set_for_current`].
+    fn set_priority(&self, priority: ThreadPriority) -> Result<(), Error> {
+        priority.set_for_current()
+    }
+
+    /// Returns the native thread id.
+    /// For more info read [`thread_native_id`].
+    fn get_native_id(&self) -> ThreadId {
+        thread_native_id()
+    }
+}
+
+/// Auto-implementation of this trait for the [`std::thread::Thread`].
+impl ThreadExt for std::thread::Thread {}
+
+/// Implementation of this trait for the [`std::thread::JoinHandle`].
+impl<T> ThreadExt for std::thread::JoinHandle<T> {}
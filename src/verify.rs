@@ -0,0 +1,199 @@
+//! This module defines the setters which read the scheduling back.
+//!
+//! Some systems report the success of a change they haven't fully applied:
+//! the containers may clamp the realtime priorities, or ignore the requests
+//! they don't allow. The verified setters read the effective scheduling back
+//! right after the change and fail with `VerifyError::Mismatch` when it isn't
+//! the requested one, so that the "succeeded but not applied" changes can be
+//! told apart from the applied ones.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::verify::*;
+//!
+//! match set_current_thread_priority_verified(ThreadPriority::Min) {
+//!     Ok(()) => {}
+//!     Err(VerifyError::Failed(e)) => println!("Couldn't set the priority: {}", e),
+//!     Err(e) => println!("The priority has been adjusted: {}", e),
+//! }
+//! ```
+
+#[cfg(windows)]
+use std::convert::TryFrom;
+
+#[cfg(any(unix, windows))]
+use crate::ThreadId;
+use crate::{Error, ThreadPriority};
+
+/// An error of the verified setters.
+#[derive(Debug, Copy, Clone)]
+pub enum VerifyError {
+    /// The change or the reading back of the scheduling has failed.
+    Failed(Error),
+    /// The change has succeeded but the effective scheduling is another one, the priority
+    /// being read back as the OS value.
+    #[cfg(unix)]
+    Mismatch {
+        /// The requested scheduling.
+        requested: crate::parse::Scheduling,
+        /// The effective scheduling.
+        effective: crate::parse::Scheduling,
+    },
+    /// The change has succeeded but the effective priority is another one.
+    #[cfg(windows)]
+    Mismatch {
+        /// The requested priority.
+        requested: crate::WinAPIThreadPriority,
+        /// The effective priority.
+        effective: crate::WinAPIThreadPriority,
+    },
+}
+
+impl From<Error> for VerifyError {
+    fn from(error: Error) -> Self {
+        VerifyError::Failed(error)
+    }
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::Failed(e) => e.fmt(f),
+            #[cfg(unix)]
+            VerifyError::Mismatch {
+                requested,
+                effective,
+            } => write!(
+                f,
+                "The scheduling {} has been requested, but it is {}",
+                requested, effective
+            ),
+            #[cfg(windows)]
+            VerifyError::Mismatch {
+                requested,
+                effective,
+            } => write!(
+                f,
+                "The priority {:?} has been requested, but it is {:?}",
+                requested, effective
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Sets the priority and the policy of the thread, then reads them back.
+///
+/// * May require privileges
+///
+/// * On Linux, the deadline parameters are only read back for the current thread, only
+///   the policy is checked for the other ones.
+///
+/// For more info read [`crate::set_thread_priority_and_policy`].
+#[cfg(unix)]
+pub fn set_thread_priority_and_policy_verified(
+    native: ThreadId,
+    priority: ThreadPriority,
+    policy: crate::ThreadSchedulePolicy,
+) -> Result<(), VerifyError> {
+    crate::set_thread_priority_and_policy(native, priority, policy)?;
+    let (effective_policy, params) = crate::thread_schedule_policy_param(native)?;
+    let effective_priority = match priority {
+        #[cfg(target_os = "linux")]
+        ThreadPriority::Deadline(..) => match crate::unix::thread_tid_of(native) {
+            Ok(tid) => match crate::unix::deadline_params_for_tid(tid)? {
+                Some((runtime, deadline, period)) => {
+                    ThreadPriority::Deadline(runtime, deadline, period)
+                }
+                None => {
+                    ThreadPriority::Os(crate::ThreadPriorityOsValue(params.sched_priority as u32))
+                }
+            },
+            // The parameters of the other threads can't be read.
+            Err(_) => priority,
+        },
+        _ => ThreadPriority::Os(crate::ThreadPriorityOsValue(params.sched_priority as u32)),
+    };
+    let requested = crate::parse::Scheduling { policy, priority };
+    let effective = crate::parse::Scheduling {
+        policy: effective_policy,
+        priority: effective_priority,
+    };
+    let same_priority = match (priority, effective_priority) {
+        #[cfg(target_os = "linux")]
+        (ThreadPriority::Deadline(..), _) => priority == effective_priority,
+        _ => priority.to_posix(policy).ok() == effective_priority.to_posix(effective_policy).ok(),
+    };
+    if effective_policy.to_posix() == policy.to_posix() && same_priority {
+        Ok(())
+    } else {
+        Err(VerifyError::Mismatch {
+            requested,
+            effective,
+        })
+    }
+}
+
+/// Sets the priority of the thread, then reads it back.
+///
+/// * May require privileges
+///
+/// For more info read [`crate::set_thread_priority`].
+#[cfg(windows)]
+pub fn set_thread_priority_verified(
+    native: ThreadId,
+    priority: ThreadPriority,
+) -> Result<(), VerifyError> {
+    let requested = crate::WinAPIThreadPriority::try_from(priority)?;
+    crate::set_thread_priority(native, priority)?;
+    let effective = crate::WinAPIThreadPriority::try_from(crate::get_thread_priority(native)?)?;
+    if effective == requested {
+        Ok(())
+    } else {
+        Err(VerifyError::Mismatch {
+            requested,
+            effective,
+        })
+    }
+}
+
+/// Sets the priority of the current thread, with the normal policy on unix, then reads it
+/// back.
+///
+/// * May require privileges
+///
+/// For more info read [`crate::set_current_thread_priority`].
+pub fn set_current_thread_priority_verified(priority: ThreadPriority) -> Result<(), VerifyError> {
+    #[cfg(unix)]
+    return set_thread_priority_and_policy_verified(
+        crate::thread_native_id(),
+        priority,
+        crate::ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Normal),
+    );
+    #[cfg(windows)]
+    return set_thread_priority_verified(crate::thread_native_id(), priority);
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = priority;
+        Err(VerifyError::Failed(Error::Unsupported(
+            "Verifying the priority is not supported on this platform",
+        )))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_current_thread_priority_verified_test() {
+        assert!(set_current_thread_priority_verified(ThreadPriority::Min).is_ok());
+        assert!(matches!(
+            set_current_thread_priority_verified(ThreadPriority::Os(5u32.into())),
+            Err(VerifyError::Failed(_))
+        ));
+    }
+}
@@ -0,0 +1,112 @@
+//! This module defines the VxWorks task priority control.
+//!
+//! The VxWorks threads are tasks, scheduled preemptively by a native priority
+//! from `0`, the highest, to `255`, the lowest, which is the reverse of the POSIX
+//! order. This module provides the native priorities and maps the crate's
+//! priorities onto them.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::*;
+//! use thread_priority::vxworks::*;
+//!
+//! assert!(set_current_task_priority(ThreadPriority::Min).is_ok());
+//! assert_eq!(current_task_priority().unwrap(), TASK_PRIORITY_LOWEST);
+//! ```
+
+use std::convert::TryFrom;
+
+use crate::unix::errno;
+use crate::{Error, ThreadPriority, ThreadPriorityValue};
+
+extern "C" {
+    fn taskPrioritySet(tid: libc::TASK_ID, new_priority: libc::c_int) -> libc::c_int;
+    fn taskPriorityGet(tid: libc::TASK_ID, priority: *mut libc::c_int) -> libc::c_int;
+}
+
+/// An alias type for a task id.
+pub type TaskId = libc::TASK_ID;
+
+/// The highest native task priority.
+pub const TASK_PRIORITY_HIGHEST: u8 = 0;
+/// The lowest native task priority.
+pub const TASK_PRIORITY_LOWEST: u8 = 255;
+
+/// Returns the id of the current task.
+pub fn task_id_self() -> TaskId {
+    unsafe { libc::taskIdSelf() }
+}
+
+/// Maps the priority onto a native task priority. The cross-platform values are
+/// spread over the whole native range, the OS values are the native priorities.
+pub fn task_priority_from(priority: ThreadPriority) -> Result<u8, Error> {
+    Ok(match priority {
+        ThreadPriority::Min => TASK_PRIORITY_LOWEST,
+        ThreadPriority::Crossplatform(ThreadPriorityValue(p)) => {
+            if p > 99 {
+                return Err(Error::Priority("The value is out of range [0; 99]."));
+            }
+            (u32::from(TASK_PRIORITY_LOWEST) - u32::from(p) * u32::from(TASK_PRIORITY_LOWEST) / 99)
+                as u8
+        }
+        ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => {
+            u8::try_from(p).map_err(|_| Error::Priority("The value is out of range [0; 255]."))?
+        }
+        ThreadPriority::Max => TASK_PRIORITY_HIGHEST,
+    })
+}
+
+/// Sets the native priority of the task.
+///
+/// Based on `taskPrioritySet`.
+pub fn set_task_priority(tid: TaskId, priority: ThreadPriority) -> Result<(), Error> {
+    let priority = task_priority_from(priority)?;
+    unsafe {
+        match taskPrioritySet(tid, libc::c_int::from(priority)) {
+            0 => Ok(()),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Sets the native priority of the current task.
+/// For more info read [`set_task_priority`].
+pub fn set_current_task_priority(priority: ThreadPriority) -> Result<(), Error> {
+    set_task_priority(task_id_self(), priority)
+}
+
+/// Returns the native priority of the task.
+///
+/// Based on `taskPriorityGet`.
+pub fn task_priority(tid: TaskId) -> Result<u8, Error> {
+    let mut priority = 0;
+    unsafe {
+        match taskPriorityGet(tid, &mut priority) {
+            0 => u8::try_from(priority).map_err(|_| Error::Ffi("Invalid task priority")),
+            _ => Err(Error::OS(errno())),
+        }
+    }
+}
+
+/// Returns the native priority of the current task.
+/// For more info read [`task_priority`].
+pub fn current_task_priority() -> Result<u8, Error> {
+    task_priority(task_id_self())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_priority_from_test() {
+        let value = |p| ThreadPriority::Crossplatform(ThreadPriorityValue(p));
+        assert_eq!(task_priority_from(value(0)).unwrap(), TASK_PRIORITY_LOWEST);
+        assert_eq!(
+            task_priority_from(value(99)).unwrap(),
+            TASK_PRIORITY_HIGHEST
+        );
+        assert!(task_priority_from(value(100)).is_err());
+    }
+}
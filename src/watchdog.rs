@@ -0,0 +1,365 @@
+//! This module defines the watchdog of the realtime threads.
+//!
+//! A realtime thread stuck in a loop never gives the CPU up, and freezes the
+//! threads of the lower priorities, possibly the whole machine. Linux can cap
+//! the CPU time of the realtime threads with `RLIMIT_RTTIME`, the other
+//! systems can't. A [`Watchdog`] checks the CPU time of the watched threads
+//! at every interval, and demotes the ones which have used more than their
+//! budget to the normal scheduling, or only reports them.
+//!
+//! The watchdog thread tries to run under the FIFO realtime policy at its
+//! highest priority, so that the threads it watches can't starve it.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use thread_priority::watchdog::*;
+//! use std::time::Duration;
+//!
+//! # #[cfg(any(target_os = "linux", target_os = "android", windows, target_vendor = "apple"))] {
+//! let watchdog = Watchdog::start(
+//!     WatchdogConfig::new(Duration::from_millis(100), Duration::from_millis(90))
+//!         .on_overrun(|overrun| println!("A thread has overrun: {:?}", overrun)),
+//! )
+//! .unwrap();
+//! let guard = watchdog.watch_current_thread().unwrap();
+//! // The realtime work.
+//! drop(guard);
+//! # }
+//! ```
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::registry::NativeId;
+use crate::{Error, ThreadBuilder, ThreadPriority};
+
+/// What the watchdog does with the threads which have overrun their budget.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WatchdogAction {
+    /// The thread is demoted to the lowest priority of the normal scheduling, and the
+    /// overrun is reported.
+    Demote,
+    /// The overrun is only reported.
+    Notify,
+}
+
+/// A thread which has used more than its budget of CPU time.
+#[derive(Debug, Clone)]
+pub struct Overrun {
+    /// The name of the thread.
+    pub name: Option<String>,
+    /// The CPU time the thread has used during the interval.
+    pub cpu_time: Duration,
+    /// The time elapsed since the previous check.
+    pub elapsed: Duration,
+    /// The result of the demotion, `None` if the thread hasn't been demoted.
+    pub demotion: Option<Result<(), Error>>,
+}
+
+type OverrunCallback = Arc<dyn Fn(&Overrun) + Send + Sync>;
+
+/// The configuration of a [`Watchdog`].
+#[derive(Clone)]
+pub struct WatchdogConfig {
+    interval: Duration,
+    budget: Duration,
+    action: WatchdogAction,
+    on_overrun: Option<OverrunCallback>,
+}
+
+impl std::fmt::Debug for WatchdogConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchdogConfig")
+            .field("interval", &self.interval)
+            .field("budget", &self.budget)
+            .field("action", &self.action)
+            .finish()
+    }
+}
+
+impl WatchdogConfig {
+    /// Creates the configuration checking the threads at every interval, each thread
+    /// being allowed the budget of CPU time per interval. The threads overrunning it are
+    /// demoted.
+    pub fn new(interval: Duration, budget: Duration) -> Self {
+        WatchdogConfig {
+            interval,
+            budget,
+            action: WatchdogAction::Demote,
+            on_overrun: None,
+        }
+    }
+
+    /// What to do with the threads overrunning their budget.
+    pub fn action(mut self, value: WatchdogAction) -> Self {
+        self.action = value;
+        self
+    }
+
+    /// Calls the closure, on the watchdog thread, for every overrun. Each thread is
+    /// reported once until it is watched again.
+    pub fn on_overrun<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Overrun) + Send + Sync + 'static,
+    {
+        self.on_overrun = Some(Arc::new(f));
+        self
+    }
+}
+
+struct Entry {
+    id: u64,
+    name: Option<String>,
+    native: NativeId,
+    cpu_time: Option<Duration>,
+    overrun: bool,
+}
+
+impl std::fmt::Debug for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entry")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// The watchdog thread, stopped when it is dropped.
+#[derive(Debug)]
+pub struct Watchdog {
+    entries: Arc<Mutex<Vec<Entry>>>,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+static NEXT_ENTRY_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // The entries of the current thread, unwatched at its exit even if their guards have
+    // been leaked, as the watchdog mustn't address the exited threads.
+    static WATCHED: RefCell<Vec<Unwatch>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Unwatches the thread of the entry when it is dropped.
+struct Unwatch {
+    id: u64,
+    entries: Arc<Mutex<Vec<Entry>>>,
+}
+
+impl Drop for Unwatch {
+    fn drop(&mut self) {
+        lock(&self.entries).retain(|entry| entry.id != self.id);
+    }
+}
+
+fn lock(entries: &Mutex<Vec<Entry>>) -> std::sync::MutexGuard<'_, Vec<Entry>> {
+    // The entries are always left consistent, a panic can't poison them.
+    entries.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Watchdog {
+    /// Starts the watchdog thread.
+    ///
+    /// * May require privileges for the watchdog thread to be realtime, it runs with the
+    ///   normal scheduling otherwise.
+    ///
+    /// The platforms whose CPU time of the threads is unknown aren't supported, see
+    /// [`crate::cpu_time::thread_cpu_time`].
+    pub fn start(config: WatchdogConfig) -> Result<Self, Error> {
+        crate::cpu_time::current_thread_cpu_time()?;
+        if config.interval.is_zero() {
+            return Err(Error::Priority("The interval must be positive."));
+        }
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let (stop, stopped) = mpsc::channel();
+        let thread = watchdog_builder()
+            .name("rt-watchdog")
+            .spawn({
+                let entries = entries.clone();
+                move |result| {
+                    if let Err(e) = result {
+                        log::warn!("The watchdog thread isn't realtime: {:?}", e);
+                    }
+                    run(&config, &entries, &stopped);
+                }
+            })?;
+        Ok(Watchdog {
+            entries,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Watches the current thread until the returned guard is dropped.
+    pub fn watch_current_thread(&self) -> Result<WatchGuard, Error> {
+        let id = NEXT_ENTRY_ID.fetch_add(1, Ordering::Relaxed);
+        let native = NativeId::current()?;
+        let cpu_time = crate::cpu_time::thread_cpu_time(native.get()).ok();
+        lock(&self.entries).push(Entry {
+            id,
+            name: std::thread::current().name().map(str::to_owned),
+            native,
+            cpu_time,
+            overrun: false,
+        });
+        let unwatch = Unwatch {
+            id,
+            entries: self.entries.clone(),
+        };
+        WATCHED.with(|watched| watched.borrow_mut().push(unwatch));
+        Ok(WatchGuard {
+            id,
+            _not_send: std::marker::PhantomData,
+        })
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        // The watchdog thread stops once the sender is dropped.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A watched thread, unwatched when the guard is dropped, or when the thread exits. The
+/// guard must be dropped on the thread it watches, so it can't be sent to another thread.
+#[derive(Debug)]
+pub struct WatchGuard {
+    id: u64,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        // Dropping the unwatch removes the entry. The thread-local storage is gone when the
+        // guard is dropped by the destructor of another one, and the entry with it.
+        let _ = WATCHED.try_with(|watched| {
+            watched
+                .borrow_mut()
+                .retain(|unwatch| unwatch.id != self.id)
+        });
+    }
+}
+
+#[cfg(unix)]
+fn watchdog_builder() -> ThreadBuilder {
+    ThreadBuilder::default()
+        .policy(crate::ThreadSchedulePolicy::Realtime(
+            crate::RealtimeThreadSchedulePolicy::Fifo,
+        ))
+        .priority(ThreadPriority::Max)
+}
+
+#[cfg(not(unix))]
+fn watchdog_builder() -> ThreadBuilder {
+    ThreadBuilder::default().priority(ThreadPriority::Max)
+}
+
+#[cfg(unix)]
+fn demote(native: crate::ThreadId) -> Result<(), Error> {
+    crate::set_thread_priority_and_policy(
+        native,
+        ThreadPriority::Min,
+        crate::ThreadSchedulePolicy::Normal(crate::NormalThreadSchedulePolicy::Normal),
+    )
+}
+
+#[cfg(not(unix))]
+fn demote(native: crate::ThreadId) -> Result<(), Error> {
+    crate::set_thread_priority(native, ThreadPriority::Min)
+}
+
+fn run(config: &WatchdogConfig, entries: &Mutex<Vec<Entry>>, stopped: &mpsc::Receiver<()>) {
+    let mut checked = Instant::now();
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(config.interval) {
+        let now = Instant::now();
+        let elapsed = now - checked;
+        checked = now;
+        // The budget is scaled when the watchdog has been late.
+        let budget = config
+            .budget
+            .mul_f64(elapsed.as_secs_f64() / config.interval.as_secs_f64());
+        let mut overruns = Vec::new();
+        for entry in lock(entries).iter_mut() {
+            let cpu_time = match crate::cpu_time::thread_cpu_time(entry.native.get()) {
+                Ok(cpu_time) => cpu_time,
+                Err(_) => continue,
+            };
+            let used = entry
+                .cpu_time
+                .map_or(Duration::ZERO, |previous| cpu_time.saturating_sub(previous));
+            entry.cpu_time = Some(cpu_time);
+            if entry.overrun || used <= budget {
+                continue;
+            }
+            entry.overrun = true;
+            overruns.push(Overrun {
+                name: entry.name.clone(),
+                cpu_time: used,
+                elapsed,
+                demotion: match config.action {
+                    WatchdogAction::Demote => Some(demote(entry.native.get())),
+                    WatchdogAction::Notify => None,
+                },
+            });
+        }
+        for overrun in &overruns {
+            log::warn!("A watched thread has overrun its CPU budget: {:?}", overrun);
+            if let Some(on_overrun) = &config.on_overrun {
+                on_overrun(overrun);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdog_overrun_test() {
+        let (sender, receiver) = mpsc::channel();
+        let watchdog = Watchdog::start(
+            WatchdogConfig::new(Duration::from_millis(20), Duration::ZERO)
+                .action(WatchdogAction::Notify)
+                .on_overrun(move |overrun| {
+                    let _ = sender.send(overrun.name.clone());
+                }),
+        )
+        .unwrap();
+        let watchdog = Arc::new(watchdog);
+        let thread = std::thread::Builder::new()
+            .name("busy".to_owned())
+            .spawn({
+                let watchdog = watchdog.clone();
+                move || {
+                    let _guard = watchdog.watch_current_thread().unwrap();
+                    let start = Instant::now();
+                    while start.elapsed() < Duration::from_millis(100) {
+                        std::hint::spin_loop();
+                    }
+                }
+            })
+            .unwrap();
+        thread.join().unwrap();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            Some("busy".to_owned())
+        );
+
+        // The leaked guards are unwatched when their thread exits.
+        std::thread::spawn({
+            let watchdog = watchdog.clone();
+            move || std::mem::forget(watchdog.watch_current_thread().unwrap())
+        })
+        .join()
+        .unwrap();
+        assert!(lock(&watchdog.entries).is_empty());
+    }
+}
@@ -5,14 +5,24 @@
 //! better control over those.
 
 use winapi::ctypes::c_int;
-use winapi::shared::minwindef::DWORD;
+use winapi::shared::minwindef::{DWORD, LPVOID};
+use winapi::um::avrt::{
+    AvRevertMmThreadCharacteristics, AvSetMmThreadCharacteristicsW, AvSetMmThreadPriority,
+};
 use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::mmsystem::{TIMECAPS, TIMERR_NOERROR};
 use winapi::um::processthreadsapi::{
-    GetCurrentThread, GetThreadPriority, SetThreadIdealProcessor, SetThreadPriority,
-    SetThreadPriorityBoost,
+    GetCurrentProcess, GetCurrentThread, GetPriorityClass, GetProcessIdOfThread, GetThreadId,
+    GetThreadIdealProcessorEx, GetThreadInformation, GetThreadPriority, GetThreadPriorityBoost,
+    OpenThread, SetPriorityClass, SetThreadIdealProcessor, SetThreadIdealProcessorEx,
+    SetThreadInformation, SetThreadPriority, SetThreadPriorityBoost, ThreadMemoryPriority,
+    THREAD_INFORMATION_CLASS,
 };
+use winapi::um::realtimeapiset::QueryThreadCycleTime;
+use winapi::um::timeapi::{timeBeginPeriod, timeEndPeriod, timeGetDevCaps};
 use winapi::um::winbase;
-use winapi::um::winnt::HANDLE;
+use winapi::um::winnt::{HANDLE, PROCESSOR_NUMBER};
 
 use crate::{Error, ThreadPriority};
 
@@ -23,6 +33,13 @@ pub type IdealProcessor = DWORD;
 /// An alias type for a thread id.
 pub type ThreadId = HANDLE;
 
+/// An alias type for a system-wide thread id, as returned by `GetCurrentThreadId`.
+/// Unlike the [`ThreadId`], it can be used to address threads of other processes.
+pub type ThreadTid = DWORD;
+
+/// An alias type for a process id.
+pub type ProcessId = DWORD;
+
 /// The WinAPI priority representation. Check out MSDN for more info:
 /// <https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setthreadpriority>
 #[repr(u32)]
@@ -66,6 +83,33 @@ pub enum WinAPIThreadPriority {
     /// NORMAL_PRIORITY_CLASS, ABOVE_NORMAL_PRIORITY_CLASS, or HIGH_PRIORITY_CLASS
     /// processes, and a base priority of 31 for REALTIME_PRIORITY_CLASS processes.
     TimeCritical = winbase::THREAD_PRIORITY_TIME_CRITICAL,
+    /// Priority 7 points below the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimeMinus7 = -7i32 as u32,
+    /// Priority 6 points below the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimeMinus6 = -6i32 as u32,
+    /// Priority 5 points below the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimeMinus5 = -5i32 as u32,
+    /// Priority 4 points below the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimeMinus4 = -4i32 as u32,
+    /// Priority 3 points below the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimeMinus3 = -3i32 as u32,
+    /// Priority 3 points above the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimePlus3 = 3,
+    /// Priority 4 points above the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimePlus4 = 4,
+    /// Priority 5 points above the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimePlus5 = 5,
+    /// Priority 6 points above the priority class.
+    /// Only valid for the threads of REALTIME_PRIORITY_CLASS processes.
+    RealtimePlus6 = 6,
 }
 
 impl std::convert::TryFrom<ThreadPriority> for WinAPIThreadPriority {
@@ -77,29 +121,17 @@ impl std::convert::TryFrom<ThreadPriority> for WinAPIThreadPriority {
             ThreadPriority::Crossplatform(crate::ThreadPriorityValue(p)) => match p {
                 0 => WinAPIThreadPriority::Idle,
                 1..=19 => WinAPIThreadPriority::Lowest,
-                21..=39 => WinAPIThreadPriority::BelowNormal,
-                41..=59 => WinAPIThreadPriority::Normal,
-                61..=79 => WinAPIThreadPriority::AboveNormal,
-                81..=98 => WinAPIThreadPriority::Highest,
+                20..=39 => WinAPIThreadPriority::BelowNormal,
+                40..=59 => WinAPIThreadPriority::Normal,
+                60..=79 => WinAPIThreadPriority::AboveNormal,
+                80..=98 => WinAPIThreadPriority::Highest,
                 99 => WinAPIThreadPriority::TimeCritical,
                 _ => return Err(Error::Priority("The value is out of range [0; 99].")),
             },
-            ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => match p {
-                winbase::THREAD_MODE_BACKGROUND_BEGIN => WinAPIThreadPriority::BackgroundModeBegin,
-                winbase::THREAD_MODE_BACKGROUND_END => WinAPIThreadPriority::BackgroundModeEnd,
-                winbase::THREAD_PRIORITY_ABOVE_NORMAL => WinAPIThreadPriority::AboveNormal,
-                winbase::THREAD_PRIORITY_BELOW_NORMAL => WinAPIThreadPriority::BelowNormal,
-                winbase::THREAD_PRIORITY_HIGHEST => WinAPIThreadPriority::Highest,
-                winbase::THREAD_PRIORITY_IDLE => WinAPIThreadPriority::Idle,
-                winbase::THREAD_PRIORITY_LOWEST => WinAPIThreadPriority::Lowest,
-                winbase::THREAD_PRIORITY_NORMAL => WinAPIThreadPriority::Normal,
-                winbase::THREAD_PRIORITY_TIME_CRITICAL => WinAPIThreadPriority::TimeCritical,
-                _ => {
-                    return Err(Error::Priority(
-                        "The value is out of range of allowed values.",
-                    ))
-                }
-            },
+            ThreadPriority::Os(crate::ThreadPriorityOsValue(p)) => {
+                WinAPIThreadPriority::try_from(p)
+                    .map_err(|_| Error::Priority("The value is out of range of allowed values."))?
+            }
             ThreadPriority::Max => WinAPIThreadPriority::Highest,
         })
     }
@@ -119,7 +151,19 @@ impl std::convert::TryFrom<DWORD> for WinAPIThreadPriority {
             winbase::THREAD_PRIORITY_LOWEST => WinAPIThreadPriority::Lowest,
             winbase::THREAD_PRIORITY_NORMAL => WinAPIThreadPriority::Normal,
             winbase::THREAD_PRIORITY_TIME_CRITICAL => WinAPIThreadPriority::TimeCritical,
-            _ => return Err(Error::Priority("Priority couldn't be parsed")),
+            // The levels accepted in the REALTIME_PRIORITY_CLASS processes only.
+            _ => match priority as i32 {
+                -7 => WinAPIThreadPriority::RealtimeMinus7,
+                -6 => WinAPIThreadPriority::RealtimeMinus6,
+                -5 => WinAPIThreadPriority::RealtimeMinus5,
+                -4 => WinAPIThreadPriority::RealtimeMinus4,
+                -3 => WinAPIThreadPriority::RealtimeMinus3,
+                3 => WinAPIThreadPriority::RealtimePlus3,
+                4 => WinAPIThreadPriority::RealtimePlus4,
+                5 => WinAPIThreadPriority::RealtimePlus5,
+                6 => WinAPIThreadPriority::RealtimePlus6,
+                _ => return Err(Error::Priority("Priority couldn't be parsed")),
+            },
         })
     }
 }
@@ -130,6 +174,20 @@ impl From<WinAPIThreadPriority> for crate::ThreadPriorityOsValue {
     }
 }
 
+impl From<WinAPIThreadPriority> for ThreadPriority {
+    fn from(p: WinAPIThreadPriority) -> Self {
+        ThreadPriority::Os(p.into())
+    }
+}
+
+impl std::convert::TryFrom<crate::ThreadPriorityOsValue> for WinAPIThreadPriority {
+    type Error = crate::Error;
+
+    fn try_from(value: crate::ThreadPriorityOsValue) -> Result<Self, Self::Error> {
+        WinAPIThreadPriority::try_from(value.0)
+    }
+}
+
 /// Sets thread's priority and schedule policy.
 ///
 /// * May require privileges
@@ -173,6 +231,25 @@ pub fn set_thread_priority(native: ThreadId, priority: ThreadPriority) -> Result
 pub fn set_winapi_thread_priority(
     native: ThreadId,
     priority: WinAPIThreadPriority,
+) -> Result<(), Error> {
+    traced!(
+        DEBUG,
+        crate::hooks::notified(
+            crate::hooks::Change::Priority {
+                thread: native,
+                priority
+            },
+            || set_native_winapi_thread_priority(native, priority)
+        ),
+        "the priority",
+        thread = ?native,
+        ?priority
+    )
+}
+
+fn set_native_winapi_thread_priority(
+    native: ThreadId,
+    priority: WinAPIThreadPriority,
 ) -> Result<(), Error> {
     unsafe {
         if SetThreadPriority(native, priority as c_int) != 0 {
@@ -183,6 +260,211 @@ pub fn set_winapi_thread_priority(
     }
 }
 
+/// An owned handle of a thread, which is closed when dropped. It is either opened by
+/// the system-wide thread id of a thread of any process, or duplicated from a handle
+/// obtained elsewhere.
+///
+/// When opened by id, the thread is opened with `THREAD_SET_INFORMATION | THREAD_QUERY_INFORMATION` access,
+/// which is what the priority related functions of this module need, so they can be
+/// called with [`OwnedThreadHandle::as_thread_id`].
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let tid = unsafe { winapi::um::processthreadsapi::GetCurrentThreadId() };
+/// let handle = OwnedThreadHandle::open(std::process::id(), tid).unwrap();
+/// assert!(unsafe { set_thread_priority_boost(handle.as_thread_id(), true) }.is_ok());
+/// ```
+#[derive(Debug)]
+pub struct OwnedThreadHandle {
+    handle: HANDLE,
+}
+
+impl OwnedThreadHandle {
+    /// Opens the thread `tid`, checking that it belongs to the process `pid`, returning
+    /// `ERROR_INVALID_PARAMETER` otherwise, so that a recycled thread id can't make
+    /// the caller change an unrelated thread.
+    ///
+    /// * May require privileges
+    ///
+    /// If there's an error, a result of
+    /// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+    pub fn open(pid: ProcessId, tid: ThreadTid) -> Result<Self, Error> {
+        use winapi::um::winnt::{THREAD_QUERY_INFORMATION, THREAD_SET_INFORMATION};
+
+        let handle =
+            unsafe { OpenThread(THREAD_SET_INFORMATION | THREAD_QUERY_INFORMATION, 0, tid) };
+        if handle.is_null() {
+            return Err(Error::OS(unsafe { GetLastError() } as i32));
+        }
+        let handle = OwnedThreadHandle { handle };
+        if unsafe { GetProcessIdOfThread(handle.handle) } == pid {
+            Ok(handle)
+        } else {
+            Err(Error::OS(
+                winapi::shared::winerror::ERROR_INVALID_PARAMETER as i32,
+            ))
+        }
+    }
+
+    /// Duplicates a thread handle obtained elsewhere, for example from FFI or
+    /// `CreateRemoteThread`, with the same access. The handle stays owned by the caller,
+    /// the duplicate is owned by the returned value, so it stays valid regardless of
+    /// when the original one is closed.
+    ///
+    /// # Safety
+    ///
+    /// The handle must be a valid thread handle of the current process for the
+    /// duration of the call.
+    pub unsafe fn duplicate(handle: HANDLE) -> Result<Self, Error> {
+        use winapi::um::handleapi::DuplicateHandle;
+        use winapi::um::processthreadsapi::GetCurrentProcess;
+        use winapi::um::winnt::DUPLICATE_SAME_ACCESS;
+
+        let mut duplicate = std::ptr::null_mut();
+        if DuplicateHandle(
+            GetCurrentProcess(),
+            handle,
+            GetCurrentProcess(),
+            &mut duplicate,
+            0,
+            0,
+            DUPLICATE_SAME_ACCESS,
+        ) != 0
+        {
+            Ok(OwnedThreadHandle { handle: duplicate })
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+
+    /// Returns a real handle of the current thread. Unlike the pseudo handle returned
+    /// by [`thread_native_id`], it addresses the current thread even when used from
+    /// other threads.
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// let handle = OwnedThreadHandle::current().unwrap();
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| {
+    ///         assert!(set_thread_priority(handle.as_thread_id(), ThreadPriority::Min).is_ok());
+    ///     });
+    /// });
+    /// ```
+    pub fn current() -> Result<Self, Error> {
+        unsafe { Self::duplicate(thread_native_id()) }
+    }
+
+    /// Duplicates the handle of a standard library's thread, like a
+    /// [`std::thread::JoinHandle`]. For more info read [`OwnedThreadHandle::duplicate`].
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// let thread = std::thread::spawn(|| std::thread::sleep(std::time::Duration::from_millis(10)));
+    /// let handle = OwnedThreadHandle::duplicate_from(&thread).unwrap();
+    /// assert!(set_thread_priority(handle.as_thread_id(), ThreadPriority::Min).is_ok());
+    /// thread.join().unwrap();
+    /// ```
+    pub fn duplicate_from<T: std::os::windows::io::AsRawHandle>(thread: &T) -> Result<Self, Error> {
+        unsafe { Self::duplicate(thread.as_raw_handle() as HANDLE) }
+    }
+
+    /// Returns the handle, valid as long as this value is alive.
+    pub fn as_thread_id(&self) -> ThreadId {
+        self.handle
+    }
+}
+
+impl Drop for OwnedThreadHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+// Thread handles can be used from any thread of the process.
+unsafe impl Send for OwnedThreadHandle {}
+unsafe impl Sync for OwnedThreadHandle {}
+
+impl std::os::windows::io::AsRawHandle for OwnedThreadHandle {
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        self.handle as std::os::windows::io::RawHandle
+    }
+}
+
+impl std::os::windows::io::FromRawHandle for OwnedThreadHandle {
+    /// Takes the ownership of the thread handle, which is closed when the returned value
+    /// is dropped.
+    unsafe fn from_raw_handle(handle: std::os::windows::io::RawHandle) -> Self {
+        OwnedThreadHandle {
+            handle: handle as HANDLE,
+        }
+    }
+}
+
+impl std::os::windows::io::IntoRawHandle for OwnedThreadHandle {
+    /// Releases the ownership of the thread handle, which the caller must close.
+    fn into_raw_handle(self) -> std::os::windows::io::RawHandle {
+        let handle = self.handle;
+        std::mem::forget(self);
+        handle as std::os::windows::io::RawHandle
+    }
+}
+
+/// Sets the priority of a thread of any process, addressed by its system-wide thread id.
+/// For more info on how the thread is opened read [`OwnedThreadHandle::open`].
+///
+/// * May require privileges
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let tid = unsafe { winapi::um::processthreadsapi::GetCurrentThreadId() };
+/// assert!(set_thread_priority_for_tid(std::process::id(), tid, ThreadPriority::Min).is_ok());
+/// ```
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+pub fn set_thread_priority_for_tid(
+    pid: ProcessId,
+    tid: ThreadTid,
+    priority: ThreadPriority,
+) -> Result<(), Error> {
+    let handle = OwnedThreadHandle::open(pid, tid)?;
+    set_thread_priority(handle.as_thread_id(), priority)
+}
+
+/// Returns the priority of a thread of any process, addressed by its system-wide thread id.
+/// For more info on how the thread is opened read [`OwnedThreadHandle::open`], and on
+/// the returned value read [`thread_priority`].
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let tid = unsafe { winapi::um::processthreadsapi::GetCurrentThreadId() };
+/// assert!(set_thread_priority_for_tid(std::process::id(), tid, ThreadPriority::Min).is_ok());
+/// assert_eq!(
+///     thread_priority_for_tid(std::process::id(), tid).unwrap(),
+///     ThreadPriority::Os(WinAPIThreadPriority::Lowest.into()),
+/// );
+/// ```
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+pub fn thread_priority_for_tid(pid: ProcessId, tid: ThreadTid) -> Result<ThreadPriority, Error> {
+    let handle = OwnedThreadHandle::open(pid, tid)?;
+    get_thread_priority(handle.as_thread_id())
+}
+
 /// Set current thread's priority.
 ///
 /// * May require privileges
@@ -200,34 +482,71 @@ pub fn set_winapi_thread_priority(
 ///
 /// If there's an error, a result of
 /// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
-
 pub fn set_current_thread_priority(priority: ThreadPriority) -> Result<(), Error> {
     let thread_id = thread_native_id();
     set_thread_priority(thread_id, priority)
 }
 
+/// Checks whether the priority could be set, without touching any thread, so that the
+/// configuration can be validated at startup, before the threads are spawned. All the
+/// thread priorities are available without privileges.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(validate(ThreadPriority::Max).is_ok());
+/// ```
+pub fn validate(priority: ThreadPriority) -> Result<(), Error> {
+    <WinAPIThreadPriority as std::convert::TryFrom<ThreadPriority>>::try_from(priority).map(|_| ())
+}
+
 /// Get current thread's priority value.
 ///
-/// Returns current thread id, which is the current OS's native handle.
-/// It may or may not be equal or even related to rust's thread id,
-/// there is absolutely no guarantee for that.
+/// The value returned by `GetThreadPriority` is always kept as is in the
+/// [`ThreadPriority::Os`] variant, even when it is none of the documented levels,
+/// and can be interpreted by converting it to a [`WinAPIThreadPriority`]. Note that
+/// a thread in the background mode reports its lowered priority, not the
+/// `THREAD_MODE_BACKGROUND_BEGIN` value.
 ///
 /// # Usage
 ///
 /// ```rust
-/// use thread_priority::thread_priority;
+/// use std::convert::TryFrom;
+/// use thread_priority::*;
 ///
-/// assert!(thread_priority().is_ok());
+/// assert!(set_current_thread_priority(ThreadPriority::Os(WinAPIThreadPriority::Lowest.into())).is_ok());
+/// let priority = thread_priority().unwrap();
+/// assert_eq!(priority, ThreadPriority::Os(WinAPIThreadPriority::Lowest.into()));
+/// assert_eq!(WinAPIThreadPriority::try_from(priority).unwrap(), WinAPIThreadPriority::Lowest);
 /// ```
 pub fn thread_priority() -> Result<ThreadPriority, Error> {
-    use std::convert::TryFrom;
+    get_thread_priority(thread_native_id())
+}
 
+/// Returns the priority of the thread.
+/// For more info read [`thread_priority`].
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(get_thread_priority(thread_native_id()).is_ok());
+/// ```
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+pub fn get_thread_priority(native: ThreadId) -> Result<ThreadPriority, Error> {
+    get_native_thread_priority(native)
+}
+
+fn get_native_thread_priority(native: ThreadId) -> Result<ThreadPriority, Error> {
     unsafe {
-        let ret = GetThreadPriority(thread_native_id());
+        let ret = GetThreadPriority(native);
         if ret as u32 != winbase::THREAD_PRIORITY_ERROR_RETURN {
-            Ok(ThreadPriority::Os(crate::ThreadPriorityOsValue(
-                WinAPIThreadPriority::try_from(ret as DWORD)? as u32,
-            )))
+            Ok(ThreadPriority::Os(crate::ThreadPriorityOsValue(ret as u32)))
         } else {
             Err(Error::OS(GetLastError() as i32))
         }
@@ -249,6 +568,161 @@ pub fn thread_native_id() -> ThreadId {
     unsafe { GetCurrentThread() }
 }
 
+/// Returns the kernel thread id of the thread.
+pub(crate) fn thread_tid_of(native: ThreadId) -> Result<ThreadTid, Error> {
+    match unsafe { GetThreadId(native) } {
+        0 => Err(Error::OS(unsafe { GetLastError() } as i32)),
+        tid => Ok(tid),
+    }
+}
+
+/// The priority class of a process, which the priorities of its threads are relative to.
+/// Check out MSDN for more info:
+/// <https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setpriorityclass>
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ProcessPriorityClass {
+    /// The threads run only when the system is idle.
+    Idle = winbase::IDLE_PRIORITY_CLASS,
+    /// Priority above `Idle` but below `Normal`.
+    BelowNormal = winbase::BELOW_NORMAL_PRIORITY_CLASS,
+    /// The default priority class.
+    Normal = winbase::NORMAL_PRIORITY_CLASS,
+    /// Priority above `Normal` but below `High`.
+    AboveNormal = winbase::ABOVE_NORMAL_PRIORITY_CLASS,
+    /// The class for time-critical tasks that must be executed immediately.
+    High = winbase::HIGH_PRIORITY_CLASS,
+    /// The highest possible priority class, which preempts even the operating system
+    /// processes. Without the `SeIncreaseBasePriorityPrivilege` privilege the system
+    /// silently applies `High` instead.
+    Realtime = winbase::REALTIME_PRIORITY_CLASS,
+}
+
+impl std::convert::TryFrom<DWORD> for ProcessPriorityClass {
+    type Error = crate::Error;
+
+    fn try_from(class: DWORD) -> Result<Self, Self::Error> {
+        Ok(match class {
+            winbase::IDLE_PRIORITY_CLASS => ProcessPriorityClass::Idle,
+            winbase::BELOW_NORMAL_PRIORITY_CLASS => ProcessPriorityClass::BelowNormal,
+            winbase::NORMAL_PRIORITY_CLASS => ProcessPriorityClass::Normal,
+            winbase::ABOVE_NORMAL_PRIORITY_CLASS => ProcessPriorityClass::AboveNormal,
+            winbase::HIGH_PRIORITY_CLASS => ProcessPriorityClass::High,
+            winbase::REALTIME_PRIORITY_CLASS => ProcessPriorityClass::Realtime,
+            _ => return Err(Error::Priority("Priority class couldn't be parsed")),
+        })
+    }
+}
+
+/// Sets the priority class of the current process.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(set_current_process_priority_class(ProcessPriorityClass::Normal).is_ok());
+/// assert_eq!(current_process_priority_class().unwrap(), ProcessPriorityClass::Normal);
+/// ```
+pub fn set_current_process_priority_class(class: ProcessPriorityClass) -> Result<(), Error> {
+    unsafe {
+        if SetPriorityClass(GetCurrentProcess(), class as DWORD) != 0 {
+            Ok(())
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Returns the priority class of the current process.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+pub fn current_process_priority_class() -> Result<ProcessPriorityClass, Error> {
+    use std::convert::TryFrom;
+
+    unsafe {
+        match GetPriorityClass(GetCurrentProcess()) {
+            0 => Err(Error::OS(GetLastError() as i32)),
+            class => ProcessPriorityClass::try_from(class),
+        }
+    }
+}
+
+/// The step of [`elevate_current_thread`] which has failed.
+#[derive(Debug, Copy, Clone)]
+pub enum ElevationError {
+    /// The process priority class couldn't be set, nothing has been changed.
+    ProcessClass(Error),
+    /// The thread priority couldn't be set, the previous process priority class has
+    /// been restored.
+    ThreadPriority(Error),
+}
+
+/// Raises the priority class of the current process and the priority of the current
+/// thread together, which is what reaching the time-critical behaviour takes on Windows,
+/// as the thread priorities are relative to the class. On success, the previous process
+/// priority class is returned, so that it can be restored later.
+///
+/// If the thread priority can't be set, the previous process priority class is restored.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let previous = elevate_current_thread(
+///     ProcessPriorityClass::AboveNormal,
+///     ThreadPriority::Os(WinAPIThreadPriority::Highest.into()),
+/// )
+/// .unwrap();
+/// assert!(set_current_process_priority_class(previous).is_ok());
+/// ```
+pub fn elevate_current_thread(
+    class: ProcessPriorityClass,
+    priority: ThreadPriority,
+) -> Result<ProcessPriorityClass, ElevationError> {
+    let previous = current_process_priority_class().map_err(ElevationError::ProcessClass)?;
+    set_current_process_priority_class(class).map_err(ElevationError::ProcessClass)?;
+    if let Err(e) = set_current_thread_priority(priority) {
+        let _ = set_current_process_priority_class(previous);
+        return Err(ElevationError::ThreadPriority(e));
+    }
+    Ok(previous)
+}
+
+/// Returns the number of CPU clock cycles used by the thread so far. This is the closest
+/// Windows analogue to the Linux scheduler statistics and can be used to verify how much
+/// CPU time a thread actually got after its priority was changed.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// assert!(unsafe { thread_cycle_time(thread_native_id()) }.is_ok());
+/// ```
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn thread_cycle_time(native: ThreadId) -> Result<u64, Error> {
+    let mut cycles = 0u64;
+    unsafe {
+        if QueryThreadCycleTime(native, &mut cycles) != 0 {
+            Ok(cycles)
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
 /// Disables or enables the ability of the system to temporarily boost the priority of a thread.
 ///
 /// If there's an error, a result of
@@ -260,11 +734,16 @@ pub fn thread_native_id() -> ThreadId {
 /// use thread_priority::*;
 ///
 /// let thread_id = thread_native_id();
-/// assert!(set_thread_priority_boost(thread_id, false).is_ok())
+/// assert!(unsafe { set_thread_priority_boost(thread_id, false) }.is_ok())
 /// ```
-pub fn set_thread_priority_boost(native: ThreadId, enabled: bool) -> Result<(), Error> {
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn set_thread_priority_boost(native: ThreadId, enabled: bool) -> Result<(), Error> {
     unsafe {
-        if SetThreadPriorityBoost(native, enabled as i32) != 0 {
+        // The function takes whether the boost is *disabled*.
+        if SetThreadPriorityBoost(native, !enabled as i32) != 0 {
             Ok(())
         } else {
             Err(Error::OS(GetLastError() as i32))
@@ -279,15 +758,55 @@ pub fn set_thread_priority_boost(native: ThreadId, enabled: bool) -> Result<(),
 ///
 /// This is a short-hand of the `set_thread_priority_boost` function for the current thread.
 pub fn set_current_thread_priority_boost(enabled: bool) -> Result<(), Error> {
-    set_thread_priority_boost(thread_native_id(), enabled)
+    unsafe { set_thread_priority_boost(thread_native_id(), enabled) }
+}
+
+/// Returns whether the ability of the system to temporarily boost the priority of a thread
+/// is enabled.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let thread_id = thread_native_id();
+/// assert!(unsafe { set_thread_priority_boost(thread_id, false) }.is_ok());
+/// assert_eq!(unsafe { thread_priority_boost(thread_id) }.unwrap(), false);
+/// assert!(unsafe { set_thread_priority_boost(thread_id, true) }.is_ok());
+/// assert_eq!(unsafe { thread_priority_boost(thread_id) }.unwrap(), true);
+/// ```
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn thread_priority_boost(native: ThreadId) -> Result<bool, Error> {
+    let mut disabled = 0;
+    unsafe {
+        if GetThreadPriorityBoost(native, &mut disabled) != 0 {
+            Ok(disabled == 0)
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Returns whether the ability of the system to temporarily boost the priority of a current
+/// thread is enabled.
+///
+/// This is a short-hand of the `thread_priority_boost` function for the current thread.
+pub fn current_thread_priority_boost() -> Result<bool, Error> {
+    unsafe { thread_priority_boost(thread_native_id()) }
 }
 
 /// Sets a preferred processor for a thread. The system schedules threads on their preferred
 /// processors whenever possible.
 ///
 /// On a system with more than 64 processors, this function sets the preferred processor to a
-/// logical processor in the processor group to which the calling thread is assigned. Use the
-/// `SetThreadIdealProcessorEx` function to specify a processor group and preferred processor.
+/// logical processor in the processor group to which the calling thread is assigned. Use
+/// [`set_thread_ideal_processor_ex`] to specify a processor group and preferred processor.
 ///
 /// If there's an error, a result of
 /// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
@@ -302,15 +821,19 @@ pub fn set_current_thread_priority_boost(enabled: bool) -> Result<(), Error> {
 /// use thread_priority::*;
 ///
 /// let thread_id = thread_native_id();
-/// assert!(set_thread_ideal_processor(thread_id, 0).is_ok())
+/// assert!(unsafe { set_thread_ideal_processor(thread_id, 0) }.is_ok())
 /// ```
-pub fn set_thread_ideal_processor(
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn set_thread_ideal_processor(
     native: ThreadId,
     ideal_processor: IdealProcessor,
 ) -> Result<IdealProcessor, Error> {
     unsafe {
         let ret = SetThreadIdealProcessor(native, ideal_processor);
-        if ret == IdealProcessor::max_value() - 1 {
+        if ret == IdealProcessor::MAX - 1 {
             Err(Error::OS(GetLastError() as i32))
         } else {
             Ok(ret)
@@ -325,32 +848,640 @@ pub fn set_thread_ideal_processor(
 pub fn set_current_thread_ideal_processor(
     ideal_processor: IdealProcessor,
 ) -> Result<IdealProcessor, Error> {
-    set_thread_ideal_processor(thread_native_id(), ideal_processor)
+    unsafe { set_thread_ideal_processor(thread_native_id(), ideal_processor) }
+}
+
+/// A processor identified by its processor group and number within the group, which is
+/// how the processors are addressed on the systems with more than 64 of them.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ProcessorNumber {
+    /// The processor group.
+    pub group: u16,
+    /// The processor number within the group.
+    pub number: u8,
+}
+
+impl ProcessorNumber {
+    /// Returns the processor numbered as in a [`crate::affinity::CpuSet`], so
+    /// `group * 64 + number`.
+    pub fn cpu(self) -> usize {
+        self.group as usize * 64 + self.number as usize
+    }
+
+    fn to_native(self) -> PROCESSOR_NUMBER {
+        PROCESSOR_NUMBER {
+            Group: self.group,
+            Number: self.number,
+            Reserved: 0,
+        }
+    }
+
+    fn from_native(native: &PROCESSOR_NUMBER) -> Self {
+        ProcessorNumber {
+            group: native.Group,
+            number: native.Number,
+        }
+    }
+}
+
+/// Sets a preferred processor for a thread, in any processor group. This is a soft
+/// placement preference, unlike the affinity. On success, the function returns the
+/// previously assigned processor.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let thread_id = thread_native_id();
+/// let processor = ProcessorNumber { group: 0, number: 0 };
+/// assert!(unsafe { set_thread_ideal_processor_ex(thread_id, processor) }.is_ok());
+/// assert_eq!(unsafe { thread_ideal_processor_ex(thread_id) }.unwrap(), processor);
+/// ```
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn set_thread_ideal_processor_ex(
+    native: ThreadId,
+    ideal_processor: ProcessorNumber,
+) -> Result<ProcessorNumber, Error> {
+    let mut previous = ProcessorNumber::default().to_native();
+    unsafe {
+        if SetThreadIdealProcessorEx(native, &mut ideal_processor.to_native(), &mut previous) != 0 {
+            Ok(ProcessorNumber::from_native(&previous))
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Sets a preferred processor for a current thread, in any processor group.
+///
+/// This is a short-hand of the `set_thread_ideal_processor_ex` function for the current thread.
+pub fn set_current_thread_ideal_processor_ex(
+    ideal_processor: ProcessorNumber,
+) -> Result<ProcessorNumber, Error> {
+    unsafe { set_thread_ideal_processor_ex(thread_native_id(), ideal_processor) }
+}
+
+/// Returns the preferred processor of a thread.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn thread_ideal_processor_ex(native: ThreadId) -> Result<ProcessorNumber, Error> {
+    let mut processor = ProcessorNumber::default().to_native();
+    unsafe {
+        if GetThreadIdealProcessorEx(native, &mut processor) != 0 {
+            Ok(ProcessorNumber::from_native(&processor))
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Returns the preferred processor of the current thread.
+///
+/// This is a short-hand of the `thread_ideal_processor_ex` function for the current thread.
+pub fn current_thread_ideal_processor_ex() -> Result<ProcessorNumber, Error> {
+    unsafe { thread_ideal_processor_ex(thread_native_id()) }
+}
+
+const THREAD_POWER_THROTTLING: THREAD_INFORMATION_CLASS = 3;
+const THREAD_POWER_THROTTLING_CURRENT_VERSION: DWORD = 1;
+const THREAD_POWER_THROTTLING_EXECUTION_SPEED: DWORD = 0x1;
+
+/// Copy of the WinAPI `THREAD_POWER_THROTTLING_STATE` type.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct ThreadPowerThrottlingState {
+    version: DWORD,
+    control_mask: DWORD,
+    state_mask: DWORD,
+}
+
+/// The power throttling (execution speed) state of a thread.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ThreadPowerThrottling {
+    /// The system decides whether to throttle the thread, based on the process state.
+    SystemManaged,
+    /// The thread is throttled for efficiency (EcoQoS): the system runs it on the most
+    /// efficient CPUs at the most efficient frequencies. This is how background work
+    /// is expected to be tagged.
+    EcoQoS,
+    /// The thread is explicitly excluded from the throttling (HighQoS).
+    HighQoS,
+}
+
+/// Sets the power throttling state of a thread.
+///
+/// Based on `SetThreadInformation(ThreadPowerThrottling)`, supported since Windows 10 1709.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let thread_id = thread_native_id();
+/// assert!(unsafe { set_thread_power_throttling(thread_id, ThreadPowerThrottling::EcoQoS) }.is_ok());
+/// assert_eq!(unsafe { thread_power_throttling(thread_id) }.unwrap(), ThreadPowerThrottling::EcoQoS);
+/// assert!(unsafe { set_thread_power_throttling(thread_id, ThreadPowerThrottling::SystemManaged) }.is_ok());
+/// ```
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn set_thread_power_throttling(
+    native: ThreadId,
+    throttling: ThreadPowerThrottling,
+) -> Result<(), Error> {
+    let (control_mask, state_mask) = match throttling {
+        ThreadPowerThrottling::SystemManaged => (0, 0),
+        ThreadPowerThrottling::EcoQoS => (
+            THREAD_POWER_THROTTLING_EXECUTION_SPEED,
+            THREAD_POWER_THROTTLING_EXECUTION_SPEED,
+        ),
+        ThreadPowerThrottling::HighQoS => (THREAD_POWER_THROTTLING_EXECUTION_SPEED, 0),
+    };
+    let mut state = ThreadPowerThrottlingState {
+        version: THREAD_POWER_THROTTLING_CURRENT_VERSION,
+        control_mask,
+        state_mask,
+    };
+    unsafe {
+        if SetThreadInformation(
+            native,
+            THREAD_POWER_THROTTLING,
+            &mut state as *mut _ as LPVOID,
+            std::mem::size_of::<ThreadPowerThrottlingState>() as DWORD,
+        ) != 0
+        {
+            Ok(())
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Sets the power throttling state of the current thread.
+///
+/// This is a short-hand of the `set_thread_power_throttling` function for the current thread.
+pub fn set_current_thread_power_throttling(throttling: ThreadPowerThrottling) -> Result<(), Error> {
+    unsafe { set_thread_power_throttling(thread_native_id(), throttling) }
+}
+
+/// Returns the power throttling state of a thread.
+///
+/// Based on `GetThreadInformation(ThreadPowerThrottling)`.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn thread_power_throttling(native: ThreadId) -> Result<ThreadPowerThrottling, Error> {
+    let mut state = ThreadPowerThrottlingState {
+        version: THREAD_POWER_THROTTLING_CURRENT_VERSION,
+        ..Default::default()
+    };
+    unsafe {
+        if GetThreadInformation(
+            native,
+            THREAD_POWER_THROTTLING,
+            &mut state as *mut _ as LPVOID,
+            std::mem::size_of::<ThreadPowerThrottlingState>() as DWORD,
+        ) == 0
+        {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+    }
+    Ok(
+        if state.control_mask & THREAD_POWER_THROTTLING_EXECUTION_SPEED == 0 {
+            ThreadPowerThrottling::SystemManaged
+        } else if state.state_mask & THREAD_POWER_THROTTLING_EXECUTION_SPEED != 0 {
+            ThreadPowerThrottling::EcoQoS
+        } else {
+            ThreadPowerThrottling::HighQoS
+        },
+    )
+}
+
+/// Returns the power throttling state of the current thread.
+///
+/// This is a short-hand of the `thread_power_throttling` function for the current thread.
+pub fn current_thread_power_throttling() -> Result<ThreadPowerThrottling, Error> {
+    unsafe { thread_power_throttling(thread_native_id()) }
+}
+
+/// The memory priority of a thread, which tells how long the pages it uses stay in the
+/// working set of the process before being trimmed. Lowering it for background threads
+/// reduces their pressure on the memory of the foreground activity.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum MemoryPriority {
+    /// The lowest memory priority.
+    VeryLow = 1,
+    /// Low memory priority.
+    Low = 2,
+    /// Medium memory priority.
+    Medium = 3,
+    /// Below normal memory priority.
+    BelowNormal = 4,
+    /// Normal memory priority, the default one.
+    Normal = 5,
+}
+
+impl std::convert::TryFrom<u32> for MemoryPriority {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => MemoryPriority::VeryLow,
+            2 => MemoryPriority::Low,
+            3 => MemoryPriority::Medium,
+            4 => MemoryPriority::BelowNormal,
+            5 => MemoryPriority::Normal,
+            _ => return Err(Error::Priority("The value is not a memory priority.")),
+        })
+    }
+}
+
+/// Copy of the WinAPI `MEMORY_PRIORITY_INFORMATION` type.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct MemoryPriorityInformation {
+    memory_priority: u32,
+}
+
+/// Sets the memory priority of a thread.
+///
+/// Based on `SetThreadInformation(ThreadMemoryPriority)`.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let thread_id = thread_native_id();
+/// assert!(unsafe { set_thread_memory_priority(thread_id, MemoryPriority::Low) }.is_ok());
+/// assert_eq!(unsafe { thread_memory_priority(thread_id) }.unwrap(), MemoryPriority::Low);
+/// assert!(unsafe { set_thread_memory_priority(thread_id, MemoryPriority::Normal) }.is_ok());
+/// ```
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn set_thread_memory_priority(
+    native: ThreadId,
+    priority: MemoryPriority,
+) -> Result<(), Error> {
+    let mut information = MemoryPriorityInformation {
+        memory_priority: priority as u32,
+    };
+    unsafe {
+        if SetThreadInformation(
+            native,
+            ThreadMemoryPriority,
+            &mut information as *mut _ as LPVOID,
+            std::mem::size_of::<MemoryPriorityInformation>() as DWORD,
+        ) != 0
+        {
+            Ok(())
+        } else {
+            Err(Error::OS(GetLastError() as i32))
+        }
+    }
+}
+
+/// Sets the memory priority of the current thread.
+///
+/// This is a short-hand of the `set_thread_memory_priority` function for the current thread.
+pub fn set_current_thread_memory_priority(priority: MemoryPriority) -> Result<(), Error> {
+    unsafe { set_thread_memory_priority(thread_native_id(), priority) }
+}
+
+/// Returns the memory priority of a thread.
+///
+/// Based on `GetThreadInformation(ThreadMemoryPriority)`.
+///
+/// If there's an error, a result of
+/// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror) is returned.
+///
+/// # Safety
+///
+/// `native` must be a valid thread handle for the duration of the call.
+pub unsafe fn thread_memory_priority(native: ThreadId) -> Result<MemoryPriority, Error> {
+    use std::convert::TryFrom;
+
+    let mut information = MemoryPriorityInformation::default();
+    unsafe {
+        if GetThreadInformation(
+            native,
+            ThreadMemoryPriority,
+            &mut information as *mut _ as LPVOID,
+            std::mem::size_of::<MemoryPriorityInformation>() as DWORD,
+        ) == 0
+        {
+            return Err(Error::OS(GetLastError() as i32));
+        }
+    }
+    MemoryPriority::try_from(information.memory_priority)
+}
+
+/// Returns the memory priority of the current thread.
+///
+/// This is a short-hand of the `thread_memory_priority` function for the current thread.
+pub fn current_thread_memory_priority() -> Result<MemoryPriority, Error> {
+    unsafe { thread_memory_priority(thread_native_id()) }
+}
+
+/// The background processing mode of the current thread, the Windows analogue of
+/// `SCHED_IDLE`: the system lowers the CPU, I/O and memory priorities of the thread, so
+/// that it can perform maintenance work without affecting the foreground activity.
+///
+/// The mode is ended when the guard is dropped, so it must be dropped on the thread
+/// which has created it.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let guard = BackgroundModeGuard::begin().unwrap();
+/// // Run the maintenance work.
+/// drop(guard);
+/// ```
+#[derive(Debug)]
+pub struct BackgroundModeGuard {
+    // The mode belongs to the thread it was begun on.
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl BackgroundModeGuard {
+    /// Begins the background processing mode for the current thread. Fails if the thread
+    /// is already in this mode.
+    pub fn begin() -> Result<Self, Error> {
+        set_winapi_thread_priority(
+            thread_native_id(),
+            WinAPIThreadPriority::BackgroundModeBegin,
+        )?;
+        Ok(BackgroundModeGuard {
+            _not_send: std::marker::PhantomData,
+        })
+    }
+}
+
+impl Drop for BackgroundModeGuard {
+    fn drop(&mut self) {
+        let _ =
+            set_winapi_thread_priority(thread_native_id(), WinAPIThreadPriority::BackgroundModeEnd);
+    }
+}
+
+/// A raised resolution of the system timer, which the sleeps and the waits of all the
+/// threads are rounded to. A high thread priority rarely achieves the expected latency
+/// without a 1 millisecond resolution, as the default one is about 15.6 milliseconds.
+///
+/// The resolution is restored when the guard is dropped.
+///
+/// Based on `timeBeginPeriod` and `timeEndPeriod`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let guard = TimerResolutionGuard::new(1).unwrap();
+/// assert_eq!(guard.period(), 1);
+/// std::thread::sleep(std::time::Duration::from_millis(1));
+/// drop(guard);
+/// ```
+#[derive(Debug)]
+pub struct TimerResolutionGuard {
+    period: u32,
+}
+
+impl TimerResolutionGuard {
+    /// Raises the resolution of the system timer to the period, in milliseconds.
+    /// Fails with [`Error::Priority`] if the period is out of the range the timer supports,
+    /// see [`TimerResolutionGuard::supported_periods`].
+    pub fn new(period: u32) -> Result<Self, Error> {
+        match unsafe { timeBeginPeriod(period) } {
+            TIMERR_NOERROR => Ok(TimerResolutionGuard { period }),
+            _ => Err(Error::Priority(
+                "The period is out of the range the timer supports",
+            )),
+        }
+    }
+
+    /// Returns the period the resolution has been raised to, in milliseconds.
+    pub fn period(&self) -> u32 {
+        self.period
+    }
+
+    /// Returns the minimum and the maximum periods the system timer supports, in
+    /// milliseconds.
+    pub fn supported_periods() -> Result<(u32, u32), Error> {
+        let mut caps = TIMECAPS {
+            wPeriodMin: 0,
+            wPeriodMax: 0,
+        };
+        match unsafe { timeGetDevCaps(&mut caps, std::mem::size_of::<TIMECAPS>() as u32) } {
+            TIMERR_NOERROR => Ok((caps.wPeriodMin, caps.wPeriodMax)),
+            _ => Err(Error::Ffi("Can't get the capabilities of the system timer")),
+        }
+    }
+}
+
+impl Drop for TimerResolutionGuard {
+    fn drop(&mut self) {
+        unsafe {
+            timeEndPeriod(self.period);
+        }
+    }
+}
+
+/// A Multimedia Class Scheduler Service (MMCSS) task, as registered in
+/// `HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Multimedia\SystemProfile\Tasks`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum MmcssTask {
+    /// The `Audio` task.
+    Audio,
+    /// The `Capture` task.
+    Capture,
+    /// The `Distribution` task.
+    Distribution,
+    /// The `Games` task.
+    Games,
+    /// The `Playback` task.
+    Playback,
+    /// The `Pro Audio` task, for the low-latency audio processing.
+    ProAudio,
+    /// The `Window Manager` task.
+    WindowManager,
+    /// A custom task registered under the given name.
+    Custom(&'static str),
+}
+
+impl MmcssTask {
+    /// Returns the name the task is registered under.
+    pub fn name(self) -> &'static str {
+        match self {
+            MmcssTask::Audio => "Audio",
+            MmcssTask::Capture => "Capture",
+            MmcssTask::Distribution => "Distribution",
+            MmcssTask::Games => "Games",
+            MmcssTask::Playback => "Playback",
+            MmcssTask::ProAudio => "Pro Audio",
+            MmcssTask::WindowManager => "Window Manager",
+            MmcssTask::Custom(name) => name,
+        }
+    }
+}
+
+/// The priority of a thread within its MMCSS task.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum MmcssPriority {
+    /// Lowest priority.
+    VeryLow = -2,
+    /// Below normal priority.
+    Low = -1,
+    /// Normal priority.
+    Normal = 0,
+    /// Above normal priority.
+    High = 1,
+    /// Highest priority.
+    Critical = 2,
+}
+
+/// The registration of the current thread with an MMCSS task, which makes the service
+/// boost the thread's priority as the task requires. Plain thread priorities are not
+/// enough for glitch-free audio and video on Windows, as the other processes' threads
+/// may take precedence.
+///
+/// The registration is reverted when the value is dropped, so it must be dropped on
+/// the thread which has created it.
+///
+/// Based on `AvSetMmThreadCharacteristicsW`.
+///
+/// # Usage
+///
+/// ```rust
+/// use thread_priority::*;
+///
+/// let characteristics = WindowsThreadCharacteristics::new(MmcssTask::ProAudio).unwrap();
+/// assert!(characteristics.set_priority(MmcssPriority::High).is_ok());
+/// drop(characteristics);
+/// ```
+#[derive(Debug)]
+pub struct WindowsThreadCharacteristics {
+    handle: HANDLE,
+    task_index: DWORD,
+}
+
+impl WindowsThreadCharacteristics {
+    /// Registers the current thread with the MMCSS task.
+    pub fn new(task: MmcssTask) -> Result<Self, Error> {
+        let name: Vec<u16> = task.name().encode_utf16().chain(Some(0)).collect();
+        let mut task_index = 0;
+        let handle = unsafe { AvSetMmThreadCharacteristicsW(name.as_ptr(), &mut task_index) };
+        if handle.is_null() {
+            Err(Error::OS(unsafe { GetLastError() } as i32))
+        } else {
+            Ok(WindowsThreadCharacteristics { handle, task_index })
+        }
+    }
+
+    /// Returns the index of the task the thread is registered with, which is used to
+    /// register the other threads of the same activity with the same task instance.
+    pub fn task_index(&self) -> DWORD {
+        self.task_index
+    }
+
+    /// Sets the priority of the thread within its task.
+    ///
+    /// Based on `AvSetMmThreadPriority`.
+    pub fn set_priority(&self, priority: MmcssPriority) -> Result<(), Error> {
+        unsafe {
+            if AvSetMmThreadPriority(self.handle, priority as i32 as u32) != 0 {
+                Ok(())
+            } else {
+                Err(Error::OS(GetLastError() as i32))
+            }
+        }
+    }
+}
+
+impl Drop for WindowsThreadCharacteristics {
+    fn drop(&mut self) {
+        unsafe {
+            AvRevertMmThreadCharacteristics(self.handle);
+        }
+    }
 }
 
 impl std::convert::TryFrom<u32> for crate::ThreadPriorityOsValue {
     type Error = ();
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        Ok(crate::ThreadPriorityOsValue(match value {
-            winbase::THREAD_MODE_BACKGROUND_BEGIN
-            | winbase::THREAD_MODE_BACKGROUND_END
-            | winbase::THREAD_PRIORITY_ABOVE_NORMAL
-            | winbase::THREAD_PRIORITY_BELOW_NORMAL
-            | winbase::THREAD_PRIORITY_HIGHEST
-            | winbase::THREAD_PRIORITY_IDLE
-            | winbase::THREAD_PRIORITY_LOWEST
-            | winbase::THREAD_PRIORITY_NORMAL
-            | winbase::THREAD_PRIORITY_TIME_CRITICAL => value,
-            _ => return Err(()),
-        }))
+        WinAPIThreadPriority::try_from(value)
+            .map(Into::into)
+            .map_err(|_| ())
+    }
+}
+
+impl std::convert::TryFrom<i32> for crate::ThreadPriorityOsValue {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        WinAPIThreadPriority::try_from(value as u32).map(Into::into)
     }
 }
 
+impl crate::ThreadPriorityOsValue {
+    /// `THREAD_PRIORITY_IDLE`.
+    pub const IDLE: Self = crate::ThreadPriorityOsValue(winbase::THREAD_PRIORITY_IDLE);
+    /// `THREAD_PRIORITY_LOWEST`.
+    pub const LOWEST: Self = crate::ThreadPriorityOsValue(winbase::THREAD_PRIORITY_LOWEST);
+    /// `THREAD_PRIORITY_BELOW_NORMAL`.
+    pub const BELOW_NORMAL: Self =
+        crate::ThreadPriorityOsValue(winbase::THREAD_PRIORITY_BELOW_NORMAL);
+    /// `THREAD_PRIORITY_NORMAL`.
+    pub const NORMAL: Self = crate::ThreadPriorityOsValue(winbase::THREAD_PRIORITY_NORMAL);
+    /// `THREAD_PRIORITY_ABOVE_NORMAL`.
+    pub const ABOVE_NORMAL: Self =
+        crate::ThreadPriorityOsValue(winbase::THREAD_PRIORITY_ABOVE_NORMAL);
+    /// `THREAD_PRIORITY_HIGHEST`.
+    pub const HIGHEST: Self = crate::ThreadPriorityOsValue(winbase::THREAD_PRIORITY_HIGHEST);
+    /// `THREAD_PRIORITY_TIME_CRITICAL`.
+    pub const TIME_CRITICAL: Self =
+        crate::ThreadPriorityOsValue(winbase::THREAD_PRIORITY_TIME_CRITICAL);
+}
+
 /// Windows-specific complemented part of the [`crate::ThreadExt`] trait.
+///
+/// The methods apply to the thread [`ThreadExt::get_native_id`] returns. A
+/// [`std::thread::Thread`] doesn't know its native id, so it stands for the
+/// current thread, while a [`std::thread::JoinHandle`] applies the methods to the
+/// thread it refers to.
 pub trait ThreadExt {
-    /// Returns current thread's priority.
-    /// For more info see [`thread_priority`].
+    /// Returns the thread's priority.
+    /// For more info see [`get_thread_priority`].
     ///
     /// ```rust
     /// use thread_priority::*;
@@ -358,11 +1489,11 @@ pub trait ThreadExt {
     /// assert!(std::thread::current().get_priority().is_ok());
     /// ```
     fn get_priority(&self) -> Result<ThreadPriority, Error> {
-        thread_priority()
+        get_thread_priority(self.get_native_id())
     }
 
-    /// Sets current thread's priority.
-    /// For more info see [`set_current_thread_priority`].
+    /// Sets the thread's priority.
+    /// For more info see [`set_thread_priority`].
     ///
     /// ```rust
     /// use thread_priority::*;
@@ -370,7 +1501,7 @@ pub trait ThreadExt {
     /// assert!(std::thread::current().set_priority(ThreadPriority::Min).is_ok());
     /// ```
     fn set_priority(&self, priority: ThreadPriority) -> Result<(), Error> {
-        set_current_thread_priority(priority)
+        set_thread_priority(self.get_native_id(), priority)
     }
 
     /// Returns current thread's windows id.
@@ -384,8 +1515,8 @@ pub trait ThreadExt {
     fn get_native_id(&self) -> ThreadId {
         thread_native_id()
     }
-    /// Sets current thread's ideal processor.
-    /// For more info see [`set_current_thread_ideal_processor`].
+    /// Sets the thread's ideal processor.
+    /// For more info see [`set_thread_ideal_processor`].
     ///
     /// ```rust
     /// use thread_priority::*;
@@ -396,11 +1527,11 @@ pub trait ThreadExt {
         &self,
         ideal_processor: IdealProcessor,
     ) -> Result<IdealProcessor, Error> {
-        set_current_thread_ideal_processor(ideal_processor)
+        unsafe { set_thread_ideal_processor(self.get_native_id(), ideal_processor) }
     }
 
-    /// Sets current thread's priority boost.
-    /// For more info see [`set_current_thread_priority_boost`].
+    /// Sets the thread's priority boost.
+    /// For more info see [`set_thread_priority_boost`].
     ///
     /// ```rust
     /// use thread_priority::*;
@@ -408,9 +1539,29 @@ pub trait ThreadExt {
     /// assert!(std::thread::current().set_priority_boost(true).is_ok());
     /// ```
     fn set_priority_boost(&self, enabled: bool) -> Result<(), Error> {
-        set_current_thread_priority_boost(enabled)
+        unsafe { set_thread_priority_boost(self.get_native_id(), enabled) }
+    }
+
+    /// Returns whether the thread's priority boost is enabled.
+    /// For more info see [`thread_priority_boost`].
+    ///
+    /// ```rust
+    /// use thread_priority::*;
+    ///
+    /// assert!(std::thread::current().get_priority_boost().is_ok());
+    /// ```
+    fn get_priority_boost(&self) -> Result<bool, Error> {
+        unsafe { thread_priority_boost(self.get_native_id()) }
     }
 }
 
 /// Auto-implementation of this trait for the [`std::thread::Thread`].
 impl ThreadExt for std::thread::Thread {}
+
+/// Implementation of this trait for the [`std::thread::JoinHandle`], for the thread it
+/// refers to.
+impl<T> ThreadExt for std::thread::JoinHandle<T> {
+    fn get_native_id(&self) -> ThreadId {
+        std::os::windows::io::AsRawHandle::as_raw_handle(self) as ThreadId
+    }
+}